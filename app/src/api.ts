@@ -15,6 +15,10 @@ async function parseJsonResponse<T>(res: Response): Promise<ApiResponse<T>> {
 
 export type ApiResponse<T> = { success: boolean; data?: T; error?: string };
 
+/** The node reports balances and supply in integer micro-PULSE; the UI shows PULSE. */
+const MICRO_PER_PULSE = 1_000_000;
+const fromMicro = (amount: number) => amount / MICRO_PER_PULSE;
+
 export type NetworkStats = {
   chain_length: number;
   total_minted: number;
@@ -38,7 +42,9 @@ export type Heartbeat = {
   motion: Motion;
   temperature: number;
   device_pubkey: string;
+  signature_version?: number;
   signature: string;
+  recoverable?: boolean;
 };
 
 export type Transaction = {
@@ -46,8 +52,10 @@ export type Transaction = {
   sender_pubkey: string;
   recipient_pubkey: string;
   amount: number;
+  fee?: number;
   timestamp: number;
   heartbeat_signature: string;
+  signature_version?: number;
   signature: string;
 };
 
@@ -71,19 +79,51 @@ export type Account = {
   blocks_participated: number;
 };
 
-export async function getAccounts(nodeUrl: string): Promise<ApiResponse<Account[]>> {
-  const res = await fetch(`${base(nodeUrl)}/accounts`);
-  return parseJsonResponse<Account[]>(res);
+export type AccountSort = 'balance' | 'total_earned' | 'blocks_participated';
+
+export type PaginatedAccounts = {
+  accounts: Account[];
+  sort: AccountSort;
+  total: number;
+  offset: number;
+  limit: number;
+};
+
+export async function getAccounts(
+  nodeUrl: string,
+  offset?: number,
+  limit?: number,
+  sort?: AccountSort,
+): Promise<ApiResponse<PaginatedAccounts>> {
+  const params = new URLSearchParams();
+  if (offset !== undefined) params.set('offset', String(offset));
+  if (limit !== undefined) params.set('limit', String(limit));
+  if (sort !== undefined) params.set('sort', sort);
+  const qs = params.toString();
+  const res = await fetch(`${base(nodeUrl)}/accounts${qs ? '?' + qs : ''}`);
+  const parsed = await parseJsonResponse<PaginatedAccounts>(res);
+  if (parsed.data) {
+    parsed.data.accounts = parsed.data.accounts.map((a) => ({ ...a, balance: fromMicro(a.balance), total_earned: fromMicro(a.total_earned) }));
+  }
+  return parsed;
 }
 
-export async function health(nodeUrl: string): Promise<ApiResponse<string>> {
+export type Health = {
+  storage_ok: boolean;
+  last_block_age_ms: number;
+  peer_count: number;
+};
+
+export async function health(nodeUrl: string): Promise<ApiResponse<Health>> {
   const res = await fetch(`${base(nodeUrl)}/health`);
-  return parseJsonResponse<string>(res);
+  return parseJsonResponse<Health>(res);
 }
 
 export async function getStats(nodeUrl: string): Promise<ApiResponse<NetworkStats>> {
   const res = await fetch(`${base(nodeUrl)}/stats`);
-  return parseJsonResponse<NetworkStats>(res);
+  const parsed = await parseJsonResponse<NetworkStats>(res);
+  if (parsed.data) parsed.data.total_minted = fromMicro(parsed.data.total_minted);
+  return parsed;
 }
 
 export async function getChain(nodeUrl: string): Promise<ApiResponse<ChainInfo>> {
@@ -119,7 +159,9 @@ export async function getBlockByIndex(nodeUrl: string, index: number): Promise<A
 
 export async function getBalance(nodeUrl: string, pubkey: string): Promise<ApiResponse<{ pubkey: string; balance: number }>> {
   const res = await fetch(`${base(nodeUrl)}/balance/${encodeURIComponent(pubkey)}`);
-  return parseJsonResponse<{ pubkey: string; balance: number }>(res);
+  const parsed = await parseJsonResponse<{ pubkey: string; balance: number }>(res);
+  if (parsed.data) parsed.data.balance = fromMicro(parsed.data.balance);
+  return parsed;
 }
 
 export async function submitHeartbeat(nodeUrl: string, body: Heartbeat): Promise<ApiResponse<unknown>> {
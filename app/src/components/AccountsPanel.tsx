@@ -18,7 +18,7 @@ export default function AccountsPanel({ nodeUrl }: { nodeUrl: string }) {
     setBalances((prev) => ({ ...prev, ...next }));
     const accRes = await getAccounts(nodeUrl);
     if (accRes.success && accRes.data) {
-      setNetworkAccounts(accRes.data.map((a) => ({ pubkey: a.pubkey, balance: a.balance })));
+      setNetworkAccounts(accRes.data.accounts.map((a) => ({ pubkey: a.pubkey, balance: a.balance })));
     }
   }, [nodeUrl, accounts]);
 
@@ -9,6 +9,7 @@ export default function Dashboard({ nodeUrl }: { nodeUrl: string }) {
   const [chain, setChain] = useState<ChainInfo | null>(null);
   const [block, setBlock] = useState<PulseBlock | null>(null);
   const [heartbeatPoolSize, setHeartbeatPoolSize] = useState(0);
+  const [peerCount, setPeerCount] = useState<number | null>(null);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [blocksPerSecond, setBlocksPerSecond] = useState<string>('—');
@@ -36,6 +37,9 @@ export default function Dashboard({ nodeUrl }: { nodeUrl: string }) {
         case 'heartbeat_count':
           setHeartbeatPoolSize(event.count);
           break;
+        case 'peers':
+          setPeerCount(event.count);
+          break;
       }
     }, []),
   });
@@ -50,7 +54,7 @@ export default function Dashboard({ nodeUrl }: { nodeUrl: string }) {
         getChain(nodeUrl),
         getLatestBlock(nodeUrl),
       ]);
-      setHealthMsg(h.success ? (h.data ?? 'OK') : h.error ?? 'Failed');
+      setHealthMsg(h.success ? `OK · ${h.data?.peer_count ?? 0} peers` : h.error ?? 'Failed');
       setStats(s.success ? s.data ?? null : null);
       setChain(c.success ? (c.data ?? null) : null);
       setBlock(b.success ? (b.data ?? null) : null);
@@ -117,6 +121,7 @@ export default function Dashboard({ nodeUrl }: { nodeUrl: string }) {
               <dt>Latest hash</dt><dd className="hash-text">{block ? block.block_hash : chain.latest_hash || '—'}</dd>
               <dt>Heartbeat pool</dt><dd>{heartbeatPoolSize || chain.heartbeat_pool_size}</dd>
               <dt>Block rate</dt><dd>{blocksPerSecond} blocks/s</dd>
+              <dt>Peers</dt><dd>{peerCount ?? '—'}</dd>
             </>
           )}
         </dl>
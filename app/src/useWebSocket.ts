@@ -5,7 +5,8 @@ import type { NetworkStats, PulseBlock } from './api';
 export type WsEvent =
   | { type: 'new_block'; block: PulseBlock }
   | { type: 'stats'; stats: NetworkStats }
-  | { type: 'heartbeat_count'; count: number };
+  | { type: 'heartbeat_count'; count: number }
+  | { type: 'peers'; count: number };
 
 type WsStatus = 'connecting' | 'connected' | 'disconnected';
 
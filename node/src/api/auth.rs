@@ -0,0 +1,74 @@
+//! Optional bearer-token check for write and admin endpoints.
+//!
+//! Heartbeats and transactions are already signed, so on `/pulse` and `/tx`
+//! this isn't about authenticity — it keeps strangers from spending a
+//! semi-private node's rate-limit and verification budget. Admin routes can
+//! rewrite the local chain, so they're only mounted when a token is set.
+//! Reads stay open either way.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+
+use super::ApiResponse;
+
+/// Middleware: reject requests without `Authorization: Bearer <token>` matching `token`
+pub async fn require_bearer_token(
+    State(token): State<Arc<str>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let presented = req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(bearer_token);
+
+    match presented {
+        Some(presented) if tokens_match(presented, &token) => next.run(req).await,
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(ApiResponse::<()>::err("Missing or invalid API token")),
+        ).into_response(),
+    }
+}
+
+/// The token from an `Authorization` header value, if it uses the Bearer scheme
+pub(super) fn bearer_token(value: &str) -> Option<&str> {
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+/// Compare without short-circuiting so response timing doesn't leak a prefix match
+pub(super) fn tokens_match(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented.bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_bearer_header() {
+        assert_eq!(bearer_token("Bearer s3cret"), Some("s3cret"));
+        assert_eq!(bearer_token("bearer s3cret"), Some("s3cret"));
+        assert_eq!(bearer_token("Basic dXNlcjpwYXNz"), None);
+        assert_eq!(bearer_token("s3cret"), None);
+    }
+
+    #[test]
+    fn test_token_comparison() {
+        assert!(tokens_match("s3cret", "s3cret"));
+        assert!(!tokens_match("s3cres", "s3cret"));
+        assert!(!tokens_match("s3cre", "s3cret"));
+        assert!(!tokens_match("", "s3cret"));
+    }
+}
@@ -1,15 +1,23 @@
 //! Event log for tracking node activity.
-//! Ring buffer of recent events for the activity feed.
+//! Ring buffer of recent events for the activity feed, optionally backed by
+//! the chain store so the feed survives restarts.
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing::warn;
+
+use crate::storage::ChainStore;
 
 const MAX_EVENTS: usize = 200;
 
+/// Most events `since` returns when it has to read from storage; callers page
+/// on by asking again from the last timestamp
+const MAX_STORED_EVENTS_PER_QUERY: usize = 1000;
+
 /// Types of events the node can emit
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum NodeEvent {
     #[serde(rename = "heartbeat_received")]
@@ -18,6 +26,11 @@ pub enum NodeEvent {
         device_pubkey: String,
         heart_rate: u16,
         weight: f64,
+        /// Biometric confidence and HRV, known when logged on acceptance via `/pulse`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        confidence: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hrv_sdnn: Option<f64>,
     },
     #[serde(rename = "block_created")]
     BlockCreated {
@@ -37,6 +50,18 @@ pub enum NodeEvent {
         recipient: String,
         amount: f64,
     },
+    #[serde(rename = "peer_connected")]
+    PeerConnected {
+        timestamp: u64,
+        peer_id: String,
+        peer_count: usize,
+    },
+    #[serde(rename = "peer_disconnected")]
+    PeerDisconnected {
+        timestamp: u64,
+        peer_id: String,
+        peer_count: usize,
+    },
     #[serde(rename = "node_started")]
     NodeStarted {
         timestamp: u64,
@@ -51,26 +76,65 @@ impl NodeEvent {
             NodeEvent::HeartbeatReceived { timestamp, .. } => *timestamp,
             NodeEvent::BlockCreated { timestamp, .. } => *timestamp,
             NodeEvent::TransactionReceived { timestamp, .. } => *timestamp,
+            NodeEvent::PeerConnected { timestamp, .. } => *timestamp,
+            NodeEvent::PeerDisconnected { timestamp, .. } => *timestamp,
             NodeEvent::NodeStarted { timestamp, .. } => *timestamp,
         }
     }
 }
 
+/// Current wall-clock time in ms, for event timestamps
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
 /// Thread-safe event log with ring buffer
 #[derive(Clone)]
 pub struct EventLog {
     events: Arc<RwLock<VecDeque<NodeEvent>>>,
+    store: Option<Arc<dyn ChainStore>>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EventLog {
     pub fn new() -> Self {
         Self {
             events: Arc::new(RwLock::new(VecDeque::with_capacity(MAX_EVENTS))),
+            store: None,
+        }
+    }
+
+    /// Event log that saves every event to `store`, starting with the most
+    /// recent ones already saved there
+    pub fn with_storage(store: Arc<dyn ChainStore>) -> Self {
+        let events = match store.load_recent_events(MAX_EVENTS) {
+            Ok(events) => events,
+            Err(e) => {
+                warn!("⚠️ Failed to load saved events: {}", e);
+                Vec::new()
+            }
+        };
+        Self {
+            events: Arc::new(RwLock::new(events.into())),
+            store: Some(store),
         }
     }
 
     /// Push an event to the log
     pub async fn push(&self, event: NodeEvent) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.save_event(&event) {
+                warn!("⚠️ Failed to save event: {}", e);
+            }
+        }
         let mut events = self.events.write().await;
         if events.len() >= MAX_EVENTS {
             events.pop_front();
@@ -84,12 +148,62 @@ impl EventLog {
         events.iter().rev().take(limit).cloned().collect()
     }
 
-    /// Get events since a given timestamp
+    /// Get events since a given timestamp, oldest first. Served from memory
+    /// when the ring buffer reaches back that far, from storage otherwise.
     pub async fn since(&self, timestamp: u64) -> Vec<NodeEvent> {
         let events = self.events.read().await;
+        let in_memory = events.front().is_none_or(|oldest| oldest.timestamp() <= timestamp);
+        if let (false, Some(store)) = (in_memory, &self.store) {
+            match store.load_events_since(timestamp, MAX_STORED_EVENTS_PER_QUERY) {
+                Ok(stored) => return stored,
+                Err(e) => warn!("⚠️ Failed to load saved events: {}", e),
+            }
+        }
         events.iter()
             .filter(|e| e.timestamp() > timestamp)
             .cloned()
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    fn peer_event(timestamp: u64, peer_count: usize) -> NodeEvent {
+        NodeEvent::PeerConnected { timestamp, peer_id: format!("peer-{}", peer_count), peer_count }
+    }
+
+    #[tokio::test]
+    async fn test_events_reloaded_from_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let store: Arc<dyn ChainStore> = Arc::new(Storage::open(dir.path()).unwrap());
+        let start = now_ms();
+        {
+            let log = EventLog::with_storage(store.clone());
+            for i in 0..MAX_EVENTS + 50 {
+                log.push(peer_event(start + i as u64, i)).await;
+            }
+        }
+
+        // A fresh log starts with the most recent MAX_EVENTS
+        let log = EventLog::with_storage(store);
+        let latest = log.latest(MAX_EVENTS + 50).await;
+        assert_eq!(latest.len(), MAX_EVENTS);
+        assert_eq!(latest[0].timestamp(), start + (MAX_EVENTS + 49) as u64);
+        assert_eq!(latest[MAX_EVENTS - 1].timestamp(), start + 50);
+
+        // `since` reaches past the in-memory window into storage...
+        let older = log.since(start + 9).await;
+        assert_eq!(older.len(), MAX_EVENTS + 40);
+        assert_eq!(older[0].timestamp(), start + 10);
+        // ...and stays in memory when it doesn't need to
+        assert_eq!(log.since(start + MAX_EVENTS as u64 + 48).await.len(), 1);
+
+        // Without storage the feed is memory only
+        let memory_only = EventLog::new();
+        memory_only.push(peer_event(start, 1)).await;
+        assert_eq!(memory_only.since(0).await.len(), 1);
+    }
+}
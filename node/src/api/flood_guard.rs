@@ -0,0 +1,95 @@
+//! Connection-level flood guard.
+//!
+//! Runs before routing and body extraction, so a burst of requests from one
+//! IP is turned away with 429 without paying for JSON parsing or signature
+//! checks. The per-endpoint semantic limiters still apply to whatever gets
+//! through; this only catches bursts well above any legitimate rate.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::net::SocketAddr;
+
+use super::rate_limit::RateLimiter;
+
+/// Middleware: reject requests from IPs over the burst threshold before the body is read
+pub async fn reject_floods(
+    State(limiter): State<RateLimiter>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let ip = req.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+
+    if let Some(ip) = ip {
+        if !limiter.check(&ip).await {
+            return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+                "success": false,
+                "error": "Too many requests"
+            }))).into_response();
+        }
+    }
+
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rate_limit::RateLimitConfig;
+    use axum::{routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_burst_rejected_before_handler() {
+        let reached = Arc::new(AtomicUsize::new(0));
+        let counter = reached.clone();
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_requests: 5,
+            window: Duration::from_secs(60),
+            ..Default::default()
+        });
+
+        let router = Router::new()
+            .route("/pulse", post(move |Json(_): Json<serde_json::Value>| {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    "ok"
+                }
+            }))
+            .layer(axum::middleware::from_fn_with_state(limiter, reject_floods));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .unwrap();
+        });
+
+        let client = reqwest::Client::new();
+        let mut statuses = Vec::new();
+        for _ in 0..12 {
+            let resp = client.post(format!("http://{}/pulse", addr))
+                .header("content-type", "application/json")
+                .body("{}")
+                .send()
+                .await
+                .unwrap();
+            statuses.push(resp.status().as_u16());
+        }
+
+        // The first 5 reach the handler; the rest are turned away before body parsing
+        assert!(statuses[..5].iter().all(|s| *s == 200), "{:?}", statuses);
+        assert!(statuses[5..].iter().all(|s| *s == 429), "{:?}", statuses);
+        assert_eq!(reached.load(Ordering::SeqCst), 5);
+    }
+}
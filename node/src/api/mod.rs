@@ -1,13 +1,16 @@
 //! HTTP API for the Pulse Node.
 //! Endpoints for devices to submit heartbeats and query network state.
 
+pub mod auth;
 pub mod rate_limit;
 pub mod websocket;
 pub mod events;
+pub mod flood_guard;
+pub mod request_id;
 
 use axum::{
-    extract::{ConnectInfo, Path, Query, State, Json},
-    http::StatusCode,
+    extract::{ConnectInfo, DefaultBodyLimit, Path, Query, State, Json},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
     response::IntoResponse,
     routing::{get, post},
     Router,
@@ -15,15 +18,21 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::limit::RequestBodyLimitLayer;
 use tracing::info;
 
-use crate::consensus::ProofOfLife;
+use crate::consensus::{ConsensusError, ProofOfLife};
+use crate::crypto::verify_signature;
+use crate::metrics::{self, NodeGauges};
 use crate::network::NetworkHandle;
-use crate::types::{Account, Heartbeat, Transaction};
+use crate::simulation::{SimulationConfig, SimulationControl};
+use crate::types::{to_pulse, Account, AccountSort, Amount, Heartbeat, PulseBlock, Transaction};
+use events::{now_ms, NodeEvent};
 use rate_limit::{RateLimiter, RateLimitConfig};
+use websocket::WsEvent;
 pub use websocket::WsBroadcaster;
 pub use events::EventLog;
 
@@ -35,10 +44,90 @@ pub type AppState = Arc<RwLock<ProofOfLife>>;
 pub struct ApiState {
     pub consensus: AppState,
     pub pulse_limiter: RateLimiter,
+    /// `/pulse` budget per device pubkey, checked alongside the per-IP one
+    pub pulse_pubkey_limiter: Option<RateLimiter>,
+    pub tx_limiter: RateLimiter,
     pub query_limiter: RateLimiter,
     pub ws_broadcaster: Arc<WsBroadcaster>,
     pub event_log: EventLog,
     pub network: NetworkHandle,
+    /// `/health` fails without a chain store (see [`ApiConfig::require_storage`])
+    pub require_storage: bool,
+    pub started_at: Instant,
+    /// Runtime control of the heartbeat simulator (`/admin/simulate`)
+    pub simulation: SimulationControl,
+}
+
+/// API server configuration
+#[derive(Debug, Clone)]
+pub struct ApiConfig {
+    /// Assign/propagate an `X-Request-Id` and open a tracing span per request
+    pub request_ids: bool,
+    /// Per-IP burst threshold checked before any body parsing (None disables)
+    pub flood_guard: Option<RateLimitConfig>,
+    /// `/pulse` limit per IP
+    pub pulse_limit: RateLimitConfig,
+    /// `/pulse` limit per `device_pubkey`, checked after the per-IP one; whichever
+    /// runs out first rejects (None disables)
+    pub pulse_device_limit: Option<RateLimitConfig>,
+    /// `/tx` limit per IP
+    pub tx_limit: RateLimitConfig,
+    /// Events buffered per WebSocket client before a slow one starts skipping
+    pub ws_capacity: usize,
+    /// Most WebSocket clients connected at once; more get 503. 0 is unlimited.
+    pub max_ws_connections: usize,
+    /// Token `/ws` clients must present as `?token=` or `Authorization: Bearer`
+    /// (None leaves it open, like the other reads)
+    pub ws_token: Option<String>,
+    /// Bearer token required on write (`/pulse`, `/tx`) and `/admin` routes.
+    /// Without one writes are open and the admin routes aren't mounted.
+    pub api_token: Option<String>,
+    /// Browser origins allowed to call the API (with credentials); empty keeps
+    /// the permissive any-origin policy
+    pub cors_origins: Vec<HeaderValue>,
+    /// Report the node unhealthy when consensus has no chain store, i.e. it
+    /// fell back to in-memory mode instead of persisting
+    pub require_storage: bool,
+    /// Largest request body accepted; bigger ones get 413 before any parsing
+    pub max_body_bytes: usize,
+    /// Body limit of the `/admin` import routes, which take whole blocks and chains
+    pub max_admin_body_bytes: usize,
+    /// Mount `/admin/simulate`, which starts and stops the heartbeat simulator
+    /// (debug builds allow it by default, release builds only when asked)
+    pub allow_simulate: bool,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            request_ids: true,
+            flood_guard: Some(RateLimitConfig {
+                max_requests: 50,
+                window: Duration::from_secs(1),
+                ..Default::default()
+            }),
+            pulse_limit: RateLimitConfig {
+                max_requests: 30,
+                window: Duration::from_secs(60),
+                ..Default::default()
+            },
+            pulse_device_limit: None,
+            tx_limit: RateLimitConfig {
+                max_requests: 30,
+                window: Duration::from_secs(60),
+                ..Default::default()
+            },
+            ws_capacity: 256,
+            max_ws_connections: 1000,
+            ws_token: None,
+            api_token: None,
+            cors_origins: Vec::new(),
+            require_storage: false,
+            max_body_bytes: 64 * 1024,
+            max_admin_body_bytes: 64 * 1024 * 1024,
+            allow_simulate: cfg!(debug_assertions),
+        }
+    }
 }
 
 /// Node version info
@@ -51,6 +140,12 @@ pub struct PaginationParams {
     pub limit: Option<u64>,
 }
 
+/// Ranking for `/accounts`
+#[derive(Deserialize)]
+pub struct AccountSortParams {
+    pub sort: Option<AccountSort>,
+}
+
 /// API response wrapper
 #[derive(Serialize)]
 pub struct ApiResponse<T> {
@@ -74,118 +169,302 @@ impl ApiResponse<()> {
 }
 
 /// Create the API router
-pub fn create_router(state: AppState, network: NetworkHandle) -> (Router, Arc<WsBroadcaster>, EventLog) {
-    let ws_broadcaster = Arc::new(WsBroadcaster::new(256));
-    let event_log = EventLog::new();
+pub fn create_router(
+    state: AppState,
+    network: NetworkHandle,
+    config: &ApiConfig,
+    event_log: EventLog,
+    simulation: SimulationControl,
+) -> (Router, Arc<WsBroadcaster>) {
+    let ws_broadcaster = Arc::new(WsBroadcaster::new(config.ws_capacity.max(1)));
     
     let api_state = ApiState {
         consensus: state,
-        pulse_limiter: RateLimiter::new(RateLimitConfig {
-            max_requests: 30,
-            window: Duration::from_secs(60),
-        }),
+        pulse_limiter: RateLimiter::new(config.pulse_limit.clone()),
+        pulse_pubkey_limiter: config.pulse_device_limit.clone().map(RateLimiter::new),
+        tx_limiter: RateLimiter::new(config.tx_limit.clone()),
         query_limiter: RateLimiter::new(RateLimitConfig {
             max_requests: 120,
             window: Duration::from_secs(60),
+            ..Default::default()
         }),
         ws_broadcaster: ws_broadcaster.clone(),
         event_log: event_log.clone(),
         network,
+        require_storage: config.require_storage,
+        started_at: Instant::now(),
+        simulation,
     };
 
+    let flood_limiter = config.flood_guard.clone().map(RateLimiter::new);
+
     // Spawn rate limiter cleanup task
     let cleanup_state = api_state.clone();
+    let cleanup_flood = flood_limiter.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(300));
         loop {
             interval.tick().await;
             cleanup_state.pulse_limiter.cleanup().await;
+            if let Some(ref limiter) = cleanup_state.pulse_pubkey_limiter {
+                limiter.cleanup().await;
+            }
+            cleanup_state.tx_limiter.cleanup().await;
             cleanup_state.query_limiter.cleanup().await;
+            if let Some(ref limiter) = cleanup_flood {
+                limiter.cleanup().await;
+            }
         }
     });
 
-    let router = Router::new()
-        .route("/health", get(health_check))
+    // Write and admin routes; reads below stay open even when a token is set
+    let mut writes = body_limit(Router::new()
         .route("/pulse", post(submit_heartbeat))
-        .route("/tx", post(submit_transaction))
+        .route("/tx", post(submit_transaction)), config.max_body_bytes);
+    
+    if let Some(ref token) = config.api_token {
+        let token: Arc<str> = token.as_str().into();
+        let admin = body_limit(Router::new()
+            .route("/admin/import-block", post(admin_import_block))
+            .route("/admin/import-chain", post(admin_import_chain)), config.max_admin_body_bytes);
+        writes = writes.merge(admin);
+        if config.allow_simulate {
+            let simulate = Router::new().route("/admin/simulate", post(admin_simulate));
+            writes = writes.merge(body_limit(simulate, config.max_body_bytes));
+        }
+        writes = writes
+            .route_layer(axum::middleware::from_fn_with_state(token, auth::require_bearer_token));
+    }
+    
+    let reads = Router::new()
+        .route("/health", get(health_check))
+        .route("/tx/:tx_id", get(get_transaction))
         .route("/stats", get(get_stats))
+        .route("/weight/preview", post(preview_weight))
+        .route("/verify", post(verify_signature_request))
+        .route("/pool", get(get_pool))
+        .route("/audit/supply", get(get_supply_audit))
         .route("/balance/{pubkey}", get(get_balance))
         .route("/accounts", get(get_accounts))
         .route("/block/latest", get(get_latest_block))
         .route("/blocks", get(get_blocks))
+        .route("/headers", get(get_headers))
         .route("/block/:index", get(get_block_by_index))
+        .route("/block/:index/heartbeats", get(get_block_heartbeats))
+        .route("/participant/:pubkey/blocks", get(get_participant_blocks))
+        .route("/account/:pubkey/transactions", get(get_account_transactions))
         .route("/chain", get(get_chain_info))
+        .route("/chain/fingerprint", get(get_chain_fingerprint))
+        .route("/sync/status", get(get_sync_status))
         .route("/info", get(get_node_info))
         .route("/events", get(get_events))
         .route("/peers", get(get_peers))
-        .route("/ws", get(websocket::ws_handler).with_state(ws_broadcaster.clone()))
-        .layer(CorsLayer::permissive())
+        .route("/metrics", get(get_metrics))
+        .route("/snapshot", get(get_snapshot))
+        .route("/ws", get(websocket::ws_handler).with_state(websocket::WsState {
+            broadcaster: ws_broadcaster.clone(),
+            max_connections: config.max_ws_connections,
+            token: config.ws_token.as_deref().map(Arc::from),
+        }));
+    let mut router = body_limit(reads, config.max_body_bytes).merge(writes);
+    
+    if let Some(limiter) = flood_limiter {
+        router = router.layer(axum::middleware::from_fn_with_state(limiter, flood_guard::reject_floods));
+    }
+    
+    if config.request_ids {
+        router = router.layer(axum::middleware::from_fn(request_id::propagate_request_id));
+    }
+    
+    let router = router
+        .layer(cors_layer(&config.cors_origins))
         .with_state(api_state);
 
-    (router, ws_broadcaster, event_log)
+    (router, ws_broadcaster)
+}
+
+/// Reject bodies over `max_bytes` with 413, in place of axum's default limit
+fn body_limit<S: Clone + Send + Sync + 'static>(router: Router<S>, max_bytes: usize) -> Router<S> {
+    router
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(max_bytes))
+}
+
+/// CORS policy: the given origins only (credentials allowed), or permissive when there are none
+fn cors_layer(origins: &[HeaderValue]) -> CorsLayer {
+    if origins.is_empty() {
+        return CorsLayer::permissive();
+    }
+    let request_id = HeaderName::from_static(request_id::REQUEST_ID_HEADER);
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins.iter().cloned()))
+        .allow_credentials(true)
+        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION, request_id.clone()])
+        .expose_headers([request_id])
+}
+
+/// Parse a browser origin (`scheme://host[:port]`, no path) for the CORS allowlist
+pub fn parse_cors_origin(origin: &str) -> Result<HeaderValue, String> {
+    let invalid = || format!("invalid CORS origin '{}' (expected e.g. https://dash.example.com)", origin);
+    let authority = origin.strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"))
+        .ok_or_else(invalid)?;
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    };
+    let host_ok = !host.is_empty()
+        && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+    let port_ok = port.is_none_or(|p| p.parse::<u16>().is_ok());
+    if !host_ok || !port_ok {
+        return Err(invalid());
+    }
+    HeaderValue::from_str(origin).map_err(|_| invalid())
 }
 
-/// Health check endpoint
-async fn health_check() -> impl IntoResponse {
-    Json(ApiResponse::ok("Pulse node is alive"))
+/// `/health` fails once a ready pool has waited this many block intervals for a block
+const HEALTH_STALL_INTERVALS: u64 = 5;
+
+/// Readiness probe: 503 when the node runs without the storage it was meant
+/// to have, block production has stalled while enough heartbeats are waiting,
+/// or the P2P network task is gone
+async fn health_check(State(state): State<ApiState>) -> impl IntoResponse {
+    #[derive(Serialize)]
+    struct Health {
+        storage_ok: bool,
+        /// Since the tip block's timestamp
+        last_block_age_ms: u64,
+        peer_count: usize,
+    }
+
+    let pol = state.consensus.read().await;
+    let storage_ok = pol.store().is_some() || !state.require_storage;
+    let last_block_age_ms = pol.latest_block().map_or(0, |b| now_ms().saturating_sub(b.timestamp));
+    // Give a freshly started node the same grace before calling it stalled
+    let stall_after = Duration::from_millis(pol.config().block_interval_ms * HEALTH_STALL_INTERVALS);
+    let stalled = pol.heartbeat_pool_size() >= pol.config().n_threshold.max(1)
+        && Duration::from_millis(last_block_age_ms) > stall_after
+        && state.started_at.elapsed() > stall_after;
+    drop(pol);
+
+    let mut problems = Vec::new();
+    if !storage_ok {
+        problems.push("running without persistent storage");
+    }
+    if stalled {
+        problems.push("block production stalled");
+    }
+    if state.network.is_closed() {
+        problems.push("network task stopped");
+    }
+
+    let health = Health { storage_ok, last_block_age_ms, peer_count: state.network.info.peer_count() };
+    if problems.is_empty() {
+        (StatusCode::OK, Json(ApiResponse::ok(health)))
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse {
+            success: false,
+            data: Some(health),
+            error: Some(problems.join("; ")),
+        }))
+    }
 }
 
 /// Submit a heartbeat
 async fn submit_heartbeat(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<ApiState>,
-    Json(heartbeat): Json<Heartbeat>,
+    Json(mut heartbeat): Json<Heartbeat>,
 ) -> impl IntoResponse {
     let ip = addr.ip().to_string();
     if !state.pulse_limiter.check(&ip).await {
         return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
             "success": false,
-            "error": "Rate limit exceeded. Max 30 heartbeats per minute."
+            "error": "Rate limit exceeded"
         })));
     }
 
-    if heartbeat.device_pubkey.len() < 32 || heartbeat.device_pubkey.len() > 256 {
+    if heartbeat.resolve_pubkey().is_err() {
         return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
             "success": false,
-            "error": "Invalid public key length"
+            "error": "Invalid recoverable signature"
         })));
     }
 
-    if heartbeat.signature.is_empty() {
+    if heartbeat.device_pubkey.len() < 32 || heartbeat.device_pubkey.len() > 256 {
         return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
             "success": false,
-            "error": "Signature is required"
+            "error": "Invalid public key length"
         })));
     }
 
-    if heartbeat.heart_rate == 0 || heartbeat.heart_rate > 300 {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "success": false,
-            "error": "Heart rate out of range (1-300)"
-        })));
+    if let Some(ref limiter) = state.pulse_pubkey_limiter {
+        if !limiter.check(&heartbeat.device_pubkey).await {
+            return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+                "success": false,
+                "error": "Rate limit exceeded for this device"
+            })));
+        }
     }
 
-    if heartbeat.temperature < 25.0 || heartbeat.temperature > 45.0 {
+    if heartbeat.signature.is_empty() {
         return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
             "success": false,
-            "error": "Temperature out of range (25-45°C)"
+            "error": "Signature is required"
         })));
     }
 
-    // Forward to P2P network
-    let hb_for_p2p = heartbeat.clone();
-    let net = state.network.clone();
-    tokio::spawn(async move {
-        net.broadcast_heartbeat(&hb_for_p2p).await;
-    });
+    {
+        let pol = state.consensus.read().await;
+        let config = pol.config();
+        if !config.heart_rate_in_bounds(heartbeat.heart_rate) {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+                "success": false,
+                "error": format!("Heart rate out of range ({}-{})",
+                    config.min_heart_rate, config.max_heart_rate)
+            })));
+        }
+        if !config.temperature_in_bounds(heartbeat.temperature) {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+                "success": false,
+                "error": format!("Temperature out of range ({}-{}°C)",
+                    config.min_temperature, config.max_temperature)
+            })));
+        }
+    }
 
-    let mut pol = state.consensus.write().await;
+    let (timestamp, device_pubkey, heart_rate, weight) = (
+        heartbeat.timestamp,
+        heartbeat.device_pubkey[..16].to_string() + "...",
+        heartbeat.heart_rate,
+        heartbeat.weight(),
+    );
+    let hb_for_p2p = heartbeat.clone();
+    let result = state.consensus.write().await.receive_heartbeat(heartbeat);
     
-    match pol.receive_heartbeat(heartbeat) {
-        Ok(()) => (StatusCode::OK, Json(serde_json::json!({
-            "success": true,
-            "message": "Heartbeat accepted"
-        }))),
+    match result {
+        Ok(accepted) => {
+            // Forward to P2P network only once verified, so peers never score us down for it
+            let net = state.network.clone();
+            tokio::spawn(async move {
+                net.broadcast_heartbeat(&hb_for_p2p).await;
+            });
+            state.event_log.push(NodeEvent::HeartbeatReceived {
+                timestamp,
+                device_pubkey,
+                heart_rate,
+                weight,
+                confidence: Some(accepted.confidence),
+                hrv_sdnn: Some(accepted.hrv_sdnn),
+            }).await;
+            (StatusCode::OK, Json(serde_json::json!({
+                "success": true,
+                "message": "Heartbeat accepted",
+                "confidence": accepted.confidence,
+                "hrv_sdnn": accepted.hrv_sdnn
+            })))
+        }
         Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({
             "success": false,
             "error": e.to_string()
@@ -200,7 +479,7 @@ async fn submit_transaction(
     Json(tx): Json<Transaction>,
 ) -> impl IntoResponse {
     let ip = addr.ip().to_string();
-    if !state.pulse_limiter.check(&ip).await {
+    if !state.tx_limiter.check(&ip).await {
         return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
             "success": false,
             "error": "Rate limit exceeded"
@@ -228,12 +507,26 @@ async fn submit_transaction(
         })));
     }
 
-    let mut pol = state.consensus.write().await;
+    let event = NodeEvent::TransactionReceived {
+        timestamp: now_ms(),
+        tx_id: tx.tx_id.clone(),
+        sender: tx.sender_pubkey.clone(),
+        recipient: tx.recipient_pubkey.clone(),
+        amount: tx.amount,
+    };
+    let result = state.consensus.write().await.receive_transaction(tx);
     
-    match pol.receive_transaction(tx) {
-        Ok(()) => (StatusCode::OK, Json(serde_json::json!({
-            "success": true,
-            "message": "Transaction queued"
+    match result {
+        Ok(()) => {
+            state.event_log.push(event).await;
+            (StatusCode::OK, Json(serde_json::json!({
+                "success": true,
+                "message": "Transaction queued"
+            })))
+        }
+        Err(e @ ConsensusError::MempoolFull(_)) => (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({
+            "success": false,
+            "error": e.to_string()
         }))),
         Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({
             "success": false,
@@ -242,6 +535,43 @@ async fn submit_transaction(
     }
 }
 
+/// Look up a confirmed transaction by id
+async fn get_transaction(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+    Path(tx_id): Path<String>,
+) -> impl IntoResponse {
+    #[derive(Serialize)]
+    struct TransactionResponse {
+        transaction: Transaction,
+        block_index: u64,
+        /// Blocks on top of the including block (0 = in the tip)
+        confirmations: u64,
+    }
+
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    let pol = state.consensus.read().await;
+    match pol.find_transaction(&tx_id) {
+        Ok(Some((block_index, transaction))) => {
+            let confirmations = pol.chain_height().saturating_sub(block_index);
+            (StatusCode::OK, Json(ApiResponse::ok(TransactionResponse {
+                transaction,
+                block_index,
+                confirmations,
+            }))).into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::err("Transaction not found"))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse::<()>::err(e.to_string()))).into_response(),
+    }
+}
+
 /// Get network statistics
 async fn get_stats(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -259,6 +589,138 @@ async fn get_stats(
     Json(ApiResponse::ok(pol.get_stats())).into_response()
 }
 
+/// Body of a weight preview request
+#[derive(Deserialize)]
+pub struct WeightPreviewRequest {
+    pub heartbeat: Heartbeat,
+    /// Continuity factor in [0, 1]; values outside are clamped
+    pub continuity: f64,
+}
+
+/// Break a heartbeat's weight into its normalized components, using this node's
+/// coefficients. A pure calculation, so the heartbeat needn't be signed.
+async fn preview_weight(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+    Json(req): Json<WeightPreviewRequest>,
+) -> impl IntoResponse {
+    #[derive(Serialize)]
+    struct WeightPreview {
+        #[serde(flatten)]
+        breakdown: crate::types::WeightBreakdown,
+        alpha: f64,
+        beta: f64,
+        gamma: f64,
+    }
+
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    if !req.continuity.is_finite() {
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::err("Continuity must be a number"))).into_response();
+    }
+
+    let params = state.consensus.read().await.config().weight_params();
+    Json(ApiResponse::ok(WeightPreview {
+        breakdown: req.heartbeat.weight_breakdown(req.continuity, &params),
+        alpha: params.alpha,
+        beta: params.beta,
+        gamma: params.gamma,
+    })).into_response()
+}
+
+/// Longest message `/verify` checks; signable bytes of heartbeats and transactions are far smaller
+const MAX_VERIFY_MESSAGE_BYTES: usize = 16 * 1024;
+
+/// Body of a signature check request
+#[derive(Deserialize)]
+pub struct VerifyRequest {
+    pub pubkey: String,
+    /// The exact bytes that were signed, hex-encoded
+    pub message_hex: String,
+    pub signature: String,
+}
+
+/// Check a signature without submitting anything, for debugging client-side
+/// signing (e.g. of `signable_bytes`). Stateless.
+async fn verify_signature_request(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+    Json(req): Json<VerifyRequest>,
+) -> impl IntoResponse {
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    let is_hex = |s: &str| s.len().is_multiple_of(2) && s.chars().all(|c| c.is_ascii_hexdigit());
+    if req.pubkey.len() < 32 || req.pubkey.len() > 256 || !is_hex(&req.pubkey) {
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::err("Invalid public key format"))).into_response();
+    }
+    if req.message_hex.len() > MAX_VERIFY_MESSAGE_BYTES * 2 || !is_hex(&req.message_hex) {
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::err(format!(
+            "message_hex must be hex, at most {} bytes", MAX_VERIFY_MESSAGE_BYTES
+        )))).into_response();
+    }
+    if req.signature.is_empty() || req.signature.len() > 256 || !is_hex(&req.signature) {
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::err("Invalid signature format"))).into_response();
+    }
+
+    #[derive(Serialize)]
+    struct Verification {
+        valid: bool,
+    }
+
+    // Hex was checked above, so this only fails on keys or signatures that don't parse
+    let message = hex::decode(&req.message_hex).unwrap_or_default();
+    match verify_signature(&req.pubkey, &message, &req.signature) {
+        Ok(valid) => Json(ApiResponse::ok(Verification { valid })).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::err(e.to_string()))).into_response(),
+    }
+}
+
+/// Heartbeat and transaction pools (pubkeys shortened), for debugging block production
+async fn get_pool(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    let pol = state.consensus.read().await;
+    Json(ApiResponse::ok(pol.pool_snapshot())).into_response()
+}
+
+/// Supply audit: balances (+ staked + burned) against total minted
+async fn get_supply_audit(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    let pol = state.consensus.read().await;
+    Json(ApiResponse::ok(pol.supply_audit())).into_response()
+}
+
 /// Get account balance
 async fn get_balance(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -283,16 +745,19 @@ async fn get_balance(
     #[derive(Serialize)]
     struct BalanceResponse {
         pubkey: String,
-        balance: f64,
+        /// micro-PULSE
+        balance: Amount,
     }
     
     Json(ApiResponse::ok(BalanceResponse { pubkey, balance })).into_response()
 }
 
-/// Get all accounts
+/// Get accounts ranked by balance (or `?sort=total_earned|blocks_participated`), with pagination
 async fn get_accounts(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<ApiState>,
+    Query(params): Query<PaginationParams>,
+    Query(AccountSortParams { sort }): Query<AccountSortParams>,
 ) -> impl IntoResponse {
     let ip = addr.ip().to_string();
     if !state.query_limiter.check(&ip).await {
@@ -302,9 +767,29 @@ async fn get_accounts(
         }))).into_response();
     }
 
+    let limit = params.limit.unwrap_or(50).min(200);
+    let offset = params.offset.unwrap_or(0);
+    let sort = sort.unwrap_or_default();
+
     let pol = state.consensus.read().await;
-    let accounts: Vec<Account> = pol.get_accounts().values().cloned().collect();
-    Json(ApiResponse::ok(accounts)).into_response()
+    let (accounts, total) = pol.accounts_page(sort, offset as usize, limit as usize);
+
+    #[derive(Serialize)]
+    struct PaginatedAccounts {
+        accounts: Vec<Account>,
+        sort: AccountSort,
+        total: u64,
+        offset: u64,
+        limit: u64,
+    }
+
+    Json(ApiResponse::ok(PaginatedAccounts {
+        accounts,
+        sort,
+        total: total as u64,
+        offset,
+        limit,
+    })).into_response()
 }
 
 /// Get the latest block
@@ -376,11 +861,11 @@ async fn get_blocks(
     })).into_response()
 }
 
-/// Get block by index
-async fn get_block_by_index(
+/// Whether the node is caught up with its peers' reported tips; clients should
+/// treat balances and blocks as possibly stale while `syncing`
+async fn get_sync_status(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<ApiState>,
-    Path(index): Path<u64>,
 ) -> impl IntoResponse {
     let ip = addr.ip().to_string();
     if !state.query_limiter.check(&ip).await {
@@ -391,16 +876,15 @@ async fn get_block_by_index(
     }
 
     let pol = state.consensus.read().await;
-    match pol.get_block_by_index(index) {
-        Some(block) => (StatusCode::OK, Json(ApiResponse::ok(block))).into_response(),
-        None => (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::err("Block not found"))).into_response(),
-    }
+    Json(ApiResponse::ok(pol.sync_state())).into_response()
 }
 
-/// Get chain info
-async fn get_chain_info(
+/// Get compact block headers, including pruned blocks', for light clients.
+/// Paginated like `/blocks`: the latest page by default.
+async fn get_headers(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<ApiState>,
+    Query(params): Query<PaginationParams>,
 ) -> impl IntoResponse {
     let ip = addr.ip().to_string();
     if !state.query_limiter.check(&ip).await {
@@ -411,63 +895,344 @@ async fn get_chain_info(
     }
 
     let pol = state.consensus.read().await;
+    let total = pol.header_count() as u64;
     
-    #[derive(Serialize)]
-    struct ChainInfo {
-        height: u64,
-        latest_hash: String,
-        heartbeat_pool_size: usize,
-    }
-    
-    let info = ChainInfo {
-        height: pol.chain_height(),
-        latest_hash: pol.latest_block()
-            .map(|b| b.block_hash.clone())
-            .unwrap_or_default(),
-        heartbeat_pool_size: pol.heartbeat_pool_size(),
-    };
-    
-    Json(ApiResponse::ok(info)).into_response()
-}
+    let limit = params.limit.unwrap_or(50).min(200);
+    let offset = params.offset.unwrap_or(total.saturating_sub(limit));
+    let headers = pol.headers_page(offset as usize, limit as usize);
 
-/// Get node info
-async fn get_node_info(
-    State(state): State<ApiState>,
-) -> impl IntoResponse {
-    let pol = state.consensus.read().await;
-    
     #[derive(Serialize)]
-    struct NodeInfo {
-        version: String,
-        chain_height: u64,
-        active_accounts: usize,
-        heartbeat_pool_size: usize,
-        ws_clients: usize,
-        peer_id: String,
-        peer_count: usize,
+    struct PaginatedHeaders {
+        headers: Vec<crate::types::BlockHeader>,
+        total: u64,
+        offset: u64,
+        limit: u64,
     }
-    
-    Json(ApiResponse::ok(NodeInfo {
-        version: NODE_VERSION.to_string(),
-        chain_height: pol.chain_height(),
-        active_accounts: pol.get_accounts().len(),
-        heartbeat_pool_size: pol.heartbeat_pool_size(),
-        ws_clients: state.ws_broadcaster.subscriber_count(),
-        peer_id: state.network.info.peer_id.clone(),
-        peer_count: state.network.info.peer_count(),
+
+    Json(ApiResponse::ok(PaginatedHeaders {
+        headers,
+        total,
+        offset,
+        limit,
     })).into_response()
 }
 
-/// Get connected P2P peers (lock-free!)
-async fn get_peers(
+/// Get block by index
+async fn get_block_by_index(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<ApiState>,
+    Path(index): Path<u64>,
 ) -> impl IntoResponse {
-    #[derive(Serialize)]
-    struct PeerInfo {
-        peer_id: String,
-        peer_count: usize,
-        connected_peers: Vec<String>,
-    }
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    let pol = state.consensus.read().await;
+    match pol.get_block_by_index(index) {
+        Some(block) => (StatusCode::OK, Json(ApiResponse::ok(block))).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::err("Block not found"))).into_response(),
+    }
+}
+
+/// Query parameters for the block heartbeats endpoint
+#[derive(Deserialize)]
+pub struct HeartbeatBundleParams {
+    /// Re-verify each heartbeat signature on the node
+    pub verify: Option<bool>,
+}
+
+/// Export a block's heartbeats (with pubkeys and signatures) for independent verification
+async fn get_block_heartbeats(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+    Path(index): Path<u64>,
+    Query(params): Query<HeartbeatBundleParams>,
+) -> impl IntoResponse {
+    #[derive(Serialize)]
+    struct HeartbeatVerification {
+        pubkey: String,
+        valid: bool,
+    }
+
+    #[derive(Serialize)]
+    struct HeartbeatBundle {
+        index: u64,
+        block_hash: String,
+        heartbeats: Vec<Heartbeat>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        verification: Option<Vec<HeartbeatVerification>>,
+    }
+
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    let block = {
+        let pol = state.consensus.read().await;
+        match pol.get_block_by_index(index) {
+            Some(block) => block,
+            None => return (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::err("Block not found"))).into_response(),
+        }
+    };
+
+    // Signature checks run outside the consensus lock
+    let verification = params.verify.unwrap_or(false).then(|| {
+        crate::consensus::verify_block_heartbeats(&block)
+            .into_iter()
+            .map(|(pubkey, valid)| HeartbeatVerification { pubkey, valid })
+            .collect()
+    });
+
+    Json(ApiResponse::ok(HeartbeatBundle {
+        index: block.index,
+        block_hash: block.block_hash,
+        heartbeats: block.heartbeats,
+        verification,
+    })).into_response()
+}
+
+/// Get blocks a participant contributed heartbeats to, with pagination
+async fn get_participant_blocks(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+    Path(pubkey): Path<String>,
+    Query(params): Query<PaginationParams>,
+) -> impl IntoResponse {
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    if pubkey.len() < 32 || pubkey.len() > 256 || !pubkey.chars().all(|c| c.is_ascii_hexdigit()) {
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::err("Invalid public key format"))).into_response();
+    }
+
+    let limit = params.limit.unwrap_or(50).min(200);
+    let offset = params.offset.unwrap_or(0);
+
+    let pol = state.consensus.read().await;
+    let (blocks, total) = pol.get_participant_blocks(&pubkey, offset as usize, limit as usize);
+
+    #[derive(Serialize)]
+    struct ParticipantBlocks {
+        pubkey: String,
+        blocks: Vec<crate::types::PulseBlock>,
+        total: u64,
+        offset: u64,
+        limit: u64,
+    }
+
+    Json(ApiResponse::ok(ParticipantBlocks {
+        pubkey,
+        blocks,
+        total: total as u64,
+        offset,
+        limit,
+    })).into_response()
+}
+
+/// Get an account's sent and received transactions, newest first
+async fn get_account_transactions(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+    Path(pubkey): Path<String>,
+    Query(params): Query<PaginationParams>,
+) -> impl IntoResponse {
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    if pubkey.len() < 32 || pubkey.len() > 256 || !pubkey.chars().all(|c| c.is_ascii_hexdigit()) {
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::err("Invalid public key format"))).into_response();
+    }
+
+    let limit = params.limit.unwrap_or(50).min(200);
+    let offset = params.offset.unwrap_or(0);
+
+    let pol = state.consensus.read().await;
+    let (history, total) = match pol.account_transactions(&pubkey, offset as usize, limit as usize) {
+        Ok(result) => result,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse::<()>::err(e.to_string()))).into_response(),
+    };
+
+    #[derive(Serialize)]
+    struct AccountTransaction {
+        block_index: u64,
+        /// "sent" or "received" from this account's point of view
+        direction: &'static str,
+        transaction: Transaction,
+    }
+
+    #[derive(Serialize)]
+    struct AccountTransactions {
+        pubkey: String,
+        transactions: Vec<AccountTransaction>,
+        total: u64,
+        offset: u64,
+        limit: u64,
+    }
+
+    let transactions = history.into_iter()
+        .map(|(block_index, transaction)| AccountTransaction {
+            block_index,
+            direction: if transaction.sender_pubkey == pubkey { "sent" } else { "received" },
+            transaction,
+        })
+        .collect();
+
+    Json(ApiResponse::ok(AccountTransactions {
+        pubkey,
+        transactions,
+        total: total as u64,
+        offset,
+        limit,
+    })).into_response()
+}
+
+/// Get chain info
+async fn get_chain_info(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    let pol = state.consensus.read().await;
+    
+    #[derive(Serialize)]
+    struct ChainInfo {
+        height: u64,
+        latest_hash: String,
+        heartbeat_pool_size: usize,
+    }
+    
+    let info = ChainInfo {
+        height: pol.chain_height(),
+        latest_hash: pol.latest_block()
+            .map(|b| b.block_hash.clone())
+            .unwrap_or_default(),
+        heartbeat_pool_size: pol.heartbeat_pool_size(),
+    };
+    
+    Json(ApiResponse::ok(info)).into_response()
+}
+
+/// Query parameters for the chain fingerprint endpoint
+#[derive(Deserialize)]
+pub struct FingerprintParams {
+    pub interval: Option<u64>,
+}
+
+/// Get sampled (height, hash) pairs for cheap cross-node chain comparison
+async fn get_chain_fingerprint(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+    Query(params): Query<FingerprintParams>,
+) -> impl IntoResponse {
+    /// Upper bound on samples per response
+    const MAX_SAMPLES: u64 = 1000;
+
+    #[derive(Serialize)]
+    struct FingerprintSample {
+        height: u64,
+        hash: String,
+    }
+
+    #[derive(Serialize)]
+    struct ChainFingerprint {
+        interval: u64,
+        samples: Vec<FingerprintSample>,
+    }
+
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    let pol = state.consensus.read().await;
+    // Widen the interval on long chains so the response stays bounded
+    let min_interval = pol.chain_height().div_ceil(MAX_SAMPLES).max(1);
+    let interval = params.interval.unwrap_or(100).max(min_interval);
+    let samples = pol.chain_fingerprint(interval)
+        .into_iter()
+        .map(|(height, hash)| FingerprintSample { height, hash })
+        .collect();
+
+    Json(ApiResponse::ok(ChainFingerprint { interval, samples })).into_response()
+}
+
+/// Get node info
+async fn get_node_info(
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    let pol = state.consensus.read().await;
+    
+    #[derive(Serialize)]
+    struct NodeInfo {
+        version: String,
+        /// Empty on the original network
+        chain_id: String,
+        chain_height: u64,
+        active_accounts: usize,
+        heartbeat_pool_size: usize,
+        /// Devices with continuity/biometric state held in memory
+        tracked_devices: usize,
+        ws_clients: usize,
+        peer_id: String,
+        peer_count: usize,
+    }
+    
+    Json(ApiResponse::ok(NodeInfo {
+        version: NODE_VERSION.to_string(),
+        chain_id: pol.config().chain_id.clone(),
+        chain_height: pol.chain_height(),
+        active_accounts: pol.get_accounts().len(),
+        heartbeat_pool_size: pol.heartbeat_pool_size(),
+        tracked_devices: pol.tracked_devices(),
+        ws_clients: state.ws_broadcaster.connection_count(),
+        peer_id: state.network.info.peer_id.clone(),
+        peer_count: state.network.info.peer_count(),
+    })).into_response()
+}
+
+/// Get connected P2P peers (lock-free!)
+async fn get_peers(
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    #[derive(Serialize)]
+    struct PeerInfo {
+        peer_id: String,
+        peer_count: usize,
+        connected_peers: Vec<String>,
+        /// Misbehaviour score per connected peer (0 is neutral)
+        peer_scores: std::collections::BTreeMap<String, i32>,
+        /// Direction, connection time and last activity per connected peer
+        peers: Vec<crate::network::PeerDetail>,
+        publish: crate::network::PublishStats,
+    }
     
     let peers = state.network.info.connected_peers().await;
     
@@ -475,9 +1240,34 @@ async fn get_peers(
         peer_id: state.network.info.peer_id.clone(),
         peer_count: peers.len(),
         connected_peers: peers,
+        peer_scores: state.network.info.peer_scores().await,
+        peers: state.network.info.peer_details().await,
+        publish: state.network.publish_stats(),
     })).into_response()
 }
 
+/// Prometheus metrics (not rate limited, so scrapers never see gaps)
+async fn get_metrics(
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    let pol = state.consensus.read().await;
+    let gauges = NodeGauges {
+        chain_height: pol.chain_height(),
+        heartbeat_pool_size: pol.heartbeat_pool_size(),
+        tx_pool_size: pol.tx_pool_size(),
+        active_accounts: pol.get_accounts().len(),
+        total_minted: to_pulse(pol.total_minted()),
+        peer_count: state.network.info.peer_count(),
+        ws_clients: state.ws_broadcaster.connection_count(),
+    };
+    let body = metrics::render(&gauges, pol.metrics());
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        body,
+    ).into_response()
+}
+
 /// Query parameters for events endpoint
 #[derive(Deserialize)]
 pub struct EventParams {
@@ -485,6 +1275,31 @@ pub struct EventParams {
     pub since: Option<u64>,
 }
 
+/// Download a chain snapshot for bootstrapping another node (`--bootstrap-url`).
+/// Encoded under the read lock so it's a consistent point in time, then sent.
+async fn get_snapshot(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ApiState>,
+) -> impl IntoResponse {
+    let ip = addr.ip().to_string();
+    if !state.query_limiter.check(&ip).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({
+            "success": false,
+            "error": "Rate limit exceeded"
+        }))).into_response();
+    }
+
+    let mut body = Vec::new();
+    let result = state.consensus.read().await.export_snapshot(&mut body);
+    match result {
+        Ok(summary) => {
+            info!("📦 Snapshot served to {} ({} blocks, {} bytes)", ip, summary.blocks, body.len());
+            ([(header::CONTENT_TYPE, "application/octet-stream")], body).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiResponse::<()>::err(e.to_string()))).into_response(),
+    }
+}
+
 /// Get recent events
 async fn get_events(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -508,31 +1323,791 @@ async fn get_events(
     Json(ApiResponse::ok(events)).into_response()
 }
 
+/// Apply a block by hand (e.g. one copied from a healthy node) through the
+/// same validation as a gossiped block
+async fn admin_import_block(
+    State(state): State<ApiState>,
+    Json(block): Json<PulseBlock>,
+) -> impl IntoResponse {
+    let index = block.index;
+    let mut pol = state.consensus.write().await;
+    if let Some(response) = oversized_import(std::slice::from_ref(&block), pol.config()) {
+        return response;
+    }
+    match pol.receive_block(block.clone()) {
+        Ok(()) => {
+            info!("🛠️ Block #{} imported via admin API", index);
+            state.ws_broadcaster.broadcast(WsEvent::NewBlock { block });
+            state.ws_broadcaster.broadcast(WsEvent::Stats { stats: pol.get_stats() });
+            (StatusCode::OK, Json(serde_json::json!({
+                "success": true,
+                "message": "Block imported",
+                "height": pol.chain_height()
+            })))
+        }
+        Err(e) => import_rejected(index, &e),
+    }
+}
+
+/// Offer a whole chain by hand; adopted only if it's valid and beats ours under fork choice
+async fn admin_import_chain(
+    State(state): State<ApiState>,
+    Json(blocks): Json<Vec<PulseBlock>>,
+) -> impl IntoResponse {
+    let mut pol = state.consensus.write().await;
+    if let Some(response) = oversized_import(&blocks, pol.config()) {
+        return response;
+    }
+    match pol.import_chain(blocks) {
+        Ok(adopted) => {
+            if adopted {
+                info!("🛠️ Chain replaced via admin API (height {})", pol.chain_height());
+                state.ws_broadcaster.broadcast(WsEvent::Stats { stats: pol.get_stats() });
+            }
+            (StatusCode::OK, Json(serde_json::json!({
+                "success": true,
+                "message": if adopted { "Chain replaced" } else { "Chain doesn't outweigh ours; kept local chain" },
+                "adopted": adopted,
+                "height": pol.chain_height()
+            })))
+        }
+        Err(e) => import_rejected(e.index, &e.source),
+    }
+}
+
+/// `/admin/simulate` body; omitted settings take the simulator defaults
+#[derive(Debug, Deserialize)]
+struct SimulateRequest {
+    /// Start (or restart with these settings) when true, stop when false
+    enabled: bool,
+    #[serde(default)]
+    devices: Option<usize>,
+    #[serde(default)]
+    interval_ms: Option<u64>,
+}
+
+/// Most devices one `/admin/simulate` call may simulate
+const MAX_SIMULATED_DEVICES: usize = 1000;
+/// Shortest heartbeat round interval `/admin/simulate` accepts
+const MIN_SIMULATION_INTERVAL_MS: u64 = 100;
+
+/// Start or stop the heartbeat simulator; answers with the simulated device pubkeys
+async fn admin_simulate(
+    State(state): State<ApiState>,
+    Json(req): Json<SimulateRequest>,
+) -> impl IntoResponse {
+    if !req.enabled {
+        let was_running = state.simulation.stop().await;
+        return (StatusCode::OK, Json(serde_json::json!({
+            "success": true,
+            "running": false,
+            "stopped": was_running,
+            "devices": Vec::<String>::new()
+        })));
+    }
+
+    let defaults = SimulationConfig::default();
+    let config = SimulationConfig {
+        devices: req.devices.unwrap_or(defaults.devices),
+        interval_ms: req.interval_ms.unwrap_or(defaults.interval_ms),
+        ..defaults
+    };
+    if config.devices == 0 || config.devices > MAX_SIMULATED_DEVICES {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "success": false,
+            "error": format!("devices must be between 1 and {}", MAX_SIMULATED_DEVICES)
+        })));
+    }
+    if config.interval_ms < MIN_SIMULATION_INTERVAL_MS {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "success": false,
+            "error": format!("interval_ms must be at least {}", MIN_SIMULATION_INTERVAL_MS)
+        })));
+    }
+
+    let devices = state.simulation.start(config).await;
+    info!("🛠️ Simulation started via admin API ({} devices)", devices.len());
+    (StatusCode::OK, Json(serde_json::json!({
+        "success": true,
+        "running": true,
+        "devices": devices
+    })))
+}
+
+/// Most blocks accepted by one `/admin/import-chain` call
+const MAX_IMPORT_BLOCKS: usize = 100_000;
+
+/// 413 for an admin import with more blocks, or blocks with more heartbeats or
+/// transactions, than consensus would ever produce or pool
+fn oversized_import(
+    blocks: &[PulseBlock],
+    config: &crate::consensus::ConsensusConfig,
+) -> Option<(StatusCode, Json<serde_json::Value>)> {
+    let error = if blocks.len() > MAX_IMPORT_BLOCKS {
+        format!("Too many blocks ({} > {})", blocks.len(), MAX_IMPORT_BLOCKS)
+    } else if let Some(block) = blocks.iter().find(|b| b.heartbeats.len() > config.max_heartbeats_per_block) {
+        format!("Block #{}: too many heartbeats ({} > {})",
+            block.index, block.heartbeats.len(), config.max_heartbeats_per_block)
+    } else if let Some(block) = blocks.iter().find(|b| b.transactions.len() > config.max_tx_pool) {
+        format!("Block #{}: too many transactions ({} > {})",
+            block.index, block.transactions.len(), config.max_tx_pool)
+    } else {
+        return None;
+    };
+    Some((StatusCode::PAYLOAD_TOO_LARGE, Json(serde_json::json!({
+        "success": false,
+        "error": error
+    }))))
+}
+
+/// Error body for a rejected admin import, naming the block at fault
+fn import_rejected(index: u64, e: &ConsensusError) -> (StatusCode, Json<serde_json::Value>) {
+    let status = match e {
+        ConsensusError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    (status, Json(serde_json::json!({
+        "success": false,
+        "error": format!("Block #{}: {}", index, e),
+        "block_index": index,
+        "kind": e.kind()
+    })))
+}
+
 /// Return type for start_server
 pub struct ServerHandles {
     pub broadcaster: Arc<WsBroadcaster>,
     pub event_log: EventLog,
+    /// The simulator `/admin/simulate` controls
+    pub simulation: SimulationControl,
+    /// Address the server is bound to (resolves port 0)
+    pub local_addr: SocketAddr,
+    /// Server task; finishes once `shutdown` resolves and open requests drain
+    pub server: tokio::task::JoinHandle<()>,
 }
 
-/// Start the API server
+/// Start the API server. It stops accepting connections when `shutdown` resolves.
 pub async fn start_server(
     state: AppState,
     addr: &str,
     network: NetworkHandle,
+    config: &ApiConfig,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
 ) -> anyhow::Result<ServerHandles> {
-    let (router, broadcaster, event_log) = create_router(state, network);
+    // The activity feed is persisted alongside the chain, when there is one
+    let event_log = match state.read().await.store() {
+        Some(store) => EventLog::with_storage(store),
+        None => EventLog::new(),
+    };
+    let simulation = SimulationControl::new(state.clone());
+    let (router, broadcaster) = create_router(state, network, config, event_log.clone(), simulation.clone());
     let listener = tokio::net::TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
     
-    info!("🌐 API server listening on {}", addr);
-    info!("🔌 WebSocket endpoint: ws://{}/ws", addr);
+    info!("🌐 API server listening on {}", local_addr);
+    info!("🔌 WebSocket endpoint: ws://{}/ws", local_addr);
     
     let bc = broadcaster.clone();
     let el = event_log.clone();
-    tokio::spawn(async move {
+    let server = tokio::spawn(async move {
         axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(shutdown)
             .await
             .unwrap();
     });
     
-    Ok(ServerHandles { broadcaster: bc, event_log: el })
+    Ok(ServerHandles { broadcaster: bc, event_log: el, simulation, local_addr, server })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::ConsensusConfig;
+    use crate::crypto::Keypair;
+    use crate::network::NetworkConfig;
+    use crate::types::Motion;
+
+    fn heartbeat(kp: &Keypair, offset_ms: u64) -> Heartbeat {
+        let mut hb = Heartbeat {
+            timestamp: events::now_ms() + offset_ms,
+            heart_rate: 72,
+            motion: Motion { x: 0.1, y: 0.1, z: 0.05 },
+            temperature: 36.7,
+            device_pubkey: kp.public_key_hex(),
+            signature: String::new(),
+            signature_version: 0,
+            recoverable: false,
+            metadata: Default::default(),
+        };
+        hb.signature = kp.sign(&hb.signable_bytes());
+        hb
+    }
+
+    #[tokio::test]
+    async fn test_pulse_limited_per_device_pubkey() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let config = ApiConfig {
+            pulse_device_limit: Some(RateLimitConfig {
+                max_requests: 2,
+                window: Duration::from_secs(60),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let server = start_server(state, "127.0.0.1:0", network, &config, std::future::pending()).await.unwrap();
+        let url = format!("http://{}/pulse", server.local_addr);
+        let client = reqwest::Client::new();
+        let pulse = |hb: Heartbeat| {
+            let req = client.post(&url).json(&hb);
+            async move { req.send().await.unwrap().status() }
+        };
+
+        let (alice, bob) = (Keypair::generate(), Keypair::generate());
+        for i in 0..2 {
+            assert_ne!(pulse(heartbeat(&alice, i)).await, StatusCode::TOO_MANY_REQUESTS);
+        }
+        assert_eq!(pulse(heartbeat(&alice, 2)).await, StatusCode::TOO_MANY_REQUESTS);
+
+        // Same IP, different device: its own budget
+        for i in 0..2 {
+            assert_ne!(pulse(heartbeat(&bob, i)).await, StatusCode::TOO_MANY_REQUESTS);
+        }
+        assert_eq!(pulse(heartbeat(&bob, 2)).await, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_pulse_reports_signal_quality() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let server = start_server(state, "127.0.0.1:0", network, &ApiConfig::default(), std::future::pending())
+            .await.unwrap();
+
+        let body: serde_json::Value = reqwest::Client::new()
+            .post(format!("http://{}/pulse", server.local_addr))
+            .json(&heartbeat(&Keypair::generate(), 0))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(body["success"], true);
+        assert!(body["confidence"].as_f64().unwrap() > 0.9);
+        assert_eq!(body["hrv_sdnn"].as_f64(), Some(0.0));
+
+        let events = server.event_log.latest(10).await;
+        let logged = serde_json::to_value(&events[0]).unwrap();
+        assert_eq!(logged["type"], "heartbeat_received");
+        assert_eq!(logged["confidence"], body["confidence"]);
+    }
+
+    #[tokio::test]
+    async fn test_only_accepted_heartbeats_forwarded_to_peers() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, mut commands) = NetworkHandle::detached(NetworkConfig::default());
+        let server = start_server(state, "127.0.0.1:0", network, &ApiConfig::default(), std::future::pending())
+            .await.unwrap();
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/pulse", server.local_addr);
+
+        // Well-formed, but signed by another key: consensus rejects it
+        let mut forged = heartbeat(&Keypair::generate(), 0);
+        forged.signature = Keypair::generate().sign(&forged.signable_bytes());
+        let status = client.post(&url).json(&forged).send().await.unwrap().status();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let kp = Keypair::generate();
+        let status = client.post(&url).json(&heartbeat(&kp, 0)).send().await.unwrap().status();
+        assert_eq!(status, StatusCode::OK);
+
+        // Only the accepted one reaches the network
+        match tokio::time::timeout(Duration::from_secs(5), commands.recv()).await {
+            Ok(Some(crate::network::NetworkCommand::BroadcastHeartbeat(hb))) => {
+                assert_eq!(hb.device_pubkey, kp.public_key_hex());
+            }
+            other => panic!("expected the accepted heartbeat to be broadcast, got {:?}", other),
+        }
+        assert!(commands.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pool_shows_pending_heartbeats_without_full_pubkeys() {
+        let config = ConsensusConfig { n_threshold: 3, ..Default::default() };
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(config)));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let server = start_server(state, "127.0.0.1:0", network, &ApiConfig::default(), std::future::pending())
+            .await.unwrap();
+        let client = reqwest::Client::new();
+
+        let kp = Keypair::generate();
+        let status = client.post(format!("http://{}/pulse", server.local_addr))
+            .json(&heartbeat(&kp, 0))
+            .send().await.unwrap()
+            .status();
+        assert_eq!(status, StatusCode::OK);
+
+        let response = client.get(format!("http://{}/pool", server.local_addr)).send().await.unwrap();
+        let text = response.text().await.unwrap();
+        assert!(!text.contains(&kp.public_key_hex()), "full pubkey exposed: {}", text);
+
+        let body: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let pool = &body["data"];
+        assert_eq!(pool["n_threshold"], 3);
+        assert_eq!(pool["heartbeats_needed"], 2);
+        let heartbeats = pool["heartbeats"].as_array().unwrap();
+        assert_eq!(heartbeats.len(), 1);
+        assert_eq!(heartbeats[0]["pubkey_prefix"], format!("{}...", &kp.public_key_hex()[..16]));
+        assert_eq!(heartbeats[0]["heart_rate"], 72);
+        assert!(heartbeats[0]["weight"].as_f64().unwrap() > 0.0);
+        assert_eq!(pool["transactions"].as_array().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_admin_import_requires_token_and_names_failing_block() {
+        let kp = Keypair::generate();
+        let mut source = ProofOfLife::new(ConsensusConfig::default());
+        for i in 0..3 {
+            let mut hb = heartbeat(&kp, i);
+            hb.heart_rate += i as u16;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            source.receive_heartbeat(hb).unwrap();
+            source.try_create_block().unwrap().unwrap();
+        }
+        let chain: Vec<PulseBlock> = (0..=3).map(|h| source.get_block_by_index(h).unwrap()).collect();
+
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let config = ApiConfig { api_token: Some("s3cret".to_string()), ..Default::default() };
+        let server = start_server(state.clone(), "127.0.0.1:0", network, &config, std::future::pending())
+            .await.unwrap();
+        let client = reqwest::Client::new();
+        let block_url = format!("http://{}/admin/import-block", server.local_addr);
+        let chain_url = format!("http://{}/admin/import-chain", server.local_addr);
+
+        let status = client.post(&block_url).json(&chain[1]).send().await.unwrap().status();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let status = client.post(&block_url).bearer_auth("wrong").json(&chain[1]).send().await.unwrap().status();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // Block #2 doesn't extend our genesis-only chain
+        let resp = client.post(&block_url).bearer_auth("s3cret").json(&chain[2]).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["block_index"], 2);
+        assert_eq!(body["kind"], "invalid_previous_hash");
+
+        let resp = client.post(&block_url).bearer_auth("s3cret").json(&chain[1]).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(state.read().await.chain_height(), 1);
+
+        let mut broken = chain.clone();
+        broken.remove(2);
+        let body: serde_json::Value = client.post(&chain_url).bearer_auth("s3cret").json(&broken)
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["block_index"], 3);
+
+        let body: serde_json::Value = client.post(&chain_url).bearer_auth("s3cret").json(&chain)
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(body["adopted"], true);
+        assert_eq!(state.read().await.chain_height(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_absent_without_token() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let server = start_server(state, "127.0.0.1:0", network, &ApiConfig::default(), std::future::pending())
+            .await.unwrap();
+        let status = reqwest::Client::new()
+            .post(format!("http://{}/admin/import-chain", server.local_addr))
+            .json(&serde_json::json!([]))
+            .send().await.unwrap()
+            .status();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_admin_simulate_starts_and_stops_simulator() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let config = ApiConfig { api_token: Some("s3cret".to_string()), allow_simulate: true, ..Default::default() };
+        let server = start_server(state.clone(), "127.0.0.1:0", network, &config, std::future::pending()).await.unwrap();
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/admin/simulate", server.local_addr);
+        let start = serde_json::json!({ "enabled": true, "devices": 2, "interval_ms": 100 });
+
+        let status = client.post(&url).json(&start).send().await.unwrap().status();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let status = client.post(&url).bearer_auth("s3cret")
+            .json(&serde_json::json!({ "enabled": true, "devices": 0 }))
+            .send().await.unwrap().status();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let body: serde_json::Value = client.post(&url).bearer_auth("s3cret").json(&start)
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(body["running"], true);
+        let devices: Vec<String> = serde_json::from_value(body["devices"].clone()).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(server.simulation.devices().await, devices);
+
+        // The simulated devices' heartbeats reach the pool
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while state.read().await.heartbeat_pool_size() == 0 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }).await.expect("simulator never submitted a heartbeat");
+
+        let body: serde_json::Value = client.post(&url).bearer_auth("s3cret")
+            .json(&serde_json::json!({ "enabled": false }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(body["stopped"], true);
+        assert!(server.simulation.devices().await.is_empty());
+
+        // Not mounted unless allowed
+        let config = ApiConfig { allow_simulate: false, ..config };
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let server = start_server(state, "127.0.0.1:0", network, &config, std::future::pending()).await.unwrap();
+        let status = client.post(format!("http://{}/admin/simulate", server.local_addr)).bearer_auth("s3cret")
+            .json(&start).send().await.unwrap().status();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_token_and_connection_limit() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let config = ApiConfig { max_ws_connections: 2, ws_token: Some("w5".to_string()), ..Default::default() };
+        let server = start_server(state, "127.0.0.1:0", network, &config, std::future::pending()).await.unwrap();
+        let client = reqwest::Client::new();
+        let upgrade = |query: &str| client.get(format!("http://{}/ws{}", server.local_addr, query))
+            .header(header::CONNECTION, "Upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==");
+
+        let resp = upgrade("").send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = upgrade("?token=wrong").send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Two clients fit (token as query param or header); the third is refused
+        let first = upgrade("?token=w5").send().await.unwrap();
+        assert_eq!(first.status(), StatusCode::SWITCHING_PROTOCOLS);
+        let second = upgrade("").bearer_auth("w5").send().await.unwrap();
+        assert_eq!(second.status(), StatusCode::SWITCHING_PROTOCOLS);
+        let resp = upgrade("?token=w5").send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // A client leaving frees its slot
+        drop(first);
+        let admitted = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let resp = upgrade("?token=w5").send().await.unwrap();
+                if resp.status() == StatusCode::SWITCHING_PROTOCOLS {
+                    return resp;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }).await;
+        assert!(admitted.is_ok(), "slot never released");
+        drop(second);
+    }
+
+    #[tokio::test]
+    async fn test_api_token_guards_writes_only() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let config = ApiConfig { api_token: Some("s3cret".to_string()), ..Default::default() };
+        let server = start_server(state, "127.0.0.1:0", network, &config, std::future::pending()).await.unwrap();
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/pulse", server.local_addr);
+        let kp = Keypair::generate();
+
+        let resp = client.post(&url).json(&heartbeat(&kp, 0)).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"], "Missing or invalid API token");
+
+        let status = client.post(&url).bearer_auth("wrong").json(&heartbeat(&kp, 0))
+            .send().await.unwrap().status();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let status = client.post(format!("http://{}/tx", server.local_addr)).json(&serde_json::json!({}))
+            .send().await.unwrap().status();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let status = client.post(&url).bearer_auth("s3cret").json(&heartbeat(&kp, 0))
+            .send().await.unwrap().status();
+        assert_eq!(status, StatusCode::OK);
+
+        // Reads stay open
+        let status = client.get(format!("http://{}/stats", server.local_addr)).send().await.unwrap().status();
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_parse_cors_origin() {
+        assert!(parse_cors_origin("https://dash.example.com").is_ok());
+        assert!(parse_cors_origin("http://localhost:3000").is_ok());
+        assert!(parse_cors_origin("dash.example.com").is_err());
+        assert!(parse_cors_origin("https://dash.example.com/").is_err());
+        assert!(parse_cors_origin("https://dash.example.com/app").is_err());
+        assert!(parse_cors_origin("http://localhost:http").is_err());
+        assert!(parse_cors_origin("https://").is_err());
+        assert!(parse_cors_origin("*").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cors_allowlist() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let config = ApiConfig {
+            cors_origins: vec![parse_cors_origin("https://dash.example.com").unwrap()],
+            ..Default::default()
+        };
+        let server = start_server(state, "127.0.0.1:0", network, &config, std::future::pending()).await.unwrap();
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/stats", server.local_addr);
+
+        let resp = client.get(&url).header(header::ORIGIN, "https://dash.example.com").send().await.unwrap();
+        assert_eq!(resp.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://dash.example.com");
+        assert_eq!(resp.headers()[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+
+        let resp = client.get(&url).header(header::ORIGIN, "https://evil.example.com").send().await.unwrap();
+        assert!(resp.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_weight_preview_matches_consensus_weight() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let server = start_server(state, "127.0.0.1:0", network, &ApiConfig::default(), std::future::pending())
+            .await.unwrap();
+
+        let mut hb = heartbeat(&Keypair::generate(), 0);
+        hb.signature = String::new();
+        let body: serde_json::Value = reqwest::Client::new()
+            .post(format!("http://{}/weight/preview", server.local_addr))
+            .json(&serde_json::json!({ "heartbeat": hb, "continuity": 0.5 }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        let preview = &body["data"];
+        assert_eq!(preview["hr_norm"].as_f64(), Some(Heartbeat::normalize_heart_rate(72)));
+        assert_eq!(preview["motion_norm"].as_f64(), Some(Heartbeat::normalize_motion(hb.motion.magnitude())));
+        assert_eq!(preview["continuity"].as_f64(), Some(0.5));
+        let params = ConsensusConfig::default().weight_params();
+        assert_eq!(preview["weight"].as_f64(), Some(hb.weight_with_params(0.5, &params)));
+        assert_eq!(preview["alpha"].as_f64(), Some(params.alpha));
+    }
+
+    #[tokio::test]
+    async fn test_accounts_paginated_and_sorted() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kps: Vec<Keypair> = (0..3).map(|_| Keypair::generate()).collect();
+        for (i, kp) in kps.iter().enumerate() {
+            // Device i takes part in i + 1 blocks
+            for j in 0..=i as u64 {
+                let mut hb = heartbeat(kp, j);
+                hb.heart_rate += j as u16;
+                hb.signature = kp.sign(&hb.signable_bytes());
+                pol.receive_heartbeat(hb).unwrap();
+                pol.try_create_block().unwrap().unwrap();
+            }
+        }
+        let state: AppState = Arc::new(RwLock::new(pol));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let server = start_server(state, "127.0.0.1:0", network, &ApiConfig::default(), std::future::pending())
+            .await.unwrap();
+        let client = reqwest::Client::new();
+        let get = |query: &str| {
+            let req = client.get(format!("http://{}/accounts{}", server.local_addr, query));
+            async move { req.send().await.unwrap() }
+        };
+
+        let body: serde_json::Value = get("?sort=blocks_participated&limit=2").await.json().await.unwrap();
+        let page = &body["data"];
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["sort"], "blocks_participated");
+        let accounts = page["accounts"].as_array().unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0]["pubkey"], kps[2].public_key_hex());
+        assert_eq!(accounts[1]["pubkey"], kps[1].public_key_hex());
+
+        let body: serde_json::Value = get("?offset=2").await.json().await.unwrap();
+        assert_eq!(body["data"]["sort"], "balance");
+        assert_eq!(body["data"]["accounts"].as_array().unwrap().len(), 1);
+
+        assert_eq!(get("?sort=nonce").await.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_headers_follow_the_chain() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kp = Keypair::generate();
+        for i in 0..3 {
+            let mut hb = heartbeat(&kp, i);
+            hb.heart_rate += i as u16;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+            pol.try_create_block().unwrap().unwrap();
+        }
+        let tip = pol.latest_block().unwrap().clone();
+        let state: AppState = Arc::new(RwLock::new(pol));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let server = start_server(state, "127.0.0.1:0", network, &ApiConfig::default(), std::future::pending())
+            .await.unwrap();
+        let get = |query: &str| reqwest::get(format!("http://{}/headers{}", server.local_addr, query));
+
+        // Latest page by default
+        let body: serde_json::Value = get("?limit=2").await.unwrap().json().await.unwrap();
+        let page = &body["data"];
+        assert_eq!(page["total"], 4);
+        assert_eq!(page["offset"], 2);
+        let headers = page["headers"].as_array().unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers[1]["block_hash"], tip.block_hash);
+        assert_eq!(headers[1]["previous_hash"], headers[0]["block_hash"]);
+        assert_eq!(headers[1]["heartbeats_root"], tip.heartbeats_root);
+        assert!(headers[1].get("heartbeats").is_none());
+
+        let body: serde_json::Value = get("?offset=0&limit=1").await.unwrap().json().await.unwrap();
+        assert_eq!(body["data"]["headers"][0]["index"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_bodies_rejected() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let config = ApiConfig {
+            max_body_bytes: 1024,
+            api_token: Some("secret".to_string()),
+            ..Default::default()
+        };
+        let server = start_server(state, "127.0.0.1:0", network, &config, std::future::pending())
+            .await.unwrap();
+        let client = reqwest::Client::new();
+        let post = |path: &str, body: String| client.post(format!("http://{}{}", server.local_addr, path))
+            .bearer_auth("secret")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send();
+
+        // A huge pubkey never reaches the handler's length checks
+        let kp = Keypair::generate();
+        let mut hb = serde_json::to_value(heartbeat(&kp, 0)).unwrap();
+        hb["device_pubkey"] = "ab".repeat(4096).into();
+        let resp = post("/pulse", hb.to_string()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp = post("/verify", format!("{{\"pubkey\": \"{}\"}}", "0".repeat(2048))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Small bodies still go through
+        let resp = post("/pulse", serde_json::to_string(&heartbeat(&kp, 0)).unwrap()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Admin imports take more, but not more blocks than a chain could hold
+        let mut producer = ProofOfLife::new(ConsensusConfig::default());
+        for i in 0..3 {
+            let device = Keypair::generate();
+            producer.receive_heartbeat(heartbeat(&device, i)).unwrap();
+            producer.try_create_block().unwrap().unwrap();
+        }
+        let chain = producer.get_blocks();
+        let body = serde_json::to_string(&chain).unwrap();
+        assert!(body.len() > 1024);
+        let resp = post("/admin/import-chain", body).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let mut crowded = chain[1].clone();
+        crowded.heartbeats = vec![heartbeat(&kp, 0); ConsensusConfig::default().max_heartbeats_per_block + 1];
+        let resp = post("/admin/import-block", serde_json::to_string(&crowded).unwrap()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_readiness() {
+        let config = ConsensusConfig { block_interval_ms: 1, ..Default::default() };
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(config)));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let server = start_server(state.clone(), "127.0.0.1:0", network.clone(), &ApiConfig::default(), std::future::pending())
+            .await.unwrap();
+        let url = format!("http://{}/health", server.local_addr);
+        let health = || async {
+            let resp = reqwest::get(&url).await.unwrap();
+            (resp.status(), resp.json::<serde_json::Value>().await.unwrap())
+        };
+
+        let (status, body) = health().await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["data"]["storage_ok"], true);
+        assert_eq!(body["data"]["peer_count"], 0);
+
+        // A ready pool that never becomes a block
+        state.write().await.receive_heartbeat(heartbeat(&Keypair::generate(), 0)).unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let (status, body) = health().await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body["error"].as_str().unwrap().contains("stalled"), "{}", body);
+        assert!(body["data"]["last_block_age_ms"].as_u64().unwrap() > 5);
+
+        state.write().await.try_create_block().unwrap().unwrap();
+        network.shutdown().await;
+        let (status, body) = health().await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"], "network task stopped");
+
+        // In-memory consensus when storage was required
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let config = ApiConfig { require_storage: true, ..Default::default() };
+        let server = start_server(state, "127.0.0.1:0", network, &config, std::future::pending()).await.unwrap();
+        let body: serde_json::Value = reqwest::get(format!("http://{}/health", server.local_addr))
+            .await.unwrap().json().await.unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["data"]["storage_ok"], false);
+    }
+
+    #[tokio::test]
+    async fn test_verify_checks_signatures_statelessly() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (network, _incoming) = crate::network::start(0, NetworkConfig::default()).await.unwrap();
+        let server = start_server(state.clone(), "127.0.0.1:0", network, &ApiConfig::default(), std::future::pending())
+            .await.unwrap();
+        let client = reqwest::Client::new();
+        let verify = |body: serde_json::Value| {
+            let req = client.post(format!("http://{}/verify", server.local_addr)).json(&body);
+            async move {
+                let resp = req.send().await.unwrap();
+                (resp.status(), resp.json::<serde_json::Value>().await.unwrap())
+            }
+        };
+
+        let kp = Keypair::generate();
+        let hb = heartbeat(&kp, 0);
+        let message_hex = hex::encode(hb.signable_bytes());
+        let (status, body) = verify(serde_json::json!({
+            "pubkey": kp.public_key_hex(), "message_hex": message_hex, "signature": hb.signature,
+        })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["valid"], true);
+
+        // Signed bytes that differ (e.g. keys serialized in another order) don't verify
+        let mut other = hb.clone();
+        other.heart_rate += 1;
+        let (status, body) = verify(serde_json::json!({
+            "pubkey": kp.public_key_hex(), "message_hex": hex::encode(other.signable_bytes()), "signature": hb.signature,
+        })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["valid"], false);
+
+        let (status, _) = verify(serde_json::json!({
+            "pubkey": kp.public_key_hex(), "message_hex": "not hex", "signature": hb.signature,
+        })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Nothing was submitted
+        assert_eq!(state.read().await.heartbeat_pool_size(), 0);
+    }
 }
@@ -1,10 +1,27 @@
 //! Simple in-memory rate limiter for API endpoints.
+//!
+//! Fixed windows are cheap but let a client spend two windows' worth of
+//! requests back to back across a boundary; token buckets smooth that out.
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
+/// How requests are counted against the limit
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum RateLimitMode {
+    /// At most `max_requests` per `window`, counted from the first request of the window
+    #[default]
+    FixedWindow,
+    /// A bucket of up to `burst` tokens, refilled at one token per `refill_every`;
+    /// each request takes a token (`max_requests` and `window` are unused)
+    TokenBucket {
+        burst: u32,
+        refill_every: Duration,
+    },
+}
+
 /// Rate limiter configuration
 #[derive(Clone, Debug)]
 pub struct RateLimitConfig {
@@ -12,6 +29,7 @@ pub struct RateLimitConfig {
     pub max_requests: u32,
     /// Time window
     pub window: Duration,
+    pub mode: RateLimitMode,
 }
 
 impl Default for RateLimitConfig {
@@ -19,14 +37,19 @@ impl Default for RateLimitConfig {
         Self {
             max_requests: 60,
             window: Duration::from_secs(60),
+            mode: RateLimitMode::FixedWindow,
         }
     }
 }
 
 /// Per-key rate limit state
 struct RateState {
+    /// Requests in the current window (fixed window)
     count: u32,
     window_start: Instant,
+    /// Tokens left as of `last_refill` (token bucket)
+    tokens: f64,
+    last_refill: Instant,
 }
 
 /// Thread-safe rate limiter
@@ -52,8 +75,24 @@ impl RateLimiter {
         let entry = state.entry(key.to_string()).or_insert(RateState {
             count: 0,
             window_start: now,
+            tokens: match self.config.mode {
+                RateLimitMode::TokenBucket { burst, .. } => burst as f64,
+                RateLimitMode::FixedWindow => 0.0,
+            },
+            last_refill: now,
         });
 
+        if let RateLimitMode::TokenBucket { burst, refill_every } = self.config.mode {
+            let refilled = now.duration_since(entry.last_refill).as_secs_f64() / refill_every.as_secs_f64();
+            entry.tokens = (entry.tokens + refilled).min(burst as f64);
+            entry.last_refill = now;
+            if entry.tokens < 1.0 {
+                return false;
+            }
+            entry.tokens -= 1.0;
+            return true;
+        }
+
         // Reset window if expired
         if now.duration_since(entry.window_start) > self.config.window {
             entry.count = 0;
@@ -72,7 +111,18 @@ impl RateLimiter {
     pub async fn cleanup(&self) {
         let mut state = self.state.lock().await;
         let now = Instant::now();
-        state.retain(|_, v| now.duration_since(v.window_start) <= self.config.window * 2);
+        match self.config.mode {
+            RateLimitMode::FixedWindow => {
+                state.retain(|_, v| now.duration_since(v.window_start) <= self.config.window * 2);
+            }
+            // A bucket that has refilled completely is no different from a new one
+            RateLimitMode::TokenBucket { burst, refill_every } => {
+                state.retain(|_, v| {
+                    let refilled = now.duration_since(v.last_refill).as_secs_f64() / refill_every.as_secs_f64();
+                    v.tokens + refilled < burst as f64
+                });
+            }
+        }
     }
 }
 
@@ -85,6 +135,7 @@ mod tests {
         let limiter = RateLimiter::new(RateLimitConfig {
             max_requests: 5,
             window: Duration::from_secs(60),
+            ..Default::default()
         });
         for _ in 0..5 {
             assert!(limiter.check("user1").await);
@@ -96,6 +147,7 @@ mod tests {
         let limiter = RateLimiter::new(RateLimitConfig {
             max_requests: 3,
             window: Duration::from_secs(60),
+            ..Default::default()
         });
         assert!(limiter.check("user1").await);
         assert!(limiter.check("user1").await);
@@ -108,6 +160,7 @@ mod tests {
         let limiter = RateLimiter::new(RateLimitConfig {
             max_requests: 1,
             window: Duration::from_secs(60),
+            ..Default::default()
         });
         assert!(limiter.check("a").await);
         assert!(limiter.check("b").await);
@@ -120,6 +173,7 @@ mod tests {
         let limiter = RateLimiter::new(RateLimitConfig {
             max_requests: 1,
             window: Duration::from_millis(50),
+            ..Default::default()
         });
         assert!(limiter.check("k").await);
         assert!(!limiter.check("k").await);
@@ -132,6 +186,7 @@ mod tests {
         let limiter = RateLimiter::new(RateLimitConfig {
             max_requests: 10,
             window: Duration::from_millis(10),
+            ..Default::default()
         });
         limiter.check("x").await;
         tokio::time::sleep(Duration::from_millis(30)).await;
@@ -145,5 +200,37 @@ mod tests {
         let cfg = RateLimitConfig::default();
         assert_eq!(cfg.max_requests, 60);
         assert_eq!(cfg.window, Duration::from_secs(60));
+        assert_eq!(cfg.mode, RateLimitMode::FixedWindow);
     }
-}
+
+    fn token_bucket(burst: u32, refill_every: Duration) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            mode: RateLimitMode::TokenBucket { burst, refill_every },
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_token_bucket_refills_smoothly() {
+        let limiter = token_bucket(1, Duration::from_millis(100));
+        assert!(limiter.check("k").await);
+        assert!(!limiter.check("k").await);
+
+        // Half a refill interval isn't a whole token yet
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!limiter.check("k").await);
+        tokio::time::sleep(Duration::from_millis(70)).await;
+        assert!(limiter.check("k").await);
+        assert!(!limiter.check("k").await);
+    }
+
+    #[tokio::test]
+    async fn test_token_bucket_caps_burst() {
+        let limiter = token_bucket(3, Duration::from_millis(10));
+        // Idle long enough for ten refills, but the bucket only holds three
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        for _ in 0..3 {
+            assert!(limiter.check("k").await);
+        }
+        assert!(!limiter.check("k").await);
+    }}
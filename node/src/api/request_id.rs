@@ -0,0 +1,175 @@
+//! Request-id propagation for API requests.
+//!
+//! Every request gets an `X-Request-Id` — taken from the client if supplied,
+//! generated otherwise — that is:
+//! - recorded on a tracing span wrapping the handler (so consensus and network
+//!   logs emitted while serving the request carry it)
+//! - echoed back in the response headers
+//! - injected into JSON error bodies so users can quote it in bug reports
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{info_span, Instrument};
+
+/// Header used to carry the request id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied request id we accept before generating our own
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Largest error body we'll buffer to inject the request id
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+
+/// Request id, available to handlers via `Extension<RequestId>`
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Middleware: assign/propagate a request id and open a tracing span for the request
+pub async fn propagate_request_id(mut req: Request, next: Next) -> Response {
+    let request_id = req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| is_valid_request_id(v))
+        .map(str::to_string)
+        .unwrap_or_else(generate_request_id);
+
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.uri().path(),
+    );
+    let response = next.run(req).instrument(span).await;
+
+    let status = response.status();
+    let mut response = if status.is_client_error() || status.is_server_error() {
+        attach_to_error_body(response, &request_id).await
+    } else {
+        response
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Generate a fresh random request id (128 bits, hex)
+fn generate_request_id() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+/// Client-supplied ids must be short, printable ASCII
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Add a `request_id` field to a JSON object error body (other bodies pass through)
+async fn attach_to_error_body(response: Response, request_id: &str) -> Response {
+    let is_json = response.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| ct.starts_with("application/json"))
+        .unwrap_or(false);
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_ERROR_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(mut map)) => {
+            map.insert("request_id".to_string(), request_id.into());
+            match serde_json::to_vec(&map) {
+                Ok(json) => Body::from(json),
+                Err(_) => Body::from(bytes),
+            }
+        }
+        _ => Body::from(bytes),
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get, Json, Router};
+
+    async fn spawn_test_server() -> String {
+        let router = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/fail", get(|| async {
+                (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+                    "success": false,
+                    "error": "nope"
+                })))
+            }))
+            .layer(axum::middleware::from_fn(propagate_request_id));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_echoes_client_request_id() {
+        let base = spawn_test_server().await;
+        let resp = reqwest::Client::new()
+            .get(format!("{}/ok", base))
+            .header(REQUEST_ID_HEADER, "client-abc-123")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.headers()[REQUEST_ID_HEADER], "client-abc-123");
+    }
+
+    #[tokio::test]
+    async fn test_generates_request_id_when_absent() {
+        let base = spawn_test_server().await;
+        let resp = reqwest::get(format!("{}/ok", base)).await.unwrap();
+        let id = resp.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert_eq!(id.len(), 32);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[tokio::test]
+    async fn test_error_body_includes_request_id() {
+        let base = spawn_test_server().await;
+        let resp = reqwest::Client::new()
+            .get(format!("{}/fail", base))
+            .header(REQUEST_ID_HEADER, "trace-me")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["request_id"], "trace-me");
+        assert_eq!(body["error"], "nope");
+    }
+
+    #[test]
+    fn test_rejects_malformed_client_ids() {
+        assert!(is_valid_request_id("abc-123"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("has space"));
+        assert!(!is_valid_request_id(&"x".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+}
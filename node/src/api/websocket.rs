@@ -4,17 +4,27 @@
 //! - New blocks as they're created
 //! - Heartbeat pool updates
 //! - Network stats changes
+//!
+//! A client that falls more than the broadcast capacity behind skips the
+//! oldest events and is sent a `lagged` notice with how many it missed.
+//!
+//! Connections are capped (503 past the cap) and, if a token is configured,
+//! must present it as `?token=` or `Authorization: Bearer` before upgrading.
 
 use axum::{
-    extract::{State, WebSocketUpgrade, ws::{Message, WebSocket}},
-    response::IntoResponse,
+    extract::{Query, State, WebSocketUpgrade, ws::{Message, WebSocket}},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
 };
 use futures_util::{SinkExt, StreamExt};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::broadcast::{self, error::RecvError};
 use tracing::{info, debug, warn};
 
+use super::{auth, ApiResponse};
 use crate::types::{PulseBlock, NetworkStats};
 
 /// Events broadcast to WebSocket clients
@@ -33,18 +43,53 @@ pub enum WsEvent {
     HeartbeatCount {
         count: usize,
     },
+    #[serde(rename = "peers")]
+    Peers {
+        count: usize,
+    },
+    /// Sent to a client in place of the events it fell too far behind to receive
+    #[serde(rename = "lagged")]
+    Lagged {
+        missed: u64,
+    },
 }
 
 /// Broadcaster for WebSocket events
 #[derive(Clone)]
 pub struct WsBroadcaster {
     sender: broadcast::Sender<WsEvent>,
+    /// Connected clients, counted from upgrade until the socket closes
+    connections: Arc<AtomicUsize>,
+}
+
+/// A client's place in the connection count, given up when dropped
+pub struct ConnectionSlot {
+    connections: Arc<AtomicUsize>,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.connections.fetch_sub(1, Ordering::AcqRel);
+    }
 }
 
 impl WsBroadcaster {
     pub fn new(capacity: usize) -> Self {
         let (sender, _) = broadcast::channel(capacity);
-        Self { sender }
+        Self { sender, connections: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// Count a new client, unless `max` are already connected (0 is unlimited)
+    pub fn try_connect(&self, max: usize) -> Option<ConnectionSlot> {
+        self.connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| (max == 0 || n < max).then_some(n + 1))
+            .ok()?;
+        Some(ConnectionSlot { connections: self.connections.clone() })
+    }
+
+    /// Clients currently connected
+    pub fn connection_count(&self) -> usize {
+        self.connections.load(Ordering::Acquire)
     }
 
     /// Broadcast an event to all connected clients
@@ -64,28 +109,64 @@ impl WsBroadcaster {
     }
 }
 
-/// WebSocket upgrade handler
+/// State of the `/ws` route
+#[derive(Clone)]
+pub struct WsState {
+    pub broadcaster: Arc<WsBroadcaster>,
+    /// Most clients connected at once; further upgrades get 503. 0 is unlimited.
+    pub max_connections: usize,
+    /// Token clients must present before upgrading (None leaves `/ws` open)
+    pub token: Option<Arc<str>>,
+}
+
+/// `/ws` query parameters
+#[derive(Debug, Deserialize)]
+pub struct WsParams {
+    /// Alternative to the Authorization header, for browsers (which can't set it on a WebSocket)
+    token: Option<String>,
+}
+
+/// WebSocket upgrade handler: checks the token and connection cap before upgrading
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
-    State(broadcaster): State<Arc<WsBroadcaster>>,
-) -> impl IntoResponse {
-    let count = broadcaster.subscriber_count() + 1;
-    info!("🔌 WebSocket client connecting (total: {})", count);
-    
-    ws.on_upgrade(move |socket| handle_ws_connection(socket, broadcaster))
+    State(state): State<WsState>,
+    Query(params): Query<WsParams>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(ref token) = state.token {
+        let presented = params.token.as_deref().or_else(|| {
+            headers.get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(auth::bearer_token)
+        });
+        if !presented.is_some_and(|presented| auth::tokens_match(presented, token)) {
+            return (StatusCode::UNAUTHORIZED, Json(ApiResponse::<()>::err("Missing or invalid WebSocket token")))
+                .into_response();
+        }
+    }
+
+    let Some(slot) = state.broadcaster.try_connect(state.max_connections) else {
+        warn!("🔌 WebSocket client refused: {} already connected", state.broadcaster.connection_count());
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(ApiResponse::<()>::err("Too many WebSocket connections")))
+            .into_response();
+    };
+    info!("🔌 WebSocket client connecting (total: {})", state.broadcaster.connection_count());
+
+    let broadcaster = state.broadcaster;
+    ws.on_upgrade(move |socket| handle_ws_connection(socket, broadcaster, slot))
 }
 
-/// Handle an individual WebSocket connection
-async fn handle_ws_connection(socket: WebSocket, broadcaster: Arc<WsBroadcaster>) {
+/// Handle an individual WebSocket connection; `slot` is released when it closes
+async fn handle_ws_connection(socket: WebSocket, broadcaster: Arc<WsBroadcaster>, slot: ConnectionSlot) {
     let (mut ws_sender, mut ws_receiver) = socket.split();
     let mut rx = broadcaster.subscribe();
 
     // Send events to client
     let send_task = tokio::spawn(async move {
-        while let Ok(event) = rx.recv().await {
+        while let Some(event) = next_event(&mut rx).await {
             match serde_json::to_string(&event) {
                 Ok(json) => {
-                    if ws_sender.send(Message::Text(json.into())).await.is_err() {
+                    if ws_sender.send(Message::Text(json)).await.is_err() {
                         break; // Client disconnected
                     }
                 }
@@ -116,6 +197,60 @@ async fn handle_ws_connection(socket: WebSocket, broadcaster: Arc<WsBroadcaster>
         _ = recv_task => {},
     }
 
-    info!("🔌 WebSocket client disconnected (remaining: {})", 
-        broadcaster.subscriber_count().saturating_sub(1));
+    drop(slot);
+    info!("🔌 WebSocket client disconnected (remaining: {})", broadcaster.connection_count());
+}
+
+/// Next event for a client; a lagging client gets a `Lagged` notice and carries
+/// on from the oldest event still buffered. `None` once the broadcaster is gone.
+async fn next_event(rx: &mut broadcast::Receiver<WsEvent>) -> Option<WsEvent> {
+    match rx.recv().await {
+        Ok(event) => Some(event),
+        Err(RecvError::Lagged(missed)) => {
+            warn!("🐢 WebSocket client lagging, skipped {} events", missed);
+            Some(WsEvent::Lagged { missed })
+        }
+        Err(RecvError::Closed) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lagging_client_notified_and_kept() {
+        let broadcaster = WsBroadcaster::new(2);
+        let mut rx = broadcaster.subscribe();
+        for count in 0..5 {
+            broadcaster.broadcast(WsEvent::HeartbeatCount { count });
+        }
+
+        assert!(matches!(next_event(&mut rx).await, Some(WsEvent::Lagged { missed: 3 })));
+        assert!(matches!(next_event(&mut rx).await, Some(WsEvent::HeartbeatCount { count: 3 })));
+        assert!(matches!(next_event(&mut rx).await, Some(WsEvent::HeartbeatCount { count: 4 })));
+        assert_eq!(
+            serde_json::to_value(WsEvent::Lagged { missed: 3 }).unwrap(),
+            serde_json::json!({"type": "lagged", "missed": 3})
+        );
+
+        drop(broadcaster);
+        assert!(next_event(&mut rx).await.is_none());
+    }
+
+    #[test]
+    fn test_connection_slots_capped_and_released() {
+        let broadcaster = WsBroadcaster::new(4);
+        let first = broadcaster.try_connect(2).unwrap();
+        let _second = broadcaster.try_connect(2).unwrap();
+        assert!(broadcaster.try_connect(2).is_none());
+        assert_eq!(broadcaster.connection_count(), 2);
+
+        drop(first);
+        assert_eq!(broadcaster.connection_count(), 1);
+        assert!(broadcaster.try_connect(2).is_some());
+        // The slot from the line above was dropped straight away
+        assert_eq!(broadcaster.connection_count(), 1);
+        assert!(broadcaster.try_connect(0).is_some(), "0 is unlimited");
+    }
 }
@@ -5,6 +5,8 @@
 //! 2. Biometric entropy extraction for block randomness
 //! 3. Anomaly detection for spoofed sensor data
 
+use crate::types::Heartbeat;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use tracing::{warn, debug};
 
@@ -12,12 +14,83 @@ use tracing::{warn, debug};
 const MAX_HR_HISTORY: usize = 60; // ~5 minutes at 5s intervals
 const MAX_MOTION_HISTORY: usize = 60;
 
+/// RMSSD below this (ms) means successive readings barely move — a smoothed
+/// or interpolated signal. Resting humans typically sit around 20-80ms.
+const MIN_RMSSD_MS: f64 = 5.0;
+/// pNN50 below this (%) while SDNN looks healthy means the variability is all
+/// slow drift with no beat-to-beat jumps, the signature of a ramp or sine generator
+const MIN_PNN50_PERCENT: f64 = 1.0;
+/// SDNN (BPM) above which a zero pNN50 is treated as drift rather than a calm heart
+const DRIFT_SDNN_BPM: f64 = 3.0;
+
+/// Global HRV bounds, used until a device has a baseline
+const MIN_SDNN_BPM: f64 = 0.5;
+const MAX_SDNN_BPM: f64 = 40.0;
+/// Average HR (BPM) treated as exertion for the HR/motion mismatch check
+const HIGH_HR_BPM: f64 = 130.0;
+
+/// Once calibrated, a device is flagged when its SDNN/RMSSD fall below this
+/// fraction of its own baseline (never stricter than the global minimums) ...
+const BASELINE_LOW_FACTOR: f64 = 0.5;
+/// ... or its SDNN exceeds this multiple of the baseline (never stricter than the global maximum)
+const BASELINE_HIGH_FACTOR: f64 = 3.0;
+/// ... and "high HR" means this far above its resting mean, rather than `HIGH_HR_BPM`
+const BASELINE_EXERTION_BPM: f64 = 60.0;
+
+/// Tunable bounds for biometric plausibility checks
+#[derive(Debug, Clone)]
+pub struct BiometricConfig {
+    /// Valid heart rate range (BPM) — readings outside are invalid outright
+    pub min_heart_rate: u16,
+    pub max_heart_rate: u16,
+    /// Lower bound of plausible human temperature (°C) — below this confidence is penalized
+    pub plausible_min_temperature: f32,
+    /// Upper bound of plausible human temperature (°C) — above this confidence is penalized
+    pub plausible_max_temperature: f32,
+    /// Heartbeats a device must submit before its personal baseline is fixed
+    /// (capped at the history length). 0 disables calibration.
+    pub baseline_samples: usize,
+}
+
+impl Default for BiometricConfig {
+    fn default() -> Self {
+        Self {
+            min_heart_rate: 30,
+            max_heart_rate: 220,
+            plausible_min_temperature: 33.0,
+            plausible_max_temperature: 42.0,
+            baseline_samples: 30,
+        }
+    }
+}
+
+/// A device's resting heart profile, fixed after calibration. Later heartbeats
+/// are judged against it instead of population-wide constants, so athletes and
+/// people with naturally low HRV aren't flagged as synthetic.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Mean heart rate over the calibration window (BPM)
+    pub mean_hr: f64,
+    /// SDNN over the calibration window (BPM)
+    pub sdnn: f64,
+    /// RMSSD over the calibration window (ms)
+    pub rmssd: f64,
+    /// Readings the baseline was computed from
+    pub samples: usize,
+}
+
 /// Biometric validator that tracks per-device history for anomaly detection
 pub struct BiometricValidator {
+    /// Plausibility bounds
+    config: BiometricConfig,
     /// Heart rate history per device (pubkey -> recent HR values)
     hr_history: std::collections::HashMap<String, VecDeque<u16>>,
     /// Motion history per device
     motion_history: std::collections::HashMap<String, VecDeque<f64>>,
+    /// Calibrated baselines per device
+    baselines: std::collections::HashMap<String, Baseline>,
+    /// Confidence of each device's most recently validated heartbeat
+    last_confidence: std::collections::HashMap<String, f64>,
 }
 
 /// Result of biometric validation
@@ -33,16 +106,49 @@ pub struct BiometricResult {
     pub entropy_bits: Vec<u8>,
     /// Heart rate variability (SDNN in BPM) — 0 if not enough history
     pub hrv_sdnn: f64,
+    /// Root mean square of successive RR-interval differences (ms) — 0 if not enough history
+    pub hrv_rmssd: f64,
+    /// Percentage of successive RR intervals differing by more than 50ms — 0 if not enough history
+    pub hrv_pnn50: f64,
+}
+
+impl Default for BiometricValidator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BiometricValidator {
     pub fn new() -> Self {
+        Self::with_config(BiometricConfig::default())
+    }
+
+    /// Create a validator with custom plausibility bounds
+    pub fn with_config(config: BiometricConfig) -> Self {
         Self {
+            config,
             hr_history: std::collections::HashMap::new(),
             motion_history: std::collections::HashMap::new(),
+            baselines: std::collections::HashMap::new(),
+            last_confidence: std::collections::HashMap::new(),
         }
     }
 
+    /// Confidence of the last heartbeat validated for a device (accepted or not)
+    pub fn last_confidence(&self, pubkey: &str) -> Option<f64> {
+        self.last_confidence.get(pubkey).copied()
+    }
+
+    /// Calibrated baseline for a device, if it has submitted enough clean heartbeats
+    pub fn baseline_for(&self, pubkey: &str) -> Option<Baseline> {
+        self.baselines.get(pubkey).copied()
+    }
+
+    /// Install a previously calibrated baseline (e.g. restored from storage)
+    pub fn set_baseline(&mut self, pubkey: &str, baseline: Baseline) {
+        self.baselines.insert(pubkey.to_string(), baseline);
+    }
+
     /// Validate a heartbeat and extract biometric entropy.
     /// Returns validation result with confidence score and entropy.
     pub fn validate(
@@ -58,19 +164,23 @@ impl BiometricValidator {
         // --- 1. Physiological range checks ---
         
         // Temperature should be in human range
-        if temperature < 33.0 || temperature > 42.0 {
+        let plausible_temp = self.config.plausible_min_temperature..=self.config.plausible_max_temperature;
+        if !plausible_temp.contains(&temperature) {
             confidence *= 0.3;
             reasons.push(format!("Temperature {:.1}°C outside human range", temperature));
         }
         
         // Heart rate physiological bounds (already checked in consensus, but double-check)
-        if heart_rate < 30 || heart_rate > 220 {
+        if !(self.config.min_heart_rate..=self.config.max_heart_rate).contains(&heart_rate) {
+            self.last_confidence.insert(device_pubkey.to_string(), 0.0);
             return BiometricResult {
                 is_valid: false,
                 confidence: 0.0,
                 reason: Some(format!("HR {} outside physiological bounds", heart_rate)),
                 entropy_bits: vec![],
                 hrv_sdnn: 0.0,
+                hrv_rmssd: 0.0,
+                hrv_pnn50: 0.0,
             };
         }
 
@@ -87,25 +197,48 @@ impl BiometricValidator {
             hr_queue.pop_front();
         }
         
-        let hrv_sdnn = if hr_queue.len() >= 5 {
-            Self::calculate_sdnn(hr_queue)
+        let (hrv_sdnn, hrv_rmssd, hrv_pnn50) = if hr_queue.len() >= 5 {
+            (Self::calculate_sdnn(hr_queue), Self::calculate_rmssd(hr_queue), Self::calculate_pnn50(hr_queue))
         } else {
-            0.0 // Not enough data yet
+            (0.0, 0.0, 0.0) // Not enough data yet
+        };
+        
+        // Thresholds relative to the device's own baseline once it has one
+        let baseline = self.baselines.get(device_pubkey).copied();
+        let (min_sdnn, max_sdnn, min_rmssd, high_hr) = match baseline {
+            Some(b) => (
+                (b.sdnn * BASELINE_LOW_FACTOR).min(MIN_SDNN_BPM),
+                (b.sdnn * BASELINE_HIGH_FACTOR).max(MAX_SDNN_BPM),
+                (b.rmssd * BASELINE_LOW_FACTOR).min(MIN_RMSSD_MS),
+                b.mean_hr + BASELINE_EXERTION_BPM,
+            ),
+            None => (MIN_SDNN_BPM, MAX_SDNN_BPM, MIN_RMSSD_MS, HIGH_HR_BPM),
         };
         
         if hr_queue.len() >= 10 {
             // Check for suspiciously LOW variability (constant HR = likely fake)
-            if hrv_sdnn < 0.5 {
+            if hrv_sdnn < min_sdnn {
                 confidence *= 0.2;
                 reasons.push(format!("HRV too low ({:.2} BPM SDNN) — possible synthetic signal", hrv_sdnn));
             }
             
             // Check for suspiciously HIGH variability (random noise = likely fake)
-            if hrv_sdnn > 40.0 {
+            if hrv_sdnn > max_sdnn {
                 confidence *= 0.4;
                 reasons.push(format!("HRV too high ({:.2} BPM SDNN) — possible random noise", hrv_sdnn));
             }
             
+            // Check for suspiciously SMOOTH beat-to-beat changes (interpolated signal)
+            if hrv_rmssd < min_rmssd {
+                confidence *= 0.5;
+                reasons.push(format!("RMSSD too low ({:.2} ms) — possible smoothed signal", hrv_rmssd));
+            } else if hrv_pnn50 < MIN_PNN50_PERCENT && hrv_sdnn > DRIFT_SDNN_BPM {
+                confidence *= 0.7;
+                reasons.push(format!(
+                    "pNN50 {:.1}% with SDNN {:.2} BPM — variability is slow drift only", hrv_pnn50, hrv_sdnn
+                ));
+            }
+            
             // Check for perfectly periodic patterns (e.g., HR alternating between 2 values)
             if Self::is_periodic(hr_queue) {
                 confidence *= 0.3;
@@ -131,8 +264,8 @@ impl BiometricValidator {
             let avg_hr: f64 = hr_queue.iter().map(|h| *h as f64).sum::<f64>() / hr_queue.len() as f64;
             let avg_motion: f64 = motion_queue.iter().sum::<f64>() / motion_queue.len() as f64;
             
-            // High HR (>130) with essentially no motion for 10+ readings
-            if avg_hr > 130.0 && avg_motion < 0.05 {
+            // High HR (>130, or well above the device's resting mean) with essentially no motion for 10+ readings
+            if avg_hr > high_hr && avg_motion < 0.05 {
                 confidence *= 0.5;
                 reasons.push(format!(
                     "HR/motion mismatch: avg HR={:.0} but avg motion={:.3}", avg_hr, avg_motion
@@ -149,13 +282,28 @@ impl BiometricValidator {
             }
         }
 
-        // --- 4. Extract biometric entropy ---
+        // --- 4. Baseline calibration ---
+        // Fixed once, from a window that isn't a constant or alternating signal,
+        // so a spoofer can't calibrate a flat line as "normal"
+        let calibration_samples = self.config.baseline_samples.min(MAX_HR_HISTORY);
+        if baseline.is_none() && calibration_samples > 0 && hr_queue.len() >= calibration_samples
+            && hrv_sdnn > 0.0 && !Self::is_periodic(hr_queue)
+        {
+            let mean_hr = hr_queue.iter().map(|h| *h as f64).sum::<f64>() / hr_queue.len() as f64;
+            let baseline = Baseline { mean_hr, sdnn: hrv_sdnn, rmssd: hrv_rmssd, samples: hr_queue.len() };
+            debug!("📐 Biometric baseline for {}...: {:.1} BPM, SDNN {:.2}, RMSSD {:.1}ms",
+                &device_pubkey[..8.min(device_pubkey.len())], mean_hr, hrv_sdnn, hrv_rmssd);
+            self.baselines.insert(device_pubkey.to_string(), baseline);
+        }
+
+        // --- 5. Extract biometric entropy ---
         // Use the least significant bits of HR and motion as entropy source.
         // Real biometric data has natural noise = good entropy.
         let entropy_bits = Self::extract_entropy(heart_rate, motion_magnitude, hrv_sdnn);
 
         // --- Result ---
         let is_valid = confidence >= 0.3; // Threshold: reject only very suspicious
+        self.last_confidence.insert(device_pubkey.to_string(), confidence);
         
         if !is_valid {
             warn!("🚨 Biometric validation FAILED for {}...: confidence={:.2}, reasons: {:?}",
@@ -171,6 +319,8 @@ impl BiometricValidator {
             reason: if reasons.is_empty() { None } else { Some(reasons.join("; ")) },
             entropy_bits,
             hrv_sdnn,
+            hrv_rmssd,
+            hrv_pnn50,
         }
     }
 
@@ -187,6 +337,29 @@ impl BiometricValidator {
         variance.sqrt()
     }
 
+    /// RR interval (ms between beats) implied by each heart rate reading
+    fn rr_intervals_ms(values: &VecDeque<u16>) -> Vec<f64> {
+        values.iter().map(|hr| 60_000.0 / (*hr).max(1) as f64).collect()
+    }
+
+    /// Root Mean Square of Successive Differences between RR intervals (ms).
+    /// Captures beat-to-beat variability, which smoothing removes even when
+    /// the overall spread (SDNN) looks natural.
+    fn calculate_rmssd(values: &VecDeque<u16>) -> f64 {
+        let rr = Self::rr_intervals_ms(values);
+        if rr.len() < 2 { return 0.0; }
+        let sum_sq = rr.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum::<f64>();
+        (sum_sq / (rr.len() - 1) as f64).sqrt()
+    }
+
+    /// pNN50: percentage of successive RR intervals differing by more than 50ms
+    fn calculate_pnn50(values: &VecDeque<u16>) -> f64 {
+        let rr = Self::rr_intervals_ms(values);
+        if rr.len() < 2 { return 0.0; }
+        let nn50 = rr.windows(2).filter(|w| (w[1] - w[0]).abs() > 50.0).count();
+        100.0 * nn50 as f64 / (rr.len() - 1) as f64
+    }
+
     fn calculate_sdnn_f64(values: &VecDeque<f64>) -> f64 {
         if values.len() < 2 { return 0.0; }
         let n = values.len() as f64;
@@ -230,48 +403,48 @@ impl BiometricValidator {
         hasher.update(motion_mag.to_le_bytes());
         hasher.update(hrv.to_le_bytes());
         
-        // Add nanosecond timestamp for additional entropy
-        let nanos = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-        hasher.update(nanos.to_le_bytes());
-        
         // Output 32 bytes of entropy
         hasher.finalize().to_vec()
     }
 
-    /// Clean up stale device histories
+    /// Drop all history for one device
+    pub fn forget(&mut self, pubkey: &str) {
+        self.hr_history.remove(pubkey);
+        self.motion_history.remove(pubkey);
+        self.baselines.remove(pubkey);
+        self.last_confidence.remove(pubkey);
+    }
+
+    /// Clean up stale device histories (baselines are kept)
     pub fn cleanup(&mut self, active_pubkeys: &[String]) {
         let active_set: std::collections::HashSet<&String> = active_pubkeys.iter().collect();
         self.hr_history.retain(|k, _| active_set.contains(k));
         self.motion_history.retain(|k, _| active_set.contains(k));
+        self.last_confidence.retain(|k, _| active_set.contains(k));
     }
+}
 
-    /// Get aggregate biometric entropy from all active devices.
-    /// Combines entropy from multiple humans for block-level randomness.
-    pub fn aggregate_entropy(&self) -> Vec<u8> {
-        use sha2::{Sha256, Digest};
-        let mut hasher = Sha256::new();
-        
-        // Mix all recent HR values from all devices
-        for (pubkey, hrs) in &self.hr_history {
-            hasher.update(pubkey.as_bytes());
-            for hr in hrs {
-                hasher.update(hr.to_le_bytes());
-            }
-        }
-        
-        // Mix all motion data
-        for (pubkey, motions) in &self.motion_history {
-            hasher.update(pubkey.as_bytes());
-            for m in motions {
-                hasher.update(m.to_le_bytes());
-            }
-        }
-        
-        hasher.finalize().to_vec()
+/// Block-level biometric entropy: SHA-256 over the pubkey, heart rate and motion
+/// of every heartbeat in the block. Depends only on the heartbeat set (not its
+/// order or any validator state), so receiving nodes can recompute it.
+pub fn block_entropy(heartbeats: &[Heartbeat]) -> Vec<u8> {
+    use sha2::{Sha256, Digest};
+    
+    let mut entries: Vec<Vec<u8>> = heartbeats.iter().map(|hb| {
+        let mut entry = hb.device_pubkey.as_bytes().to_vec();
+        entry.extend_from_slice(&hb.heart_rate.to_le_bytes());
+        entry.extend_from_slice(&hb.motion.x.to_le_bytes());
+        entry.extend_from_slice(&hb.motion.y.to_le_bytes());
+        entry.extend_from_slice(&hb.motion.z.to_le_bytes());
+        entry
+    }).collect();
+    entries.sort();
+    
+    let mut hasher = Sha256::new();
+    for entry in &entries {
+        hasher.update(entry);
     }
+    hasher.finalize().to_vec()
 }
 
 #[cfg(test)]
@@ -293,6 +466,25 @@ mod tests {
         assert!(!result.is_valid || result.confidence < 0.5);
     }
 
+    #[test]
+    fn test_implausible_temperature_penalized_but_valid() {
+        let mut v = BiometricValidator::new();
+        let result = v.validate("device1", 72, 0.1, 43.0);
+        assert!(result.is_valid, "43°C should be accepted");
+        assert!(result.confidence < 1.0, "43°C should be penalized: {}", result.confidence);
+    }
+
+    #[test]
+    fn test_custom_plausible_temperature_bounds() {
+        let mut v = BiometricValidator::with_config(BiometricConfig {
+            plausible_min_temperature: 30.0,
+            plausible_max_temperature: 44.0,
+            ..Default::default()
+        });
+        let result = v.validate("device1", 72, 0.1, 43.0);
+        assert!(result.confidence > 0.9);
+    }
+
     #[test]
     fn test_constant_hr_detected_as_synthetic() {
         let mut v = BiometricValidator::new();
@@ -318,6 +510,94 @@ mod tests {
         assert!(result.confidence > 0.7, "Natural HRV should have high confidence: {}", result.confidence);
     }
 
+    #[test]
+    fn test_natural_hrv_has_beat_to_beat_variability() {
+        let mut v = BiometricValidator::new();
+        let hrs = [72, 74, 71, 75, 73, 70, 76, 72, 74, 71, 73, 75, 72, 74, 70];
+        let motions = [0.08, 0.12, 0.09, 0.15, 0.11, 0.07, 0.13, 0.10, 0.14, 0.08, 0.12, 0.09, 0.11, 0.13, 0.10];
+        for i in 0..hrs.len() {
+            v.validate("device1", hrs[i], motions[i], 36.7);
+        }
+        let result = v.validate("device1", 73, 0.11, 36.7);
+        assert!(result.hrv_rmssd > MIN_RMSSD_MS, "RMSSD: {}", result.hrv_rmssd);
+        assert!(result.hrv_pnn50 > 0.0, "pNN50: {}", result.hrv_pnn50);
+        assert!(result.reason.is_none(), "{:?}", result.reason);
+    }
+
+    #[test]
+    fn test_constant_hr_has_zero_rmssd() {
+        let mut v = BiometricValidator::new();
+        for _ in 0..15 {
+            v.validate("device1", 72, 0.1, 36.7);
+        }
+        let result = v.validate("device1", 72, 0.1, 36.7);
+        assert!(result.hrv_rmssd < 1e-9);
+        assert_eq!(result.hrv_pnn50, 0.0);
+        assert!(result.reason.unwrap().contains("RMSSD too low"));
+    }
+
+    #[test]
+    fn test_smooth_drift_penalized_despite_healthy_sdnn() {
+        // A slow ramp has plenty of spread but no beat-to-beat jumps
+        let mut v = BiometricValidator::new();
+        for hr in 66..84 {
+            v.validate("device1", hr, 0.1 + hr as f64 * 0.001, 36.7);
+        }
+        let result = v.validate("device1", 84, 0.2, 36.7);
+        assert!(result.hrv_sdnn > DRIFT_SDNN_BPM);
+        assert_eq!(result.hrv_pnn50, 0.0);
+        assert!(result.confidence < 1.0, "confidence: {}", result.confidence);
+    }
+
+    /// Irregular 72/73 BPM readings: SDNN just under the global 0.5 BPM floor
+    const LOW_HRV: [u16; 40] = [
+        72, 72, 72, 72, 73, 72, 72, 72, 72, 72, 73, 72, 73, 72, 72, 72, 72, 73, 72, 73,
+        73, 73, 72, 72, 72, 73, 72, 73, 72, 72, 73, 72, 72, 72, 72, 73, 73, 72, 73, 72,
+    ];
+
+    #[test]
+    fn test_baseline_calibrated_after_enough_heartbeats() {
+        let mut v = BiometricValidator::new();
+        let hrs = [72, 74, 71, 75, 73, 70, 76, 72, 74, 71];
+        for (i, hr) in hrs.iter().cycle().take(29).enumerate() {
+            v.validate("device1", *hr + (i % 3) as u16, 0.1 + i as f64 * 0.01, 36.7);
+        }
+        assert!(v.baseline_for("device1").is_none());
+        v.validate("device1", 73, 0.12, 36.7);
+        let baseline = v.baseline_for("device1").expect("calibrated after 30 heartbeats");
+        assert_eq!(baseline.samples, 30);
+        assert!((baseline.mean_hr - 73.0).abs() < 2.0, "mean: {}", baseline.mean_hr);
+        assert!(baseline.sdnn > 0.5);
+        assert!(v.baseline_for("device2").is_none());
+    }
+
+    #[test]
+    fn test_baseline_accepts_naturally_low_hrv() {
+        let mut v = BiometricValidator::new();
+        let mut before = None;
+        for (i, hr) in LOW_HRV.iter().enumerate() {
+            let result = v.validate("device1", *hr, 0.1 + (i % 7) as f64 * 0.01, 36.7);
+            if i == 28 {
+                before = Some(result);
+            }
+        }
+        // Globally this looks too flat; against its own baseline it's normal
+        assert!(before.unwrap().reason.unwrap().contains("HRV too low"));
+        assert!(v.baseline_for("device1").unwrap().sdnn < 0.5);
+        let after = v.validate("device1", 72, 0.12, 36.7);
+        assert!(after.reason.is_none(), "{:?}", after.reason);
+        assert!(after.confidence > 0.9);
+    }
+
+    #[test]
+    fn test_constant_hr_never_calibrates() {
+        let mut v = BiometricValidator::new();
+        for _ in 0..40 {
+            v.validate("device1", 72, 0.1, 36.7);
+        }
+        assert!(v.baseline_for("device1").is_none());
+    }
+
     #[test]
     fn test_entropy_extraction() {
         let mut v = BiometricValidator::new();
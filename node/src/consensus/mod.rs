@@ -1,12 +1,18 @@
 //! Proof-of-Life consensus engine for the Pulse Network.
 
 pub mod biometrics;
+pub mod sig_cache;
 
-use crate::crypto::{verify_signature, CryptoError};
-use crate::storage::Storage;
-use crate::types::{Heartbeat, PulseBlock, Transaction, Account};
-use biometrics::BiometricValidator;
-use std::collections::HashMap;
+use crate::crypto::{parse_pubkey, verify_batch, verify_signature, CryptoError};
+use crate::metrics::ConsensusMetrics;
+use crate::storage::snapshot::{SnapshotSummary, SnapshotWriter};
+use crate::storage::ChainStore;
+use crate::types::{BlockHeader, Heartbeat, PulseBlock, Transaction, Account, AccountSort, WeightParams};
+use crate::types::{to_micro, to_pulse, Amount, SIGNATURE_VERSION};
+use crate::types::{PoolSnapshot, PooledHeartbeat, PooledTransaction};
+use biometrics::{BiometricConfig, BiometricValidator};
+use sig_cache::SignatureCache;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
@@ -14,35 +20,189 @@ use tracing::{info, warn, debug, error};
 
 #[derive(Error, Debug)]
 pub enum ConsensusError {
+    #[error("Invalid public key (not a valid secp256k1 point)")]
+    InvalidPublicKey,
     #[error("Invalid heartbeat signature")]
     InvalidHeartbeatSignature,
     #[error("Stale heartbeat (too old)")]
     StaleHeartbeat,
+    #[error("Heartbeat timestamp is in the future")]
+    FutureHeartbeat,
     #[error("Invalid heart rate: {0}")]
     InvalidHeartRate(u16),
+    #[error("Invalid temperature: {0}°C")]
+    InvalidTemperature(f32),
     #[error("Insufficient live participants: {0}/{1}")]
     InsufficientParticipants(usize, usize),
     #[error("Invalid transaction signature")]
     InvalidTransactionSignature,
+    #[error("Unsupported signature version {0}")]
+    UnsupportedSignatureVersion(u8),
+    #[error("Transaction {0} is already pending")]
+    DuplicateTransaction(String),
+    #[error("Transaction pool full ({0} pending)")]
+    MempoolFull(usize),
+    #[error("Invalid transaction nonce: expected {0}, got {1}")]
+    InvalidNonce(u64, u64),
+    #[error("Transaction timelocked until height {0}")]
+    TransactionTimelocked(u64),
     #[error("Insufficient balance")]
     InsufficientBalance,
+    #[error("Invalid transaction fee: {0}")]
+    InvalidFee(f64),
     #[error("Sender not pulsing")]
     SenderNotPulsing,
+    #[error("Transaction doesn't reference the sender's current heartbeat")]
+    InvalidHeartbeatReference,
     #[error("Biometric validation failed: {0}")]
     BiometricValidationFailed(String),
     #[error("Invalid block hash")]
     InvalidBlockHash,
     #[error("Invalid previous hash (block doesn't extend chain)")]
     InvalidPreviousHash,
+    #[error("Invalid block timestamp {0} (must follow its predecessor and not be in the future)")]
+    InvalidTimestamp(u64),
+    #[error("Insufficient block security: {0:.4} < required {1:.4}")]
+    InsufficientSecurity(f64, f64),
+    #[error("Block weights don't match its heartbeats and continuity factors")]
+    InvalidBlockWeights,
+    #[error("Block bio_entropy doesn't match its heartbeats")]
+    InvalidBioEntropy,
+    #[error("Incompatible network (foreign genesis {0})")]
+    IncompatibleNetwork(String),
+    #[error("Block belongs to another network (chain id '{0}')")]
+    ForeignChainId(String),
+    #[error("Corrupt chain at block #{0}")]
+    CorruptChain(u64),
+    #[error("Invalid consensus config: {0}")]
+    InvalidConfig(String),
     #[error("Crypto error: {0}")]
     Crypto(#[from] CryptoError),
     #[error("Storage error: {0}")]
     Storage(#[from] crate::storage::StorageError),
 }
 
+impl ConsensusError {
+    /// Stable snake_case name of the variant, used as a metrics label
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::InvalidPublicKey => "invalid_public_key",
+            Self::InvalidHeartbeatSignature => "invalid_heartbeat_signature",
+            Self::StaleHeartbeat => "stale_heartbeat",
+            Self::FutureHeartbeat => "future_heartbeat",
+            Self::InvalidHeartRate(_) => "invalid_heart_rate",
+            Self::InvalidTemperature(_) => "invalid_temperature",
+            Self::InsufficientParticipants(..) => "insufficient_participants",
+            Self::InvalidTransactionSignature => "invalid_transaction_signature",
+            Self::UnsupportedSignatureVersion(_) => "unsupported_signature_version",
+            Self::DuplicateTransaction(_) => "duplicate_transaction",
+            Self::MempoolFull(_) => "mempool_full",
+            Self::InvalidNonce(..) => "invalid_nonce",
+            Self::TransactionTimelocked(_) => "transaction_timelocked",
+            Self::InsufficientBalance => "insufficient_balance",
+            Self::InvalidFee(_) => "invalid_fee",
+            Self::SenderNotPulsing => "sender_not_pulsing",
+            Self::InvalidHeartbeatReference => "invalid_heartbeat_reference",
+            Self::BiometricValidationFailed(_) => "biometric_validation_failed",
+            Self::InvalidBlockHash => "invalid_block_hash",
+            Self::InvalidPreviousHash => "invalid_previous_hash",
+            Self::InvalidTimestamp(_) => "invalid_timestamp",
+            Self::InsufficientSecurity(..) => "insufficient_security",
+            Self::InvalidBlockWeights => "invalid_block_weights",
+            Self::InvalidBioEntropy => "invalid_bio_entropy",
+            Self::IncompatibleNetwork(_) => "incompatible_network",
+            Self::ForeignChainId(_) => "foreign_chain_id",
+            Self::CorruptChain(_) => "corrupt_chain",
+            Self::InvalidConfig(_) => "invalid_config",
+            Self::Crypto(_) => "crypto",
+            Self::Storage(_) => "storage",
+        }
+    }
+}
+
+/// A chain rejected because of one of its blocks
+#[derive(Error, Debug)]
+#[error("Block #{index}: {source}")]
+pub struct ChainImportError {
+    /// Index of the offending block
+    pub index: u64,
+    #[source]
+    pub source: ConsensusError,
+}
+
+impl ChainImportError {
+    fn at(block: &PulseBlock, source: ConsensusError) -> Self {
+        Self { index: block.index, source }
+    }
+}
+
+/// What happens to the reward share of a device still in its warmup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WarmupRewardPolicy {
+    /// The share is never minted
+    Burn,
+    /// The share goes to the block's other participants, by weight
+    #[default]
+    Redistribute,
+}
+
+/// How a block reward is split among its participants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardPolicy {
+    /// In proportion to heartbeat weight, so more active wearers earn more
+    #[default]
+    Weighted,
+    /// The same share for every live participant (pure proof-of-life)
+    Equal,
+    /// In proportion to the square root of weight, compressing the range
+    SqrtWeighted,
+}
+
+impl RewardPolicy {
+    /// The weight a participant's share is proportional to
+    fn share_weight(self, weight: f64) -> f64 {
+        match self {
+            RewardPolicy::Weighted => weight,
+            RewardPolicy::Equal => 1.0,
+            RewardPolicy::SqrtWeighted => weight.max(0.0).sqrt(),
+        }
+    }
+}
+
+impl std::str::FromStr for RewardPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "weighted" => Ok(RewardPolicy::Weighted),
+            "equal" => Ok(RewardPolicy::Equal),
+            "sqrt_weighted" | "sqrt" => Ok(RewardPolicy::SqrtWeighted),
+            other => Err(format!("unknown reward policy '{}' (weighted|equal|sqrt_weighted)", other)),
+        }
+    }
+}
+
+/// Limit on one participant's share of a block reward, to blunt the advantage
+/// of splitting one wearer across many devices
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EarningCapPolicy {
+    /// Shares follow weight alone
+    #[default]
+    Off,
+    /// No participant earns more than this many PULSE from one block. The
+    /// excess goes to the participants below the cap, by weight; once every
+    /// participant is at the cap the rest is never minted.
+    PerBlock(f64),
+}
+
 /// Configuration for the consensus engine
 #[derive(Debug, Clone)]
 pub struct ConsensusConfig {
+    /// Network identifier committed to by the genesis and every block hash, so
+    /// separate networks never accept each other's blocks. Empty is the original
+    /// network, whose blocks predate chain ids.
+    pub chain_id: String,
     /// Minimum number of live participants to create a block
     pub n_threshold: usize,
     /// Block interval in milliseconds
@@ -51,6 +211,8 @@ pub struct ConsensusConfig {
     pub initial_reward_per_block: f64,
     /// Maximum heartbeat age in milliseconds
     pub max_heartbeat_age_ms: u64,
+    /// How far ahead of local time a heartbeat's timestamp may be (device clock skew)
+    pub max_clock_skew_ms: u64,
     /// Fork probability constant (k)
     pub fork_constant: f64,
     /// Halving interval: reward halves every N blocks
@@ -60,40 +222,344 @@ pub struct ConsensusConfig {
     pub min_reward_per_block: f64,
     /// Smoothing window: average inflation over last N blocks to prevent spikes
     pub inflation_smoothing_window: usize,
+    /// Valid heart rate range (BPM) — readings outside are rejected by the API and consensus
+    pub min_heart_rate: u16,
+    pub max_heart_rate: u16,
+    /// Hard temperature bounds (°C) — readings outside are rejected by the API and consensus
+    pub min_temperature: f32,
+    pub max_temperature: f32,
+    /// Plausible human temperature range (°C) — readings outside (but within the
+    /// hard bounds) are accepted with a biometric confidence penalty
+    pub plausible_min_temperature: f32,
+    pub plausible_max_temperature: f32,
+    /// Minimum block security (S = Σ W_i) required to produce or accept a block.
+    /// A difficulty analog: low-security blocks are cheap to fork.
+    pub min_block_security: f64,
+    /// Additional required security per block of height, so the bar rises as the chain matures
+    pub min_security_per_height: f64,
+    /// Remember chains rooted in a different genesis and reject their blocks quietly,
+    /// instead of treating them as forks and re-requesting sync forever
+    pub blacklist_incompatible_chains: bool,
+    /// Recompute received blocks' weights from their heartbeats and embedded
+    /// continuity factors, rejecting blocks whose claimed total doesn't match
+    pub verify_block_rewards: bool,
+    /// First height whose blocks must embed continuity factors. Blocks below it
+    /// without factors were produced before they existed and are accepted with
+    /// their weights unchecked. 0 requires factors in every block.
+    pub continuity_factors_height: u64,
+    /// Refuse heartbeats and transactions signed without a domain tag
+    /// (signature version 0). Off by default so existing clients keep working.
+    pub require_domain_tags: bool,
+    /// Hard cap on devices tracked for continuity/duplicate detection; beyond it the
+    /// least-recently-pulsed device is evicted, even if no staleness sweep runs
+    pub max_tracked_devices: usize,
+    /// Expected fraction of a block's participants that also pulsed in the previous
+    /// block. Blocks below it (e.g. a rotating botnet) earn reduced rewards. 0 disables.
+    pub min_participant_overlap: f64,
+    /// Reward multiplier applied to blocks below `min_participant_overlap`
+    pub low_overlap_reward_factor: f64,
+    /// Blocks a device must have participated in before it earns rewards, to
+    /// discourage drive-by devices. Until then it still counts toward `n_live`
+    /// and block weight. 0 disables.
+    pub min_blocks_before_reward: u64,
+    /// What happens to the rewards devices in their warmup would have earned.
+    /// A block whose participants are all warming up mints nothing either way.
+    pub warmup_reward_policy: WarmupRewardPolicy,
+    /// How block rewards are split among participants. Like `earning_cap`,
+    /// every node must agree on it.
+    pub reward_policy: RewardPolicy,
+    /// Per-participant cap on block rewards. Every node must use the same
+    /// policy, or they disagree on balances.
+    pub earning_cap: EarningCapPolicy,
+    /// On startup, truncate a corrupted stored chain to its last valid block and
+    /// rebuild accounts from it. Off by default: it deletes data.
+    pub repair_chain_on_startup: bool,
+    /// On startup, recompute every account from the stored blocks and overwrite
+    /// the stored accounts (see `rebuild_accounts_from_chain`)
+    pub rebuild_accounts_on_startup: bool,
+    /// Largest heartbeat metadata (key + value bytes) kept in the pool; larger
+    /// tag sets are dropped, the heartbeat itself still counts. 0 drops all tags.
+    pub max_heartbeat_metadata_bytes: usize,
+    /// Maximum number of pending transactions; new ones are refused when full
+    pub max_tx_pool: usize,
+    /// Minimum sum of pooled heartbeats' biometric confidences to produce a block,
+    /// so a crowd of barely-valid devices can't mint on its own. 0 disables.
+    pub min_confidence_sum: f64,
+    /// How far ahead of local time a received block's timestamp may be
+    pub max_future_drift_ms: u64,
+    /// Heartbeats before a device's personal biometric baseline is calibrated. 0 disables.
+    pub biometric_baseline_samples: usize,
+    /// Save calibrated biometric baselines to storage and restore them on startup
+    pub persist_biometric_baselines: bool,
+    /// Heart rate coefficient (α) of the heartbeat weight formula
+    pub weight_alpha: f64,
+    /// Motion coefficient (β) of the heartbeat weight formula
+    pub weight_beta: f64,
+    /// Continuity coefficient (γ) of the heartbeat weight formula
+    pub weight_gamma: f64,
+    /// Time a device must pulse continuously to earn full continuity credit
+    pub max_continuity_ms: u64,
+    /// Heartbeat signatures remembered as valid, so a heartbeat seen again
+    /// (re-gossiped or also submitted locally) skips the secp256k1 verify. 0 disables.
+    pub signature_cache_size: usize,
+    /// Most heartbeats included in one block. When more are pooled the heaviest
+    /// are included (ties to the lowest pubkey) and the rest wait for the next block.
+    pub max_heartbeats_per_block: usize,
+    /// Most transactions included in one block. When more are ready the highest
+    /// fees go first (each sender's still in nonce order) and the rest wait.
+    pub max_transactions_per_block: usize,
+    /// Latest blocks whose account changes are remembered, so a reorg forking
+    /// within this depth is rolled back in place. Deeper reorgs replay the
+    /// candidate chain from genesis. 0 always replays.
+    pub reorg_undo_depth: usize,
 }
 
 impl Default for ConsensusConfig {
     fn default() -> Self {
         Self {
+            chain_id: String::new(),
             n_threshold: 1,
             block_interval_ms: 5000,
             initial_reward_per_block: 100.0,
             max_heartbeat_age_ms: 30000,
+            max_clock_skew_ms: 5_000,
             fork_constant: 0.5,
             // Halving every 210,000 blocks (~12 days at 5s intervals)
             // Inspired by Bitcoin's model but on a faster cycle since blocks are faster
             halving_interval: 210_000,
             min_reward_per_block: 0.01,
             inflation_smoothing_window: 100,
+            min_heart_rate: 30,
+            max_heart_rate: 220,
+            min_temperature: 25.0,
+            max_temperature: 45.0,
+            plausible_min_temperature: 33.0,
+            plausible_max_temperature: 42.0,
+            min_block_security: 0.0,
+            min_security_per_height: 0.0,
+            blacklist_incompatible_chains: true,
+            verify_block_rewards: true,
+            continuity_factors_height: 0,
+            require_domain_tags: false,
+            max_tracked_devices: 100_000,
+            min_participant_overlap: 0.0,
+            low_overlap_reward_factor: 0.5,
+            min_blocks_before_reward: 0,
+            warmup_reward_policy: WarmupRewardPolicy::default(),
+            reward_policy: RewardPolicy::default(),
+            earning_cap: EarningCapPolicy::default(),
+            repair_chain_on_startup: false,
+            rebuild_accounts_on_startup: false,
+            max_heartbeat_metadata_bytes: 1024,
+            max_tx_pool: 10_000,
+            min_confidence_sum: 0.0,
+            max_future_drift_ms: 60_000,
+            biometric_baseline_samples: 30,
+            persist_biometric_baselines: false,
+            weight_alpha: 0.4,
+            weight_beta: 0.3,
+            weight_gamma: 0.3,
+            // 5 minutes for full continuity credit
+            max_continuity_ms: 300_000,
+            signature_cache_size: 10_000,
+            max_heartbeats_per_block: 5_000,
+            max_transactions_per_block: 1_000,
+            reorg_undo_depth: 100,
         }
     }
 }
 
+/// Allowed deviation of α + β + γ from 1.0
+const WEIGHT_SUM_TOLERANCE: f64 = 1e-6;
+
 impl ConsensusConfig {
-    /// Calculate the block reward at a given block height, applying halvings.
+    /// Check settings that would make weights or rewards meaningless.
+    /// The weight coefficients must be non-negative and sum to 1.0.
+    pub fn validate(&self) -> Result<(), ConsensusError> {
+        let params = self.weight_params();
+        if [params.alpha, params.beta, params.gamma].iter().any(|c| !c.is_finite() || *c < 0.0) {
+            return Err(ConsensusError::InvalidConfig(
+                "weight coefficients must be non-negative".to_string(),
+            ));
+        }
+        let sum = params.alpha + params.beta + params.gamma;
+        if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+            return Err(ConsensusError::InvalidConfig(format!(
+                "weight coefficients must sum to 1.0 (alpha + beta + gamma = {sum})"
+            )));
+        }
+        if self.min_heart_rate > self.max_heart_rate {
+            return Err(ConsensusError::InvalidConfig(format!(
+                "min_heart_rate ({}) must not exceed max_heart_rate ({})",
+                self.min_heart_rate, self.max_heart_rate
+            )));
+        }
+        if self.max_continuity_ms == 0 {
+            return Err(ConsensusError::InvalidConfig(
+                "max_continuity_ms must be greater than 0".to_string(),
+            ));
+        }
+        if self.chain_id.len() > MAX_CHAIN_ID_LEN
+            || !self.chain_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        {
+            return Err(ConsensusError::InvalidConfig(format!(
+                "chain_id must be at most {} characters of [A-Za-z0-9._-]", MAX_CHAIN_ID_LEN
+            )));
+        }
+        if self.max_heartbeats_per_block < self.n_threshold.max(1) {
+            return Err(ConsensusError::InvalidConfig(format!(
+                "max_heartbeats_per_block ({}) must be at least n_threshold ({})",
+                self.max_heartbeats_per_block, self.n_threshold.max(1)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Heartbeat weight coefficients
+    pub fn weight_params(&self) -> WeightParams {
+        WeightParams {
+            alpha: self.weight_alpha,
+            beta: self.weight_beta,
+            gamma: self.weight_gamma,
+        }
+    }
+
+    /// Calculate the block reward (micro-PULSE) at a given block height, applying halvings.
     /// R(h) = initial_reward / 2^(h / halving_interval)
     /// Clamped to min_reward_per_block.
-    pub fn reward_at_height(&self, block_height: u64) -> f64 {
+    pub fn reward_at_height(&self, block_height: u64) -> Amount {
+        let initial = to_micro(self.initial_reward_per_block);
+        let min = to_micro(self.min_reward_per_block);
         if self.halving_interval == 0 {
-            return self.initial_reward_per_block;
+            return initial;
         }
         let halvings = block_height / self.halving_interval;
         // After 64 halvings the reward is effectively 0
         if halvings >= 64 {
-            return self.min_reward_per_block;
+            return min;
+        }
+        (initial >> halvings).max(min)
+    }
+    
+    /// Fork constant k scaled to network size:
+    /// small networks (1-10 participants) need strong per-participant security
+    /// (k=2.0 for a single participant), large ones much less (k→0.000001 at 1M+).
+    /// Formula: k = base_k / ln(1 + n_live), clamped
+    pub fn adaptive_fork_constant(&self, n_live: usize) -> f64 {
+        if n_live <= 1 {
+            2.0
+        } else {
+            (self.fork_constant / (1.0 + n_live as f64).ln()).max(0.000001)
+        }
+    }
+    
+    /// Block reward after the participant-overlap penalty, given the block it extends.
+    /// Only applies when an overlap expectation is configured and the previous block had participants.
+    pub fn reward_for_block(&self, prev: Option<&PulseBlock>, block: &PulseBlock) -> Amount {
+        let reward = self.reward_at_height(block.index);
+        match prev {
+            Some(prev) if self.min_participant_overlap > 0.0
+                && !prev.heartbeats.is_empty()
+                && participant_overlap(prev, block) < self.min_participant_overlap =>
+            {
+                (reward as f64 * self.low_overlap_reward_factor.clamp(0.0, 1.0)) as Amount
+            }
+            _ => reward,
+        }
+    }
+
+    /// Minimum security a block at the given height must carry.
+    /// S_min(h) = min_block_security + min_security_per_height · h
+    pub fn min_security_at_height(&self, block_height: u64) -> f64 {
+        self.min_block_security + self.min_security_per_height * block_height as f64
+    }
+
+    /// Whether a heart rate is within the valid range
+    pub fn heart_rate_in_bounds(&self, heart_rate: u16) -> bool {
+        (self.min_heart_rate..=self.max_heart_rate).contains(&heart_rate)
+    }
+
+    /// Whether a temperature reading is within the hard accept bounds
+    pub fn temperature_in_bounds(&self, temperature: f32) -> bool {
+        (self.min_temperature..=self.max_temperature).contains(&temperature)
+    }
+
+    /// Plausibility bounds handed to the biometric validator
+    pub fn biometric_config(&self) -> BiometricConfig {
+        BiometricConfig {
+            min_heart_rate: self.min_heart_rate,
+            max_heart_rate: self.max_heart_rate,
+            plausible_min_temperature: self.plausible_min_temperature,
+            plausible_max_temperature: self.plausible_max_temperature,
+            baseline_samples: self.biometric_baseline_samples,
+        }
+    }
+}
+
+/// Longest accepted `ConsensusConfig::chain_id`
+const MAX_CHAIN_ID_LEN: usize = 64;
+/// Maximum number of foreign chain tips tracked for incompatible-network detection
+const MAX_INCOMPATIBLE_TIPS: usize = 64;
+
+/// Heartbeat hashes remembered per device for duplicate detection; older ones
+/// are also forgotten once their heartbeat is past `max_heartbeat_age_ms`
+const MAX_RECENT_HASHES_PER_DEVICE: usize = 64;
+
+/// Recent blocks considered for the `/stats` Nakamoto coefficient
+pub const NAKAMOTO_WINDOW_BLOCKS: usize = 100;
+
+/// Recent blocks considered for the `/stats` transaction throughput and average block time
+pub const TPS_WINDOW_BLOCKS: usize = 20;
+
+/// Signal quality of an accepted heartbeat, from biometric validation
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct HeartbeatAccepted {
+    /// Confidence [0, 1] that the heartbeat came from a live human
+    pub confidence: f64,
+    /// Heart rate variability (SDNN in BPM) — 0 until the device has enough history
+    pub hrv_sdnn: f64,
+}
+
+/// Largest fee a transaction may carry, in PULSE. Far above any balance, and
+/// small enough that it converts to micro-PULSE exactly, without saturating.
+const MAX_TX_FEE: f64 = 1e15;
+
+/// A peer-reported tip we don't have yet is forgotten after this long without
+/// a fresh report, so a sync that died doesn't hold the node in `Syncing`
+const SYNC_TARGET_TTL_MS: u64 = 60_000;
+
+/// Whether the chain has caught up with the tips peers report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SyncState {
+    /// Behind a peer: our tip is `current`, theirs `target`
+    Syncing { current: u64, target: u64 },
+    Synced,
+}
+
+/// Account state a block changed, kept so a reorg can undo it
+#[derive(Debug, Clone)]
+struct BlockUndo {
+    /// State before the block of every account it touched (`None`: the block created it)
+    accounts: Vec<(String, Option<Account>)>,
+    /// Rewards the block minted
+    minted: Amount,
+}
+
+impl BlockUndo {
+    /// Capture the accounts `block` is about to touch
+    fn before(accounts: &HashMap<String, Account>, block: &PulseBlock) -> Self {
+        let touched: BTreeSet<&str> = block.heartbeats.iter()
+            .map(|hb| hb.device_pubkey.as_str())
+            .chain(block.transactions.iter()
+                .flat_map(|tx| [tx.sender_pubkey.as_str(), tx.recipient_pubkey.as_str()]))
+            .collect();
+        Self {
+            accounts: touched.into_iter()
+                .map(|pubkey| (pubkey.to_string(), accounts.get(pubkey).cloned()))
+                .collect(),
+            minted: 0,
         }
-        let reward = self.initial_reward_per_block / (2u64.pow(halvings as u32) as f64);
-        reward.max(self.min_reward_per_block)
     }
 }
 
@@ -102,55 +568,137 @@ pub struct ProofOfLife {
     config: ConsensusConfig,
     /// Current chain
     chain: Vec<PulseBlock>,
+    /// Headers of pruned blocks (between genesis and the first block in `chain` after it)
+    pruned_headers: Vec<BlockHeader>,
     /// Pool of verified heartbeats awaiting block inclusion
     heartbeat_pool: HashMap<String, Heartbeat>, // pubkey -> heartbeat
+    /// Biometric confidence of each pooled heartbeat (pubkey -> confidence)
+    pool_confidence: HashMap<String, f64>,
     /// Pool of pending transactions
     tx_pool: Vec<Transaction>,
+    /// Transactions dropped from the last block attempt (sender no longer live or
+    /// short of funds), held until `take_dropped_transactions` hands them back
+    dropped_txs: Vec<Transaction>,
     /// Account balances
     accounts: HashMap<String, Account>,
     /// Total tokens minted
-    total_minted: f64,
+    total_minted: Amount,
+    /// How to undo the account changes of the latest blocks, oldest first; the
+    /// last entry belongs to the tip. Lets a reorg roll back in place.
+    undo_log: VecDeque<BlockUndo>,
     /// Persistent storage (optional — None means in-memory only)
-    storage: Option<Arc<Storage>>,
+    storage: Option<Arc<dyn ChainStore>>,
     /// Tracks when each device first started pulsing in current session (pubkey -> timestamp_ms)
     /// Used for continuity factor (γ·Δt_i)
     continuity_start: HashMap<String, u64>,
-    /// Tracks last seen heartbeat hash per pubkey to prevent duplicate submissions
-    last_heartbeat_hash: HashMap<String, String>,
+    /// Hashes of each device's heartbeats still within the age window, as
+    /// (heartbeat timestamp, hash) in arrival order, to reject any resubmission
+    recent_heartbeat_hashes: HashMap<String, VecDeque<(u64, String)>>,
+    /// LRU order of tracked devices (pulse sequence number -> pubkey, oldest first)
+    device_recency: BTreeMap<u64, String>,
+    /// Latest pulse sequence number per tracked device (index into `device_recency`)
+    device_last_seq: HashMap<String, u64>,
+    /// Monotonic counter for `device_recency`
+    pulse_seq: u64,
     /// Cumulative chain weight (sum of all block security values)
     /// Used for fork resolution: heaviest chain wins
     cumulative_weight: f64,
     /// Biometric validator for sensor spoofing detection
     biometric_validator: BiometricValidator,
+    /// Per-participant block index (pubkey -> heights of blocks they pulsed in, ascending)
+    participant_index: HashMap<String, Vec<u64>>,
+    /// Genesis hashes of foreign networks we've seen (each is warned about once)
+    incompatible_genesis: HashSet<String>,
+    /// Latest known tips of foreign chains (tip hash, genesis hash), so blocks
+    /// extending them are recognized without another sync round-trip
+    incompatible_tips: VecDeque<(String, String)>,
+    /// Event counters exported on `/metrics`
+    metrics: ConsensusMetrics,
+    /// Heartbeat signatures already verified as valid
+    signature_cache: SignatureCache,
+    /// Highest tip a peer reported beyond ours, and when (ms) it was last reported
+    sync_target: Option<(u64, u64)>,
 }
 
 impl ProofOfLife {
     /// Create a new consensus engine with genesis block (in-memory only)
     pub fn new(config: ConsensusConfig) -> Self {
-        let genesis = Self::create_genesis_block();
+        let genesis = Self::create_genesis_block(&config.chain_id);
         info!("🌱 Genesis block created: {}...", &genesis.block_hash[..16]);
+        let biometric_validator = BiometricValidator::with_config(config.biometric_config());
+        let signature_cache = SignatureCache::new(config.signature_cache_size);
         
         Self {
             config,
             chain: vec![genesis],
+            pruned_headers: Vec::new(),
             heartbeat_pool: HashMap::new(),
+            pool_confidence: HashMap::new(),
             tx_pool: Vec::new(),
+            dropped_txs: Vec::new(),
+            metrics: ConsensusMetrics::default(),
             accounts: HashMap::new(),
-            total_minted: 0.0,
+            total_minted: 0,
+            undo_log: VecDeque::new(),
             storage: None,
             continuity_start: HashMap::new(),
-            last_heartbeat_hash: HashMap::new(),
+            recent_heartbeat_hashes: HashMap::new(),
+            device_recency: BTreeMap::new(),
+            device_last_seq: HashMap::new(),
+            pulse_seq: 0,
             cumulative_weight: 0.0,
-            biometric_validator: BiometricValidator::new(),
+            biometric_validator,
+            signature_cache,
+            sync_target: None,
+            participant_index: HashMap::new(),
+            incompatible_genesis: HashSet::new(),
+            incompatible_tips: VecDeque::new(),
         }
     }
 
     /// Create a new consensus engine with persistent storage.
-    /// Loads existing chain from disk if present, otherwise creates genesis.
-    pub fn with_storage(config: ConsensusConfig, storage: Arc<Storage>) -> Result<Self, ConsensusError> {
+    /// Loads existing chain from the store if present, otherwise creates genesis.
+    /// A stored chain that fails validation is an error (`CorruptChain`) unless
+    /// `repair_chain_on_startup` is set.
+    pub fn with_storage(config: ConsensusConfig, storage: Arc<dyn ChainStore>) -> Result<Self, ConsensusError> {
+        config.validate()?;
+        
         // Try to load existing chain
-        let stored_blocks = storage.load_all_blocks()?;
-        let stored_accounts = storage.load_all_accounts()?;
+        let mut stored_blocks = storage.load_all_blocks()?;
+        let mut stored_accounts = storage.load_all_accounts()?;
+        let pruned_headers = storage.load_block_headers()?;
+        
+        if let Some(genesis) = stored_blocks.first().filter(|b| b.index == 0 && b.chain_id != config.chain_id) {
+            error!("❌ Stored chain belongs to chain id '{}', not '{}' — use a separate data directory per network",
+                genesis.chain_id, config.chain_id);
+            return Err(ConsensusError::ForeignChainId(genesis.chain_id.clone()));
+        }
+        
+        if let Some(bad_height) = Self::first_invalid_height(&stored_blocks, &pruned_headers, &config.chain_id) {
+            if config.repair_chain_on_startup && !pruned_headers.is_empty() {
+                error!("❌ Stored chain is corrupt at height {} — can't repair a pruned chain \
+                    (accounts can't be replayed without the pruned blocks)", bad_height);
+                return Err(ConsensusError::CorruptChain(bad_height));
+            } else if config.repair_chain_on_startup {
+                stored_accounts = Self::repair_stored_chain(&config, storage.as_ref(), &mut stored_blocks, bad_height)?;
+            } else {
+                error!("❌ Stored chain is corrupt at height {} — restart with chain repair \
+                    enabled to truncate it", bad_height);
+                return Err(ConsensusError::CorruptChain(bad_height));
+            }
+        }
+        
+        let mut biometric_validator = BiometricValidator::with_config(config.biometric_config());
+        let signature_cache = SignatureCache::new(config.signature_cache_size);
+        if config.persist_biometric_baselines {
+            let baselines = storage.load_baselines()?;
+            if !baselines.is_empty() {
+                info!("📐 Restored {} biometric baseline(s)", baselines.len());
+            }
+            for (pubkey, baseline) in baselines {
+                biometric_validator.set_baseline(&pubkey, baseline);
+            }
+        }
         
         if !stored_blocks.is_empty() {
             // Reconstruct from storage
@@ -163,34 +711,57 @@ impl ProofOfLife {
             }
             
             // Calculate total minted from accounts
-            let total_minted: f64 = accounts.values().map(|a| a.total_earned).sum();
+            let total_minted: Amount = accounts.values().map(|a| a.total_earned).sum();
             
             info!("💾 Loaded chain from storage:");
             info!("   Chain height: {}", chain_height);
             info!("   Blocks: {}", stored_blocks.len());
             info!("   Accounts: {}", accounts.len());
-            // Calculate cumulative chain weight from stored blocks
-            let cumulative_weight: f64 = stored_blocks.iter().map(|b| b.security).sum();
+            // Calculate cumulative chain weight from stored blocks and pruned headers
+            let cumulative_weight = chain_weight_of(&stored_blocks)
+                + pruned_headers.iter().map(|h| h.security).sum::<f64>();
+            if !pruned_headers.is_empty() {
+                info!("   Pruned blocks: {} (headers only)", pruned_headers.len());
+            }
             
-            info!("   Total minted: {:.4} PULSE", total_minted);
+            info!("   Total minted: {:.4} PULSE", to_pulse(total_minted));
             info!("   Cumulative weight: {:.4}", cumulative_weight);
             
-            Ok(Self {
+            let participant_index = Self::build_participant_index(&stored_blocks);
+            
+            let mut pol = Self {
                 config,
                 chain: stored_blocks,
+                pruned_headers,
                 heartbeat_pool: HashMap::new(),
+                pool_confidence: HashMap::new(),
                 tx_pool: Vec::new(),
+                dropped_txs: Vec::new(),
+                metrics: ConsensusMetrics::default(),
                 accounts,
                 total_minted,
+                undo_log: VecDeque::new(),
                 storage: Some(storage),
                 continuity_start: HashMap::new(),
-                last_heartbeat_hash: HashMap::new(),
+                recent_heartbeat_hashes: HashMap::new(),
+                device_recency: BTreeMap::new(),
+                device_last_seq: HashMap::new(),
+                pulse_seq: 0,
                 cumulative_weight,
-                biometric_validator: BiometricValidator::new(),
-            })
+                biometric_validator,
+                signature_cache,
+                sync_target: None,
+                participant_index,
+                incompatible_genesis: HashSet::new(),
+                incompatible_tips: VecDeque::new(),
+            };
+            if pol.config.rebuild_accounts_on_startup {
+                pol.rebuild_accounts_from_chain()?;
+            }
+            Ok(pol)
         } else {
             // Fresh start with genesis
-            let genesis = Self::create_genesis_block();
+            let genesis = Self::create_genesis_block(&config.chain_id);
             info!("🌱 Genesis block created: {}...", &genesis.block_hash[..16]);
             
             // Persist genesis block
@@ -204,22 +775,36 @@ impl ProofOfLife {
             Ok(Self {
                 config,
                 chain: vec![genesis],
+                pruned_headers: Vec::new(),
                 heartbeat_pool: HashMap::new(),
+                pool_confidence: HashMap::new(),
                 tx_pool: Vec::new(),
+                dropped_txs: Vec::new(),
+                metrics: ConsensusMetrics::default(),
                 accounts: HashMap::new(),
-                total_minted: 0.0,
+                total_minted: 0,
+                undo_log: VecDeque::new(),
                 storage: Some(storage),
                 continuity_start: HashMap::new(),
-                last_heartbeat_hash: HashMap::new(),
+                recent_heartbeat_hashes: HashMap::new(),
+                device_recency: BTreeMap::new(),
+                device_last_seq: HashMap::new(),
+                pulse_seq: 0,
                 cumulative_weight: 0.0,
-            biometric_validator: BiometricValidator::new(),
+                biometric_validator,
+                signature_cache,
+                sync_target: None,
+                participant_index: HashMap::new(),
+                incompatible_genesis: HashSet::new(),
+                incompatible_tips: VecDeque::new(),
             })
         }
     }
     
-    fn create_genesis_block() -> PulseBlock {
+    fn create_genesis_block(chain_id: &str) -> PulseBlock {
         // Genesis timestamp is fixed so all nodes produce the same genesis hash.
         // This is critical for P2P — nodes must agree on genesis to sync chains.
+        // The chain id is the only thing that varies it between networks.
         const GENESIS_TIMESTAMP: u64 = 1739145600000; // 2025-02-10T00:00:00Z
         
         let mut block = PulseBlock {
@@ -232,6 +817,12 @@ impl ProofOfLife {
             total_weight: 0.0,
             security: 0.0,
             bio_entropy: "0".repeat(64),
+            continuity_factors: BTreeMap::new(),
+            confidence_factors: BTreeMap::new(),
+            // Genesis keeps the legacy full-content hash so every node agrees on it
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: chain_id.to_string(),
             block_hash: String::new(),
         };
         block.block_hash = block.compute_hash();
@@ -265,74 +856,309 @@ impl ProofOfLife {
         }
     }
     
-    /// Verify and add a heartbeat to the pool
-    pub fn receive_heartbeat(&mut self, hb: Heartbeat) -> Result<(), ConsensusError> {
-        // 1. Verify signature
-        let valid = verify_signature(
-            &hb.device_pubkey,
-            &hb.signable_bytes(),
-            &hb.signature,
-        )?;
+    /// Check the integrity of a full chain (see `first_invalid_height`);
+    /// fails with the index of the first bad block
+    pub fn validate_chain(blocks: &[PulseBlock], chain_id: &str) -> Result<(), ConsensusError> {
+        match Self::first_invalid_height(blocks, &[], chain_id) {
+            Some(height) => Err(ConsensusError::CorruptChain(height)),
+            None => Ok(()),
+        }
+    }
+    
+    /// Height of the first block that breaks chain integrity (the genesis for `chain_id`,
+    /// index sequence, block hashes, hash links, timestamp order or heartbeat
+    /// signatures), or None if the chain is sound. Pruned headers stand in for missing bodies:
+    /// their links and order are checked, their contents can't be.
+    fn first_invalid_height(blocks: &[PulseBlock], pruned: &[BlockHeader], chain_id: &str) -> Option<u64> {
+        // (index, hash, timestamp) of the block or header before the current one
+        let mut prev: Option<(u64, &str, u64)> = None;
+        let follows = |prev: Option<(u64, &str, u64)>, index: u64, previous_hash: &str, timestamp: u64| match prev {
+            None => index == 0,
+            Some((p_index, p_hash, p_time)) => index == p_index + 1 && previous_hash == p_hash && timestamp > p_time,
+        };
+        let mut headers = pruned.iter().peekable();
+        let genesis_hash = Self::create_genesis_block(chain_id).block_hash;
         
-        if !valid {
-            warn!("❌ Invalid signature from {}...", &hb.device_pubkey[..8]);
+        for block in blocks {
+            while let Some(header) = headers.next_if(|h| h.index < block.index) {
+                if !follows(prev, header.index, &header.previous_hash, header.timestamp) {
+                    return Some(header.index);
+                }
+                prev = Some((header.index, &header.block_hash, header.timestamp));
+            }
+            let broken = !follows(prev, block.index, &block.previous_hash, block.timestamp)
+                || (block.index == 0 && block.block_hash != genesis_hash)
+                || block.block_hash != block.compute_hash()
+                || !block.merkle_roots_valid()
+                || verify_block_heartbeats(block).iter().any(|(_, valid)| !valid);
+            if broken {
+                return Some(prev.map(|(index, _, _)| index + 1).unwrap_or(0));
+            }
+            prev = Some((block.index, &block.block_hash, block.timestamp));
+        }
+        // Headers past the last body mean the tip itself was pruned
+        headers.next().map(|h| h.index)
+    }
+    
+    /// Verify the integrity of the current chain; returns the first bad height
+    pub fn verify_chain(&self) -> Option<u64> {
+        Self::first_invalid_height(&self.chain, &self.pruned_headers, &self.config.chain_id)
+    }
+    
+    /// Drop the bodies of blocks `1..keep_after` from memory and storage,
+    /// keeping their headers (see `ChainStore::prune_blocks`). Lookups of
+    /// pruned blocks return nothing; the cumulative weight is unchanged.
+    /// Returns how many blocks were pruned.
+    pub fn prune(&mut self, keep_after: u64) -> Result<usize, ConsensusError> {
+        if let Some(ref storage) = self.storage {
+            storage.prune_blocks(keep_after)?;
+        }
+        let before = self.chain.len();
+        let mut retained = Vec::with_capacity(before);
+        for block in std::mem::take(&mut self.chain) {
+            if block.index > 0 && block.index < keep_after {
+                self.pruned_headers.push(block.header());
+            } else {
+                retained.push(block);
+            }
+        }
+        self.chain = retained;
+        let pruned = before - self.chain.len();
+        if pruned > 0 {
+            debug!("✂️ Pruned {} block(s) below #{}", pruned, keep_after);
+        }
+        Ok(pruned)
+    }
+    
+    /// Truncate the stored chain to the blocks below `bad_height`, rebuild and
+    /// persist accounts from what remains, and return the rebuilt accounts
+    fn repair_stored_chain(
+        config: &ConsensusConfig,
+        storage: &dyn ChainStore,
+        blocks: &mut Vec<PulseBlock>,
+        bad_height: u64,
+    ) -> Result<Vec<Account>, ConsensusError> {
+        let discarded = blocks.len() as u64 - bad_height;
+        warn!("🔧 Chain repair: corruption at height {}, discarding {} block(s) (#{}..=#{})",
+            bad_height, discarded, bad_height, blocks.len() as u64 - 1);
+        
+        blocks.truncate(bad_height as usize);
+        storage.truncate_blocks_from(bad_height)?;
+        
+        let (accounts, total_minted) = Self::replay_accounts(config, blocks);
+        storage.replace_accounts(&accounts.values().cloned().collect::<Vec<_>>())?;
+        storage.flush()?;
+        
+        warn!("🔧 Chain repair: resumed at height {} with {} accounts ({:.4} PULSE minted)",
+            bad_height.saturating_sub(1), accounts.len(), to_pulse(total_minted));
+        Ok(accounts.into_values().collect())
+    }
+    
+    /// Recompute every account and the total minted by replaying the chain's
+    /// rewards and transactions from genesis, replacing the incrementally
+    /// maintained state and the stored accounts. Returns how many accounts
+    /// differed from the replay. Needs every block body, so fails on a pruned chain.
+    pub fn rebuild_accounts_from_chain(&mut self) -> Result<usize, ConsensusError> {
+        if let Some(header) = self.pruned_headers.first() {
+            return Err(crate::storage::StorageError::BlockPruned(header.index).into());
+        }
+        let (accounts, total_minted) = Self::replay_accounts(&self.config, &self.chain);
+        let corrected = accounts.values().filter(|a| self.accounts.get(&a.pubkey) != Some(*a)).count()
+            + self.accounts.keys().filter(|pubkey| !accounts.contains_key(*pubkey)).count();
+        
+        if let Some(ref storage) = self.storage {
+            storage.replace_accounts(&accounts.values().cloned().collect::<Vec<_>>())?;
+            storage.flush()?;
+        }
+        if corrected > 0 {
+            warn!("🧮 Rebuilt accounts from {} block(s): corrected {} of {} account(s)",
+                self.chain.len(), corrected, accounts.len());
+        } else {
+            info!("🧮 Rebuilt accounts from {} block(s): all {} account(s) matched",
+                self.chain.len(), accounts.len());
+        }
+        
+        self.accounts = accounts;
+        self.total_minted = total_minted;
+        // Undo entries hold pre-rebuild account states
+        self.undo_log.clear();
+        Ok(corrected)
+    }
+    
+    /// Rebuild account state by replaying rewards and transactions of a chain.
+    /// Returns the accounts and the total minted.
+    fn replay_accounts(config: &ConsensusConfig, blocks: &[PulseBlock]) -> (HashMap<String, Account>, Amount) {
+        let mut accounts: HashMap<String, Account> = HashMap::new();
+        let mut total_minted = 0;
+        
+        for (i, block) in blocks.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|p| &blocks[p]);
+            total_minted += apply_block_accounts(config, &mut accounts, prev, block).0;
+        }
+        
+        (accounts, total_minted)
+    }
+    
+    /// Build the per-participant block index from a full chain
+    fn build_participant_index(blocks: &[PulseBlock]) -> HashMap<String, Vec<u64>> {
+        let mut index: HashMap<String, Vec<u64>> = HashMap::new();
+        for block in blocks {
+            Self::index_participants(&mut index, block);
+        }
+        index
+    }
+    
+    /// Record a block's participants in the index (blocks must be indexed in height order)
+    fn index_participants(index: &mut HashMap<String, Vec<u64>>, block: &PulseBlock) {
+        for hb in &block.heartbeats {
+            let heights = index.entry(hb.device_pubkey.clone()).or_default();
+            if heights.last() != Some(&block.index) {
+                heights.push(block.index);
+            }
+        }
+    }
+    
+    /// Verify and add a heartbeat to the pool, returning its biometric signal quality
+    pub fn receive_heartbeat(&mut self, hb: Heartbeat) -> Result<HeartbeatAccepted, ConsensusError> {
+        let result = self.verify_and_pool_heartbeat(hb);
+        match &result {
+            Ok(_) => self.metrics.heartbeats_accepted += 1,
+            Err(e) => self.metrics.record_heartbeat_rejected(e.kind()),
+        }
+        result
+    }
+    
+    /// Refuse signing formats this node doesn't know, and untagged ones when
+    /// `require_domain_tags` is set
+    fn check_signature_version(&self, version: u8) -> Result<(), ConsensusError> {
+        if version > SIGNATURE_VERSION || (version == 0 && self.config.require_domain_tags) {
+            return Err(ConsensusError::UnsupportedSignatureVersion(version));
+        }
+        Ok(())
+    }
+    
+    /// Verify a heartbeat's signature, skipping the secp256k1 verify when the
+    /// same signed data was already verified. Only valid signatures are cached.
+    fn verify_heartbeat_signature(&mut self, hb: &Heartbeat) -> Result<bool, ConsensusError> {
+        let signable = hb.signable_bytes();
+        let key = SignatureCache::key(&signable, &hb.signature);
+        if self.signature_cache.contains(&key) {
+            self.metrics.signature_cache_hits += 1;
+            return Ok(true);
+        }
+        let valid = verify_signature(&hb.device_pubkey, &signable, &hb.signature)?;
+        if valid {
+            self.signature_cache.insert(key);
+        }
+        Ok(valid)
+    }
+    
+    fn verify_and_pool_heartbeat(&mut self, mut hb: Heartbeat) -> Result<HeartbeatAccepted, ConsensusError> {
+        self.check_signature_version(hb.signature_version)?;
+        
+        // 0. Recoverable heartbeats: the signer's key comes from the signature
+        if hb.resolve_pubkey().is_err() {
+            warn!("❌ Recoverable heartbeat signature does not match its pubkey");
+            return Err(ConsensusError::InvalidHeartbeatSignature);
+        }
+
+        // 0b. Pubkey must be a point on the curve — reported as such rather than
+        // as a signature failure
+        if parse_pubkey(&hb.device_pubkey).is_err() {
+            return Err(ConsensusError::InvalidPublicKey);
+        }
+        
+        // 1. Verify signature
+        if !self.verify_heartbeat_signature(&hb)? {
+            warn!(pubkey = &hb.device_pubkey[..8], "❌ Invalid heartbeat signature");
             return Err(ConsensusError::InvalidHeartbeatSignature);
         }
         
-        // 2. Check timestamp freshness
+        // 2. Check timestamp freshness — neither stale nor ahead of our clock beyond skew
         let now = current_time_ms();
         if now.saturating_sub(hb.timestamp) > self.config.max_heartbeat_age_ms {
-            warn!("❌ Stale heartbeat from {}...", &hb.device_pubkey[..8]);
+            warn!(pubkey = &hb.device_pubkey[..8], age_ms = now - hb.timestamp, "❌ Stale heartbeat");
             return Err(ConsensusError::StaleHeartbeat);
         }
+        if hb.timestamp > now.saturating_add(self.config.max_clock_skew_ms) {
+            warn!(pubkey = &hb.device_pubkey[..8], ahead_ms = hb.timestamp - now, "❌ Future-dated heartbeat");
+            return Err(ConsensusError::FutureHeartbeat);
+        }
         
         // 3. Validate heart rate range
-        if hb.heart_rate < 30 || hb.heart_rate > 220 {
+        if !self.config.heart_rate_in_bounds(hb.heart_rate) {
             return Err(ConsensusError::InvalidHeartRate(hb.heart_rate));
         }
         
+        // 3b. Validate temperature against the hard bounds (plausibility is scored below)
+        if !self.config.temperature_in_bounds(hb.temperature) {
+            return Err(ConsensusError::InvalidTemperature(hb.temperature));
+        }
+        
         // 4. Biometric validation — detect synthetic/spoofed heartbeats
+        let had_baseline = self.biometric_validator.baseline_for(&hb.device_pubkey).is_some();
         let bio_result = self.biometric_validator.validate(
             &hb.device_pubkey,
             hb.heart_rate,
             hb.motion.magnitude(),
             hb.temperature,
         );
+        if !had_baseline && self.config.persist_biometric_baselines {
+            if let (Some(baseline), Some(storage)) = (self.biometric_validator.baseline_for(&hb.device_pubkey), &self.storage) {
+                if let Err(e) = storage.save_baseline(&hb.device_pubkey, &baseline) {
+                    warn!("⚠️ Failed to persist biometric baseline: {}", e);
+                }
+            }
+        }
         
         if !bio_result.is_valid {
             let reason = bio_result.reason.unwrap_or_else(|| "Unknown".to_string());
-            warn!("🚨 Biometric validation failed for {}...: {}", &hb.device_pubkey[..8], reason);
+            warn!(pubkey = &hb.device_pubkey[..8], reason = %reason, "🚨 Biometric validation failed");
             return Err(ConsensusError::BiometricValidationFailed(reason));
         }
         
-        // 5. Duplicate check — reject identical heartbeat data resubmission
-        // (renumbered after adding biometric check above)
+        // 5. Duplicate check — reject resubmission of any heartbeat the device sent
+        // within the age window, not just its latest (older ones are stale anyway)
         let hb_hash = crate::crypto::hash_sha256(&hb.signable_bytes());
-        if let Some(last_hash) = self.last_heartbeat_hash.get(&hb.device_pubkey) {
-            if *last_hash == hb_hash {
-                warn!("❌ Duplicate heartbeat from {}...", &hb.device_pubkey[..8]);
-                return Err(ConsensusError::StaleHeartbeat);
-            }
+        let recent = self.recent_heartbeat_hashes.entry(hb.device_pubkey.clone()).or_default();
+        while recent.front().is_some_and(|(ts, _)| now.saturating_sub(*ts) > self.config.max_heartbeat_age_ms) {
+            recent.pop_front();
+        }
+        if recent.iter().any(|(_, hash)| *hash == hb_hash) {
+            warn!(pubkey = &hb.device_pubkey[..8], "❌ Duplicate heartbeat");
+            return Err(ConsensusError::StaleHeartbeat);
         }
-        self.last_heartbeat_hash.insert(hb.device_pubkey.clone(), hb_hash);
+        if recent.len() >= MAX_RECENT_HASHES_PER_DEVICE {
+            recent.pop_front();
+        }
+        recent.push_back((hb.timestamp, hb_hash));
         
         // 5. Track continuity — record when this device first started pulsing
         let now = current_time_ms();
         self.continuity_start
             .entry(hb.device_pubkey.clone())
             .or_insert(now);
+        self.touch_device(&hb.device_pubkey);
+        
+        // 6. Metadata is pool-only analytics; drop oversized tag sets rather than the heartbeat
+        if hb.metadata_bytes() > self.config.max_heartbeat_metadata_bytes {
+            debug!(pubkey = &hb.device_pubkey[..8], bytes = hb.metadata_bytes(), "🏷️ Dropping oversized metadata");
+            hb.metadata.clear();
+        }
         
-        // 6. Add to pool (update if already present)
-        debug!("✅ Heartbeat verified: {}... HR={} W={:.3}", 
-            &hb.device_pubkey[..8], hb.heart_rate, hb.weight());
+        // 7. Add to pool (update if already present)
+        debug!(pubkey = &hb.device_pubkey[..8], heart_rate = hb.heart_rate, weight = hb.weight(),
+            "✅ Heartbeat verified");
+        self.pool_confidence.insert(hb.device_pubkey.clone(), bio_result.confidence);
         self.heartbeat_pool.insert(hb.device_pubkey.clone(), hb);
         
-        Ok(())
+        Ok(HeartbeatAccepted { confidence: bio_result.confidence, hrv_sdnn: bio_result.hrv_sdnn })
     }
     
     /// Verify and add a transaction to the pool
     pub fn receive_transaction(&mut self, tx: Transaction) -> Result<(), ConsensusError> {
         // 1. Verify signature
+        self.check_signature_version(tx.signature_version)?;
         let valid = verify_signature(
             &tx.sender_pubkey,
             &tx.signable_bytes(),
@@ -343,19 +1169,44 @@ impl ProofOfLife {
             return Err(ConsensusError::InvalidTransactionSignature);
         }
         
-        // 2. Check sender balance
+        // 2. Nonce must follow the sender's last included or pooled transaction,
+        //    so a signed transaction can't be replayed
+        let expected_nonce = self.next_nonce(&tx.sender_pubkey);
+        if tx.nonce != expected_nonce {
+            return Err(ConsensusError::InvalidNonce(expected_nonce, tx.nonce));
+        }
+        
+        // 3. The pool is bounded and holds each tx_id at most once
+        if self.tx_pool.iter().any(|pending| pending.tx_id == tx.tx_id) {
+            return Err(ConsensusError::DuplicateTransaction(tx.tx_id));
+        }
+        if self.tx_pool.len() >= self.config.max_tx_pool {
+            warn!("❌ Transaction pool full ({} pending)", self.tx_pool.len());
+            return Err(ConsensusError::MempoolFull(self.tx_pool.len()));
+        }
+        
+        // 4. Check sender balance covers the amount and the fee
+        if !fee_valid(tx.fee) {
+            return Err(ConsensusError::InvalidFee(tx.fee));
+        }
         let balance = self.accounts
             .get(&tx.sender_pubkey)
             .map(|a| a.balance)
-            .unwrap_or(0.0);
+            .unwrap_or(0);
         
-        if balance < tx.amount {
+        if tx_cost(&tx).is_none_or(|cost| balance < cost) {
             return Err(ConsensusError::InsufficientBalance);
         }
         
-        // 3. Check sender is actively pulsing
-        if !self.heartbeat_pool.contains_key(&tx.sender_pubkey) {
+        // 5. Check sender is actively pulsing
+        let Some(pooled) = self.heartbeat_pool.get(&tx.sender_pubkey) else {
             return Err(ConsensusError::SenderNotPulsing);
+        };
+        
+        // 6. ...and that the transaction's proof of life is that heartbeat, not
+        //    one fabricated or borrowed while another device does the pulsing
+        if tx.heartbeat_signature != pooled.signature {
+            return Err(ConsensusError::InvalidHeartbeatReference);
         }
         
         debug!("📨 Transaction queued: {}... → {}... ({} PULSE)",
@@ -365,136 +1216,215 @@ impl ProofOfLife {
         Ok(())
     }
     
+    /// Nonce the sender's next transaction must carry, counting transactions
+    /// still waiting in the pool
+    pub fn next_nonce(&self, pubkey: &str) -> u64 {
+        let included = self.accounts.get(pubkey).map(|a| a.nonce).unwrap_or(0);
+        let pooled = self.tx_pool.iter()
+            .filter(|tx| tx.sender_pubkey == pubkey)
+            .map(|tx| tx.nonce)
+            .max()
+            .unwrap_or(0);
+        included.max(pooled) + 1
+    }
+    
+    /// Hand back transactions dropped while building blocks, so the caller can
+    /// re-queue them (via `receive_transaction`) once the sender can cover them
+    pub fn take_dropped_transactions(&mut self) -> Vec<Transaction> {
+        std::mem::take(&mut self.dropped_txs)
+    }
+    
     /// Attempt to create a new block
     pub fn try_create_block(&mut self) -> Result<Option<PulseBlock>, ConsensusError> {
-        let n_live = self.heartbeat_pool.len();
+        let pooled = self.heartbeat_pool.len();
         
         // Check threshold
-        if n_live < self.config.n_threshold {
-            debug!("⏳ Waiting for heartbeats: {}/{}", n_live, self.config.n_threshold);
+        if pooled < self.config.n_threshold {
+            debug!(live = pooled, needed = self.config.n_threshold, "⏳ Waiting for heartbeats");
+            return Ok(None);
+        }
+        
+        // Check confidence-weighted quorum
+        let confidence_sum = self.pool_confidence_sum();
+        if confidence_sum < self.config.min_confidence_sum {
+            debug!("⏳ Waiting for confident heartbeats: confidence sum {:.2}/{:.2}",
+                confidence_sum, self.config.min_confidence_sum);
             return Ok(None);
         }
         
         // Calculate metrics with proper continuity factors
         let now = current_time_ms();
-        let heartbeats: Vec<Heartbeat> = self.heartbeat_pool.values().cloned().collect();
         
         // Calculate continuity-weighted contributions
-        // Continuity factor: time pulsing / max_continuity_window (5 minutes)
-        const MAX_CONTINUITY_MS: f64 = 300_000.0; // 5 minutes for full continuity credit
+        let params = self.config.weight_params();
         
         // Pre-compute weights with continuity so we use the SAME values
-        // for both total_weight and per-participant rewards (mathematical consistency)
-        let weighted_heartbeats: Vec<(Heartbeat, f64)> = heartbeats.iter().map(|h| {
-            let start = self.continuity_start
-                .get(&h.device_pubkey)
-                .copied()
-                .unwrap_or(now);
-            let duration_ms = now.saturating_sub(start) as f64;
-            let continuity = (duration_ms / MAX_CONTINUITY_MS).min(1.0);
-            let w = h.weight_with_continuity(continuity);
-            (h.clone(), w)
+        // for both total_weight and per-participant rewards (mathematical consistency).
+        // Each weight is scaled by the biometric confidence of the pooled heartbeat,
+        // so a suspicious-but-accepted signal participates but earns less.
+        // Entries are (heartbeat, continuity, confidence, weight).
+        let mut weighted_heartbeats: Vec<(&Heartbeat, f64, f64, f64)> = self.heartbeat_pool.values().map(|h| {
+            let continuity = self.continuity_factor(&h.device_pubkey, now);
+            let confidence = self.pool_confidence.get(&h.device_pubkey).copied().unwrap_or(1.0).clamp(0.0, 1.0);
+            let w = h.weight_with_params(continuity, &params) * confidence;
+            (h, continuity, confidence, w)
         }).collect();
         
-        let total_weight: f64 = weighted_heartbeats.iter().map(|(_, w)| w).sum();
+        // Over the size cap, keep the heaviest; the rest carry over to the next block
+        if weighted_heartbeats.len() > self.config.max_heartbeats_per_block {
+            weighted_heartbeats.sort_by(|a, b| {
+                b.3.total_cmp(&a.3).then_with(|| a.0.device_pubkey.cmp(&b.0.device_pubkey))
+            });
+            weighted_heartbeats.truncate(self.config.max_heartbeats_per_block);
+        }
+        // Pool iteration order varies between nodes; blocks (and the float sum
+        // below) must not, so that identical pools hash identically
+        weighted_heartbeats.sort_by(|a, b| a.0.device_pubkey.cmp(&b.0.device_pubkey));
+        
+        let mut continuity_factors = BTreeMap::new();
+        let mut confidence_factors = BTreeMap::new();
+        for (h, continuity, confidence, _) in &weighted_heartbeats {
+            continuity_factors.insert(h.device_pubkey.clone(), *continuity);
+            confidence_factors.insert(h.device_pubkey.clone(), *confidence);
+        }
+        let total_weight: f64 = weighted_heartbeats.iter().map(|(_, _, _, w)| w).sum();
+        let heartbeats: Vec<Heartbeat> = weighted_heartbeats.into_iter().map(|(h, ..)| h.clone()).collect();
+        let n_live = heartbeats.len();
         
         let security = total_weight;
         
-        // Adaptive fork constant: scales with network size
-        // Small network (1-10 participants): k=2.0 (need strong per-participant security)
-        // Medium (10-100): k=0.5
-        // Large (100+): k=0.1
-        // Global (1M+): k=0.000001
-        // Formula: k = base_k / ln(1 + n_live), clamped
-        let adaptive_k = if n_live <= 1 {
-            2.0
-        } else {
-            (self.config.fork_constant / (1.0 + n_live as f64).ln()).max(0.000001)
-        };
+        // Difficulty analog: don't produce blocks below the required security
+        let required_security = self.config.min_security_at_height(self.chain_height() + 1);
+        if security < required_security {
+            debug!("⏳ Waiting for security: {:.4}/{:.4}", security, required_security);
+            return Ok(None);
+        }
+        
+        let adaptive_k = self.config.adaptive_fork_constant(n_live);
         let fork_prob = (-adaptive_k * security).exp();
         
-        // Extract biometric entropy from all active devices
-        let bio_entropy_bytes = self.biometric_validator.aggregate_entropy();
-        let bio_entropy = hex::encode(&bio_entropy_bytes);
+        // Biometric entropy from the included heartbeats, so peers can recompute it
+        let bio_entropy = hex::encode(biometrics::block_entropy(&heartbeats));
+        
+        // Timelocked transactions stay pooled until the chain reaches their height,
+        // along with any later-nonce transactions from the same sender.
+        // Ready transactions are re-validated against the sender's state now:
+        // the sender must still be pulsing and the balance (before this block's
+        // reward) must cover everything selected so far, so no balance goes
+        // negative. A dropped transaction takes the sender's later nonces with it.
+        let next_height = self.chain_height() + 1;
+        let mut next_nonces: HashMap<String, u64> = HashMap::new();
+        let mut spent: HashMap<String, Amount> = HashMap::new();
+        let mut blocked_senders: HashSet<String> = HashSet::new();
+        let mut ready_txs = Vec::new();
+        let mut deferred_txs = Vec::new();
+        let mut dropped_txs = Vec::new();
+        for tx in std::mem::take(&mut self.tx_pool) {
+            let next = next_nonces.entry(tx.sender_pubkey.clone()).or_insert_with(|| {
+                self.accounts.get(&tx.sender_pubkey).map(|a| a.nonce).unwrap_or(0) + 1
+            });
+            if tx.nonce < *next {
+                continue; // already included (e.g. via a peer's block)
+            }
+            if blocked_senders.contains(&tx.sender_pubkey) {
+                dropped_txs.push(tx);
+                continue;
+            }
+            if tx.nonce != *next || tx.not_before_height > next_height {
+                deferred_txs.push(tx);
+                continue;
+            }
+            
+            let balance = self.accounts.get(&tx.sender_pubkey).map(|a| a.balance).unwrap_or(0);
+            let left = balance.saturating_sub(spent.get(&tx.sender_pubkey).copied().unwrap_or(0));
+            let cost = tx_cost(&tx).filter(|cost| *cost <= left);
+            if !self.heartbeat_pool.contains_key(&tx.sender_pubkey) {
+                warn!("⚠️ Dropping tx {} from block: sender {}... no longer pulsing",
+                    tx.tx_id, &tx.sender_pubkey[..8]);
+            } else if let Some(cost) = cost {
+                *next += 1;
+                *spent.entry(tx.sender_pubkey.clone()).or_insert(0) += cost;
+                ready_txs.push(tx);
+                continue;
+            } else {
+                warn!("⚠️ Dropping tx {} from block: sender {}... has {:.4} PULSE left, needs {:.4}",
+                    tx.tx_id, &tx.sender_pubkey[..8], to_pulse(left), tx.amount + tx.fee);
+            }
+            blocked_senders.insert(tx.sender_pubkey.clone());
+            dropped_txs.push(tx);
+        }
+        // Over the size cap, include the highest fees; what's left over goes back
+        // to the pool ahead of the deferred transactions, keeping nonce order
+        let (mut ready_txs, mut left_over) = select_by_fee(ready_txs, self.config.max_transactions_per_block);
+        left_over.extend(deferred_txs);
+        self.tx_pool = left_over;
+        self.dropped_txs.extend(dropped_txs);
+        ready_txs.sort_by(|a, b| a.tx_id.cmp(&b.tx_id));
         
-        // Create block
+        // Create block (always timestamped after its predecessor, even if
+        // we adopted a peer chain stamped slightly ahead of our clock)
         let previous = self.chain.last().unwrap();
         let mut block = PulseBlock {
             index: previous.index + 1,
-            timestamp: current_time_ms(),
+            timestamp: current_time_ms().max(previous.timestamp + 1),
             previous_hash: previous.block_hash.clone(),
-            heartbeats: heartbeats.clone(),
-            transactions: self.tx_pool.clone(),
+            // Metadata stays in the pool; blocks only carry consensus data
+            heartbeats: heartbeats.iter().cloned().map(|mut hb| {
+                hb.metadata.clear();
+                hb
+            }).collect(),
+            transactions: ready_txs,
             n_live,
             total_weight,
             security,
             bio_entropy,
+            continuity_factors,
+            confidence_factors,
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: self.config.chain_id.clone(),
             block_hash: String::new(),
         };
+        block.update_merkle_roots();
         block.block_hash = block.compute_hash();
         
-        info!("\n💓 PULSE BLOCK #{}", block.index);
-        info!("   Hash: {}...", &block.block_hash[..16]);
-        info!("   Live participants: {}", n_live);
-        info!("   Total weight: {:.4}", total_weight);
-        info!("   Security (S): {:.4}", security);
-        info!("   Fork probability: {:.6}", fork_prob);
-        
-        // Track affected accounts for persistence
-        let mut affected_pubkeys: Vec<String> = Vec::new();
-        
-        // Calculate block reward with halving schedule
-        let block_reward = self.config.reward_at_height(block.index);
-        
-        info!("   Block reward: {:.4} PULSE (halving epoch {})", 
-            block_reward, block.index / self.config.halving_interval.max(1));
-        
-        // Distribute rewards using the SAME pre-computed weights
-        if total_weight > 0.0 {
-            for (hb, w_i) in &weighted_heartbeats {
-                let reward = (w_i / total_weight) * block_reward;
-                
-                let account = self.accounts
-                    .entry(hb.device_pubkey.clone())
-                    .or_insert_with(|| Account {
-                        pubkey: hb.device_pubkey.clone(),
-                        ..Default::default()
-                    });
-                
-                account.balance += reward;
-                account.total_earned += reward;
-                account.last_heartbeat = hb.timestamp;
-                account.blocks_participated += 1;
-                
-                self.total_minted += reward;
-                affected_pubkeys.push(hb.device_pubkey.clone());
-                
-                info!("   💰 {}... earned {:.4} PULSE", &hb.device_pubkey[..8], reward);
-            }
-        }
+        info!(
+            block_index = block.index,
+            block_hash = &block.block_hash[..16],
+            participants = n_live,
+            total_weight,
+            security,
+            fork_probability = fork_prob,
+            "💓 PULSE BLOCK #{}", block.index
+        );
         
-        // Process transactions
-        for tx in &self.tx_pool {
-            if let Some(sender) = self.accounts.get_mut(&tx.sender_pubkey) {
-                sender.balance -= tx.amount;
-                affected_pubkeys.push(tx.sender_pubkey.clone());
-            }
-            
-            let recipient = self.accounts
-                .entry(tx.recipient_pubkey.clone())
-                .or_insert_with(|| Account {
-                    pubkey: tx.recipient_pubkey.clone(),
-                    ..Default::default()
-                });
-            recipient.balance += tx.amount;
-            affected_pubkeys.push(tx.recipient_pubkey.clone());
-            
+        // Calculate block reward with halving schedule (and overlap penalty, if configured)
+        let block_reward = self.config.reward_for_block(self.chain.last(), &block);
+        
+        info!(
+            block_index = block.index,
+            reward = to_pulse(block_reward),
+            halving_epoch = block.index / self.config.halving_interval.max(1),
+            "   Block reward: {:.4} PULSE", to_pulse(block_reward)
+        );
+        
+        // Distribute rewards, then move transactions and fees, exactly as peers
+        // applying this block will
+        let mut undo = BlockUndo::before(&self.accounts, &block);
+        let (minted, affected_pubkeys) =
+            apply_block_accounts(&self.config, &mut self.accounts, self.chain.last(), &block);
+        self.total_minted += minted;
+        undo.minted = minted;
+        info!("   💰 {} participants earned {:.4} PULSE", n_live, to_pulse(minted));
+        for tx in &block.transactions {
             info!("   📤 TX: {}... → {}... ({} PULSE)",
                 &tx.sender_pubkey[..8], &tx.recipient_pubkey[..8], tx.amount);
         }
         
         // Commit block to chain
+        Self::index_participants(&mut self.participant_index, &block);
         self.chain.push(block.clone());
+        self.push_undo(undo);
         
         // Update cumulative chain weight (for fork resolution)
         self.cumulative_weight += security;
@@ -502,17 +1432,32 @@ impl ProofOfLife {
         // Persist to storage
         self.persist_block(&block, &affected_pubkeys);
         
-        // Clear pools (but keep continuity tracking for devices that keep pulsing)
-        self.heartbeat_pool.clear();
-        self.tx_pool.clear();
+        // Clear the included heartbeats from the pool; any left out by the size cap
+        // stay for the next block. Continuity tracking is kept for devices that keep
+        // pulsing, and deferred timelocked transactions were already left in tx_pool.
+        for hb in &block.heartbeats {
+            self.heartbeat_pool.remove(&hb.device_pubkey);
+            self.pool_confidence.remove(&hb.device_pubkey);
+        }
         
         // Note: continuity_start is NOT cleared — devices that keep pulsing
         // accumulate continuity across blocks. Entries are cleaned up when
         // a device stops sending heartbeats (via periodic cleanup, not here).
         
+        self.metrics.blocks_created += 1;
         Ok(Some(block))
     }
     
+    /// Get the consensus configuration
+    pub fn config(&self) -> &ConsensusConfig {
+        &self.config
+    }
+    
+    /// Backing store, if the chain is persisted
+    pub fn store(&self) -> Option<Arc<dyn ChainStore>> {
+        self.storage.clone()
+    }
+    
     /// Get current chain height
     pub fn chain_height(&self) -> u64 {
         self.chain.last().map(|b| b.index).unwrap_or(0)
@@ -528,14 +1473,60 @@ impl ProofOfLife {
         self.chain.clone()
     }
 
+    /// Number of blocks in the chain, including pruned ones
+    pub fn header_count(&self) -> usize {
+        self.chain.len() + self.pruned_headers.len()
+    }
+    
+    /// Up to `limit` block headers from height-order position `offset`,
+    /// including those of pruned blocks, for light clients
+    pub fn headers_page(&self, offset: usize, limit: usize) -> Vec<BlockHeader> {
+        // Genesis is never pruned, so pruned headers sit between it and the remaining bodies
+        let (genesis, bodies) = match self.chain.split_first() {
+            Some((genesis, bodies)) => (Some(genesis), bodies),
+            None => (None, &[][..]),
+        };
+        genesis.map(PulseBlock::header).into_iter()
+            .chain(self.pruned_headers.iter().cloned())
+            .chain(bodies.iter().map(PulseBlock::header))
+            .skip(offset)
+            .take(limit)
+            .collect()
+    }
+    
     /// Get a block by index (for "jump to block" etc.)
     pub fn get_block_by_index(&self, index: u64) -> Option<PulseBlock> {
         self.chain.iter().find(|b| b.index == index).cloned()
     }
 
-    /// Get account balance
-    pub fn get_balance(&self, pubkey: &str) -> f64 {
-        self.accounts.get(pubkey).map(|a| a.balance).unwrap_or(0.0)
+    /// Get the blocks a participant contributed a heartbeat to, oldest first.
+    /// Returns the requested page along with the total number of such blocks.
+    pub fn get_participant_blocks(&self, pubkey: &str, offset: usize, limit: usize) -> (Vec<PulseBlock>, usize) {
+        let heights = match self.participant_index.get(pubkey) {
+            Some(h) => h,
+            None => return (Vec::new(), 0),
+        };
+        
+        let blocks = heights.iter()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|&h| self.block_at(h).cloned())
+            .collect();
+        
+        (blocks, heights.len())
+    }
+    
+    /// Look up a block by height, using the position in the chain when it lines up
+    fn block_at(&self, index: u64) -> Option<&PulseBlock> {
+        match self.chain.get(index as usize) {
+            Some(block) if block.index == index => Some(block),
+            _ => self.chain.iter().find(|b| b.index == index),
+        }
+    }
+
+    /// Get account balance (micro-PULSE)
+    pub fn get_balance(&self, pubkey: &str) -> Amount {
+        self.accounts.get(pubkey).map(|a| a.balance).unwrap_or(0)
     }
     
     /// Get all accounts
@@ -543,32 +1534,248 @@ impl ProofOfLife {
         &self.accounts
     }
     
+    /// Get a page of accounts ranked by `sort`, largest first (ties by pubkey so
+    /// pages are stable). Returns the page along with the total number of accounts.
+    pub fn accounts_page(&self, sort: AccountSort, offset: usize, limit: usize) -> (Vec<Account>, usize) {
+        let key = |a: &Account| match sort {
+            AccountSort::Balance => a.balance,
+            AccountSort::TotalEarned => a.total_earned,
+            AccountSort::BlocksParticipated => a.blocks_participated as Amount,
+        };
+        let mut ranked: Vec<&Account> = self.accounts.values().collect();
+        ranked.sort_unstable_by(|a, b| key(b).cmp(&key(a)).then_with(|| a.pubkey.cmp(&b.pubkey)));
+        
+        let page = ranked.into_iter()
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect();
+        
+        (page, self.accounts.len())
+    }
+    
+    /// Audit the supply: every minted token must be accounted for in a balance.
+    /// There is no premine or staking. Rewards withheld from warmup devices or
+    /// over the earning cap are never minted rather than burned afterwards, so
+    /// `total_burned` is zero by construction.
+    pub fn supply_audit(&self) -> crate::types::SupplyAudit {
+        let sum_balances: Amount = self.accounts.values().map(|a| a.balance).sum();
+        let sum_staked = 0;
+        let total_burned = 0;
+        crate::types::SupplyAudit {
+            total_minted: self.total_minted,
+            sum_balances,
+            sum_staked,
+            total_burned,
+            discrepancy: self.total_minted as i128 - (sum_balances + sum_staked + total_burned) as i128,
+        }
+    }
+    
     /// Get network stats
     pub fn get_stats(&self) -> crate::types::NetworkStats {
         let height = self.chain_height();
         let current_reward = self.config.reward_at_height(height);
-        let halving_epoch = if self.config.halving_interval > 0 {
-            height / self.config.halving_interval
-        } else {
-            0
-        };
-        let inflation_rate = if self.total_minted > 0.0 {
-            current_reward / self.total_minted
+        let halving_epoch = height.checked_div(self.config.halving_interval).unwrap_or(0);
+        let inflation_rate = if self.total_minted > 0 {
+            current_reward as f64 / self.total_minted as f64
         } else {
             0.0
         };
+        // Security posture of the tip, with k adapted to the size it was produced at
+        let tip = self.chain.last().unwrap();
+        let adaptive_k = self.config.adaptive_fork_constant(tip.n_live);
+        let target_block_time = self.config.block_interval_ms as f64 / 1000.0;
         
         crate::types::NetworkStats {
-            chain_length: self.chain.len() as u64,
+            chain_length: self.chain_height() + 1,
             total_minted: self.total_minted,
             active_accounts: self.accounts.len(),
-            current_tps: 0.0, // TODO: calculate from recent blocks
-            avg_block_time: self.config.block_interval_ms as f64 / 1000.0,
-            total_security: self.chain.iter().map(|b| b.security).sum(),
+            current_tps: self.recent_tps(TPS_WINDOW_BLOCKS),
+            avg_block_time: self.observed_block_time(TPS_WINDOW_BLOCKS).unwrap_or(target_block_time),
+            target_block_time,
+            total_security: self.cumulative_weight,
             current_block_reward: current_reward,
             halving_epoch,
             cumulative_weight: self.cumulative_weight,
             inflation_rate,
+            liveness_continuity: self.liveness_continuity(),
+            nakamoto_coefficient: self.nakamoto_coefficient(NAKAMOTO_WINDOW_BLOCKS),
+            current_fork_probability: tip.fork_probability(adaptive_k),
+            adaptive_k,
+        }
+    }
+    
+    /// Up to the last `window` blocks, oldest first. Genesis is left out: its
+    /// timestamp is fixed, not when it was produced.
+    fn recent_blocks(&self, window: usize) -> &[PulseBlock] {
+        let start = self.chain.len().saturating_sub(window);
+        let recent = &self.chain[start..];
+        match recent.first() {
+            Some(first) if first.index == 0 => &recent[1..],
+            _ => recent,
+        }
+    }
+    
+    /// Transactions per second over the last `window` blocks: those confirmed
+    /// after the window's first block, over the time from it to the tip.
+    /// 0.0 with fewer than two blocks or no elapsed time.
+    pub fn recent_tps(&self, window: usize) -> f64 {
+        let blocks = self.recent_blocks(window);
+        let (first, last) = match (blocks.first(), blocks.last()) {
+            (Some(first), Some(last)) if blocks.len() >= 2 => (first, last),
+            _ => return 0.0,
+        };
+        let span_ms = last.timestamp.saturating_sub(first.timestamp);
+        if span_ms == 0 {
+            return 0.0;
+        }
+        let txs: usize = blocks[1..].iter().map(|b| b.transactions.len()).sum();
+        txs as f64 / (span_ms as f64 / 1000.0)
+    }
+    
+    /// Mean seconds between consecutive blocks among the last `window`, or None
+    /// with fewer than two blocks
+    pub fn observed_block_time(&self, window: usize) -> Option<f64> {
+        let blocks = self.recent_blocks(window);
+        if blocks.len() < 2 {
+            return None;
+        }
+        let span_ms = blocks[blocks.len() - 1].timestamp.saturating_sub(blocks[0].timestamp);
+        Some(span_ms as f64 / 1000.0 / (blocks.len() - 1) as f64)
+    }
+    
+    /// Average participant overlap across the most recent consecutive block pairs
+    /// (1.0 = the same people keep pulsing, 0.0 = a fresh set every block, or no data yet)
+    pub fn liveness_continuity(&self) -> f64 {
+        const WINDOW: usize = 10;
+        let overlaps: Vec<f64> = self.chain.windows(2)
+            .rev()
+            .filter(|pair| !pair[0].heartbeats.is_empty())
+            .take(WINDOW)
+            .map(|pair| participant_overlap(&pair[0], &pair[1]))
+            .collect();
+        if overlaps.is_empty() {
+            return 0.0;
+        }
+        overlaps.iter().sum::<f64>() / overlaps.len() as f64
+    }
+    
+    /// Decentralization health: the fewest devices that together contributed
+    /// more than half of the heartbeat weight over the last `window_blocks`
+    /// blocks (a Nakamoto coefficient analog). 0 when the window has no weight.
+    pub fn nakamoto_coefficient(&self, window_blocks: usize) -> usize {
+        let mut contributions: HashMap<&str, f64> = HashMap::new();
+        for block in self.chain.iter().rev().take(window_blocks) {
+            for (hb, w) in block.heartbeats.iter().zip(block.participant_weights_with(&self.config.weight_params())) {
+                *contributions.entry(hb.device_pubkey.as_str()).or_insert(0.0) += w;
+            }
+        }
+        
+        let total: f64 = contributions.values().sum();
+        let mut weights: Vec<f64> = contributions.into_values().collect();
+        weights.sort_by(|a, b| b.total_cmp(a));
+        
+        let mut controlled = 0.0;
+        for (i, w) in weights.iter().enumerate() {
+            controlled += w;
+            if controlled > total / 2.0 {
+                return i + 1;
+            }
+        }
+        0
+    }
+    
+    /// Sampled (height, block_hash) pairs every `interval` blocks, plus the tip.
+    /// Nodes compare fingerprints to locate where their chains diverge.
+    pub fn chain_fingerprint(&self, interval: u64) -> Vec<(u64, String)> {
+        let interval = interval.max(1);
+        let mut samples: Vec<(u64, String)> = self.chain.iter()
+            .filter(|b| b.index % interval == 0)
+            .map(|b| (b.index, b.block_hash.clone()))
+            .collect();
+        if let Some(tip) = self.chain.last() {
+            if samples.last().map(|(h, _)| *h) != Some(tip.index) {
+                samples.push((tip.index, tip.block_hash.clone()));
+            }
+        }
+        samples
+    }
+    
+    /// Find a confirmed transaction by id, with the index of its block.
+    /// Uses the storage index when persistent, otherwise scans the in-memory chain.
+    pub fn find_transaction(&self, tx_id: &str) -> Result<Option<(u64, Transaction)>, ConsensusError> {
+        if let Some(ref storage) = self.storage {
+            return Ok(storage.find_transaction(tx_id)?);
+        }
+        Ok(self.chain.iter().rev().find_map(|block| {
+            block.transactions.iter()
+                .find(|tx| tx.tx_id == tx_id)
+                .map(|tx| (block.index, tx.clone()))
+        }))
+    }
+    
+    /// Transactions sent or received by `pubkey`, newest first, with their block
+    /// indexes. Returns the requested page along with the total number of such transactions.
+    pub fn account_transactions(
+        &self,
+        pubkey: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<(u64, Transaction)>, usize), ConsensusError> {
+        if let Some(ref storage) = self.storage {
+            return Ok(storage.account_transactions(pubkey, offset, limit)?);
+        }
+        let history: Vec<(u64, &Transaction)> = self.chain.iter().rev()
+            .flat_map(|block| block.transactions.iter().rev().map(move |tx| (block.index, tx)))
+            .filter(|(_, tx)| tx.sender_pubkey == pubkey || tx.recipient_pubkey == pubkey)
+            .collect();
+        let page = history.iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(index, tx)| (*index, (*tx).clone()))
+            .collect();
+        Ok((page, history.len()))
+    }
+    
+    /// Continuity factor of a device as of `now`: time pulsing / max_continuity_ms, capped at 1
+    fn continuity_factor(&self, pubkey: &str, now: u64) -> f64 {
+        let start = self.continuity_start.get(pubkey).copied().unwrap_or(now);
+        let duration_ms = now.saturating_sub(start) as f64;
+        (duration_ms / self.config.max_continuity_ms as f64).min(1.0)
+    }
+    
+    /// Current heartbeat and transaction pools with pubkeys shortened, plus how
+    /// far the heartbeat pool is from `n_threshold`. Weights are what each
+    /// heartbeat would contribute to a block produced now.
+    pub fn pool_snapshot(&self) -> PoolSnapshot {
+        let now = current_time_ms();
+        let params = self.config.weight_params();
+        let mut heartbeats: Vec<PooledHeartbeat> = self.heartbeat_pool.values()
+            .map(|hb| {
+                let confidence = self.pool_confidence.get(&hb.device_pubkey).copied().unwrap_or(1.0).clamp(0.0, 1.0);
+                PooledHeartbeat {
+                    pubkey_prefix: pubkey_prefix(&hb.device_pubkey),
+                    heart_rate: hb.heart_rate,
+                    weight: hb.weight_with_params(self.continuity_factor(&hb.device_pubkey, now), &params) * confidence,
+                    age_ms: now.saturating_sub(hb.timestamp),
+                }
+            })
+            .collect();
+        heartbeats.sort_by(|a, b| a.pubkey_prefix.cmp(&b.pubkey_prefix));
+        let transactions = self.tx_pool.iter()
+            .map(|tx| PooledTransaction {
+                tx_id: tx.tx_id.clone(),
+                sender_prefix: pubkey_prefix(&tx.sender_pubkey),
+                recipient_prefix: pubkey_prefix(&tx.recipient_pubkey),
+                amount: tx.amount,
+                fee: tx.fee,
+            })
+            .collect();
+        PoolSnapshot {
+            heartbeats_needed: self.config.n_threshold.saturating_sub(self.heartbeat_pool.len()),
+            n_threshold: self.config.n_threshold,
+            heartbeats,
+            transactions,
         }
     }
     
@@ -577,6 +1784,58 @@ impl ProofOfLife {
         self.heartbeat_pool.len()
     }
     
+    /// Get current transaction pool size
+    pub fn tx_pool_size(&self) -> usize {
+        self.tx_pool.len()
+    }
+    
+    /// Total tokens minted so far (micro-PULSE)
+    pub fn total_minted(&self) -> Amount {
+        self.total_minted
+    }
+    
+    /// Flush persistent storage to disk (no-op when running in-memory)
+    pub fn flush(&self) -> Result<(), ConsensusError> {
+        if let Some(ref storage) = self.storage {
+            storage.flush()?;
+        }
+        Ok(())
+    }
+    
+    /// Write the current chain and accounts as a snapshot (see `storage::snapshot`)
+    pub fn export_snapshot(&self, writer: impl std::io::Write) -> Result<SnapshotSummary, ConsensusError> {
+        if let Some(header) = self.pruned_headers.first() {
+            return Err(crate::storage::StorageError::BlockPruned(header.index).into());
+        }
+        let mut snapshot = SnapshotWriter::new(writer)?;
+        for block in &self.chain {
+            snapshot.block(block)?;
+        }
+        let mut accounts: Vec<&Account> = self.accounts.values().collect();
+        accounts.sort_by(|a, b| a.pubkey.cmp(&b.pubkey));
+        for account in accounts {
+            snapshot.account(account)?;
+        }
+        Ok(snapshot.finish()?)
+    }
+    
+    /// Heartbeat and block counters for `/metrics`
+    pub fn metrics(&self) -> &ConsensusMetrics {
+        &self.metrics
+    }
+    
+    /// Sum of biometric confidences over the heartbeats currently pooled
+    pub fn pool_confidence_sum(&self) -> f64 {
+        self.heartbeat_pool.keys()
+            .filter_map(|pk| self.pool_confidence.get(pk))
+            .sum()
+    }
+    
+    /// The pooled heartbeat for a device, including its metadata tags
+    pub fn pooled_heartbeat(&self, pubkey: &str) -> Option<&Heartbeat> {
+        self.heartbeat_pool.get(pubkey)
+    }
+    
     /// Check if a pubkey is currently pulsing
     pub fn is_pulsing(&self, pubkey: &str) -> bool {
         self.heartbeat_pool.contains_key(pubkey)
@@ -587,19 +1846,58 @@ impl ProofOfLife {
         self.cumulative_weight
     }
     
+    /// Fork choice: should `candidate` replace our chain?
+    /// The heavier chain wins; on an exact weight tie the chain whose tip has
+    /// the lexicographically lowest `block_hash` wins, so every node settles
+    /// on the same fork regardless of arrival order. Only weighs the chains —
+    /// `replace_chain` still validates the candidate before adopting it.
+    pub fn should_adopt_chain(&self, candidate: &[PulseBlock]) -> bool {
+        let Some(candidate_tip) = candidate.last() else {
+            return false;
+        };
+        let candidate_weight = chain_weight_of(candidate);
+        
+        if candidate_weight != self.cumulative_weight {
+            return candidate_weight > self.cumulative_weight;
+        }
+        match self.latest_block() {
+            Some(tip) => candidate_tip.block_hash < tip.block_hash,
+            None => true,
+        }
+    }
+    
+    /// A block must be timestamped strictly after `previous` and at most
+    /// `max_future_drift_ms` ahead of local time
+    fn check_block_timestamp(&self, block: &PulseBlock, previous: &PulseBlock) -> Result<(), ConsensusError> {
+        if block.timestamp <= previous.timestamp {
+            warn!("❌ Block #{} timestamp {} doesn't follow block #{} ({})",
+                block.index, block.timestamp, previous.index, previous.timestamp);
+            return Err(ConsensusError::InvalidTimestamp(block.timestamp));
+        }
+        let max_allowed = current_time_ms().saturating_add(self.config.max_future_drift_ms);
+        if block.timestamp > max_allowed {
+            warn!("❌ Block #{} timestamp {} is {}ms in the future",
+                block.index, block.timestamp, block.timestamp - current_time_ms());
+            return Err(ConsensusError::InvalidTimestamp(block.timestamp));
+        }
+        Ok(())
+    }
+    
     /// Receive a block from a peer and add it to the chain.
     /// Validates the block hash, checks it extends the chain, verifies heartbeat signatures,
     /// applies rewards and transactions, and persists to storage.
     pub fn receive_block(&mut self, block: PulseBlock) -> Result<(), ConsensusError> {
-        // 1. Basic sanity: block hash must be non-empty
-        if block.block_hash.is_empty() {
-            warn!("❌ Block #{} has empty hash", block.index);
+        // 1. Block hash must match its contents (and any Merkle roots must match
+        // the heartbeats and transactions). serde_json's float_roundtrip
+        // feature keeps f64/f32 values bit-exact across the wire, so the
+        // recomputed hash is stable.
+        if block.block_hash.is_empty() || block.block_hash != block.compute_hash() || !block.merkle_roots_valid() {
+            warn!("❌ Block #{} hash doesn't match its contents", block.index);
             return Err(ConsensusError::InvalidBlockHash);
         }
-        // Note: We don't recompute the hash because JSON round-tripping f64 values
-        // (total_weight, security, temperature) can change their representation,
-        // producing a different hash. Chain integrity comes from previous_hash links
-        // and signature verification.
+        
+        // 1b. Drop blocks from a network with a different genesis
+        self.check_compatible(std::slice::from_ref(&block))?;
         
         // 2. Check it extends current chain
         let latest = self.chain.last().unwrap();
@@ -613,66 +1911,57 @@ impl ProofOfLife {
             return Err(ConsensusError::InvalidPreviousHash);
         }
         
-        // 3. Verify all heartbeat signatures in the block
-        for hb in &block.heartbeats {
-            let valid = verify_signature(
-                &hb.device_pubkey,
-                &hb.signable_bytes(),
-                &hb.signature,
-            )?;
-            if !valid {
-                warn!("❌ Invalid heartbeat signature in block #{} from {}...", 
-                    block.index, &hb.device_pubkey[..8]);
-                return Err(ConsensusError::InvalidHeartbeatSignature);
-            }
+        // 2a. Timestamp must follow the tip's and not run ahead of our clock
+        self.check_block_timestamp(&block, latest)?;
+        
+        // 2b. Check the block meets the minimum security for its height
+        self.check_block_security(&block)?;
+        
+        // 3. Verify all heartbeat signatures in the block (one batch)
+        if let Some(bad) = verify_block_heartbeats(&block).into_iter().find(|(_, valid)| !valid) {
+            warn!("❌ Invalid heartbeat signature in block #{} from {}...", 
+                block.index, &bad.0[..8.min(bad.0.len())]);
+            return Err(ConsensusError::InvalidHeartbeatSignature);
         }
         
-        // 4. Apply rewards — use the block's own weight data
-        let block_reward = self.config.reward_at_height(block.index);
-        let mut affected_pubkeys: Vec<String> = Vec::new();
+        // 3a. No transaction may be included before its timelock height
+        if let Some(tx) = block.transactions.iter().find(|tx| tx.not_before_height > block.index) {
+            warn!("❌ Block #{} includes transaction timelocked until {}", block.index, tx.not_before_height);
+            return Err(ConsensusError::TransactionTimelocked(tx.not_before_height));
+        }
         
-        if block.total_weight > 0.0 {
-            for hb in &block.heartbeats {
-                let w_i = hb.weight(); // Use basic weight (no continuity data from remote)
-                let reward = (w_i / block.total_weight) * block_reward;
-                
-                let account = self.accounts
-                    .entry(hb.device_pubkey.clone())
-                    .or_insert_with(|| Account {
-                        pubkey: hb.device_pubkey.clone(),
-                        ..Default::default()
-                    });
-                
-                account.balance += reward;
-                account.total_earned += reward;
-                account.last_heartbeat = hb.timestamp;
-                account.blocks_participated += 1;
-                
-                self.total_minted += reward;
-                affected_pubkeys.push(hb.device_pubkey.clone());
-            }
+        // 3b. Check the claimed weights against the heartbeats and continuity factors
+        if !self.block_weights_valid(&block) {
+            warn!("❌ Block #{} weights don't match its heartbeats", block.index);
+            return Err(ConsensusError::InvalidBlockWeights);
         }
         
-        // 5. Process transactions
-        for tx in &block.transactions {
-            if let Some(sender) = self.accounts.get_mut(&tx.sender_pubkey) {
-                sender.balance -= tx.amount;
-                affected_pubkeys.push(tx.sender_pubkey.clone());
-            }
-            
-            let recipient = self.accounts
-                .entry(tx.recipient_pubkey.clone())
-                .or_insert_with(|| Account {
-                    pubkey: tx.recipient_pubkey.clone(),
-                    ..Default::default()
-                });
-            recipient.balance += tx.amount;
-            affected_pubkeys.push(tx.recipient_pubkey.clone());
+        // 3c. bio_entropy is committed to the hash; it must also be the one derived
+        // from the included heartbeats
+        if block.bio_entropy != hex::encode(biometrics::block_entropy(&block.heartbeats)) {
+            warn!("❌ Block #{} bio_entropy doesn't match its heartbeats", block.index);
+            return Err(ConsensusError::InvalidBioEntropy);
+        }
+        
+        // 3d. Each sender's transactions must continue from its nonce, so an
+        // included transaction can't be replayed
+        if let Err(e) = check_block_transactions(&self.accounts, &block) {
+            warn!("❌ Block #{} transactions don't follow their senders' state: {}", block.index, e);
+            return Err(e);
         }
         
+        // 4-5. Apply rewards (from the block's own weight data) and transactions
+        let mut undo = BlockUndo::before(&self.accounts, &block);
+        let (minted, affected_pubkeys) =
+            apply_block_accounts(&self.config, &mut self.accounts, self.chain.last(), &block);
+        self.total_minted += minted;
+        undo.minted = minted;
+        
         // 6. Update cumulative weight and add to chain
         self.cumulative_weight += block.security;
+        Self::index_participants(&mut self.participant_index, &block);
         self.chain.push(block.clone());
+        self.push_undo(undo);
         
         // 7. Persist to storage
         self.persist_block(&block, &affected_pubkeys);
@@ -680,6 +1969,7 @@ impl ProofOfLife {
         info!("📥 Accepted block #{} from peer ({} heartbeats, weight={:.4})", 
             block.index, block.heartbeats.len(), block.total_weight);
         
+        self.metrics.blocks_received += 1;
         Ok(())
     }
     
@@ -691,108 +1981,187 @@ impl ProofOfLife {
             .collect()
     }
     
+    /// Up to `limit` consecutive blocks starting at `height`
+    pub fn get_blocks_window(&self, height: u64, limit: usize) -> Vec<PulseBlock> {
+        self.chain.iter()
+            .filter(|b| b.index >= height)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+    
+    /// Apply one window of a chain sync: blocks at or below our tip must be the
+    /// ones we already have, the rest are appended in order through
+    /// `receive_block`. Returns how many blocks were added.
+    pub fn apply_sync_window(&mut self, blocks: Vec<PulseBlock>) -> Result<usize, ConsensusError> {
+        let mut applied = 0;
+        for block in blocks {
+            if block.index <= self.chain_height() {
+                let known = self.chain.iter()
+                    .find(|b| b.index == block.index)
+                    .is_none_or(|ours| ours.block_hash == block.block_hash);
+                if !known {
+                    warn!("❌ Sync window forks from our chain at block #{}", block.index);
+                    return Err(ConsensusError::InvalidPreviousHash);
+                }
+                continue;
+            }
+            self.receive_block(block)?;
+            applied += 1;
+        }
+        Ok(applied)
+    }
+    
+    /// Record the tip height reported by the peer serving our sync, once it has
+    /// delivered blocks that extend our chain (a gossiped height alone proves
+    /// nothing). One beyond ours puts the node in [`SyncState::Syncing`] until
+    /// the chain reaches it, the sync is ended, or it isn't repeated for a while.
+    pub fn note_peer_height(&mut self, height: u64) {
+        if height <= self.chain_height() {
+            return;
+        }
+        let target = self.sync_target.map_or(height, |(target, _)| target.max(height));
+        self.sync_target = Some((target, current_time_ms()));
+    }
+
+    /// Forget the reported tip, e.g. when the peer serving it can't finish the sync
+    pub fn end_sync(&mut self) {
+        self.sync_target = None;
+    }
+
+    /// Whether we're behind the highest tip peers have recently reported
+    pub fn sync_state(&self) -> SyncState {
+        let current = self.chain_height();
+        match self.sync_target {
+            Some((target, noted_at))
+                if target > current && current_time_ms().saturating_sub(noted_at) <= SYNC_TARGET_TTL_MS =>
+            {
+                SyncState::Syncing { current, target }
+            }
+            _ => SyncState::Synced,
+        }
+    }
+
     /// Replace the current chain with a heavier one from a peer.
     /// Only replaces if the new chain has greater cumulative weight.
     pub fn replace_chain(&mut self, blocks: Vec<PulseBlock>) -> Result<(), ConsensusError> {
+        self.import_chain(blocks).map(|_| ()).map_err(|e| e.source)
+    }
+    
+    /// `replace_chain` that reports whether the chain was adopted and, on
+    /// rejection, which block was at fault
+    pub fn import_chain(&mut self, blocks: Vec<PulseBlock>) -> Result<bool, ChainImportError> {
         if blocks.is_empty() {
-            return Ok(());
+            return Ok(false);
         }
         
-        // Calculate cumulative weight of the incoming chain
-        let incoming_weight: f64 = blocks.iter().map(|b| b.security).sum();
+        // Refuse chains rooted in a different genesis before weighing them
+        self.check_compatible(&blocks).map_err(|e| ChainImportError::at(&blocks[0], e))?;
         
-        if incoming_weight <= self.cumulative_weight {
-            info!("📊 Peer chain weight ({:.4}) <= ours ({:.4}), keeping local chain", 
+        // Fork choice: heaviest chain wins, ties go to the lowest tip hash
+        let incoming_weight = chain_weight_of(&blocks);
+        
+        if !self.should_adopt_chain(&blocks) {
+            info!("📊 Peer chain weight ({:.4}) doesn't beat ours ({:.4}), keeping local chain", 
                 incoming_weight, self.cumulative_weight);
-            return Ok(());
+            return Ok(false);
         }
         
-        // Validate the chain: verify hash links
-        for i in 1..blocks.len() {
-            if blocks[i].previous_hash != blocks[i - 1].block_hash {
-                warn!("❌ Invalid chain from peer: hash link broken at block #{}", blocks[i].index);
-                return Err(ConsensusError::InvalidPreviousHash);
+        // Validate the chain: block hashes, contiguous indices and hash links
+        for (i, block) in blocks.iter().enumerate() {
+            if block.block_hash != block.compute_hash() || !block.merkle_roots_valid() {
+                warn!("❌ Invalid chain from peer: block #{} hash doesn't match its contents", block.index);
+                return Err(ChainImportError::at(block, ConsensusError::InvalidBlockHash));
+            }
+            if i > 0 && (block.previous_hash != blocks[i - 1].block_hash || block.index != blocks[i - 1].index + 1) {
+                warn!("❌ Invalid chain from peer: hash link broken at block #{}", block.index);
+                return Err(ChainImportError::at(block, ConsensusError::InvalidPreviousHash));
+            }
+            if i > 0 {
+                self.check_block_timestamp(block, &blocks[i - 1]).map_err(|e| ChainImportError::at(block, e))?;
             }
-            // Note: we don't recompute hashes (f64 JSON round-trip issue).
-            // Chain integrity comes from hash links + signature verification.
         }
         
-        // Verify heartbeat signatures in all blocks
+        // Every non-genesis block must meet the minimum security for its height
+        for block in blocks.iter().filter(|b| b.index > 0) {
+            self.check_block_security(block).map_err(|e| ChainImportError::at(block, e))?;
+        }
+        
+        // Verify heartbeat signatures and claimed weights in all blocks
         for block in &blocks {
-            for hb in &block.heartbeats {
-                let valid = verify_signature(
-                    &hb.device_pubkey,
-                    &hb.signable_bytes(),
-                    &hb.signature,
-                )?;
-                if !valid {
-                    return Err(ConsensusError::InvalidHeartbeatSignature);
+            if verify_block_heartbeats(block).iter().any(|(_, valid)| !valid) {
+                warn!("❌ Invalid chain from peer: bad heartbeat signature in block #{}", block.index);
+                return Err(ChainImportError::at(block, ConsensusError::InvalidHeartbeatSignature));
+            }
+            if let Some(tx) = block.transactions.iter().find(|tx| tx.not_before_height > block.index) {
+                warn!("❌ Invalid chain from peer: block #{} includes a timelocked transaction", block.index);
+                return Err(ChainImportError::at(block, ConsensusError::TransactionTimelocked(tx.not_before_height)));
+            }
+            if !self.block_weights_valid(block) {
+                warn!("❌ Invalid chain from peer: block #{} weights don't match its heartbeats", block.index);
+                return Err(ChainImportError::at(block, ConsensusError::InvalidBlockWeights));
+            }
+        }
+        
+        // Replay the accounts along the chain, checking each block's transactions
+        // against its senders' state before it
+        let mut replayed = HashMap::new();
+        let mut replayed_minted = 0;
+        for (i, block) in blocks.iter().enumerate() {
+            if block.index > 0 {
+                if let Err(e) = check_block_transactions(&replayed, block) {
+                    warn!("❌ Invalid chain from peer: block #{} transactions: {}", block.index, e);
+                    return Err(ChainImportError::at(block, e));
                 }
             }
+            let prev = i.checked_sub(1).map(|p| &blocks[p]);
+            replayed_minted += apply_block_accounts(&self.config, &mut replayed, prev, block).0;
         }
         
         info!("🔄 Replacing chain: peer weight ({:.4}) > ours ({:.4})", 
             incoming_weight, self.cumulative_weight);
         
-        // Rebuild accounts from the new chain
-        let mut accounts: HashMap<String, Account> = HashMap::new();
-        let mut total_minted = 0.0;
-        
-        for block in &blocks {
-            let block_reward = self.config.reward_at_height(block.index);
-            if block.total_weight > 0.0 {
-                for hb in &block.heartbeats {
-                    let w_i = hb.weight();
-                    let reward = (w_i / block.total_weight) * block_reward;
-                    
-                    let account = accounts
-                        .entry(hb.device_pubkey.clone())
-                        .or_insert_with(|| Account {
-                            pubkey: hb.device_pubkey.clone(),
-                            ..Default::default()
-                        });
-                    
-                    account.balance += reward;
-                    account.total_earned += reward;
-                    account.last_heartbeat = hb.timestamp;
-                    account.blocks_participated += 1;
-                    total_minted += reward;
+        // Reorg: undo our blocks past the common ancestor and apply theirs. When
+        // the fork is older than the undo log, rebuild everything from genesis.
+        let fork_height = match self.common_ancestor(&blocks) {
+            Some(ancestor) if self.can_roll_back_to(ancestor) => {
+                let orphaned = self.roll_back_to(ancestor);
+                let base = blocks[0].index;
+                for pair in blocks[(ancestor - base) as usize..].windows(2) {
+                    self.apply_reorg_block(&pair[0], &pair[1]);
                 }
+                info!("🔀 Reorg at #{}: {} block(s) orphaned, {} applied",
+                    ancestor, orphaned, self.chain_height() - ancestor);
+                ancestor + 1
             }
-            
-            for tx in &block.transactions {
-                if let Some(sender) = accounts.get_mut(&tx.sender_pubkey) {
-                    sender.balance -= tx.amount;
-                }
-                let recipient = accounts
-                    .entry(tx.recipient_pubkey.clone())
-                    .or_insert_with(|| Account {
-                        pubkey: tx.recipient_pubkey.clone(),
-                        ..Default::default()
-                    });
-                recipient.balance += tx.amount;
+            _ => {
+                self.participant_index = Self::build_participant_index(&blocks);
+                self.chain = blocks;
+                self.pruned_headers.clear();
+                self.accounts = replayed;
+                self.total_minted = replayed_minted;
+                self.undo_log.clear();
+                0
             }
-        }
-        
-        // Replace state
-        self.chain = blocks;
-        self.accounts = accounts;
-        self.total_minted = total_minted;
+        };
         self.cumulative_weight = incoming_weight;
         self.heartbeat_pool.clear();
+        self.pool_confidence.clear();
         self.tx_pool.clear();
         
-        // Persist all blocks and accounts
+        // Persist the new suffix and the rebuilt accounts; orphaned blocks and
+        // accounts that only existed on the old chain are deleted
         if let Some(ref storage) = self.storage {
-            for block in &self.chain {
+            if let Err(e) = storage.truncate_blocks_from(fork_height) {
+                error!("❌ Failed to drop orphaned blocks during chain replace: {}", e);
+            }
+            for block in self.chain.iter().filter(|b| b.index >= fork_height) {
                 if let Err(e) = storage.save_block(block) {
                     error!("❌ Failed to persist block #{} during chain replace: {}", block.index, e);
                 }
             }
-            for account in self.accounts.values() {
-                if let Err(e) = storage.save_account(account) {
-                    error!("❌ Failed to persist account during chain replace: {}", e);
-                }
+            if let Err(e) = storage.replace_accounts(&self.accounts.values().cloned().collect::<Vec<_>>()) {
+                error!("❌ Failed to persist accounts during chain replace: {}", e);
             }
             if let Err(e) = storage.flush() {
                 error!("❌ Failed to flush storage after chain replace: {}", e);
@@ -801,69 +2170,632 @@ impl ProofOfLife {
         
         info!("✅ Chain replaced: height={}, weight={:.4}", self.chain_height(), self.cumulative_weight);
         
+        Ok(true)
+    }
+    
+    /// Height of the last block `blocks` shares with our chain
+    fn common_ancestor(&self, blocks: &[PulseBlock]) -> Option<u64> {
+        let base = blocks.first()?.index;
+        self.chain.iter().rev()
+            .find(|ours| {
+                ours.index.checked_sub(base)
+                    .and_then(|offset| blocks.get(offset as usize))
+                    .is_some_and(|theirs| theirs.block_hash == ours.block_hash)
+            })
+            .map(|b| b.index)
+    }
+    
+    /// Whether every block above `ancestor` has its body and an undo entry
+    fn can_roll_back_to(&self, ancestor: u64) -> bool {
+        let orphaned = (self.chain_height() - ancestor) as usize;
+        orphaned <= self.undo_log.len() && orphaned < self.chain.len()
+    }
+    
+    /// Pop blocks down to `ancestor`, restoring the accounts, supply, weight and
+    /// participant index from before them. Returns how many were removed.
+    fn roll_back_to(&mut self, ancestor: u64) -> u64 {
+        let mut orphaned = 0;
+        while self.chain_height() > ancestor {
+            let (Some(block), Some(undo)) = (self.chain.pop(), self.undo_log.pop_back()) else {
+                break;
+            };
+            for (pubkey, prior) in undo.accounts {
+                match prior {
+                    Some(account) => self.accounts.insert(pubkey, account),
+                    None => self.accounts.remove(&pubkey),
+                };
+            }
+            self.total_minted -= undo.minted;
+            self.cumulative_weight -= block.security;
+            for hb in &block.heartbeats {
+                if let Some(heights) = self.participant_index.get_mut(&hb.device_pubkey) {
+                    if heights.last() == Some(&block.index) {
+                        heights.pop();
+                    }
+                    if heights.is_empty() {
+                        self.participant_index.remove(&hb.device_pubkey);
+                    }
+                }
+            }
+            orphaned += 1;
+        }
+        orphaned
+    }
+    
+    /// Append an already-validated block of a reorg's new suffix
+    fn apply_reorg_block(&mut self, prev: &PulseBlock, block: &PulseBlock) {
+        let mut undo = BlockUndo::before(&self.accounts, block);
+        let (minted, _) = apply_block_accounts(&self.config, &mut self.accounts, Some(prev), block);
+        self.total_minted += minted;
+        undo.minted = minted;
+        self.cumulative_weight += block.security;
+        Self::index_participants(&mut self.participant_index, block);
+        self.chain.push(block.clone());
+        self.push_undo(undo);
+    }
+    
+    /// Remember how to undo the block just added, keeping `reorg_undo_depth` entries
+    fn push_undo(&mut self, undo: BlockUndo) {
+        if self.config.reorg_undo_depth == 0 {
+            return;
+        }
+        if self.undo_log.len() >= self.config.reorg_undo_depth {
+            self.undo_log.pop_front();
+        }
+        self.undo_log.push_back(undo);
+    }
+    
+    /// A block's claimed security must be its total weight (fork choice sums it),
+    /// and the weight recomputed from its heartbeats, rather than the claim,
+    /// must meet the minimum security for its height
+    fn check_block_security(&self, block: &PulseBlock) -> Result<(), ConsensusError> {
+        if block.security != block.total_weight {
+            warn!("❌ Block #{} claims security {:.4} but total weight {:.4}",
+                block.index, block.security, block.total_weight);
+            return Err(ConsensusError::InvalidBlockWeights);
+        }
+        let security: f64 = block.participant_weights_with(&self.config.weight_params()).iter().sum();
+        let required_security = self.config.min_security_at_height(block.index);
+        if security < required_security {
+            warn!("❌ Block #{} security {:.4} below required {:.4}",
+                block.index, security, required_security);
+            return Err(ConsensusError::InsufficientSecurity(security, required_security));
+        }
         Ok(())
     }
     
-    /// Clean up continuity tracking for devices that haven't pulsed recently.
-    /// Call this periodically (e.g., every few block intervals).
-    pub fn cleanup_stale_continuity(&mut self) {
+    /// Check a block's claimed total weight against its heartbeats and embedded
+    /// continuity factors. From `continuity_factors_height` on, every block with
+    /// participants must carry the factors; one without them can't be checked and
+    /// is rejected. Older blocks without factors predate them and pass.
+    fn block_weights_valid(&self, block: &PulseBlock) -> bool {
+        if !self.config.verify_block_rewards {
+            return true;
+        }
+        if block.continuity_factors.is_empty() && block.index < self.config.continuity_factors_height {
+            return true;
+        }
+        
+        // Exactly one factor per participant, each within [0, 1]
+        let factors_valid = |factors: &BTreeMap<String, f64>| {
+            factors.len() == block.heartbeats.len()
+                && block.heartbeats.iter().all(|hb| factors.contains_key(&hb.device_pubkey))
+                && factors.values().all(|c| (0.0..=1.0).contains(c))
+        };
+        if !factors_valid(&block.continuity_factors)
+            || (!block.confidence_factors.is_empty() && !factors_valid(&block.confidence_factors))
+        {
+            return false;
+        }
+        
+        let expected: f64 = block.participant_weights_with(&self.config.weight_params()).iter().sum();
+        (expected - block.total_weight).abs() <= 1e-9 * expected.max(1.0)
+    }
+    
+    /// Hash of our genesis block
+    pub fn genesis_hash(&self) -> &str {
+        &self.chain[0].block_hash
+    }
+    
+    /// Number of distinct foreign networks (by genesis) seen so far
+    pub fn incompatible_network_count(&self) -> usize {
+        self.incompatible_genesis.len()
+    }
+    
+    /// Reject blocks belonging to another chain id or a chain with a different genesis.
+    /// Each foreign genesis is logged once; blocks extending a known foreign
+    /// tip are recognized and rejected silently.
+    fn check_compatible(&mut self, blocks: &[PulseBlock]) -> Result<(), ConsensusError> {
+        // Every block names its network, so a foreign one is conclusive at any height
+        if let Some(foreign) = blocks.iter().find(|b| b.chain_id != self.config.chain_id) {
+            debug!("🚫 Ignoring block #{} from chain id '{}'", foreign.index, foreign.chain_id);
+            return Err(ConsensusError::ForeignChainId(foreign.chain_id.clone()));
+        }
+        if !self.config.blacklist_incompatible_chains {
+            return Ok(());
+        }
+        let (first, last) = match (blocks.first(), blocks.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Ok(()),
+        };
+        
+        // Extends (or replays) a foreign chain we already know about
+        let known = self.incompatible_tips.iter()
+            .position(|(tip, _)| *tip == first.previous_hash || *tip == first.block_hash);
+        if let Some(pos) = known {
+            let genesis = self.incompatible_tips[pos].1.clone();
+            self.incompatible_tips[pos].0 = last.block_hash.clone();
+            debug!("🚫 Ignoring block #{} from incompatible network", last.index);
+            return Err(ConsensusError::IncompatibleNetwork(genesis));
+        }
+        
+        // Only the genesis link is conclusive; anything else is an ordinary fork
+        let ours = self.genesis_hash().to_string();
+        let foreign_genesis = match first.index {
+            0 if first.block_hash != ours => first.block_hash.clone(),
+            1 if first.previous_hash != ours => first.previous_hash.clone(),
+            _ => return Ok(()),
+        };
+        
+        if self.incompatible_genesis.insert(foreign_genesis.clone()) {
+            warn!("🚫 Incompatible network: peer chain has genesis {}..., ours is {}... — ignoring it from now on",
+                &foreign_genesis[..16.min(foreign_genesis.len())], &ours[..16.min(ours.len())]);
+        }
+        if self.incompatible_tips.len() >= MAX_INCOMPATIBLE_TIPS {
+            self.incompatible_tips.pop_front();
+        }
+        self.incompatible_tips.push_back((last.block_hash.clone(), foreign_genesis.clone()));
+        
+        Err(ConsensusError::IncompatibleNetwork(foreign_genesis))
+    }
+    
+    /// Clean up continuity, duplicate-detection and short-term biometric tracking
+    /// for devices that haven't pulsed recently. Returns how many were dropped.
+    /// Call this periodically (the node does every few block intervals).
+    pub fn cleanup_stale_continuity(&mut self) -> usize {
         let now = current_time_ms();
         let max_age = self.config.max_heartbeat_age_ms * 2; // 2x heartbeat timeout
+        let before = self.continuity_start.len();
         
         self.continuity_start.retain(|pubkey, start| {
-            let age = now.saturating_sub(*start);
-            // Keep if device pulsed recently or started recently
-            self.heartbeat_pool.contains_key(pubkey) || age < max_age
+            // Last pulse is the newest remembered heartbeat (the start if none)
+            let last_seen = self.recent_heartbeat_hashes.get(pubkey)
+                .and_then(|hashes| hashes.back())
+                .map_or(*start, |(ts, _)| *ts);
+            // Keep if device is pooled or pulsed recently
+            self.heartbeat_pool.contains_key(pubkey) || now.saturating_sub(last_seen) < max_age
         });
         
         // Also clean up stale heartbeat hashes
-        self.last_heartbeat_hash.retain(|pubkey, _| {
+        self.recent_heartbeat_hashes.retain(|pubkey, _| {
             self.continuity_start.contains_key(pubkey)
         });
+        self.device_last_seq.retain(|pubkey, _| {
+            self.continuity_start.contains_key(pubkey)
+        });
+        self.device_recency.retain(|_, pubkey| {
+            self.continuity_start.contains_key(pubkey)
+        });
+        let active: Vec<String> = self.continuity_start.keys().cloned().collect();
+        self.biometric_validator.cleanup(&active);
+        
+        before - self.continuity_start.len()
+    }
+    
+    /// Mark a device as just pulsed, evicting the least-recently-pulsed
+    /// devices if we're tracking more than `max_tracked_devices`
+    fn touch_device(&mut self, pubkey: &str) {
+        if let Some(prev) = self.device_last_seq.remove(pubkey) {
+            self.device_recency.remove(&prev);
+        }
+        self.pulse_seq += 1;
+        self.device_recency.insert(self.pulse_seq, pubkey.to_string());
+        self.device_last_seq.insert(pubkey.to_string(), self.pulse_seq);
+        
+        while self.device_last_seq.len() > self.config.max_tracked_devices.max(1) {
+            let Some((_, oldest)) = self.device_recency.pop_first() else { break };
+            debug!("🧹 Evicting least-recently-pulsed device {}...", &oldest[..8.min(oldest.len())]);
+            self.device_last_seq.remove(&oldest);
+            self.continuity_start.remove(&oldest);
+            self.recent_heartbeat_hashes.remove(&oldest);
+            self.biometric_validator.forget(&oldest);
+        }
+    }
+    
+    /// A device's calibrated biometric baseline, if it has one
+    pub fn biometric_baseline(&self, pubkey: &str) -> Option<biometrics::Baseline> {
+        self.biometric_validator.baseline_for(pubkey)
+    }
+    
+    /// Biometric confidence of a device's most recently validated heartbeat
+    pub fn biometric_confidence(&self, pubkey: &str) -> Option<f64> {
+        self.biometric_validator.last_confidence(pubkey)
+    }
+    
+    /// Number of devices currently tracked for continuity
+    pub fn tracked_devices(&self) -> usize {
+        self.continuity_start.len()
     }
 }
 
-/// Get current time in milliseconds
-fn current_time_ms() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as u64
+/// Cumulative weight of a chain (sum of block security), as used for fork choice
+pub fn chain_weight_of(blocks: &[PulseBlock]) -> f64 {
+    blocks.iter().map(|b| b.security).sum()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::crypto::Keypair;
-    use crate::types::Motion;
+/// Fraction of `block`'s participants that also pulsed in `prev` (0.0 if `block` is empty)
+pub fn participant_overlap(prev: &PulseBlock, block: &PulseBlock) -> f64 {
+    if block.heartbeats.is_empty() {
+        return 0.0;
+    }
+    let previous: HashSet<&str> = prev.heartbeats.iter().map(|hb| hb.device_pubkey.as_str()).collect();
+    let current: HashSet<&str> = block.heartbeats.iter().map(|hb| hb.device_pubkey.as_str()).collect();
+    current.intersection(&previous).count() as f64 / current.len() as f64
+}
+
+/// Fixed-point scale applied to participant weights before splitting a reward
+const WEIGHT_UNITS: f64 = (1u64 << 32) as f64;
+
+/// Split `reward` among participants in proportion to `weights`, in order.
+/// Weights are quantized and the split is done in integers: each share is
+/// floored and the leftover units go one each to the largest remainders
+/// (ties to the earlier participant), so the shares sum to exactly `reward`
+/// on every node. Returns all zeros if no participant has weight.
+pub fn distribute_reward(reward: Amount, weights: &[f64]) -> Vec<Amount> {
+    let units: Vec<Amount> = weights.iter().map(|w| (w * WEIGHT_UNITS) as Amount).collect();
+    let total: Amount = units.iter().sum();
+    if total == 0 {
+        return vec![0; weights.len()];
+    }
+    let mut shares: Vec<Amount> = units.iter().map(|u| reward * u / total).collect();
+    let remainders: Vec<Amount> = units.iter().map(|u| reward * u % total).collect();
+    let leftover = reward - shares.iter().sum::<Amount>();
+    let mut order: Vec<usize> = (0..weights.len()).collect();
+    order.sort_by(|&a, &b| remainders[b].cmp(&remainders[a]).then(a.cmp(&b)));
+    for &i in order.iter().take(leftover as usize) {
+        shares[i] += 1;
+    }
+    shares
+}
+
+/// Credit `block`'s rewards and apply its transactions to `accounts`, as every
+/// node does when it accepts the block after `prev`. Returns the amount minted
+/// and the accounts touched.
+fn apply_block_accounts(
+    config: &ConsensusConfig,
+    accounts: &mut HashMap<String, Account>,
+    prev: Option<&PulseBlock>,
+    block: &PulseBlock,
+) -> (Amount, Vec<String>) {
+    let mut minted = 0;
+    let mut affected_pubkeys = Vec::new();
     
-    fn create_test_heartbeat(keypair: &Keypair) -> Heartbeat {
-        let mut hb = Heartbeat {
-            timestamp: current_time_ms(),
-            heart_rate: 72,
-            motion: Motion { x: 0.1, y: 0.1, z: 0.05 },
-            temperature: 36.7,
-            device_pubkey: keypair.public_key_hex(),
-            signature: String::new(),
-        };
-        hb.signature = keypair.sign(&hb.signable_bytes());
-        hb
+    let block_reward = config.reward_for_block(prev, block);
+    if block.total_weight > 0.0 {
+        let rewards = block_rewards(config, accounts, block_reward, block);
+        for (hb, reward) in block.heartbeats.iter().zip(rewards) {
+            let account = accounts
+                .entry(hb.device_pubkey.clone())
+                .or_insert_with(|| Account {
+                    pubkey: hb.device_pubkey.clone(),
+                    ..Default::default()
+                });
+            
+            account.balance += reward;
+            account.total_earned += reward;
+            account.last_heartbeat = hb.timestamp;
+            account.blocks_participated += 1;
+            minted += reward;
+            affected_pubkeys.push(hb.device_pubkey.clone());
+        }
     }
     
-    #[test]
-    fn test_receive_valid_heartbeat() {
-        let mut pol = ProofOfLife::new(ConsensusConfig::default());
-        let kp = Keypair::generate();
-        let hb = create_test_heartbeat(&kp);
-        
-        assert!(pol.receive_heartbeat(hb).is_ok());
-        assert_eq!(pol.heartbeat_pool_size(), 1);
+    for tx in &block.transactions {
+        // Blocks are checked before they're applied; one that slipped through
+        // with an invalid fee moves nothing
+        let Some(debit) = tx_debit(tx, block) else {
+            continue;
+        };
+        let amount = to_micro(tx.amount);
+        if let Some(sender) = accounts.get_mut(&tx.sender_pubkey) {
+            sender.balance -= debit;
+            sender.nonce = sender.nonce.max(tx.nonce);
+            affected_pubkeys.push(tx.sender_pubkey.clone());
+        }
+        let recipient = accounts
+            .entry(tx.recipient_pubkey.clone())
+            .or_insert_with(|| Account {
+                pubkey: tx.recipient_pubkey.clone(),
+                ..Default::default()
+            });
+        recipient.balance += amount;
+        affected_pubkeys.push(tx.recipient_pubkey.clone());
     }
+    affected_pubkeys.extend(pay_fees(config, accounts, block));
     
-    #[test]
-    fn test_create_block() {
-        let mut pol = ProofOfLife::new(ConsensusConfig::default());
-        let kp = Keypair::generate();
+    (minted, affected_pubkeys)
+}
+
+/// Check `block`'s transactions against the accounts as they were before it.
+/// Each sender's nonces, in any order within the block, must continue exactly
+/// from its account's, so an included transaction can't be included again, and
+/// its balance before the block's rewards must cover everything it sends and
+/// pays in fees, as when the block was built. Every fee must be a valid one.
+fn check_block_transactions(
+    accounts: &HashMap<String, Account>,
+    block: &PulseBlock,
+) -> Result<(), ConsensusError> {
+    let mut by_sender: BTreeMap<&str, (Vec<u64>, Amount)> = BTreeMap::new();
+    for tx in &block.transactions {
+        if !fee_valid(tx.fee) {
+            return Err(ConsensusError::InvalidFee(tx.fee));
+        }
+        let debit = tx_debit(tx, block).ok_or(ConsensusError::InsufficientBalance)?;
+        let (nonces, spent) = by_sender.entry(&tx.sender_pubkey).or_default();
+        nonces.push(tx.nonce);
+        *spent = spent.checked_add(debit).ok_or(ConsensusError::InsufficientBalance)?;
+    }
+    for (sender, (mut nonces, spent)) in by_sender {
+        let account = accounts.get(sender);
+        nonces.sort_unstable();
+        let first = account.map_or(0, |a| a.nonce) + 1;
+        for (expected, nonce) in (first..).zip(nonces) {
+            if nonce != expected {
+                return Err(ConsensusError::InvalidNonce(expected, nonce));
+            }
+        }
+        if spent > account.map_or(0, |a| a.balance) {
+            return Err(ConsensusError::InsufficientBalance);
+        }
+    }
+    Ok(())
+}
+
+/// Whether `fee` is one a transaction may carry: finite, non-negative and at
+/// most [`MAX_TX_FEE`]
+fn fee_valid(fee: f64) -> bool {
+    (0.0..=MAX_TX_FEE).contains(&fee)
+}
+
+/// Amount plus fee of `tx` in micro-PULSE; `None` if the fee is invalid or
+/// the sum overflows
+fn tx_cost(tx: &Transaction) -> Option<Amount> {
+    if !fee_valid(tx.fee) {
+        return None;
+    }
+    to_micro(tx.amount).checked_add(to_micro(tx.fee))
+}
+
+/// What `tx` takes from its sender in `block`: the amount plus the fee. A
+/// block without participants has nobody to pay fees to, so none are charged.
+/// `None` if the fee is invalid or the cost overflows.
+fn tx_debit(tx: &Transaction, block: &PulseBlock) -> Option<Amount> {
+    let cost = tx_cost(tx)?;
+    Some(if block.heartbeats.is_empty() { to_micro(tx.amount) } else { cost })
+}
+
+/// Credit the fees of `block`'s transactions to its participants, returning
+/// the accounts paid. Fees move existing coins rather than minting new ones, so
+/// they're split by `reward_policy` alone (evenly if nobody has weight), never
+/// burned or capped, and don't count toward `total_earned`.
+fn pay_fees(
+    config: &ConsensusConfig,
+    accounts: &mut HashMap<String, Account>,
+    block: &PulseBlock,
+) -> Vec<String> {
+    // Only transactions that were charged pay out
+    let fees = block.transactions.iter()
+        .filter(|tx| tx_cost(tx).is_some())
+        .try_fold(0, |sum: Amount, tx| sum.checked_add(to_micro(tx.fee)));
+    let Some(fees) = fees.filter(|fees| *fees > 0 && !block.heartbeats.is_empty()) else {
+        return Vec::new();
+    };
+    let weights: Vec<f64> = block.participant_weights_with(&config.weight_params())
+        .into_iter()
+        .map(|weight| config.reward_policy.share_weight(weight))
+        .collect();
+    let mut shares = distribute_reward(fees, &weights);
+    if shares.iter().all(|share| *share == 0) {
+        shares = distribute_reward(fees, &vec![1.0; weights.len()]);
+    }
+    
+    let mut paid = Vec::new();
+    for (hb, share) in block.heartbeats.iter().zip(shares).filter(|(_, share)| *share > 0) {
+        accounts
+            .entry(hb.device_pubkey.clone())
+            .or_insert_with(|| Account {
+                pubkey: hb.device_pubkey.clone(),
+                ..Default::default()
+            })
+            .balance += share;
+        paid.push(hb.device_pubkey.clone());
+    }
+    paid
+}
+
+/// Split `ready` into at most `max` transactions to include and the rest, taking
+/// the highest fee (ties to the lowest tx_id) among each sender's next
+/// transaction in turn, so no sender's nonces are skipped. Both keep nonce order.
+fn select_by_fee(ready: Vec<Transaction>, max: usize) -> (Vec<Transaction>, Vec<Transaction>) {
+    if ready.len() <= max {
+        return (ready, Vec::new());
+    }
+    let mut by_sender: BTreeMap<String, VecDeque<Transaction>> = BTreeMap::new();
+    for tx in ready {
+        by_sender.entry(tx.sender_pubkey.clone()).or_default().push_back(tx);
+    }
+    let mut selected = Vec::with_capacity(max);
+    while selected.len() < max {
+        let best = by_sender.iter()
+            .filter_map(|(sender, queue)| queue.front().map(|tx| (sender, tx)))
+            .max_by(|(_, a), (_, b)| a.fee.total_cmp(&b.fee).then_with(|| b.tx_id.cmp(&a.tx_id)))
+            .map(|(sender, _)| sender.clone());
+        let Some(sender) = best else { break };
+        selected.extend(by_sender.get_mut(&sender).and_then(VecDeque::pop_front));
+    }
+    (selected, by_sender.into_values().flatten().collect())
+}
+
+/// Each participant's share of `block_reward`, in heartbeat order, given the
+/// accounts as they were before `block`, split per `reward_policy`. Devices with fewer than
+/// `min_blocks_before_reward` earlier blocks get nothing; their share is
+/// burned or split among the rest according to `warmup_reward_policy`, and
+/// shares are then limited by `earning_cap`.
+fn block_rewards(
+    config: &ConsensusConfig,
+    accounts: &HashMap<String, Account>,
+    block_reward: Amount,
+    block: &PulseBlock,
+) -> Vec<Amount> {
+    let mut weights: Vec<f64> = block.participant_weights_with(&config.weight_params())
+        .into_iter()
+        .map(|weight| config.reward_policy.share_weight(weight))
+        .collect();
+    let warming_up: Vec<bool> = block.heartbeats.iter()
+        .map(|hb| {
+            let participated = accounts.get(&hb.device_pubkey).map_or(0, |a| a.blocks_participated);
+            participated < config.min_blocks_before_reward
+        })
+        .collect();
+    if config.warmup_reward_policy == WarmupRewardPolicy::Redistribute {
+        for (weight, _) in weights.iter_mut().zip(&warming_up).filter(|(_, warm)| **warm) {
+            *weight = 0.0;
+        }
+    }
+    let mut shares = distribute_reward(block_reward, &weights);
+    for (share, _) in shares.iter_mut().zip(&warming_up).filter(|(_, warm)| **warm) {
+        *share = 0;
+    }
+    match config.earning_cap {
+        EarningCapPolicy::Off => shares,
+        EarningCapPolicy::PerBlock(cap) => {
+            // Devices in their warmup can't take anyone's excess
+            for (weight, _) in weights.iter_mut().zip(&warming_up).filter(|(_, warm)| **warm) {
+                *weight = 0.0;
+            }
+            cap_shares(shares, &weights, to_micro(cap))
+        }
+    }
+}
+
+/// Lower shares above `cap` to it and split the excess among the participants
+/// still below it by weight, repeating until none is over. Excess nobody can
+/// take is dropped.
+fn cap_shares(mut shares: Vec<Amount>, weights: &[f64], cap: Amount) -> Vec<Amount> {
+    let mut weights = weights.to_vec();
+    loop {
+        let excess: Amount = shares.iter().map(|share| share.saturating_sub(cap)).sum();
+        if excess == 0 {
+            return shares;
+        }
+        for (share, weight) in shares.iter_mut().zip(weights.iter_mut()) {
+            if *share >= cap {
+                *share = cap;
+                *weight = 0.0;
+            }
+        }
+        let extra = distribute_reward(excess, &weights);
+        if extra.iter().all(|e| *e == 0) {
+            return shares;
+        }
+        for (share, e) in shares.iter_mut().zip(extra) {
+            *share += e;
+        }
+    }
+}
+
+/// Re-verify every heartbeat signature in a block, independent of any node state.
+/// Returns (pubkey, valid) in heartbeat order; malformed keys or signatures count as invalid.
+pub fn verify_block_heartbeats(block: &PulseBlock) -> Vec<(String, bool)> {
+    let items: Vec<(String, Vec<u8>, String)> = block.heartbeats.iter()
+        .map(|hb| (hb.device_pubkey.clone(), hb.signable_bytes(), hb.signature.clone()))
+        .collect();
+    let results = verify_batch(&items);
+    items.into_iter()
+        .zip(results)
+        .map(|((pubkey, _, _), valid)| (pubkey, valid))
+        .collect()
+}
+
+/// First 16 characters of a pubkey, for display without exposing the full key
+fn pubkey_prefix(pubkey: &str) -> String {
+    let prefix: String = pubkey.chars().take(16).collect();
+    format!("{}...", prefix)
+}
+
+/// Get current time in milliseconds
+fn current_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Keypair;
+    use crate::storage::{MemStore, Storage};
+    use crate::types::{Motion, MICRO_PER_PULSE};
+    
+    fn create_test_heartbeat(keypair: &Keypair) -> Heartbeat {
+        let mut hb = Heartbeat {
+            timestamp: current_time_ms(),
+            heart_rate: 72,
+            motion: Motion { x: 0.1, y: 0.1, z: 0.05 },
+            temperature: 36.7,
+            device_pubkey: keypair.public_key_hex(),
+            signature: String::new(),
+            signature_version: 0,
+            recoverable: false,
+            metadata: BTreeMap::new(),
+        };
+        hb.signature = keypair.sign(&hb.signable_bytes());
+        hb
+    }
+    
+    /// Signature of `keypair`'s pooled heartbeat, which its transactions must reference
+    fn pooled_signature(pol: &ProofOfLife, keypair: &Keypair) -> String {
+        pol.pooled_heartbeat(&keypair.public_key_hex())
+            .map(|hb| hb.signature.clone())
+            .unwrap_or_default()
+    }
+    
+    #[test]
+    fn test_receive_valid_heartbeat() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kp = Keypair::generate();
+        let hb = create_test_heartbeat(&kp);
+        
+        assert!(pol.receive_heartbeat(hb).is_ok());
+        assert_eq!(pol.heartbeat_pool_size(), 1);
+    }
+    
+    #[test]
+    fn test_accepted_heartbeat_reports_signal_quality() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kp = Keypair::generate();
+        let first = pol.receive_heartbeat(create_test_heartbeat(&kp)).unwrap();
+        assert!(first.confidence > 0.9);
+        assert_eq!(first.hrv_sdnn, 0.0, "no HRV before enough history");
+        
+        for (i, hr) in [74, 70, 75, 71, 73].into_iter().enumerate() {
+            let mut hb = create_test_heartbeat(&kp);
+            hb.timestamp += i as u64 + 1;
+            hb.heart_rate = hr;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            let accepted = pol.receive_heartbeat(hb).unwrap();
+            assert!((0.0..=1.0).contains(&accepted.confidence));
+            if i >= 3 {
+                assert!(accepted.hrv_sdnn > 0.0);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_create_block() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kp = Keypair::generate();
         let hb = create_test_heartbeat(&kp);
         
         pol.receive_heartbeat(hb).unwrap();
@@ -953,111 +2885,2409 @@ mod tests {
         let bal1 = pol.get_balance(&kp1.public_key_hex());
         let bal2 = pol.get_balance(&kp2.public_key_hex());
         
-        // Total should be reward_per_block (100.0)
-        assert!((bal1 + bal2 - 100.0).abs() < 0.001, 
-            "Total rewards should be 100, got {}", bal1 + bal2);
+        // Total should be exactly reward_per_block (100.0)
+        assert_eq!(bal1 + bal2, 100 * MICRO_PER_PULSE,
+            "Total rewards should be 100, got {}", to_pulse(bal1 + bal2));
         
         // Active person should earn more than resting
         assert!(bal2 > bal1, "Active ({}) should earn more than rest ({})", bal2, bal1);
         
-        println!("Rewards: rest={:.4} active={:.4}", bal1, bal2);
+        println!("Rewards: rest={:.4} active={:.4}", to_pulse(bal1), to_pulse(bal2));
     }
     
     #[test]
-    fn test_duplicate_heartbeat_rejected() {
+    fn test_temperature_bounds() {
         let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        
+        // 43°C is implausible (penalized by the validator) but within hard bounds
         let kp = Keypair::generate();
-        let hb = create_test_heartbeat(&kp);
+        let mut hb = create_test_heartbeat(&kp);
+        hb.temperature = 43.0;
+        hb.signature = kp.sign(&hb.signable_bytes());
+        assert!(pol.receive_heartbeat(hb).is_ok());
         
-        // First submission should succeed
-        assert!(pol.receive_heartbeat(hb.clone()).is_ok());
+        // 50°C is outside hard bounds — rejected by consensus and the API check
+        let kp2 = Keypair::generate();
+        let mut hb2 = create_test_heartbeat(&kp2);
+        hb2.temperature = 50.0;
+        hb2.signature = kp2.sign(&hb2.signable_bytes());
+        assert!(matches!(pol.receive_heartbeat(hb2), Err(ConsensusError::InvalidTemperature(_))));
+        assert!(!pol.config().temperature_in_bounds(50.0));
+        assert!(pol.config().temperature_in_bounds(43.0));
+    }
+    
+    #[test]
+    fn test_heart_rate_bounds_from_config() {
+        let mut pol = ProofOfLife::new(ConsensusConfig {
+            min_heart_rate: 40,
+            max_heart_rate: 240,
+            ..Default::default()
+        });
+        let signed = |kp: &Keypair, heart_rate| {
+            let mut hb = create_test_heartbeat(kp);
+            hb.heart_rate = heart_rate;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            hb
+        };
         
-        // Exact same heartbeat (same data) should be rejected as duplicate
-        assert!(pol.receive_heartbeat(hb).is_err());
+        // Below the configured minimum, though within the default range
+        let low = signed(&Keypair::generate(), 35);
+        assert!(matches!(pol.receive_heartbeat(low), Err(ConsensusError::InvalidHeartRate(35))));
+        
+        // Above the default maximum but within the configured one — the validator agrees
+        assert!(pol.receive_heartbeat(signed(&Keypair::generate(), 230)).is_ok());
+        assert!(!pol.config().heart_rate_in_bounds(241));
+        
+        let inverted = ConsensusConfig { min_heart_rate: 100, max_heart_rate: 90, ..Default::default() };
+        assert!(matches!(inverted.validate(), Err(ConsensusError::InvalidConfig(_))));
     }
     
     #[test]
-    fn test_cumulative_chain_weight() {
+    fn test_future_heartbeat_rejected_beyond_clock_skew() {
         let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let skew = pol.config().max_clock_skew_ms;
+        let dated = |kp: &Keypair, timestamp| {
+            let mut hb = create_test_heartbeat(kp);
+            hb.timestamp = timestamp;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            hb
+        };
         
-        assert_eq!(pol.cumulative_chain_weight(), 0.0);
+        // A slightly fast device clock is tolerated
+        let slightly_ahead = dated(&Keypair::generate(), current_time_ms() + skew / 2);
+        assert!(pol.receive_heartbeat(slightly_ahead).is_ok());
+        
+        // Far-future heartbeats would never go stale
+        let far_ahead = dated(&Keypair::generate(), current_time_ms() + 3_600_000);
+        assert!(matches!(pol.receive_heartbeat(far_ahead), Err(ConsensusError::FutureHeartbeat)));
+        assert_eq!(pol.heartbeat_pool_size(), 1);
+    }
+    
+    #[test]
+    fn test_min_block_security() {
+        let config = ConsensusConfig {
+            min_block_security: 5.0,
+            ..Default::default()
+        };
+        assert_eq!(config.min_security_at_height(10), 5.0);
         
+        // A single participant can't reach S=5.0, so no block is produced
+        let mut strict = ProofOfLife::new(config.clone());
         let kp = Keypair::generate();
+        strict.receive_heartbeat(create_test_heartbeat(&kp)).unwrap();
+        assert!(strict.try_create_block().unwrap().is_none());
+        assert_eq!(strict.chain_height(), 0);
         
-        // Create first block
-        let hb1 = create_test_heartbeat(&kp);
-        pol.receive_heartbeat(hb1).unwrap();
-        pol.try_create_block().unwrap();
-        let weight_after_1 = pol.cumulative_chain_weight();
-        assert!(weight_after_1 > 0.0, "Cumulative weight should be > 0 after first block");
+        // A low-security block produced elsewhere is rejected...
+        let mut producer = ProofOfLife::new(ConsensusConfig::default());
+        producer.receive_heartbeat(create_test_heartbeat(&kp)).unwrap();
+        let block = producer.try_create_block().unwrap().unwrap();
+        assert!(matches!(
+            strict.receive_block(block.clone()),
+            Err(ConsensusError::InsufficientSecurity(_, _))
+        ));
         
-        // Create second block (need fresh heartbeat with different timestamp)
-        std::thread::sleep(std::time::Duration::from_millis(10));
-        let hb2 = create_test_heartbeat(&kp);
-        pol.receive_heartbeat(hb2).unwrap();
-        pol.try_create_block().unwrap();
-        let weight_after_2 = pol.cumulative_chain_weight();
+        // ...while a node with an adequate minimum accepts it
+        let mut lenient = ProofOfLife::new(ConsensusConfig {
+            min_block_security: block.security / 2.0,
+            ..Default::default()
+        });
+        assert!(lenient.receive_block(block.clone()).is_ok());
+        assert_eq!(lenient.chain_height(), 1);
         
-        // Cumulative should grow
-        assert!(weight_after_2 > weight_after_1, 
-            "Cumulative weight should grow: {} > {}", weight_after_2, weight_after_1);
+        // Claiming more security than the heartbeats carry doesn't get a block past
+        // the minimum, whether or not the total weight claims it too
+        let mut inflated = block.clone();
+        inflated.security = 100.0;
+        inflated.block_hash = inflated.compute_hash();
+        assert!(matches!(strict.receive_block(inflated), Err(ConsensusError::InvalidBlockWeights)));
+        let mut inflated = block;
+        inflated.security = 100.0;
+        inflated.total_weight = 100.0;
+        inflated.block_hash = inflated.compute_hash();
+        assert!(matches!(
+            strict.receive_block(inflated.clone()),
+            Err(ConsensusError::InsufficientSecurity(_, _))
+        ));
+        let genesis = strict.get_blocks()[0].clone();
+        assert!(matches!(
+            strict.import_chain(vec![genesis, inflated]).map_err(|e| e.source),
+            Err(ConsensusError::InsufficientSecurity(_, _))
+        ));
+    }
+    
+    #[test]
+    fn test_rewards_sum_exactly_to_block_reward() {
+        // Seven participants with uneven weights: shares of 100 PULSE don't divide evenly
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let keys: Vec<Keypair> = (0..7).map(|_| Keypair::generate()).collect();
+        for (i, kp) in keys.iter().enumerate() {
+            let mut hb = create_test_heartbeat(kp);
+            hb.heart_rate = 61 + 13 * i as u16;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        }
+        let block = pol.try_create_block().unwrap().unwrap();
+        let minted: Amount = keys.iter().map(|kp| pol.get_balance(&kp.public_key_hex())).sum();
+        assert_eq!(minted, pol.config().reward_at_height(block.index));
+        assert_eq!(pol.total_minted(), minted);
+        assert_eq!(pol.supply_audit().discrepancy, 0);
+        
+        // Leftover units go to the largest remainders, ties to the earlier participant
+        assert_eq!(distribute_reward(10, &[1.0, 1.0, 1.0]), vec![4, 3, 3]);
+        assert_eq!(distribute_reward(10, &[0.2, 0.5, 0.3]), vec![2, 5, 3]);
+        assert_eq!(distribute_reward(7, &[0.0, 0.0]), vec![0, 0]);
+        for n in 1..50 {
+            let weights: Vec<f64> = (0..n).map(|i| 0.1 + (i as f64 * 0.37) % 0.9).collect();
+            let reward = 100 * MICRO_PER_PULSE + n as Amount;
+            assert_eq!(distribute_reward(reward, &weights).iter().sum::<Amount>(), reward);
+        }
+    }
+    
+    #[test]
+    fn test_no_reward_during_warmup() {
+        let config = ConsensusConfig {
+            min_blocks_before_reward: 2,
+            warmup_reward_policy: WarmupRewardPolicy::Burn,
+            ..Default::default()
+        };
+        let mut pol = ProofOfLife::new(config.clone());
+        let kp = Keypair::generate();
+        let pulse = |pol: &mut ProofOfLife, heart_rate| {
+            let mut hb = create_test_heartbeat(&kp);
+            hb.heart_rate = heart_rate;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        };
+        
+        // Two warmup blocks: the device counts toward n_live but earns nothing
+        for heart_rate in [70, 71] {
+            pulse(&mut pol, heart_rate);
+            let block = pol.try_create_block().unwrap().unwrap();
+            assert_eq!(block.n_live, 1);
+            assert_eq!(pol.get_balance(&kp.public_key_hex()), 0);
+        }
+        assert_eq!(pol.get_accounts()[&kp.public_key_hex()].blocks_participated, 2);
+        assert_eq!(pol.total_minted(), 0);
+        
+        // Third block: warmed up, earns the full reward
+        pulse(&mut pol, 72);
+        pol.try_create_block().unwrap().unwrap();
+        assert_eq!(pol.get_balance(&kp.public_key_hex()), pol.config().reward_at_height(3));
+        assert_eq!(pol.supply_audit().discrepancy, 0);
+        
+        // Peers and replays apply the same warmup
+        let mut peer = ProofOfLife::new(config.clone());
+        for block in pol.get_blocks().into_iter().skip(1) {
+            peer.receive_block(block).unwrap();
+        }
+        assert_eq!(peer.get_balance(&kp.public_key_hex()), pol.get_balance(&kp.public_key_hex()));
+        let (accounts, minted) = ProofOfLife::replay_accounts(&config, &pol.get_blocks());
+        assert_eq!(accounts[&kp.public_key_hex()].balance, pol.get_balance(&kp.public_key_hex()));
+        assert_eq!(minted, pol.total_minted());
+    }
+    
+    #[test]
+    fn test_warmup_reward_redistributed_to_veterans() {
+        let mut pol = ProofOfLife::new(ConsensusConfig {
+            min_blocks_before_reward: 1,
+            ..Default::default()
+        });
+        let (veteran, newcomer) = (Keypair::generate(), Keypair::generate());
+        
+        // The veteran's first block is its own warmup
+        pol.receive_heartbeat(create_test_heartbeat(&veteran)).unwrap();
+        pol.try_create_block().unwrap().unwrap();
+        assert_eq!(pol.total_minted(), 0);
+        
+        // Newcomer joins: the veteran takes the whole reward
+        let mut hb = create_test_heartbeat(&veteran);
+        hb.heart_rate = 73;
+        hb.signature = veteran.sign(&hb.signable_bytes());
+        pol.receive_heartbeat(hb).unwrap();
+        pol.receive_heartbeat(create_test_heartbeat(&newcomer)).unwrap();
+        let block = pol.try_create_block().unwrap().unwrap();
+        assert_eq!(block.n_live, 2);
+        assert_eq!(pol.get_balance(&veteran.public_key_hex()), pol.config().reward_at_height(2));
+        assert_eq!(pol.get_balance(&newcomer.public_key_hex()), 0);
+        
+        // Next block both are past warmup and share it
+        for (kp, heart_rate) in [(&veteran, 74), (&newcomer, 75)] {
+            let mut hb = create_test_heartbeat(kp);
+            hb.heart_rate = heart_rate;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        }
+        pol.try_create_block().unwrap().unwrap();
+        assert!(pol.get_balance(&newcomer.public_key_hex()) > 0);
+        assert_eq!(pol.total_minted(), pol.config().reward_at_height(2) + pol.config().reward_at_height(3));
+        assert_eq!(pol.supply_audit().discrepancy, 0);
+    }
+    
+    #[test]
+    fn test_cap_shares_redistributes_excess() {
+        // 70/20/10 split of 100 with a cap of 40: the excess 30 goes 2:1 to the others
+        let shares = cap_shares(vec![70, 20, 10], &[7.0, 2.0, 1.0], 40);
+        assert_eq!(shares, vec![40, 40, 20]);
+        assert_eq!(shares.iter().sum::<Amount>(), 100);
+
+        // Everyone at the cap: the rest isn't handed out
+        assert_eq!(cap_shares(vec![70, 20, 10], &[7.0, 2.0, 1.0], 30), vec![30, 30, 30]);
+        // Under the cap nothing changes
+        assert_eq!(cap_shares(vec![70, 20, 10], &[7.0, 2.0, 1.0], 70), vec![70, 20, 10]);
     }
 
     #[test]
-    fn test_halving_schedule() {
-        let config = ConsensusConfig::default();
+    fn test_earning_cap_redistributes_block_reward() {
+        let devices: Vec<Keypair> = (0..3).map(|_| Keypair::generate()).collect();
+        let heartbeats: Vec<Heartbeat> = devices.iter().zip([60, 90, 150]).map(|(kp, heart_rate)| {
+            let mut hb = create_test_heartbeat(kp);
+            hb.heart_rate = heart_rate;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            hb
+        }).collect();
+        let balances = |earning_cap| {
+            let mut pol = ProofOfLife::new(ConsensusConfig { earning_cap, ..Default::default() });
+            for hb in &heartbeats {
+                pol.receive_heartbeat(hb.clone()).unwrap();
+            }
+            pol.try_create_block().unwrap().unwrap();
+            assert_eq!(pol.supply_audit().discrepancy, 0);
+            let balances: Vec<Amount> = devices.iter().map(|kp| pol.get_balance(&kp.public_key_hex())).collect();
+            (balances, pol.total_minted())
+        };
+
+        let (uncapped, reward) = balances(EarningCapPolicy::Off);
+        let top = *uncapped.iter().max().unwrap();
+        assert!(top > reward / 3, "weights should differ");
+
+        // A cap between the fair share and the top share trims only the top earner
+        let cap = (reward / 3 + top) / 2;
+        let (capped, minted) = balances(EarningCapPolicy::PerBlock(cap as f64 / MICRO_PER_PULSE as f64));
+        assert_eq!(minted, reward, "the excess is redistributed, not burned");
+        assert!(capped.iter().all(|b| *b <= cap));
+        for (before, after) in uncapped.iter().zip(&capped) {
+            if *before < top {
+                assert!(after > before);
+            }
+        }
+
+        // Below the fair share every device hits the cap and the rest goes unminted
+        let (_, minted) = balances(EarningCapPolicy::PerBlock(1.0));
+        assert_eq!(minted, 3 * MICRO_PER_PULSE);
+    }
+
+    #[test]
+    fn test_equal_reward_policy_ignores_activity() {
+        let devices: Vec<Keypair> = (0..3).map(|_| Keypair::generate()).collect();
+        let heartbeats: Vec<Heartbeat> = devices.iter().zip([(55, 0.0), (95, 0.3), (160, 0.8)]).map(|(kp, (heart_rate, motion))| {
+            let mut hb = create_test_heartbeat(kp);
+            hb.heart_rate = heart_rate;
+            hb.motion = Motion { x: motion, y: motion, z: motion };
+            hb.signature = kp.sign(&hb.signable_bytes());
+            hb
+        }).collect();
+        let balances = |reward_policy| {
+            let mut pol = ProofOfLife::new(ConsensusConfig { reward_policy, ..Default::default() });
+            for hb in &heartbeats {
+                pol.receive_heartbeat(hb.clone()).unwrap();
+            }
+            pol.try_create_block().unwrap().unwrap();
+            assert_eq!(pol.supply_audit().discrepancy, 0);
+            devices.iter().map(|kp| pol.get_balance(&kp.public_key_hex())).collect::<Vec<Amount>>()
+        };
+        let spread = |b: &[Amount]| b.iter().max().unwrap() - b.iter().min().unwrap();
+
+        let weighted = balances(RewardPolicy::Weighted);
+        assert!(spread(&weighted) > 1, "activity should matter when weighted");
+
+        // Equal: identical shares (the indivisible remainder unit aside), same total
+        let equal = balances(RewardPolicy::Equal);
+        assert!(spread(&equal) <= 1);
+        assert_eq!(equal.iter().sum::<Amount>(), weighted.iter().sum::<Amount>());
+
+        // Square root: same order as weighted, narrower range
+        let sqrt = balances(RewardPolicy::SqrtWeighted);
+        assert!(spread(&sqrt) < spread(&weighted) && spread(&sqrt) > spread(&equal));
+        assert_eq!("sqrt_weighted".parse::<RewardPolicy>(), Ok(RewardPolicy::SqrtWeighted));
+        assert!("random".parse::<RewardPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_received_block_rewards_verified() {
+        let mut producer = ProofOfLife::new(ConsensusConfig::default());
+        let kp1 = Keypair::generate();
+        let kp2 = Keypair::generate();
+        producer.receive_heartbeat(create_test_heartbeat(&kp1)).unwrap();
+        let mut hb2 = create_test_heartbeat(&kp2);
+        hb2.heart_rate = 130;
+        hb2.signature = kp2.sign(&hb2.signable_bytes());
+        producer.receive_heartbeat(hb2).unwrap();
+        let block = producer.try_create_block().unwrap().unwrap();
+        assert_eq!(block.continuity_factors.len(), 2);
         
-        // Block 0: full reward
-        let r0 = config.reward_at_height(0);
-        assert_eq!(r0, 100.0);
+        // Receiver reproduces the producer's exact reward split
+        let mut receiver = ProofOfLife::new(ConsensusConfig::default());
+        receiver.receive_block(block.clone()).unwrap();
+        for pk in [kp1.public_key_hex(), kp2.public_key_hex()] {
+            assert_eq!(receiver.get_balance(&pk), producer.get_balance(&pk));
+        }
         
-        // Block at first halving: half reward
-        let r1 = config.reward_at_height(config.halving_interval);
-        assert!((r1 - 50.0).abs() < 0.001, "First halving should give 50, got {}", r1);
+        // Inflating one participant's continuity without fixing the total is rejected
+        let mut tampered = block.clone();
+        tampered.continuity_factors.insert(kp1.public_key_hex(), 1.0);
+        tampered.continuity_factors.insert(kp2.public_key_hex(), 0.5);
+        tampered.block_hash = tampered.compute_hash();
+        let mut receiver = ProofOfLife::new(ConsensusConfig::default());
+        assert!(matches!(receiver.receive_block(tampered), Err(ConsensusError::InvalidBlockWeights)));
         
-        // Block at second halving: quarter reward
-        let r2 = config.reward_at_height(config.halving_interval * 2);
-        assert!((r2 - 25.0).abs() < 0.001, "Second halving should give 25, got {}", r2);
+        // Leaving the factors out doesn't skip the check
+        let mut stripped = block.clone();
+        stripped.continuity_factors.clear();
+        stripped.block_hash = stripped.compute_hash();
+        assert!(matches!(receiver.receive_block(stripped.clone()), Err(ConsensusError::InvalidBlockWeights)));
+        let genesis = receiver.get_blocks()[0].clone();
+        assert!(matches!(
+            receiver.import_chain(vec![genesis, stripped]).map_err(|e| e.source),
+            Err(ConsensusError::InvalidBlockWeights)
+        ));
         
-        // Block at third halving
-        let r3 = config.reward_at_height(config.halving_interval * 3);
-        assert!((r3 - 12.5).abs() < 0.001, "Third halving should give 12.5, got {}", r3);
+        // A claimed total that doesn't match the heartbeats is rejected too
+        let mut tampered = block.clone();
+        tampered.total_weight *= 2.0;
+        tampered.security = tampered.total_weight;
+        tampered.block_hash = tampered.compute_hash();
+        assert!(matches!(receiver.receive_block(tampered.clone()), Err(ConsensusError::InvalidBlockWeights)));
         
-        // Very far in the future: should hit minimum
-        let r_far = config.reward_at_height(config.halving_interval * 100);
-        assert_eq!(r_far, config.min_reward_per_block);
+        // ...unless verification is turned off
+        let mut lenient = ProofOfLife::new(ConsensusConfig {
+            verify_block_rewards: false,
+            ..Default::default()
+        });
+        assert!(lenient.receive_block(tampered).is_ok());
     }
     
     #[test]
-    fn test_inflation_decreases_over_time() {
-        let config = ConsensusConfig::default();
+    fn test_factorless_blocks_accepted_below_activation_height() {
+        let kp = Keypair::generate();
+        let mut producer = ProofOfLife::new(ConsensusConfig::default());
+        producer.receive_heartbeat(create_test_heartbeat(&kp)).unwrap();
+        // Block #1 as a node from before continuity factors would have produced it
+        let mut block = producer.try_create_block().unwrap().unwrap();
+        block.continuity_factors.clear();
+        block.block_hash = block.compute_hash();
+        let receiver = |continuity_factors_height| ProofOfLife::new(ConsensusConfig {
+            continuity_factors_height,
+            ..Default::default()
+        });
+        let genesis = receiver(0).get_blocks()[0].clone();
         
-        // Inflation at height 0 vs height 210_000 — should decrease
-        let r_early = config.reward_at_height(1000);
-        let r_later = config.reward_at_height(config.halving_interval + 1000);
+        // Below the activation height it's accepted, received or imported
+        receiver(2).receive_block(block.clone()).unwrap();
+        assert!(receiver(2).import_chain(vec![genesis.clone(), block.clone()]).unwrap());
         
-        assert!(r_early > r_later, 
-            "Later reward ({}) should be less than early ({})", r_later, r_early);
+        // From it on, it's rejected
+        assert!(matches!(receiver(1).receive_block(block.clone()), Err(ConsensusError::InvalidBlockWeights)));
+        assert!(matches!(
+            receiver(1).import_chain(vec![genesis, block]).map_err(|e| e.source),
+            Err(ConsensusError::InvalidBlockWeights)
+        ));
     }
-
+    
     #[test]
-    fn test_storage_persistence() {
-        let dir = tempfile::tempdir().unwrap();
-        let storage = Arc::new(Storage::open(dir.path()).unwrap());
+    fn test_supply_audit() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kp1 = Keypair::generate();
+        let kp2 = Keypair::generate();
+        pol.receive_heartbeat(create_test_heartbeat(&kp1)).unwrap();
+        pol.receive_heartbeat(create_test_heartbeat(&kp2)).unwrap();
+        pol.try_create_block().unwrap().unwrap();
         
-        let config = ConsensusConfig::default();
-        let mut pol = ProofOfLife::with_storage(config.clone(), storage.clone()).unwrap();
+        let audit = pol.supply_audit();
+        assert!(audit.total_minted > 0);
+        assert_eq!(audit.discrepancy, 0, "healthy chain discrepancy {}", audit.discrepancy);
         
-        // Create a block
+        // Corrupt a balance — the audit must notice
+        pol.accounts.get_mut(&kp1.public_key_hex()).unwrap().balance += 5;
+        let audit = pol.supply_audit();
+        assert_eq!(audit.discrepancy, -5);
+    }
+    
+    #[test]
+    fn test_off_curve_pubkey_rejected_as_invalid() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
         let kp = Keypair::generate();
-        let hb = create_test_heartbeat(&kp);
+        let mut hb = create_test_heartbeat(&kp);
+        // Valid hex, right length, but x = 5 has no point on secp256k1
+        hb.device_pubkey = format!("02{:0>64}", "5");
+        hb.signature = kp.sign(&hb.signable_bytes());
+        
+        let err = pol.receive_heartbeat(hb).unwrap_err();
+        assert!(matches!(err, ConsensusError::InvalidPublicKey));
+        assert_eq!(err.kind(), "invalid_public_key");
+        
+        // Same for an uncompressed key whose (x, y) isn't on the curve
+        let mut uncompressed = create_test_heartbeat(&kp);
+        uncompressed.device_pubkey = format!("04{:0>64}{:0>64}", "1", "1");
+        uncompressed.signature = kp.sign(&uncompressed.signable_bytes());
+        assert!(matches!(pol.receive_heartbeat(uncompressed), Err(ConsensusError::InvalidPublicKey)));
+    }
+    
+    #[test]
+    fn test_continuity_tracking_lru_eviction() {
+        let mut pol = ProofOfLife::new(ConsensusConfig {
+            max_tracked_devices: 3,
+            ..Default::default()
+        });
+        let kps: Vec<Keypair> = (0..4).map(|_| Keypair::generate()).collect();
+        for kp in &kps[..3] {
+            pol.receive_heartbeat(create_test_heartbeat(kp)).unwrap();
+        }
+        
+        // Device 0 pulses again, so device 1 is now the least recently active
+        let mut hb = create_test_heartbeat(&kps[0]);
+        hb.heart_rate = 75;
+        hb.signature = kps[0].sign(&hb.signable_bytes());
         pol.receive_heartbeat(hb).unwrap();
-        pol.try_create_block().unwrap();
         
-        assert_eq!(pol.chain_height(), 1);
+        pol.receive_heartbeat(create_test_heartbeat(&kps[3])).unwrap();
+        assert_eq!(pol.tracked_devices(), 3);
+        assert_eq!(pol.recent_heartbeat_hashes.len(), 3);
+        assert!(!pol.continuity_start.contains_key(&kps[1].public_key_hex()));
+        for i in [0, 2, 3] {
+            assert!(pol.continuity_start.contains_key(&kps[i].public_key_hex()));
+        }
+    }
+    
+    #[test]
+    fn test_cleanup_drops_idle_devices() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let idle = Keypair::generate();
+        let active = Keypair::generate();
+        pol.receive_heartbeat(create_test_heartbeat(&idle)).unwrap();
+        pol.receive_heartbeat(create_test_heartbeat(&active)).unwrap();
+        pol.try_create_block().unwrap().unwrap();
         
-        // Reconstruct from storage — chain should be restored
-        let pol2 = ProofOfLife::with_storage(config, storage).unwrap();
-        assert_eq!(pol2.chain_height(), 1);
+        // Idle device last pulsed well past the threshold; the active one has
+        // been pulsing for as long, but its latest heartbeat is fresh
+        let long_ago = current_time_ms() - pol.config.max_heartbeat_age_ms * 3;
+        if let Some(hashes) = pol.recent_heartbeat_hashes.get_mut(&idle.public_key_hex()) {
+            hashes.iter_mut().for_each(|(ts, _)| *ts = long_ago);
+        }
+        pol.continuity_start.insert(idle.public_key_hex(), long_ago);
+        pol.continuity_start.insert(active.public_key_hex(), long_ago);
+        
+        assert_eq!(pol.cleanup_stale_continuity(), 1);
+        assert_eq!(pol.tracked_devices(), 1);
+        assert!(!pol.continuity_start.contains_key(&idle.public_key_hex()));
+        assert!(!pol.recent_heartbeat_hashes.contains_key(&idle.public_key_hex()));
+        assert!(pol.continuity_start.contains_key(&active.public_key_hex()));
+        
+        // Nothing left to drop on a second pass
+        assert_eq!(pol.cleanup_stale_continuity(), 0);
+    }
+    
+    #[test]
+    fn test_verify_block_heartbeats() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kps: Vec<Keypair> = (0..3).map(|_| Keypair::generate()).collect();
+        for kp in &kps {
+            pol.receive_heartbeat(create_test_heartbeat(kp)).unwrap();
+        }
+        let mut block = pol.try_create_block().unwrap().unwrap();
+        
+        let results = verify_block_heartbeats(&block);
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|(_, valid)| *valid));
+        
+        // Tamper with one heartbeat after signing
+        block.heartbeats[1].heart_rate += 1;
+        let tampered_pk = block.heartbeats[1].device_pubkey.clone();
+        for (pk, valid) in verify_block_heartbeats(&block) {
+            assert_eq!(valid, pk != tampered_pk);
+        }
+    }
+    
+    #[test]
+    fn test_accounts_page_sorts_and_pages() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        for (pubkey, balance, total_earned, blocks_participated) in [
+            ("aa", 30, 50, 1),
+            ("bb", 10, 90, 7),
+            ("cc", 20, 70, 4),
+            ("dd", 20, 10, 2),
+        ] {
+            pol.accounts.insert(pubkey.to_string(), Account {
+                pubkey: pubkey.to_string(),
+                balance,
+                last_heartbeat: 0,
+                total_earned,
+                blocks_participated,
+                nonce: 0,
+            });
+        }
+        let pubkeys = |page: Vec<Account>| page.into_iter().map(|a| a.pubkey).collect::<Vec<_>>();
+        
+        let (page, total) = pol.accounts_page(AccountSort::Balance, 0, 10);
+        assert_eq!(total, 4);
+        assert_eq!(pubkeys(page), ["aa", "cc", "dd", "bb"]);
+        
+        let (page, total) = pol.accounts_page(AccountSort::TotalEarned, 1, 2);
+        assert_eq!(total, 4);
+        assert_eq!(pubkeys(page), ["cc", "aa"]);
+        
+        let (page, _) = pol.accounts_page(AccountSort::BlocksParticipated, 3, 10);
+        assert_eq!(pubkeys(page), ["aa"]);
+        assert!(pol.accounts_page(AccountSort::Balance, 4, 10).0.is_empty());
+    }
+    
+    fn block_with_participants(index: u64, pubkeys: &[&str]) -> PulseBlock {
+        let heartbeats = pubkeys.iter().map(|pk| Heartbeat {
+            timestamp: 0,
+            heart_rate: 72,
+            motion: Motion { x: 0.0, y: 0.0, z: 0.0 },
+            temperature: 36.6,
+            device_pubkey: pk.to_string(),
+            signature: String::new(),
+            signature_version: 0,
+            recoverable: false,
+            metadata: BTreeMap::new(),
+        }).collect();
+        PulseBlock {
+            index,
+            timestamp: 0,
+            previous_hash: String::new(),
+            heartbeats,
+            transactions: vec![],
+            n_live: pubkeys.len(),
+            total_weight: 1.0,
+            security: 1.0,
+            bio_entropy: String::new(),
+            continuity_factors: BTreeMap::new(),
+            confidence_factors: BTreeMap::new(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
+            block_hash: String::new(),
+        }
+    }
+    
+    #[test]
+    fn test_participant_overlap() {
+        let b1 = block_with_participants(1, &["a", "b", "c", "d"]);
+        let b2 = block_with_participants(2, &["c", "d", "e", "f"]);
+        let b3 = block_with_participants(3, &["x", "y"]);
+        assert!((participant_overlap(&b1, &b2) - 0.5).abs() < 1e-12);
+        assert_eq!(participant_overlap(&b2, &b3), 0.0);
+        assert_eq!(participant_overlap(&b2, &b2), 1.0);
+        
+        // Penalty only when an expectation is configured
+        let config = ConsensusConfig::default();
+        assert_eq!(config.reward_for_block(Some(&b2), &b3), config.reward_at_height(3));
+        let config = ConsensusConfig { min_participant_overlap: 0.25, ..Default::default() };
+        assert_eq!(config.reward_for_block(Some(&b1), &b2), config.reward_at_height(2));
+        assert_eq!(config.reward_for_block(Some(&b2), &b3), config.reward_at_height(3) / 2);
+        
+        // Stats average over consecutive pairs (genesis has no participants and is skipped)
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        pol.chain.extend([b1, b2, b3]);
+        assert!((pol.get_stats().liveness_continuity - 0.25).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_stats_report_tip_fork_probability() {
+        let produce = |heart_rate, motion| {
+            let mut pol = ProofOfLife::new(ConsensusConfig::default());
+            let kp = Keypair::generate();
+            let mut hb = create_test_heartbeat(&kp);
+            hb.heart_rate = heart_rate;
+            hb.motion = motion;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+            let block = pol.try_create_block().unwrap().unwrap();
+            (pol.get_stats(), block)
+        };
+        
+        // Genesis alone has no security
+        let stats = ProofOfLife::new(ConsensusConfig::default()).get_stats();
+        assert_eq!(stats.current_fork_probability, 1.0);
+        
+        let (resting, resting_block) = produce(60, Motion { x: 0.01, y: 0.01, z: 0.01 });
+        let (active, active_block) = produce(150, Motion { x: 0.5, y: 0.3, z: 0.2 });
+        assert!(active_block.security > resting_block.security);
+        assert_eq!(active.adaptive_k, resting.adaptive_k);
+        assert!(active.current_fork_probability < resting.current_fork_probability,
+            "{} vs {}", active.current_fork_probability, resting.current_fork_probability);
+        assert_eq!(active.current_fork_probability, active_block.fork_probability(active.adaptive_k));
+    }
+    
+    #[test]
+    fn test_recent_tps() {
+        let tx = |id: &str| Transaction {
+            tx_id: id.to_string(),
+            sender_pubkey: "a".to_string(),
+            recipient_pubkey: "b".to_string(),
+            amount: 1.0,
+            fee: 0.0,
+            timestamp: 0,
+            heartbeat_signature: String::new(),
+            not_before_height: 0,
+            nonce: 1,
+            signature_version: 0,
+            signature: String::new(),
+        };
+        let block = |index: u64, timestamp: u64, txs: usize| PulseBlock {
+            timestamp,
+            transactions: (0..txs).map(|i| tx(&format!("{}-{}", index, i))).collect(),
+            ..block_with_participants(index, &["a"])
+        };
+        
+        // Genesis alone, then a single block: nothing to measure
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        assert_eq!(pol.recent_tps(20), 0.0);
+        pol.chain.push(block(1, 10_000, 5));
+        assert_eq!(pol.recent_tps(20), 0.0);
+        
+        // Same timestamp: no elapsed time
+        pol.chain.push(block(2, 10_000, 3));
+        assert_eq!(pol.recent_tps(20), 0.0);
+        
+        // 3 + 6 transactions confirmed over the 3s after block 1
+        pol.chain.pop();
+        pol.chain.extend([block(2, 12_000, 3), block(3, 13_000, 6)]);
+        assert!((pol.recent_tps(20) - 3.0).abs() < 1e-12);
+        assert_eq!(pol.get_stats().current_tps, pol.recent_tps(TPS_WINDOW_BLOCKS));
+        
+        // Window of the last two blocks: 6 transactions in 1s
+        assert!((pol.recent_tps(2) - 6.0).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_observed_block_time() {
+        let block = |index: u64, timestamp: u64| PulseBlock { timestamp, ..block_with_participants(index, &["a"]) };
+        let mut pol = ProofOfLife::new(ConsensusConfig { block_interval_ms: 5000, ..Default::default() });
+        
+        // Too few blocks to observe: the target stands in
+        pol.chain.push(block(1, 100_000));
+        assert_eq!(pol.observed_block_time(20), None);
+        assert_eq!(pol.get_stats().avg_block_time, 5.0);
+        
+        // Gaps of 5s, 12s (waiting for participants) and 7s
+        pol.chain.extend([block(2, 105_000), block(3, 117_000), block(4, 124_000)]);
+        assert_eq!(pol.observed_block_time(20), Some(8.0));
+        let stats = pol.get_stats();
+        assert_eq!((stats.avg_block_time, stats.target_block_time), (8.0, 5.0));
+        
+        // Only the last two gaps
+        assert_eq!(pol.observed_block_time(3), Some(9.5));
+    }
+    
+    #[test]
+    fn test_nakamoto_coefficient() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        assert_eq!(pol.nakamoto_coefficient(10), 0);
+        
+        // Equal per-heartbeat weight, so contribution tracks participation:
+        // a=6, b=3, c..f=1 each (13 total) — a alone has 6/13, a+b has 9/13
+        pol.chain.extend([
+            block_with_participants(1, &["a", "b", "c"]),
+            block_with_participants(2, &["a", "b", "d"]),
+            block_with_participants(3, &["a", "b", "e"]),
+            block_with_participants(4, &["a", "f"]),
+            block_with_participants(5, &["a"]),
+            block_with_participants(6, &["a"]),
+        ]);
+        assert_eq!(pol.nakamoto_coefficient(10), 2);
+        assert_eq!(pol.get_stats().nakamoto_coefficient, 2);
+        
+        // Last two blocks only: a holds everything
+        assert_eq!(pol.nakamoto_coefficient(2), 1);
+        
+        // Four equal devices: two hold exactly half, which isn't a majority
+        pol.chain.push(block_with_participants(7, &["w", "x", "y", "z"]));
+        assert_eq!(pol.nakamoto_coefficient(1), 3);
+    }
+    
+    #[test]
+    fn test_timelocked_transaction_deferred_until_height() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let sender = Keypair::generate();
+        let recipient = Keypair::generate();
+        let pulse = |pol: &mut ProofOfLife, hr: u16| {
+            let mut hb = create_test_heartbeat(&sender);
+            hb.heart_rate = hr;
+            hb.signature = sender.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        };
+        
+        // Block 1: sender earns a balance
+        pulse(&mut pol, 70);
+        pol.try_create_block().unwrap().unwrap();
+        
+        pulse(&mut pol, 71);
+        let mut tx = Transaction {
+            tx_id: "vesting-1".to_string(),
+            sender_pubkey: sender.public_key_hex(),
+            recipient_pubkey: recipient.public_key_hex(),
+            amount: 1.0,
+            fee: 0.0,
+            timestamp: current_time_ms(),
+            heartbeat_signature: pooled_signature(&pol, &sender),
+            not_before_height: 3,
+            nonce: 1,
+            signature_version: 0,
+            signature: String::new(),
+        };
+        tx.signature = sender.sign(&tx.signable_bytes());
+        pol.receive_transaction(tx).unwrap();
+        
+        // Block 2: too early — stays pooled
+        let b2 = pol.try_create_block().unwrap().unwrap();
+        assert!(b2.transactions.is_empty());
+        assert_eq!(pol.get_balance(&recipient.public_key_hex()), 0);
+        
+        // Block 3: included
+        pulse(&mut pol, 72);
+        let b3 = pol.try_create_block().unwrap().unwrap();
+        assert_eq!(b3.index, 3);
+        assert_eq!(b3.transactions.len(), 1);
+        assert_eq!(pol.get_balance(&recipient.public_key_hex()), MICRO_PER_PULSE);
+        
+        // A peer block including it early is rejected
+        let mut early = b2.clone();
+        early.transactions = b3.transactions.clone();
+        early.update_merkle_roots();
+        early.block_hash = early.compute_hash();
+        let mut receiver = ProofOfLife::new(ConsensusConfig::default());
+        receiver.receive_block(pol.get_block_by_index(1).unwrap()).unwrap();
+        assert!(matches!(receiver.receive_block(early), Err(ConsensusError::TransactionTimelocked(3))));
+    }
+    
+    #[test]
+    fn test_heartbeat_metadata_stays_in_pool() {
+        let kp = Keypair::generate();
+        let mut pol = ProofOfLife::new(ConsensusConfig {
+            max_heartbeat_metadata_bytes: 32,
+            ..Default::default()
+        });
+        
+        let mut hb = create_test_heartbeat(&kp);
+        hb.metadata.insert("model".to_string(), "PulseBand S".to_string());
+        pol.receive_heartbeat(hb).unwrap();
+        assert_eq!(pol.pooled_heartbeat(&kp.public_key_hex()).unwrap().metadata["model"], "PulseBand S");
+        
+        // Blocks never carry the tags
+        let block = pol.try_create_block().unwrap().unwrap();
+        assert!(block.heartbeats[0].metadata.is_empty());
+        
+        // Oversized tag sets are dropped, the heartbeat is still accepted
+        let mut hb = create_test_heartbeat(&kp);
+        hb.heart_rate = 80;
+        hb.signature = kp.sign(&hb.signable_bytes());
+        hb.metadata.insert("notes".to_string(), "x".repeat(64));
+        pol.receive_heartbeat(hb).unwrap();
+        assert!(pol.pooled_heartbeat(&kp.public_key_hex()).unwrap().metadata.is_empty());
+    }
+    
+    #[test]
+    fn test_replayed_transaction_rejected() {
+        let sender = Keypair::generate();
+        let recipient = Keypair::generate();
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let pulse = |pol: &mut ProofOfLife, hr: u16| {
+            let mut hb = create_test_heartbeat(&sender);
+            hb.heart_rate = hr;
+            hb.signature = sender.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        };
+        let transfer = |pol: &ProofOfLife, nonce: u64| {
+            let mut tx = Transaction {
+                tx_id: format!("tx-{}", nonce),
+                sender_pubkey: sender.public_key_hex(),
+                recipient_pubkey: recipient.public_key_hex(),
+                amount: 1.0,
+                fee: 0.0,
+                timestamp: current_time_ms(),
+                heartbeat_signature: pooled_signature(pol, &sender),
+                not_before_height: 0,
+                nonce,
+                signature_version: 0,
+                signature: String::new(),
+            };
+            tx.signature = sender.sign(&tx.signable_bytes());
+            tx
+        };
+        
+        pulse(&mut pol, 70);
+        pol.try_create_block().unwrap().unwrap();
+        pulse(&mut pol, 71);
+        
+        // Nonces start at 1 and must be consecutive
+        assert!(matches!(pol.receive_transaction(transfer(&pol, 0)), Err(ConsensusError::InvalidNonce(1, 0))));
+        assert!(matches!(pol.receive_transaction(transfer(&pol, 2)), Err(ConsensusError::InvalidNonce(1, 2))));
+        pol.receive_transaction(transfer(&pol, 1)).unwrap();
+        
+        // Replaying while still pooled is rejected
+        assert!(matches!(pol.receive_transaction(transfer(&pol, 1)), Err(ConsensusError::InvalidNonce(2, 1))));
+        pol.receive_transaction(transfer(&pol, 2)).unwrap();
+        
+        let block = pol.try_create_block().unwrap().unwrap();
+        assert_eq!(block.transactions.len(), 2);
+        assert_eq!(pol.get_accounts()[&sender.public_key_hex()].nonce, 2);
+        assert_eq!(pol.get_balance(&recipient.public_key_hex()), 2 * MICRO_PER_PULSE);
+        
+        // Replaying after inclusion is rejected too
+        pulse(&mut pol, 72);
+        let replay = block.transactions[0].clone();
+        assert!(matches!(pol.receive_transaction(replay.clone()), Err(ConsensusError::InvalidNonce(3, 1))));
+        pol.receive_transaction(transfer(&pol, 3)).unwrap();
+        let next = pol.try_create_block().unwrap().unwrap();
+        
+        // ...and so is a peer block that includes it again
+        let mut replaying = next.clone();
+        replaying.transactions = vec![replay];
+        replaying.update_merkle_roots();
+        replaying.block_hash = replaying.compute_hash();
+        let mut receiver = ProofOfLife::new(ConsensusConfig::default());
+        for b in &pol.get_blocks()[1..3] {
+            receiver.receive_block(b.clone()).unwrap();
+        }
+        assert!(matches!(receiver.receive_block(replaying.clone()), Err(ConsensusError::InvalidNonce(3, 1))));
+        let mut chain = pol.get_blocks();
+        chain[3] = replaying;
+        let mut fresh = ProofOfLife::new(ConsensusConfig::default());
+        let err = fresh.import_chain(chain).unwrap_err();
+        assert_eq!(err.index, 3);
+        assert!(matches!(err.source, ConsensusError::InvalidNonce(3, 1)));
+        receiver.receive_block(next).unwrap();
+    }
+    
+    #[test]
+    fn test_require_domain_tags() {
+        let sender = Keypair::generate();
+        let recipient = Keypair::generate();
+        let mut pol = ProofOfLife::new(ConsensusConfig {
+            require_domain_tags: true,
+            ..Default::default()
+        });
+        let tagged = |kp: &Keypair, version: u8| {
+            let mut hb = create_test_heartbeat(kp);
+            hb.signature_version = version;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            hb
+        };
+        
+        assert!(matches!(pol.receive_heartbeat(create_test_heartbeat(&sender)),
+            Err(ConsensusError::UnsupportedSignatureVersion(0))));
+        assert!(matches!(pol.receive_heartbeat(tagged(&sender, SIGNATURE_VERSION + 1)),
+            Err(ConsensusError::UnsupportedSignatureVersion(_))));
+        pol.receive_heartbeat(tagged(&sender, SIGNATURE_VERSION)).unwrap();
+        pol.try_create_block().unwrap().unwrap();
+        pol.receive_heartbeat(tagged(&sender, SIGNATURE_VERSION)).unwrap();
+        
+        let transfer = |pol: &ProofOfLife, version: u8| {
+            let mut tx = Transaction {
+                tx_id: format!("v{}", version),
+                sender_pubkey: sender.public_key_hex(),
+                recipient_pubkey: recipient.public_key_hex(),
+                amount: 1.0,
+                fee: 0.0,
+                timestamp: current_time_ms(),
+                heartbeat_signature: pooled_signature(pol, &sender),
+                not_before_height: 0,
+                nonce: 1,
+                signature_version: version,
+                signature: String::new(),
+            };
+            tx.signature = sender.sign(&tx.signable_bytes());
+            tx
+        };
+        let legacy = transfer(&pol, 0);
+        assert!(matches!(pol.receive_transaction(legacy),
+            Err(ConsensusError::UnsupportedSignatureVersion(0))));
+        let tx = transfer(&pol, SIGNATURE_VERSION);
+        pol.receive_transaction(tx).unwrap();
+        
+        // Off by default: legacy clients are still accepted
+        let mut lenient = ProofOfLife::new(ConsensusConfig::default());
+        lenient.receive_heartbeat(create_test_heartbeat(&recipient)).unwrap();
+    }
+    
+    #[test]
+    fn test_recoverable_heartbeat_derives_pubkey() {
+        let kp = Keypair::generate();
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let recoverable = |claimed: String| {
+            let mut hb = create_test_heartbeat(&kp);
+            hb.device_pubkey = claimed;
+            hb.recoverable = true;
+            hb.signature = kp.sign_recoverable(&hb.signable_bytes());
+            hb
+        };
+        
+        // A claimed pubkey must be the signer's
+        assert!(matches!(pol.receive_heartbeat(recoverable(Keypair::generate().public_key_hex())),
+            Err(ConsensusError::InvalidHeartbeatSignature)));
+        
+        // Omitted, it is recovered and the heartbeat pooled under it
+        pol.receive_heartbeat(recoverable(String::new())).unwrap();
+        assert!(pol.pooled_heartbeat(&kp.public_key_hex()).is_some());
+        
+        // Blocks mixing both kinds validate
+        let legacy = Keypair::generate();
+        pol.receive_heartbeat(create_test_heartbeat(&legacy)).unwrap();
+        let block = pol.try_create_block().unwrap().unwrap();
+        assert_eq!(block.heartbeats.len(), 2);
+        assert!(ProofOfLife::validate_chain(&pol.get_blocks(), "").is_ok());
+        let mut follower = ProofOfLife::new(ConsensusConfig::default());
+        follower.receive_block(block).unwrap();
+    }
+    
+    #[test]
+    fn test_transaction_must_reference_current_heartbeat() {
+        let sender = Keypair::generate();
+        let other_device = Keypair::generate();
+        let recipient = Keypair::generate();
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        pol.receive_heartbeat(create_test_heartbeat(&sender)).unwrap();
+        pol.try_create_block().unwrap().unwrap();
+        
+        let transfer = |heartbeat_signature: String| {
+            let mut tx = Transaction {
+                tx_id: "ref".to_string(),
+                sender_pubkey: sender.public_key_hex(),
+                recipient_pubkey: recipient.public_key_hex(),
+                amount: 1.0,
+                fee: 0.0,
+                timestamp: current_time_ms(),
+                heartbeat_signature,
+                not_before_height: 0,
+                nonce: 1,
+                signature_version: 0,
+                signature: String::new(),
+            };
+            tx.signature = sender.sign(&tx.signable_bytes());
+            tx
+        };
+        let mut hb = create_test_heartbeat(&sender);
+        hb.heart_rate = 74;
+        hb.signature = sender.sign(&hb.signable_bytes());
+        let stale = pol.get_blocks()[1].heartbeats[0].signature.clone();
+        let current = hb.signature.clone();
+        pol.receive_heartbeat(hb).unwrap();
+        let other = create_test_heartbeat(&other_device);
+        let borrowed = other.signature.clone();
+        pol.receive_heartbeat(other).unwrap();
+        
+        // Fabricated, already-spent and another device's heartbeats don't prove life
+        for reference in [String::new(), "00".repeat(64), stale, borrowed] {
+            assert!(matches!(pol.receive_transaction(transfer(reference)),
+                Err(ConsensusError::InvalidHeartbeatReference)));
+        }
+        pol.receive_transaction(transfer(current)).unwrap();
+    }
+    
+    #[test]
+    fn test_confidence_quorum_gates_block_production() {
+        let config = ConsensusConfig { min_confidence_sum: 2.5, ..Default::default() };
+        
+        // Five devices reporting implausible (but in-bounds) temperatures:
+        // each is accepted at confidence 0.3, summing to 1.5
+        let mut pol = ProofOfLife::new(config.clone());
+        for _ in 0..5 {
+            let kp = Keypair::generate();
+            let mut hb = create_test_heartbeat(&kp);
+            hb.temperature = 43.0;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        }
+        assert_eq!(pol.heartbeat_pool_size(), 5);
+        assert!((pol.pool_confidence_sum() - 1.5).abs() < 1e-9);
+        assert!(pol.try_create_block().unwrap().is_none());
+        
+        // Three healthy devices clear the quorum
+        let mut pol = ProofOfLife::new(config);
+        for _ in 0..3 {
+            pol.receive_heartbeat(create_test_heartbeat(&Keypair::generate())).unwrap();
+        }
+        assert!(pol.pool_confidence_sum() >= 2.5);
+        assert!(pol.try_create_block().unwrap().is_some());
+        assert_eq!(pol.pool_confidence_sum(), 0.0);
+    }
+    
+    #[test]
+    fn test_pool_snapshot_shows_pending_work() {
+        let (sender, other, recipient) = (Keypair::generate(), Keypair::generate(), Keypair::generate());
+        let mut pol = ProofOfLife::new(ConsensusConfig { n_threshold: 2, ..Default::default() });
+        let empty = pol.pool_snapshot();
+        assert_eq!((empty.n_threshold, empty.heartbeats_needed), (2, 2));
+        assert!(empty.heartbeats.is_empty() && empty.transactions.is_empty());
+
+        // Fund the sender, then leave one heartbeat and a transfer pending
+        pol.receive_heartbeat(create_test_heartbeat(&sender)).unwrap();
+        pol.receive_heartbeat(create_test_heartbeat(&other)).unwrap();
+        pol.try_create_block().unwrap().unwrap();
+        let mut hb = create_test_heartbeat(&sender);
+        hb.heart_rate = 80;
+        hb.signature = sender.sign(&hb.signable_bytes());
+        pol.receive_heartbeat(hb).unwrap();
+        let mut tx = Transaction {
+            tx_id: "pending".to_string(),
+            sender_pubkey: sender.public_key_hex(),
+            recipient_pubkey: recipient.public_key_hex(),
+            amount: 2.5,
+            fee: 0.0,
+            timestamp: current_time_ms(),
+            heartbeat_signature: pooled_signature(&pol, &sender),
+            not_before_height: 0,
+            nonce: 1,
+            signature_version: 0,
+            signature: String::new(),
+        };
+        tx.signature = sender.sign(&tx.signable_bytes());
+        pol.receive_transaction(tx).unwrap();
+
+        let pool = pol.pool_snapshot();
+        assert_eq!(pool.heartbeats_needed, 1);
+        assert_eq!(pool.heartbeats.len(), 1);
+        let pooled = &pool.heartbeats[0];
+        assert_eq!(pooled.pubkey_prefix, format!("{}...", &sender.public_key_hex()[..16]));
+        assert_eq!(pooled.heart_rate, 80);
+        assert!(pooled.weight > 0.0 && pooled.age_ms < 60_000);
+        assert_eq!(pool.transactions.len(), 1);
+        let pending = &pool.transactions[0];
+        assert_eq!((pending.tx_id.as_str(), pending.amount), ("pending", 2.5));
+        assert_eq!(pending.recipient_prefix, format!("{}...", &recipient.public_key_hex()[..16]));
+    }
+
+    #[test]
+    fn test_block_size_cap_carries_over_lightest_heartbeats() {
+        let mut pol = ProofOfLife::new(ConsensusConfig { max_heartbeats_per_block: 3, ..Default::default() });
+        let devices: Vec<(Keypair, u16)> = (0..5).map(|i| (Keypair::generate(), 70 + 2 * i)).collect();
+        for (kp, hr) in &devices {
+            let mut hb = create_test_heartbeat(kp);
+            hb.heart_rate = *hr;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        }
+        
+        // The three fastest hearts weigh the most
+        let block = pol.try_create_block().unwrap().unwrap();
+        assert_eq!((block.heartbeats.len(), block.n_live), (3, 3));
+        let mut included: Vec<u16> = block.heartbeats.iter().map(|hb| hb.heart_rate).collect();
+        included.sort();
+        assert_eq!(included, vec![74, 76, 78]);
+        assert_eq!(block.continuity_factors.len(), 3);
+        assert_eq!(pol.heartbeat_pool_size(), 2);
+        assert_eq!(pol.get_balance(&devices[0].0.public_key_hex()), 0);
+        let minted: Amount = devices.iter().map(|(kp, _)| pol.get_balance(&kp.public_key_hex())).sum();
+        assert_eq!(minted, pol.total_minted());
+        
+        // The rest make the next block
+        let block = pol.try_create_block().unwrap().unwrap();
+        let mut carried: Vec<u16> = block.heartbeats.iter().map(|hb| hb.heart_rate).collect();
+        carried.sort();
+        assert_eq!(carried, vec![70, 72]);
+        assert_eq!(pol.heartbeat_pool_size(), 0);
+        assert!(pol.get_balance(&devices[0].0.public_key_hex()) > 0);
+    }
+
+    #[test]
+    fn test_identical_pools_build_identical_blocks() {
+        let devices: Vec<Keypair> = (0..6).map(|_| Keypair::generate()).collect();
+        let heartbeats: Vec<Heartbeat> = devices.iter().map(create_test_heartbeat).collect();
+        let build = |order: Vec<Heartbeat>| {
+            let mut pol = ProofOfLife::new(ConsensusConfig::default());
+            for hb in order {
+                pol.receive_heartbeat(hb).unwrap();
+            }
+            pol.try_create_block().unwrap().unwrap()
+        };
+        let a = build(heartbeats.clone());
+        let mut b = build(heartbeats.iter().rev().cloned().collect());
+        
+        let pubkeys: Vec<&str> = a.heartbeats.iter().map(|hb| hb.device_pubkey.as_str()).collect();
+        assert!(pubkeys.windows(2).all(|w| w[0] < w[1]), "heartbeats not sorted by pubkey");
+        let signatures = |block: &PulseBlock| block.heartbeats.iter().map(|hb| hb.signature.clone()).collect::<Vec<_>>();
+        assert_eq!(signatures(&a), signatures(&b));
+        assert_eq!((&a.heartbeats_root, &a.bio_entropy), (&b.heartbeats_root, &b.bio_entropy));
+        
+        // Only the wall-clock inputs differ; with those aligned the hashes agree
+        b.timestamp = a.timestamp;
+        b.continuity_factors = a.continuity_factors.clone();
+        b.total_weight = a.total_weight;
+        b.security = a.security;
+        assert_eq!(b.compute_hash(), a.block_hash);
+    }
+
+    #[test]
+    fn test_tx_pool_bounded_and_deduplicated() {
+        let sender = Keypair::generate();
+        let recipient = Keypair::generate();
+        let mut pol = ProofOfLife::new(ConsensusConfig { max_tx_pool: 3, ..Default::default() });
+        let transfer = |pol: &ProofOfLife, tx_id: &str, nonce: u64| {
+            let mut tx = Transaction {
+                tx_id: tx_id.to_string(),
+                sender_pubkey: sender.public_key_hex(),
+                recipient_pubkey: recipient.public_key_hex(),
+                amount: 1.0,
+                fee: 0.0,
+                timestamp: current_time_ms(),
+                heartbeat_signature: pooled_signature(pol, &sender),
+                not_before_height: 0,
+                nonce,
+                signature_version: 0,
+                signature: String::new(),
+            };
+            tx.signature = sender.sign(&tx.signable_bytes());
+            tx
+        };
+        
+        pol.receive_heartbeat(create_test_heartbeat(&sender)).unwrap();
+        pol.try_create_block().unwrap().unwrap();
+        let mut hb = create_test_heartbeat(&sender);
+        hb.heart_rate = 71;
+        hb.signature = sender.sign(&hb.signable_bytes());
+        pol.receive_heartbeat(hb).unwrap();
+        
+        pol.receive_transaction(transfer(&pol, "a", 1)).unwrap();
+        
+        // Same tx_id again — even re-signed with a fresh nonce — is refused
+        assert!(matches!(pol.receive_transaction(transfer(&pol, "a", 2)),
+            Err(ConsensusError::DuplicateTransaction(id)) if id == "a"));
+        
+        pol.receive_transaction(transfer(&pol, "b", 2)).unwrap();
+        pol.receive_transaction(transfer(&pol, "c", 3)).unwrap();
+        assert!(matches!(pol.receive_transaction(transfer(&pol, "d", 4)), Err(ConsensusError::MempoolFull(3))));
+        
+        // Producing a block frees the pool
+        let block = pol.try_create_block().unwrap().unwrap();
+        assert_eq!(block.transactions.len(), 3);
+        let mut hb = create_test_heartbeat(&sender);
+        hb.heart_rate = 72;
+        hb.signature = sender.sign(&hb.signable_bytes());
+        pol.receive_heartbeat(hb).unwrap();
+        pol.receive_transaction(transfer(&pol, "d", 4)).unwrap();
+    }
+    
+    #[test]
+    fn test_transaction_fee_paid_to_participants() {
+        let sender = Keypair::generate();
+        let other = Keypair::generate();
+        let recipient = Keypair::generate();
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let transfer = |pol: &ProofOfLife, fee: f64| {
+            let mut tx = Transaction {
+                tx_id: format!("fee-{fee}"),
+                sender_pubkey: sender.public_key_hex(),
+                recipient_pubkey: recipient.public_key_hex(),
+                amount: 1.0,
+                fee,
+                timestamp: current_time_ms(),
+                heartbeat_signature: pooled_signature(pol, &sender),
+                not_before_height: 0,
+                nonce: 1,
+                signature_version: 0,
+                signature: String::new(),
+            };
+            tx.signature = sender.sign(&tx.signable_bytes());
+            tx
+        };
+        let pulse_again = |pol: &mut ProofOfLife, keypair: &Keypair| {
+            let mut hb = create_test_heartbeat(keypair);
+            hb.heart_rate = 71;
+            hb.signature = keypair.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        };
+        
+        pol.receive_heartbeat(create_test_heartbeat(&sender)).unwrap();
+        pol.receive_heartbeat(create_test_heartbeat(&other)).unwrap();
+        pol.try_create_block().unwrap().unwrap();
+        pulse_again(&mut pol, &sender);
+        pulse_again(&mut pol, &other);
+        let before = pol.get_accounts()[&sender.public_key_hex()].clone();
+        
+        // The fee has to be valid and covered along with the amount
+        for fee in [-0.5, f64::NAN, 1e40, f64::MAX] {
+            assert!(matches!(pol.receive_transaction(transfer(&pol, fee)), Err(ConsensusError::InvalidFee(_))));
+        }
+        assert!(matches!(pol.receive_transaction(transfer(&pol, to_pulse(before.balance))),
+            Err(ConsensusError::InsufficientBalance)));
+        pol.receive_transaction(transfer(&pol, 0.5)).unwrap();
+        let block = pol.try_create_block().unwrap().unwrap();
+        assert_eq!(block.transactions.len(), 1);
+        
+        // Both participants get a share of the fee, which isn't minted
+        let mut fees = HashMap::new();
+        pay_fees(pol.config(), &mut fees, &block);
+        assert_eq!(fees.len(), 2);
+        assert_eq!(fees.values().map(|a| a.balance).sum::<Amount>(), to_micro(0.5));
+        
+        // The largest allowed fees add up and pay out without overflowing
+        let mut max_fees = block.clone();
+        max_fees.transactions = vec![transfer(&pol, MAX_TX_FEE); 3];
+        assert_eq!(tx_cost(&max_fees.transactions[0]), Some(to_micro(1.0) + to_micro(MAX_TX_FEE)));
+        let mut paid = HashMap::new();
+        pay_fees(pol.config(), &mut paid, &max_fees);
+        assert_eq!(paid.values().map(|a| a.balance).sum::<Amount>(), 3 * to_micro(MAX_TX_FEE));
+        let mut huge = transfer(&pol, MAX_TX_FEE);
+        huge.amount = f64::MAX;
+        assert_eq!(tx_cost(&huge), None);
+        
+        let after = &pol.get_accounts()[&sender.public_key_hex()];
+        let reward = after.total_earned - before.total_earned;
+        let fee_share = fees[&sender.public_key_hex()].balance;
+        assert_eq!(after.balance, before.balance + reward + fee_share - to_micro(1.5));
+        assert_eq!(pol.get_accounts()[&recipient.public_key_hex()].balance, to_micro(1.0));
+        assert_eq!(pol.supply_audit().discrepancy, 0);
+        
+        // Peers applying the block arrive at the same balances
+        let (replayed, _) = ProofOfLife::replay_accounts(pol.config(), &pol.get_blocks());
+        assert_eq!(&replayed[&sender.public_key_hex()], after);
+        assert_eq!(replayed[&other.public_key_hex()], pol.get_accounts()[&other.public_key_hex()]);
+        
+        // A peer can't put an oversized fee on its own transaction and pay it out
+        let mut receiver = ProofOfLife::new(ConsensusConfig::default());
+        receiver.receive_block(pol.get_blocks()[1].clone()).unwrap();
+        let with_fee = |fee: f64| {
+            let mut forged = block.clone();
+            forged.transactions[0].fee = fee;
+            forged.transactions[0].signature = sender.sign(&forged.transactions[0].signable_bytes());
+            forged.update_merkle_roots();
+            forged.block_hash = forged.compute_hash();
+            forged
+        };
+        for fee in [-0.5, f64::NAN, MAX_TX_FEE * 2.0, f64::MAX] {
+            assert!(matches!(receiver.receive_block(with_fee(fee)), Err(ConsensusError::InvalidFee(_))));
+        }
+        assert!(matches!(receiver.receive_block(with_fee(MAX_TX_FEE)), Err(ConsensusError::InsufficientBalance)));
+        receiver.receive_block(block).unwrap();
+        assert_eq!(receiver.supply_audit().discrepancy, 0);
+    }
+    
+    #[test]
+    fn test_full_block_takes_highest_fees_in_nonce_order() {
+        let tx = |sender: &str, nonce: u64, fee: f64| Transaction {
+            tx_id: format!("{sender}-{nonce}"),
+            sender_pubkey: sender.to_string(),
+            recipient_pubkey: "r".to_string(),
+            amount: 1.0,
+            fee,
+            timestamp: 0,
+            heartbeat_signature: String::new(),
+            not_before_height: 0,
+            nonce,
+            signature_version: 0,
+            signature: String::new(),
+        };
+        let ids = |txs: &[Transaction]| txs.iter().map(|tx| tx.tx_id.clone()).collect::<Vec<_>>();
+        let ready = vec![tx("a", 1, 0.1), tx("a", 2, 5.0), tx("b", 1, 1.0), tx("c", 1, 0.5)];
+        
+        let (selected, rest) = select_by_fee(ready.clone(), 4);
+        assert_eq!(ids(&selected), ids(&ready));
+        assert!(rest.is_empty());
+        
+        // a-2 pays the most but can't go before a-1
+        let (selected, rest) = select_by_fee(ready.clone(), 2);
+        assert_eq!(ids(&selected), ["b-1", "c-1"]);
+        assert_eq!(ids(&rest), ["a-1", "a-2"]);
+        
+        let (selected, rest) = select_by_fee(ready, 3);
+        assert_eq!(ids(&selected), ["b-1", "c-1", "a-1"]);
+        assert_eq!(ids(&rest), ["a-2"]);
+    }
+    
+    #[test]
+    fn test_conflicting_transactions_never_overdraw() {
+        let sender = Keypair::generate();
+        let recipient = Keypair::generate();
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let pulse = |pol: &mut ProofOfLife, hr: u16| {
+            let mut hb = create_test_heartbeat(&sender);
+            hb.heart_rate = hr;
+            hb.signature = sender.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        };
+        let transfer = |pol: &ProofOfLife, nonce: u64, amount: f64| {
+            let mut tx = Transaction {
+                tx_id: format!("spend-{}", nonce),
+                sender_pubkey: sender.public_key_hex(),
+                recipient_pubkey: recipient.public_key_hex(),
+                amount,
+                fee: 0.0,
+                timestamp: current_time_ms(),
+                heartbeat_signature: pooled_signature(pol, &sender),
+                not_before_height: 0,
+                nonce,
+                signature_version: 0,
+                signature: String::new(),
+            };
+            tx.signature = sender.sign(&tx.signable_bytes());
+            tx
+        };
+        
+        // Sole participant earns the full 100 PULSE reward
+        pulse(&mut pol, 70);
+        pol.try_create_block().unwrap().unwrap();
+        let balance = pol.get_balance(&sender.public_key_hex());
+        
+        // Each spend passes the pool check alone; together they'd overdraw
+        pulse(&mut pol, 71);
+        pol.receive_transaction(transfer(&pol, 1, to_pulse(balance) * 0.75)).unwrap();
+        pol.receive_transaction(transfer(&pol, 2, to_pulse(balance) * 0.75)).unwrap();
+        
+        let block = pol.try_create_block().unwrap().unwrap();
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(block.transactions[0].tx_id, "spend-1");
+        // Nothing was clamped at zero to make the books balance
+        assert_eq!(pol.supply_audit().discrepancy, 0);
+        
+        // The conflicting spend comes back to the caller, and its nonce is free again
+        let dropped = pol.take_dropped_transactions();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].tx_id, "spend-2");
+        assert!(pol.take_dropped_transactions().is_empty());
+        assert_eq!(pol.next_nonce(&sender.public_key_hex()), 2);
+        
+        // A peer block carrying both spends is refused rather than clamped
+        let mut overdrawn = block.clone();
+        overdrawn.transactions.push(dropped[0].clone());
+        overdrawn.update_merkle_roots();
+        overdrawn.block_hash = overdrawn.compute_hash();
+        let mut receiver = ProofOfLife::new(ConsensusConfig::default());
+        receiver.receive_block(pol.get_blocks()[1].clone()).unwrap();
+        assert!(matches!(receiver.receive_block(overdrawn.clone()), Err(ConsensusError::InsufficientBalance)));
+        assert_eq!(receiver.get_balance(&sender.public_key_hex()), balance);
+        let mut chain = pol.get_blocks();
+        chain[2] = overdrawn;
+        let err = ProofOfLife::new(ConsensusConfig::default()).import_chain(chain).unwrap_err();
+        assert_eq!(err.index, 2);
+        assert!(matches!(err.source, ConsensusError::InsufficientBalance));
+        receiver.receive_block(block).unwrap();
+        assert_eq!(receiver.supply_audit().discrepancy, 0);
+        
+        // A sender who stopped pulsing before the block is dropped too
+        pulse(&mut pol, 72);
+        pol.receive_transaction(transfer(&pol, 2, 1.0)).unwrap();
+        pol.heartbeat_pool.clear();
+        pol.receive_heartbeat(create_test_heartbeat(&Keypair::generate())).unwrap();
+        let block = pol.try_create_block().unwrap().unwrap();
+        assert!(block.transactions.is_empty());
+        assert_eq!(pol.take_dropped_transactions().len(), 1);
+    }
+    
+    #[test]
+    fn test_chain_fingerprint() {
+        let kp = Keypair::generate();
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        for hr in 70..77 {
+            let mut hb = create_test_heartbeat(&kp);
+            hb.heart_rate = hr;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+            pol.try_create_block().unwrap().unwrap();
+        }
+        
+        // Identical chains agree
+        let mut other = ProofOfLife::new(ConsensusConfig::default());
+        for h in 1..=7 {
+            other.receive_block(pol.get_block_by_index(h).unwrap()).unwrap();
+        }
+        let fp = pol.chain_fingerprint(3);
+        assert_eq!(fp.iter().map(|(h, _)| *h).collect::<Vec<_>>(), vec![0, 3, 6, 7]);
+        assert_eq!(fp, other.chain_fingerprint(3));
+        
+        // Diverge from height 5: samples 0 and 3 still match, 6 and the tip don't
+        for block in other.chain.iter_mut().skip(5) {
+            block.block_hash = format!("forked-{}", block.index);
+        }
+        let theirs = other.chain_fingerprint(3);
+        let first_mismatch = fp.iter().zip(&theirs).position(|(a, b)| a != b);
+        assert_eq!(first_mismatch, Some(2));
+        assert_eq!(theirs[2].0, 6);
+    }
+    
+    #[test]
+    fn test_startup_repair_truncates_corrupt_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(Storage::open(dir.path()).unwrap());
+        let kp = Keypair::generate();
+        let mut balance_at_2 = 0;
+        {
+            let mut pol = ProofOfLife::with_storage(ConsensusConfig::default(), storage.clone()).unwrap();
+            for hr in 70..75 {
+                let mut hb = create_test_heartbeat(&kp);
+                hb.heart_rate = hr;
+                hb.signature = kp.sign(&hb.signable_bytes());
+                pol.receive_heartbeat(hb).unwrap();
+                pol.try_create_block().unwrap().unwrap();
+                if pol.chain_height() == 2 {
+                    balance_at_2 = pol.get_balance(&kp.public_key_hex());
+                }
+            }
+            assert_eq!(pol.chain_height(), 5);
+        }
+        
+        // Corrupt block 3 on disk: its heartbeat no longer matches the signature
+        let mut bad = storage.load_block(3).unwrap();
+        bad.heartbeats[0].heart_rate = 200;
+        storage.save_block(&bad).unwrap();
+        
+        // Without repair the node refuses to load the broken chain
+        assert!(matches!(
+            ProofOfLife::with_storage(ConsensusConfig::default(), storage.clone()),
+            Err(ConsensusError::CorruptChain(3))
+        ));
+        
+        // With repair it truncates to #2 and rebuilds balances
+        let config = ConsensusConfig { repair_chain_on_startup: true, ..Default::default() };
+        let pol = ProofOfLife::with_storage(config, storage.clone()).unwrap();
+        assert_eq!(pol.chain_height(), 2);
+        assert_eq!(pol.verify_chain(), None);
+        assert_eq!(pol.get_balance(&kp.public_key_hex()), balance_at_2);
+        assert_eq!(pol.supply_audit().discrepancy, 0);
+        assert!(storage.load_block(3).is_err());
+        assert_eq!(storage.load_account(&kp.public_key_hex()).unwrap().unwrap().blocks_participated, 2);
+    }
+    
+    /// Produce `n` blocks on a fresh in-memory node with a single participant
+    fn produce_chain(kp: &Keypair, n: u16) -> ProofOfLife {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        for hr in 70..70 + n {
+            let mut hb = create_test_heartbeat(kp);
+            hb.heart_rate = hr;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+            pol.try_create_block().unwrap().unwrap();
+        }
+        pol
+    }
+    
+    #[test]
+    fn test_receive_block_accept_and_reject_paths() {
+        let kp = Keypair::generate();
+        let producer = produce_chain(&kp, 2);
+        let b1 = producer.get_block_by_index(1).unwrap();
+        let b2 = producer.get_block_by_index(2).unwrap();
+        
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        
+        // Out of order: doesn't extend our tip
+        assert!(matches!(pol.receive_block(b2.clone()), Err(ConsensusError::InvalidPreviousHash)));
+        
+        // Contents changed without re-hashing
+        let mut tampered = b1.clone();
+        tampered.security += 1.0;
+        assert!(matches!(pol.receive_block(tampered), Err(ConsensusError::InvalidBlockHash)));
+        
+        // Re-hashed, but a heartbeat no longer matches its signature
+        let mut forged = b1.clone();
+        forged.heartbeats[0].heart_rate = 150;
+        forged.update_merkle_roots();
+        forged.block_hash = forged.compute_hash();
+        assert!(matches!(pol.receive_block(forged), Err(ConsensusError::InvalidHeartbeatSignature)));
+        
+        // Contents swapped under unchanged roots: the hash still matches, the roots don't
+        let mut swapped = b1.clone();
+        swapped.heartbeats = b2.heartbeats.clone();
+        assert_eq!(swapped.compute_hash(), swapped.block_hash);
+        assert!(matches!(pol.receive_block(swapped), Err(ConsensusError::InvalidBlockHash)));
+        
+        // Valid block survives a JSON round-trip (the gossip encoding) with its hash intact
+        let wire: PulseBlock = serde_json::from_slice(&serde_json::to_vec(&b1).unwrap()).unwrap();
+        pol.receive_block(wire).unwrap();
+        assert_eq!(pol.chain_height(), 1);
+        assert_eq!(pol.get_balance(&kp.public_key_hex()), 100 * MICRO_PER_PULSE);
+        
+        // Replaying the same block is rejected
+        assert!(matches!(pol.receive_block(b1), Err(ConsensusError::InvalidPreviousHash)));
+        pol.receive_block(b2).unwrap();
+        assert_eq!(pol.chain_height(), 2);
+    }
+    
+    #[test]
+    fn test_block_timestamps_must_advance_and_not_run_ahead() {
+        let kp = Keypair::generate();
+        let producer = produce_chain(&kp, 2);
+        let b1 = producer.get_block_by_index(1).unwrap();
+        let genesis = producer.get_block_by_index(0).unwrap();
+        
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let restamp = |timestamp: u64| {
+            let mut block = b1.clone();
+            block.timestamp = timestamp;
+            block.block_hash = block.compute_hash();
+            block
+        };
+        
+        // Not after its predecessor
+        let past = restamp(genesis.timestamp);
+        assert!(matches!(pol.receive_block(past), Err(ConsensusError::InvalidTimestamp(_))));
+        
+        // Beyond the allowed drift
+        let future = restamp(current_time_ms() + pol.config().max_future_drift_ms + 60_000);
+        assert!(matches!(pol.receive_block(future), Err(ConsensusError::InvalidTimestamp(_))));
+        
+        // A peer chain with a backwards step is rejected as a whole
+        let mut chain = producer.get_blocks_from(0);
+        chain[2].timestamp = chain[1].timestamp;
+        chain[2].block_hash = chain[2].compute_hash();
+        assert!(matches!(pol.replace_chain(chain.clone()), Err(ConsensusError::InvalidTimestamp(_))));
+        assert_eq!(ProofOfLife::first_invalid_height(&chain, &[], ""), Some(2));
+        
+        // Slightly ahead of our clock is tolerated, and our next block still follows it
+        pol.receive_block(restamp(current_time_ms() + 10_000)).unwrap();
+        let ahead = pol.latest_block().unwrap().timestamp;
+        pol.receive_heartbeat(create_test_heartbeat(&Keypair::generate())).unwrap();
+        let next = pol.try_create_block().unwrap().unwrap();
+        assert!(next.timestamp > ahead);
+    }
+    
+    #[test]
+    fn test_replace_chain_accept_and_reject_paths() {
+        let kp = Keypair::generate();
+        let long = produce_chain(&kp, 4);
+        let short = produce_chain(&Keypair::generate(), 2);
+        let long_chain: Vec<PulseBlock> = (0..=4).map(|h| long.get_block_by_index(h).unwrap()).collect();
+        
+        // Broken link is rejected even though the chain is heavier
+        let mut broken = long_chain.clone();
+        broken.remove(2);
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        assert!(matches!(pol.replace_chain(broken), Err(ConsensusError::InvalidPreviousHash)));
+        assert_eq!(pol.chain_height(), 0);
+        
+        // Heavier valid chain is adopted, with balances rebuilt
+        let mut pol = short;
+        pol.replace_chain(long_chain.clone()).unwrap();
+        assert_eq!(pol.chain_height(), 4);
+        assert_eq!(pol.get_balance(&kp.public_key_hex()), 400 * MICRO_PER_PULSE);
+        
+        // A lighter chain is ignored
+        let lighter: Vec<PulseBlock> = long_chain[..3].to_vec();
+        pol.replace_chain(lighter).unwrap();
+        assert_eq!(pol.chain_height(), 4);
+    }
+    
+    #[test]
+    fn test_import_chain_names_failing_block() {
+        let long = produce_chain(&Keypair::generate(), 4);
+        let long_chain: Vec<PulseBlock> = (0..=4).map(|h| long.get_block_by_index(h).unwrap()).collect();
+        
+        let mut broken = long_chain.clone();
+        broken.remove(2);
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let err = pol.import_chain(broken).unwrap_err();
+        assert_eq!(err.index, 3);
+        assert!(matches!(err.source, ConsensusError::InvalidPreviousHash));
+        assert!(err.to_string().starts_with("Block #3: "), "{}", err);
+        
+        assert!(pol.import_chain(long_chain.clone()).unwrap());
+        assert!(!pol.import_chain(long_chain[..3].to_vec()).unwrap());
+    }
+    
+    #[test]
+    fn test_fork_choice_heaviest_then_lowest_tip_hash() {
+        let mut a = produce_chain(&Keypair::generate(), 1);
+        let chain_of = |pol: &ProofOfLife| -> Vec<PulseBlock> {
+            (0..=pol.chain_height()).map(|h| pol.get_block_by_index(h).unwrap()).collect()
+        };
+        let fork_a = chain_of(&a);
+        
+        // The same block stamped a millisecond later: equal weight, different tips
+        let mut fork_b = fork_a.clone();
+        fork_b[1].timestamp += 1;
+        fork_b[1].block_hash = fork_b[1].compute_hash();
+        let mut b = ProofOfLife::new(ConsensusConfig::default());
+        b.replace_chain(fork_b.clone()).unwrap();
+        assert_eq!(b.latest_block().unwrap().block_hash, fork_b[1].block_hash);
+        assert_eq!(chain_weight_of(&fork_a), chain_weight_of(&fork_b));
+        assert_eq!(chain_weight_of(&fork_a), a.cumulative_chain_weight());
+        assert_ne!(fork_a[1].block_hash, fork_b[1].block_hash);
+        
+        // The tie goes to the lower tip hash, whichever side we started on
+        let a_wins = fork_a[1].block_hash < fork_b[1].block_hash;
+        assert_eq!(b.should_adopt_chain(&fork_a), a_wins);
+        assert_eq!(a.should_adopt_chain(&fork_b), !a_wins);
+        
+        // Both nodes converge on the same tip after exchanging chains
+        a.replace_chain(fork_b.clone()).unwrap();
+        b.replace_chain(fork_a.clone()).unwrap();
+        assert_eq!(a.latest_block().unwrap().block_hash, b.latest_block().unwrap().block_hash);
+        
+        // Our own chain never displaces itself, and an empty candidate never wins
+        assert!(!a.should_adopt_chain(&chain_of(&a)));
+        assert!(!a.should_adopt_chain(&[]));
+        
+        // Weight beats hash order in both directions
+        let heavier = chain_of(&produce_chain(&Keypair::generate(), 2));
+        assert!(a.should_adopt_chain(&heavier));
+        assert!(!a.should_adopt_chain(&heavier[..1]));
+    }
+    
+    #[test]
+    fn test_reorg_reverses_orphaned_accounts() {
+        let store = Arc::new(MemStore::new());
+        let config = ConsensusConfig::default();
+        let mut a = ProofOfLife::with_storage(config.clone(), store.clone()).unwrap();
+        let miner = Keypair::generate();
+        let pulse = |pol: &mut ProofOfLife, kp: &Keypair, hr: u16| {
+            let mut hb = create_test_heartbeat(kp);
+            hb.heart_rate = hr;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        };
+        
+        // Shared 3-block prefix
+        for hr in 70..73 {
+            pulse(&mut a, &miner, hr);
+            a.try_create_block().unwrap().unwrap();
+        }
+        let mut b = ProofOfLife::new(config.clone());
+        assert!(b.import_chain(a.get_blocks()).unwrap());
+        
+        // A's suffix: one block with a transfer to an account only it knows about
+        let (orphan_device, recipient) = (Keypair::generate(), Keypair::generate());
+        pulse(&mut a, &miner, 73);
+        pulse(&mut a, &orphan_device, 80);
+        let mut tx = Transaction {
+            tx_id: "orphaned".to_string(),
+            sender_pubkey: miner.public_key_hex(),
+            recipient_pubkey: recipient.public_key_hex(),
+            amount: 5.0,
+            fee: 0.0,
+            timestamp: current_time_ms(),
+            heartbeat_signature: pooled_signature(&a, &miner),
+            not_before_height: 0,
+            nonce: 1,
+            signature_version: 0,
+            signature: String::new(),
+        };
+        tx.signature = miner.sign(&tx.signable_bytes());
+        a.receive_transaction(tx).unwrap();
+        a.try_create_block().unwrap().unwrap();
+        assert_eq!(a.accounts.get(&miner.public_key_hex()).unwrap().nonce, 1);
+        
+        // B's heavier suffix: two blocks from other devices
+        let (d1, d2) = (Keypair::generate(), Keypair::generate());
+        for hr in [90, 91] {
+            pulse(&mut b, &d1, hr);
+            pulse(&mut b, &d2, hr + 10);
+            pulse(&mut b, &miner, hr - 15);
+            b.try_create_block().unwrap().unwrap();
+        }
+        
+        let winning = b.get_blocks();
+        assert!(a.import_chain(winning.clone()).unwrap());
+        assert_eq!(a.latest_block().unwrap().block_hash, b.latest_block().unwrap().block_hash);
+        
+        // Same state as replaying the winning chain from scratch
+        let summary = |accounts: Vec<Account>| {
+            let mut rows: Vec<_> = accounts.into_iter()
+                .map(|acc| (acc.pubkey, acc.balance, acc.total_earned, acc.blocks_participated, acc.nonce, acc.last_heartbeat))
+                .collect();
+            rows.sort();
+            rows
+        };
+        let (replayed, minted) = ProofOfLife::replay_accounts(&config, &winning);
+        assert_eq!(summary(a.accounts.values().cloned().collect()), summary(replayed.into_values().collect()));
+        assert_eq!(a.total_minted, minted);
+        assert!((a.cumulative_chain_weight() - chain_weight_of(&winning)).abs() < 1e-9);
+        assert!(!a.accounts.contains_key(&recipient.public_key_hex()));
+        assert!(!a.accounts.contains_key(&orphan_device.public_key_hex()));
+        assert_eq!(a.accounts.get(&miner.public_key_hex()).unwrap().nonce, 0);
+        assert_eq!(a.get_participant_blocks(&orphan_device.public_key_hex(), 0, 10).1, 0);
+        
+        // Storage matches too, and a restart agrees
+        assert_eq!(summary(store.load_all_accounts().unwrap()), summary(a.accounts.values().cloned().collect()));
+        let restored = ProofOfLife::with_storage(config, store).unwrap();
+        assert_eq!(restored.chain_height(), 5);
+        assert_eq!(restored.latest_block().unwrap().block_hash, b.latest_block().unwrap().block_hash);
+    }
+    
+    /// Build a two-block chain rooted in a genesis that differs from ours
+    fn foreign_chain() -> Vec<PulseBlock> {
+        let mut genesis = ProofOfLife::create_genesis_block("");
+        genesis.timestamp += 1;
+        genesis.block_hash = genesis.compute_hash();
+        let mut block = PulseBlock {
+            index: 1,
+            timestamp: genesis.timestamp + 5000,
+            previous_hash: genesis.block_hash.clone(),
+            heartbeats: vec![],
+            transactions: vec![],
+            n_live: 0,
+            total_weight: 10.0,
+            security: 10.0,
+            bio_entropy: "0".repeat(64),
+            continuity_factors: BTreeMap::new(),
+            confidence_factors: BTreeMap::new(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
+            block_hash: String::new(),
+        };
+        block.block_hash = block.compute_hash();
+        vec![genesis, block]
+    }
+    
+    fn extend_foreign(prev: &PulseBlock) -> PulseBlock {
+        let mut block = PulseBlock {
+            index: prev.index + 1,
+            timestamp: prev.timestamp + 5000,
+            previous_hash: prev.block_hash.clone(),
+            ..prev.clone()
+        };
+        block.block_hash = block.compute_hash();
+        block
+    }
+    
+    #[test]
+    fn test_incompatible_genesis_blacklisted_once() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let foreign = foreign_chain();
+        
+        // Heavier chain, but a different genesis — refused and recorded once
+        assert!(matches!(pol.replace_chain(foreign.clone()), Err(ConsensusError::IncompatibleNetwork(_))));
+        assert!(matches!(pol.replace_chain(foreign.clone()), Err(ConsensusError::IncompatibleNetwork(_))));
+        assert_eq!(pol.incompatible_network_count(), 1);
+        assert_eq!(pol.chain_height(), 0);
+        
+        // Gossiped blocks extending the foreign chain are recognized as incompatible
+        // (not as prev-hash mismatches, which would trigger another sync request)
+        let b2 = extend_foreign(&foreign[1]);
+        let b3 = extend_foreign(&b2);
+        assert!(matches!(pol.receive_block(b2), Err(ConsensusError::IncompatibleNetwork(_))));
+        assert!(matches!(pol.receive_block(b3), Err(ConsensusError::IncompatibleNetwork(_))));
+        assert_eq!(pol.incompatible_network_count(), 1);
+    }
+    
+    #[test]
+    fn test_chain_id_separates_networks() {
+        let testnet = ConsensusConfig { chain_id: "pulse-testnet".to_string(), ..Default::default() };
+        let devnet = ConsensusConfig { chain_id: "pulse-devnet".to_string(), ..Default::default() };
+        assert_ne!(ProofOfLife::new(testnet.clone()).genesis_hash(), ProofOfLife::new(devnet.clone()).genesis_hash());
+        assert_ne!(ProofOfLife::new(testnet.clone()).genesis_hash(), ProofOfLife::new(ConsensusConfig::default()).genesis_hash());
+        
+        let kp = Keypair::generate();
+        let mut producer = ProofOfLife::new(testnet.clone());
+        producer.receive_heartbeat(create_test_heartbeat(&kp)).unwrap();
+        let block = producer.try_create_block().unwrap().unwrap();
+        assert_eq!(block.chain_id, "pulse-testnet");
+        
+        // Relabelling the block breaks its hash; re-hashing it still names the wrong network
+        let mut relabelled = block.clone();
+        relabelled.chain_id = "pulse-devnet".to_string();
+        assert_ne!(relabelled.compute_hash(), block.block_hash);
+        relabelled.block_hash = relabelled.compute_hash();
+        assert!(matches!(ProofOfLife::new(testnet.clone()).receive_block(relabelled),
+            Err(ConsensusError::ForeignChainId(id)) if id == "pulse-devnet"));
+        
+        let mut other = ProofOfLife::new(devnet);
+        assert!(matches!(other.receive_block(block.clone()),
+            Err(ConsensusError::ForeignChainId(id)) if id == "pulse-testnet"));
+        assert!(matches!(other.replace_chain(producer.get_blocks_from(0)), Err(ConsensusError::ForeignChainId(_))));
+        assert_eq!(other.chain_height(), 0);
+        
+        ProofOfLife::new(testnet).receive_block(block).unwrap();
+    }
+    
+    #[test]
+    fn test_incompatible_genesis_detection_can_be_disabled() {
+        let mut pol = ProofOfLife::new(ConsensusConfig {
+            blacklist_incompatible_chains: false,
+            ..Default::default()
+        });
+        let foreign = foreign_chain();
+        let b2 = extend_foreign(&foreign[1]);
+        assert!(matches!(pol.receive_block(b2), Err(ConsensusError::InvalidPreviousHash)));
+        assert_eq!(pol.incompatible_network_count(), 0);
+    }
+    
+    #[test]
+    fn test_duplicate_heartbeat_rejected() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kp = Keypair::generate();
+        let hb = create_test_heartbeat(&kp);
+        
+        // First submission should succeed
+        assert!(pol.receive_heartbeat(hb.clone()).is_ok());
+        
+        // Exact same heartbeat (same data) should be rejected as duplicate
+        assert!(pol.receive_heartbeat(hb).is_err());
+    }
+    
+    #[test]
+    fn test_alternating_heartbeats_cant_bypass_duplicate_check() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kp = Keypair::generate();
+        let signed = |i: u16| {
+            let mut hb = create_test_heartbeat(&kp);
+            // Scatter the readings so they look like natural variability
+            hb.heart_rate = 64 + (i * 7) % 19;
+            hb.motion.x = 0.1 + ((i * 37) % 11) as f64 * 0.01;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            hb
+        };
+        let (first, second) = (signed(0), signed(1));
+        
+        pol.receive_heartbeat(first.clone()).unwrap();
+        pol.receive_heartbeat(second.clone()).unwrap();
+        // Replaying the earlier payload after a newer one is still a duplicate
+        assert!(matches!(pol.receive_heartbeat(first), Err(ConsensusError::StaleHeartbeat)));
+        assert!(matches!(pol.receive_heartbeat(second), Err(ConsensusError::StaleHeartbeat)));
+        assert!(pol.receive_heartbeat(signed(2)).is_ok());
+        
+        // Memory per device stays bounded
+        for i in 3..3 + MAX_RECENT_HASHES_PER_DEVICE as u16 {
+            pol.receive_heartbeat(signed(i)).unwrap();
+        }
+        assert_eq!(pol.recent_heartbeat_hashes[&kp.public_key_hex()].len(), MAX_RECENT_HASHES_PER_DEVICE);
+    }
+
+    #[test]
+    fn test_signature_cache_skips_reverification() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let heartbeats: Vec<Heartbeat> = (0..20).map(|_| create_test_heartbeat(&Keypair::generate())).collect();
+
+        for hb in &heartbeats {
+            assert!(pol.verify_heartbeat_signature(hb).unwrap());
+        }
+        assert_eq!(pol.metrics().signature_cache_hits, 0);
+        for hb in &heartbeats {
+            assert!(pol.verify_heartbeat_signature(hb).unwrap());
+        }
+        assert_eq!(pol.metrics().signature_cache_hits, 20);
+
+        // Gossiped, then submitted locally: the second pass hits the cache (and is a duplicate)
+        let hb = create_test_heartbeat(&Keypair::generate());
+        pol.receive_heartbeat(hb.clone()).unwrap();
+        assert!(matches!(pol.receive_heartbeat(hb.clone()), Err(ConsensusError::StaleHeartbeat)));
+        assert_eq!(pol.metrics().signature_cache_hits, 21);
+
+        // A cached signature doesn't vouch for altered data, and failures aren't cached
+        let mut tampered = hb;
+        tampered.heart_rate += 1;
+        for _ in 0..2 {
+            assert!(matches!(pol.receive_heartbeat(tampered.clone()), Err(ConsensusError::InvalidHeartbeatSignature)));
+        }
+        assert_eq!(pol.metrics().signature_cache_hits, 21);
+    }
+
+    /// Verifying vs cached signature checks; run with
+    /// `cargo test --release bench_signature_cache -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_signature_cache() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let heartbeats: Vec<Heartbeat> = (0..200).map(|_| create_test_heartbeat(&Keypair::generate())).collect();
+
+        let start = std::time::Instant::now();
+        for hb in &heartbeats {
+            assert!(pol.verify_heartbeat_signature(hb).unwrap());
+        }
+        let miss = start.elapsed();
+
+        let start = std::time::Instant::now();
+        for hb in &heartbeats {
+            assert!(pol.verify_heartbeat_signature(hb).unwrap());
+        }
+        let hit = start.elapsed();
+
+        println!("200 signature checks: {:?} verifying, {:?} cached", miss, hit);
+    }
+
+    #[test]
+    fn test_metrics_count_heartbeats_and_blocks() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kp = Keypair::generate();
+        pol.receive_heartbeat(create_test_heartbeat(&kp)).unwrap();
+        
+        let hot_kp = Keypair::generate();
+        let mut hot = create_test_heartbeat(&hot_kp);
+        hot.temperature = 50.0;
+        hot.signature = hot_kp.sign(&hot.signable_bytes());
+        assert!(pol.receive_heartbeat(hot).is_err());
+        
+        let mut forged = create_test_heartbeat(&Keypair::generate());
+        forged.heart_rate += 1;
+        assert!(pol.receive_heartbeat(forged).is_err());
+        
+        pol.try_create_block().unwrap().unwrap();
+        
+        let m = pol.metrics();
+        assert_eq!(m.heartbeats_accepted, 1);
+        assert_eq!(m.heartbeats_rejected.get("invalid_temperature"), Some(&1));
+        assert_eq!(m.heartbeats_rejected.get("invalid_heartbeat_signature"), Some(&1));
+        assert_eq!(m.blocks_created, 1);
+    }
+    
+    #[test]
+    fn test_cumulative_chain_weight() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        
+        assert_eq!(pol.cumulative_chain_weight(), 0.0);
+        
+        let kp = Keypair::generate();
+        
+        // Create first block
+        let hb1 = create_test_heartbeat(&kp);
+        pol.receive_heartbeat(hb1).unwrap();
+        pol.try_create_block().unwrap();
+        let weight_after_1 = pol.cumulative_chain_weight();
+        assert!(weight_after_1 > 0.0, "Cumulative weight should be > 0 after first block");
+        
+        // Create second block (need fresh heartbeat with different timestamp)
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let hb2 = create_test_heartbeat(&kp);
+        pol.receive_heartbeat(hb2).unwrap();
+        pol.try_create_block().unwrap();
+        let weight_after_2 = pol.cumulative_chain_weight();
+        
+        // Cumulative should grow
+        assert!(weight_after_2 > weight_after_1, 
+            "Cumulative weight should grow: {} > {}", weight_after_2, weight_after_1);
+    }
+
+    #[test]
+    fn test_halving_schedule() {
+        let config = ConsensusConfig::default();
+        
+        // Block 0: full reward
+        let r0 = config.reward_at_height(0);
+        assert_eq!(r0, 100 * MICRO_PER_PULSE);
+        
+        // Block at first halving: half reward
+        let r1 = config.reward_at_height(config.halving_interval);
+        assert_eq!(r1, 50 * MICRO_PER_PULSE, "First halving should give 50, got {}", to_pulse(r1));
+        
+        // Block at second halving: quarter reward
+        let r2 = config.reward_at_height(config.halving_interval * 2);
+        assert_eq!(r2, 25 * MICRO_PER_PULSE, "Second halving should give 25, got {}", to_pulse(r2));
+        
+        // Block at third halving
+        let r3 = config.reward_at_height(config.halving_interval * 3);
+        assert_eq!(r3, 12_500_000, "Third halving should give 12.5, got {}", to_pulse(r3));
+        
+        // Very far in the future: should hit minimum
+        let r_far = config.reward_at_height(config.halving_interval * 100);
+        assert_eq!(r_far, to_micro(config.min_reward_per_block));
+    }
+    
+    #[test]
+    fn test_inflation_decreases_over_time() {
+        let config = ConsensusConfig::default();
+        
+        // Inflation at height 0 vs height 210_000 — should decrease
+        let r_early = config.reward_at_height(1000);
+        let r_later = config.reward_at_height(config.halving_interval + 1000);
+        
+        assert!(r_early > r_later, 
+            "Later reward ({}) should be less than early ({})", r_later, r_early);
+    }
+
+    #[test]
+    fn test_participant_block_index() {
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        let kp_a = Keypair::generate();
+        let kp_b = Keypair::generate();
+        
+        // A pulses in every block, B only in blocks 2 and 4
+        for height in 1..=5u64 {
+            pol.receive_heartbeat(create_test_heartbeat(&kp_a)).unwrap();
+            if height == 2 || height == 4 {
+                pol.receive_heartbeat(create_test_heartbeat(&kp_b)).unwrap();
+            }
+            pol.try_create_block().unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        
+        let (blocks, total) = pol.get_participant_blocks(&kp_b.public_key_hex(), 0, 50);
+        assert_eq!(total, 2);
+        let indices: Vec<u64> = blocks.iter().map(|b| b.index).collect();
+        assert_eq!(indices, vec![2, 4]);
+        
+        let (page, total) = pol.get_participant_blocks(&kp_a.public_key_hex(), 1, 2);
+        assert_eq!(total, 5);
+        assert_eq!(page.iter().map(|b| b.index).collect::<Vec<_>>(), vec![2, 3]);
+        
+        let (none, total) = pol.get_participant_blocks("unknown", 0, 50);
+        assert!(none.is_empty());
+        assert_eq!(total, 0);
+    }
+
+    #[test]
+    fn test_storage_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(Storage::open(dir.path()).unwrap());
+        
+        let config = ConsensusConfig::default();
+        let mut pol = ProofOfLife::with_storage(config.clone(), storage.clone()).unwrap();
+        
+        // Create a block
+        let kp = Keypair::generate();
+        let hb = create_test_heartbeat(&kp);
+        pol.receive_heartbeat(hb).unwrap();
+        pol.try_create_block().unwrap();
+        
+        assert_eq!(pol.chain_height(), 1);
+        
+        // Reconstruct from storage — chain should be restored
+        let pol2 = ProofOfLife::with_storage(config, storage).unwrap();
+        assert_eq!(pol2.chain_height(), 1);
+    }
+    
+    #[test]
+    fn test_mem_store_backend() {
+        let store = Arc::new(MemStore::new());
+        let config = ConsensusConfig::default();
+        let mut pol = ProofOfLife::with_storage(config.clone(), store.clone()).unwrap();
+        
+        let sender = Keypair::generate();
+        let recipient = Keypair::generate();
+        pol.receive_heartbeat(create_test_heartbeat(&sender)).unwrap();
+        pol.try_create_block().unwrap().unwrap();
+        
+        let mut hb = create_test_heartbeat(&sender);
+        hb.timestamp += 1;
+        hb.signature = sender.sign(&hb.signable_bytes());
+        let mut tx = Transaction {
+            tx_id: "mem-tx".into(),
+            sender_pubkey: sender.public_key_hex(),
+            recipient_pubkey: recipient.public_key_hex(),
+            amount: 10.0,
+            fee: 0.0,
+            timestamp: current_time_ms(),
+            signature: String::new(),
+            heartbeat_signature: hb.signature.clone(),
+            not_before_height: 0,
+            nonce: 1,
+            signature_version: 0,
+        };
+        tx.signature = sender.sign(&tx.signable_bytes());
+        pol.receive_heartbeat(hb).unwrap();
+        pol.receive_transaction(tx).unwrap();
+        pol.try_create_block().unwrap().unwrap();
+        
+        // Chain, accounts and transaction lookups all come back from the store
+        let restored = ProofOfLife::with_storage(config, store).unwrap();
+        assert_eq!(restored.chain_height(), 2);
+        assert_eq!(restored.get_balance(&recipient.public_key_hex()), 10 * MICRO_PER_PULSE);
+        assert_eq!(restored.find_transaction("mem-tx").unwrap().unwrap().0, 2);
+        let (history, total) = restored.account_transactions(&recipient.public_key_hex(), 0, 10).unwrap();
+        assert_eq!((history.len(), total), (1, 1));
+    }
+    
+    #[test]
+    fn test_pruned_chain_keeps_weight_and_linkage() {
+        let store = Arc::new(MemStore::new());
+        let config = ConsensusConfig::default();
+        let mut pol = ProofOfLife::with_storage(config.clone(), store.clone()).unwrap();
+        
+        let kp = Keypair::generate();
+        for i in 0..4 {
+            let mut hb = create_test_heartbeat(&kp);
+            hb.timestamp += i;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+            pol.try_create_block().unwrap().unwrap();
+        }
+        let weight = pol.cumulative_chain_weight();
+        let tip_hash = pol.latest_block().unwrap().block_hash.clone();
+        
+        assert_eq!(pol.prune(3).unwrap(), 2);
+        assert!(pol.get_block_by_index(1).is_none());
+        assert_eq!(pol.verify_chain(), None);
+        assert!(pol.export_snapshot(Vec::new()).is_err());
+        assert_eq!(pol.get_stats().chain_length, 5);
+        
+        // Light clients still get every header, in order and linked
+        let headers = pol.headers_page(0, 10);
+        assert_eq!(pol.header_count(), 5);
+        assert_eq!(headers.iter().map(|h| h.index).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert!(headers.windows(2).all(|w| w[1].previous_hash == w[0].block_hash));
+        assert_eq!(pol.headers_page(3, 1)[0].block_hash, pol.get_block_by_index(3).unwrap().block_hash);
+        
+        // A restart rebuilds cumulative weight from the retained headers
+        let restored = ProofOfLife::with_storage(config, store).unwrap();
+        assert_eq!(restored.chain_height(), 4);
+        assert!((restored.cumulative_chain_weight() - weight).abs() < 1e-9);
+        assert_eq!(restored.latest_block().unwrap().block_hash, tip_hash);
+        assert_eq!(restored.verify_chain(), None);
+    }
+    
+    #[test]
+    fn test_rebuild_accounts_matches_incremental_state() {
+        let store = Arc::new(MemStore::new());
+        let config = ConsensusConfig::default();
+        let mut pol = ProofOfLife::with_storage(config.clone(), store.clone()).unwrap();
+        let (sender, other, recipient) = (Keypair::generate(), Keypair::generate(), Keypair::generate());
+        for hr in 70..74 {
+            for kp in [&sender, &other] {
+                let mut hb = create_test_heartbeat(kp);
+                hb.heart_rate = hr;
+                hb.signature = kp.sign(&hb.signable_bytes());
+                pol.receive_heartbeat(hb).unwrap();
+            }
+            if hr == 72 {
+                let mut tx = Transaction {
+                    tx_id: "rebuild".to_string(),
+                    sender_pubkey: sender.public_key_hex(),
+                    recipient_pubkey: recipient.public_key_hex(),
+                    amount: 12.5,
+                    fee: 0.0,
+                    timestamp: current_time_ms(),
+                    heartbeat_signature: pooled_signature(&pol, &sender),
+                    not_before_height: 0,
+                    nonce: 1,
+                    signature_version: 0,
+                    signature: String::new(),
+                };
+                tx.signature = sender.sign(&tx.signable_bytes());
+                pol.receive_transaction(tx).unwrap();
+            }
+            pol.try_create_block().unwrap().unwrap();
+        }
+        let incremental = pol.accounts.clone();
+        let minted = pol.total_minted;
+        
+        assert_eq!(pol.rebuild_accounts_from_chain().unwrap(), 0);
+        assert_eq!(pol.accounts, incremental);
+        assert_eq!(pol.total_minted, minted);
+        
+        // A desynced stored balance is overwritten by the startup rebuild
+        let mut drifted = incremental[&recipient.public_key_hex()].clone();
+        drifted.balance += 1;
+        store.save_account(&drifted).unwrap();
+        store.save_account(&Account { pubkey: "ghost".to_string(), balance: 5, ..Default::default() }).unwrap();
+        let restarted = ProofOfLife::with_storage(
+            ConsensusConfig { rebuild_accounts_on_startup: true, ..config },
+            store.clone(),
+        ).unwrap();
+        assert_eq!(restarted.accounts, incremental);
+        assert_eq!(store.load_all_accounts().unwrap().len(), incremental.len());
+        
+        // Pruned chains can't be replayed
+        pol.prune(3).unwrap();
+        assert!(pol.rebuild_accounts_from_chain().is_err());
+    }
+    
+    #[test]
+    fn test_biometric_baselines_survive_restart() {
+        let store = Arc::new(MemStore::new());
+        let config = ConsensusConfig { persist_biometric_baselines: true, ..Default::default() };
+        let mut pol = ProofOfLife::with_storage(config.clone(), store.clone()).unwrap();
+        
+        let kp = Keypair::generate();
+        let hrs = [72, 74, 71, 75, 73, 70, 76, 72, 74, 71];
+        for (i, hr) in hrs.iter().cycle().take(30).enumerate() {
+            let mut hb = create_test_heartbeat(&kp);
+            hb.timestamp += i as u64;
+            hb.heart_rate = *hr + (i % 3) as u16;
+            hb.motion.x += i as f64 * 0.01;
+            hb.signature = kp.sign(&hb.signable_bytes());
+            pol.receive_heartbeat(hb).unwrap();
+        }
+        let baseline = pol.biometric_baseline(&kp.public_key_hex()).expect("calibrated");
+        
+        let restored = ProofOfLife::with_storage(config, store.clone()).unwrap();
+        assert_eq!(restored.biometric_baseline(&kp.public_key_hex()), Some(baseline));
+        
+        // Not restored unless persistence is enabled
+        let fresh = ProofOfLife::with_storage(ConsensusConfig::default(), store).unwrap();
+        assert!(fresh.biometric_baseline(&kp.public_key_hex()).is_none());
+    }
+    
+    #[test]
+    fn test_low_confidence_heartbeat_earns_less() {
+        let mut pol = ProofOfLife::new(ConsensusConfig { n_threshold: 2, ..Default::default() });
+        let (trusted, suspect) = (Keypair::generate(), Keypair::generate());
+        pol.receive_heartbeat(create_test_heartbeat(&trusted)).unwrap();
+        
+        // Same HR and motion, but an implausible (still accepted) temperature
+        let mut hb = create_test_heartbeat(&suspect);
+        hb.temperature = 43.5;
+        hb.signature = suspect.sign(&hb.signable_bytes());
+        let accepted = pol.receive_heartbeat(hb).unwrap();
+        assert!(accepted.confidence < 0.7);
+        assert_eq!(pol.biometric_confidence(&suspect.public_key_hex()), Some(accepted.confidence));
+        
+        let block = pol.try_create_block().unwrap().unwrap();
+        let trusted_balance = pol.get_balance(&trusted.public_key_hex());
+        let suspect_balance = pol.get_balance(&suspect.public_key_hex());
+        assert!(suspect_balance > 0);
+        assert!(suspect_balance < trusted_balance / 2, "{} vs {}", suspect_balance, trusted_balance);
+        assert_eq!(block.confidence_factors[&suspect.public_key_hex()], accepted.confidence);
+        
+        // Peers recompute the same scaled weights
+        let mut peer = ProofOfLife::new(ConsensusConfig::default());
+        peer.receive_block(block).unwrap();
+        assert_eq!(peer.get_balance(&suspect.public_key_hex()), suspect_balance);
+    }
+    
+    #[test]
+    fn test_configured_weight_coefficients() {
+        // Heart rate only: rewards split by normalized HR, continuity ignored
+        let config = ConsensusConfig {
+            n_threshold: 2,
+            weight_alpha: 1.0,
+            weight_beta: 0.0,
+            weight_gamma: 0.0,
+            ..Default::default()
+        };
+        let mut pol = ProofOfLife::new(config.clone());
+        let (resting, active) = (Keypair::generate(), Keypair::generate());
+        pol.receive_heartbeat(create_test_heartbeat(&resting)).unwrap();
+        let mut hb = create_test_heartbeat(&active);
+        hb.heart_rate = 120;
+        hb.signature = active.sign(&hb.signable_bytes());
+        pol.receive_heartbeat(hb.clone()).unwrap();
+        
+        let block = pol.try_create_block().unwrap().unwrap();
+        let confidence = |kp: &Keypair| block.confidence_factors.get(&kp.public_key_hex()).copied().unwrap_or(1.0);
+        let expected_ratio = hb.weight_with_params(0.0, &config.weight_params()) * confidence(&active)
+            / (create_test_heartbeat(&resting).weight_with_params(0.0, &config.weight_params()) * confidence(&resting));
+        assert!(expected_ratio > 1.0);
+        // Shares are whole micro-PULSE, so the ratio is only exact to rounding
+        let ratio = pol.get_balance(&active.public_key_hex()) as f64 / pol.get_balance(&resting.public_key_hex()) as f64;
+        assert!((ratio - expected_ratio).abs() < 1e-6, "{} vs {}", ratio, expected_ratio);
+        
+        // Peers must agree on the coefficients to verify the block's weights
+        let mut mismatched = ProofOfLife::new(ConsensusConfig::default());
+        assert!(matches!(mismatched.receive_block(block.clone()), Err(ConsensusError::InvalidBlockWeights)));
+        let mut peer = ProofOfLife::new(config);
+        peer.receive_block(block).unwrap();
+    }
+    
+    #[test]
+    fn test_config_validation_rejects_bad_weights() {
+        assert!(ConsensusConfig::default().validate().is_ok());
+        
+        let unbalanced = ConsensusConfig { weight_alpha: 0.5, ..Default::default() };
+        assert!(matches!(unbalanced.validate(), Err(ConsensusError::InvalidConfig(_))));
+        let negative = ConsensusConfig { weight_alpha: 0.8, weight_beta: -0.1, ..Default::default() };
+        assert!(matches!(negative.validate(), Err(ConsensusError::InvalidConfig(_))));
+        let no_window = ConsensusConfig { max_continuity_ms: 0, ..Default::default() };
+        assert!(no_window.validate().is_err());
+        let tiny_blocks = ConsensusConfig { n_threshold: 3, max_heartbeats_per_block: 2, ..Default::default() };
+        assert!(tiny_blocks.validate().is_err());
+        
+        let store = Arc::new(MemStore::new());
+        assert!(matches!(
+            ProofOfLife::with_storage(unbalanced, store),
+            Err(ConsensusError::InvalidConfig(_))
+        ));
+    }
+    
+    #[test]
+    fn test_bio_entropy_is_deterministic_and_verified() {
+        let config = ConsensusConfig { n_threshold: 2, ..Default::default() };
+        let (kp1, kp2) = (Keypair::generate(), Keypair::generate());
+        let (hb1, hb2) = (create_test_heartbeat(&kp1), create_test_heartbeat(&kp2));
+        
+        // Two nodes with the same heartbeats, received in a different order
+        let mut a = ProofOfLife::new(config.clone());
+        a.receive_heartbeat(hb1.clone()).unwrap();
+        a.receive_heartbeat(hb2.clone()).unwrap();
+        let mut b = ProofOfLife::new(config);
+        b.receive_heartbeat(hb2).unwrap();
+        b.receive_heartbeat(hb1).unwrap();
+        let block_a = a.try_create_block().unwrap().unwrap();
+        let block_b = b.try_create_block().unwrap().unwrap();
+        assert_eq!(block_a.bio_entropy, block_b.bio_entropy);
+        assert_eq!(block_a.bio_entropy, hex::encode(biometrics::block_entropy(&block_a.heartbeats)));
+        
+        // A rehashed block with made-up entropy is rejected
+        let mut tampered = block_a.clone();
+        tampered.bio_entropy = "ab".repeat(32);
+        tampered.block_hash = tampered.compute_hash();
+        let mut receiver = ProofOfLife::new(ConsensusConfig::default());
+        assert!(matches!(receiver.receive_block(tampered), Err(ConsensusError::InvalidBioEntropy)));
+        receiver.receive_block(block_a).unwrap();
+    }
+    
+    #[test]
+    fn test_tampered_stored_chain_detected_on_reload() {
+        let storage = Arc::new(MemStore::new());
+        let kp = Keypair::generate();
+        {
+            let mut pol = ProofOfLife::with_storage(ConsensusConfig::default(), storage.clone()).unwrap();
+            for _ in 0..3 {
+                pol.receive_heartbeat(create_test_heartbeat(&kp)).unwrap();
+                pol.try_create_block().unwrap().unwrap();
+            }
+            assert!(ProofOfLife::validate_chain(&pol.get_blocks_from(0), "").is_ok());
+        }
+        
+        // A block whose stored hash no longer matches its contents
+        let mut bad = storage.load_block(2).unwrap();
+        bad.block_hash = "ab".repeat(32);
+        storage.save_block(&bad).unwrap();
+        assert!(matches!(
+            ProofOfLife::with_storage(ConsensusConfig::default(), storage.clone()),
+            Err(ConsensusError::CorruptChain(2))
+        ));
+        
+        // A self-consistent but foreign genesis is rejected too
+        let mut chain = produce_chain(&kp, 1).get_blocks_from(0);
+        chain[0].bio_entropy = "ff".repeat(32);
+        chain[0].block_hash = chain[0].compute_hash();
+        chain[1].previous_hash = chain[0].block_hash.clone();
+        chain[1].block_hash = chain[1].compute_hash();
+        assert!(matches!(ProofOfLife::validate_chain(&chain, ""), Err(ConsensusError::CorruptChain(0))));
+    }
+    
+    #[test]
+    fn test_sync_windows_extend_chain() {
+        let kp = Keypair::generate();
+        let producer = produce_chain(&kp, 5);
+        assert_eq!(producer.get_blocks_window(2, 2).iter().map(|b| b.index).collect::<Vec<_>>(), vec![2, 3]);
+        
+        // Windows are appended in order; overlap with what we already have is skipped
+        let mut follower = ProofOfLife::new(ConsensusConfig::default());
+        assert_eq!(follower.apply_sync_window(producer.get_blocks_window(1, 3)).unwrap(), 3);
+        assert_eq!(follower.apply_sync_window(producer.get_blocks_window(2, 10)).unwrap(), 2);
+        assert_eq!(follower.chain_height(), 5);
+        assert_eq!(follower.latest_block().unwrap().block_hash, producer.latest_block().unwrap().block_hash);
+        
+        // A window from a different chain is refused
+        let other = produce_chain(&Keypair::generate(), 6);
+        assert!(matches!(
+            follower.apply_sync_window(other.get_blocks_window(4, 10)),
+            Err(ConsensusError::InvalidPreviousHash)
+        ));
+        assert_eq!(follower.chain_height(), 5);
+    }
+
+    #[test]
+    fn test_sync_state_follows_peer_tip() {
+        let kp = Keypair::generate();
+        let producer = produce_chain(&kp, 5);
+        let mut follower = ProofOfLife::new(ConsensusConfig::default());
+        assert_eq!(follower.sync_state(), SyncState::Synced);
+
+        // A peer at #5 puts us behind; lower reports don't lower the target
+        follower.note_peer_height(5);
+        follower.note_peer_height(3);
+        assert_eq!(follower.sync_state(), SyncState::Syncing { current: 0, target: 5 });
+        assert_eq!(
+            serde_json::to_value(follower.sync_state()).unwrap(),
+            serde_json::json!({"state": "syncing", "current": 0, "target": 5})
+        );
+
+        follower.apply_sync_window(producer.get_blocks_window(1, 3)).unwrap();
+        assert_eq!(follower.sync_state(), SyncState::Syncing { current: 3, target: 5 });
+
+        // Synced once our tip matches the peer's
+        follower.apply_sync_window(producer.get_blocks_window(4, 10)).unwrap();
+        assert_eq!(follower.sync_state(), SyncState::Synced);
+        follower.note_peer_height(5);
+        assert_eq!(follower.sync_state(), SyncState::Synced);
+
+        // An abandoned sync releases the node too
+        follower.note_peer_height(9);
+        assert!(matches!(follower.sync_state(), SyncState::Syncing { .. }));
+        follower.end_sync();
+        assert_eq!(follower.sync_state(), SyncState::Synced);
     }
 }
@@ -0,0 +1,98 @@
+//! Cache of heartbeat signatures already verified as valid.
+//!
+//! A heartbeat often reaches consensus more than once (gossiped by several
+//! peers, or gossiped and then submitted locally), and a secp256k1 verify is
+//! by far the most expensive check it goes through. Entries are keyed by a
+//! hash of the signed bytes (which include the device pubkey) and the
+//! signature, so a hit vouches for exactly that data; only successful
+//! verifications are ever cached.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::crypto::hash_sha256;
+
+/// Bounded LRU set of known-good (signed bytes, signature) pairs
+#[derive(Debug, Default)]
+pub struct SignatureCache {
+    capacity: usize,
+    /// Key → generation of its last use
+    entries: HashMap<String, u64>,
+    /// Generation → key, oldest first
+    order: BTreeMap<u64, String>,
+    generation: u64,
+}
+
+impl SignatureCache {
+    /// Cache holding up to `capacity` signatures; 0 disables caching
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, ..Default::default() }
+    }
+
+    /// Cache key for a signature over `signable` bytes
+    pub fn key(signable: &[u8], signature: &str) -> String {
+        let mut data = Vec::with_capacity(signable.len() + signature.len());
+        data.extend_from_slice(signable);
+        data.extend_from_slice(signature.as_bytes());
+        hash_sha256(&data)
+    }
+
+    /// Whether `key` was verified before; a hit makes it most recently used
+    pub fn contains(&mut self, key: &str) -> bool {
+        let Some(generation) = self.entries.get_mut(key) else {
+            return false;
+        };
+        self.order.remove(generation);
+        self.generation += 1;
+        *generation = self.generation;
+        self.order.insert(self.generation, key.to_string());
+        true
+    }
+
+    /// Remember a successfully verified signature, evicting the least recently used beyond capacity
+    pub fn insert(&mut self, key: String) {
+        if self.capacity == 0 || self.contains(&key) {
+            return;
+        }
+        self.generation += 1;
+        self.entries.insert(key.clone(), self.generation);
+        self.order.insert(self.generation, key);
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else { break };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = SignatureCache::new(2);
+        let (a, b, c) = (SignatureCache::key(b"a", "01"), SignatureCache::key(b"b", "01"), SignatureCache::key(b"c", "01"));
+        cache.insert(a.clone());
+        cache.insert(b.clone());
+        // Using `a` makes `b` the oldest
+        assert!(cache.contains(&a));
+        cache.insert(c.clone());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(&a) && cache.contains(&c));
+        assert!(!cache.contains(&b));
+
+        // Same signature over different data is a different entry
+        assert_ne!(SignatureCache::key(b"a", "01"), SignatureCache::key(b"a", "02"));
+
+        let mut disabled = SignatureCache::new(0);
+        disabled.insert(a.clone());
+        assert!(disabled.is_empty() && !disabled.contains(&a));
+    }
+}
@@ -4,11 +4,12 @@
 use k256::{
     ecdsa::{
         signature::{Signer, Verifier},
-        Signature, SigningKey, VerifyingKey,
+        RecoveryId, Signature, SigningKey, VerifyingKey,
     },
     SecretKey,
 };
 use rand::rngs::OsRng;
+use rand::RngCore;
 use sha2::{Sha256, Digest};
 use thiserror::Error;
 
@@ -22,6 +23,8 @@ pub enum CryptoError {
     InvalidSignature,
     #[error("Signature verification failed")]
     VerificationFailed,
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
     #[error("Hex decode error: {0}")]
     HexError(#[from] hex::FromHexError),
 }
@@ -51,6 +54,46 @@ impl Keypair {
         Ok(Self { signing_key, verifying_key })
     }
     
+    /// Generate a new keypair along with its 24-word BIP39 recovery phrase
+    /// (no passphrase; use `from_mnemonic` to add one on recovery)
+    pub fn generate_mnemonic() -> (Self, String) {
+        let mut entropy = [0u8; 32];
+        OsRng.fill_bytes(&mut entropy);
+        let mnemonic = bip39::Mnemonic::from_entropy(&entropy)
+            .expect("32 bytes is valid BIP39 entropy");
+        let phrase = mnemonic.to_string();
+        let keypair = Self::from_mnemonic(&phrase, "")
+            .expect("freshly generated mnemonic is valid");
+        (keypair, phrase)
+    }
+    
+    /// Recover a keypair from a 24-word BIP39 mnemonic and optional passphrase.
+    /// The secret is the BIP32 master key (path `m`) of the BIP39 seed, so the
+    /// same phrase and passphrase always yield the same key.
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Self, CryptoError> {
+        use hmac::{Hmac, Mac};
+        
+        let mnemonic = bip39::Mnemonic::parse_normalized(phrase.trim())
+            .map_err(|e| CryptoError::InvalidMnemonic(e.to_string()))?;
+        if mnemonic.word_count() != 24 {
+            return Err(CryptoError::InvalidMnemonic(
+                format!("expected 24 words, got {}", mnemonic.word_count())));
+        }
+        let seed = mnemonic.to_seed_normalized(passphrase);
+        
+        // BIP32 master key: I = HMAC-SHA512("Bitcoin seed", seed), secret = I[..32]
+        let mut mac = Hmac::<sha2::Sha512>::new_from_slice(b"Bitcoin seed")
+            .expect("HMAC accepts any key length");
+        mac.update(&seed);
+        let derived = mac.finalize().into_bytes();
+        
+        let secret_key = SecretKey::from_slice(&derived[..32])
+            .map_err(|_| CryptoError::InvalidPrivateKey)?;
+        let signing_key = SigningKey::from(secret_key);
+        let verifying_key = *signing_key.verifying_key();
+        Ok(Self { signing_key, verifying_key })
+    }
+    
     /// Get private key as hex string
     pub fn private_key_hex(&self) -> String {
         hex::encode(self.signing_key.to_bytes())
@@ -66,6 +109,59 @@ impl Keypair {
         let signature: Signature = self.signing_key.sign(data);
         hex::encode(signature.to_bytes())
     }
+    
+    /// Sign data with a 65-byte recoverable signature (r ‖ s ‖ recovery id),
+    /// hex-encoded. The public key can be derived from it with [`recover_pubkey`].
+    pub fn sign_recoverable(&self, data: &[u8]) -> String {
+        let (signature, recovery_id) = self.signing_key.sign_recoverable(data)
+            .expect("signing with a valid key succeeds");
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(recovery_id.to_byte());
+        hex::encode(bytes)
+    }
+}
+
+/// Length of a recoverable signature: compact (r, s) plus the recovery id
+const RECOVERABLE_SIGNATURE_LEN: usize = 65;
+
+/// Parse a compact 64-byte signature, or the (r, s) part of a recoverable one,
+/// so recoverable signatures also verify against a known pubkey
+fn parse_signature(bytes: &[u8]) -> Result<Signature, CryptoError> {
+    let compact = match bytes.len() {
+        RECOVERABLE_SIGNATURE_LEN => &bytes[..RECOVERABLE_SIGNATURE_LEN - 1],
+        _ => bytes,
+    };
+    Signature::from_slice(compact).map_err(|_| CryptoError::InvalidSignature)
+}
+
+/// Derive the hex public key that made a recoverable signature over `data`
+pub fn recover_pubkey(data: &[u8], recoverable_signature_hex: &str) -> Result<String, CryptoError> {
+    let bytes = hex::decode(recoverable_signature_hex)?;
+    if bytes.len() != RECOVERABLE_SIGNATURE_LEN {
+        return Err(CryptoError::InvalidSignature);
+    }
+    let signature = parse_signature(&bytes)?;
+    let recovery_id = RecoveryId::from_byte(bytes[RECOVERABLE_SIGNATURE_LEN - 1])
+        .ok_or(CryptoError::InvalidSignature)?;
+    let key = VerifyingKey::recover_from_msg(data, &signature, recovery_id)
+        .map_err(|_| CryptoError::VerificationFailed)?;
+    Ok(hex::encode(key.to_sec1_bytes()))
+}
+
+/// Whether two hex public keys are the same point, whatever their SEC1 encoding
+pub fn same_pubkey(a: &str, b: &str) -> Result<bool, CryptoError> {
+    Ok(parse_pubkey(a)?.to_sec1_bytes() == parse_pubkey(b)?.to_sec1_bytes())
+}
+
+/// Check that a hex string is a SEC1-encoded point on secp256k1
+pub fn is_valid_public_key(public_key_hex: &str) -> bool {
+    parse_pubkey(public_key_hex).is_ok()
+}
+
+/// Decode a hex SEC1 public key, checking it's a point on secp256k1
+pub fn parse_pubkey(public_key_hex: &str) -> Result<VerifyingKey, CryptoError> {
+    let bytes = hex::decode(public_key_hex)?;
+    VerifyingKey::from_sec1_bytes(&bytes).map_err(|_| CryptoError::InvalidPublicKey)
 }
 
 /// Verify a signature against a public key
@@ -74,17 +170,46 @@ pub fn verify_signature(
     data: &[u8],
     signature_hex: &str,
 ) -> Result<bool, CryptoError> {
-    let pubkey_bytes = hex::decode(public_key_hex)?;
-    let verifying_key = VerifyingKey::from_sec1_bytes(&pubkey_bytes)
-        .map_err(|_| CryptoError::InvalidPublicKey)?;
+    let verifying_key = parse_pubkey(public_key_hex)?;
     
     let sig_bytes = hex::decode(signature_hex)?;
-    let signature = Signature::from_slice(&sig_bytes)
-        .map_err(|_| CryptoError::InvalidSignature)?;
+    let signature = parse_signature(&sig_bytes)?;
     
     Ok(verifying_key.verify(data, &signature).is_ok())
 }
 
+/// Batches at least this large are verified in parallel
+const PARALLEL_VERIFY_THRESHOLD: usize = 64;
+
+/// Verify many (pubkey hex, message, signature hex) triples in one pass.
+/// Each distinct pubkey is parsed once; large batches are spread across cores.
+/// Returns one result per item, in order — malformed keys or signatures are `false`.
+pub fn verify_batch(items: &[(String, Vec<u8>, String)]) -> Vec<bool> {
+    use rayon::prelude::*;
+    use std::collections::HashMap;
+    
+    let mut keys: HashMap<&str, Option<VerifyingKey>> = HashMap::new();
+    for (pubkey, _, _) in items {
+        keys.entry(pubkey.as_str()).or_insert_with(|| parse_pubkey(pubkey).ok());
+    }
+    
+    let verify_one = |(pubkey, message, signature): &(String, Vec<u8>, String)| {
+        let Some(key) = keys.get(pubkey.as_str()).and_then(Option::as_ref) else {
+            return false;
+        };
+        hex::decode(signature).ok()
+            .and_then(|bytes| parse_signature(&bytes).ok())
+            .map(|sig| key.verify(message, &sig).is_ok())
+            .unwrap_or(false)
+    };
+    
+    if items.len() >= PARALLEL_VERIFY_THRESHOLD {
+        items.par_iter().map(verify_one).collect()
+    } else {
+        items.iter().map(verify_one).collect()
+    }
+}
+
 /// Hash data with SHA-256 and return hex string
 pub fn hash_sha256(data: &[u8]) -> String {
     let hash = Sha256::digest(data);
@@ -123,4 +248,92 @@ mod tests {
         let valid = verify_signature(&kp2.public_key_hex(), data, &signature).unwrap();
         assert!(!valid);
     }
+    
+    #[test]
+    fn test_recoverable_signature() {
+        let kp = Keypair::generate();
+        let data = b"recover me";
+        let sig = kp.sign_recoverable(data);
+        assert_eq!(hex::decode(&sig).unwrap().len(), 65);
+        assert_eq!(recover_pubkey(data, &sig).unwrap(), kp.public_key_hex());
+        
+        // Still verifies against a known pubkey
+        assert!(verify_signature(&kp.public_key_hex(), data, &sig).unwrap());
+        
+        // Other data recovers some other key, or none
+        assert_ne!(recover_pubkey(b"other", &sig).ok(), Some(kp.public_key_hex()));
+        // Compact signatures carry no recovery id
+        assert!(matches!(recover_pubkey(data, &kp.sign(data)), Err(CryptoError::InvalidSignature)));
+    }
+    
+    #[test]
+    fn test_mnemonic_roundtrip() {
+        let (kp, phrase) = Keypair::generate_mnemonic();
+        assert_eq!(phrase.split_whitespace().count(), 24);
+        
+        let recovered = Keypair::from_mnemonic(&phrase, "").unwrap();
+        assert_eq!(recovered.public_key_hex(), kp.public_key_hex());
+        assert_eq!(recovered.private_key_hex(), kp.private_key_hex());
+        
+        // A passphrase yields a different (but equally deterministic) key
+        let with_pass = Keypair::from_mnemonic(&phrase, "hunter2").unwrap();
+        assert_ne!(with_pass.public_key_hex(), kp.public_key_hex());
+        assert_eq!(with_pass.public_key_hex(),
+            Keypair::from_mnemonic(&phrase, "hunter2").unwrap().public_key_hex());
+    }
+    
+    #[test]
+    fn test_mnemonic_is_deterministic_and_validated() {
+        let phrase = ["abandon"; 23].join(" ") + " art";
+        let a = Keypair::from_mnemonic(&phrase, "").unwrap();
+        let b = Keypair::from_mnemonic(&format!("  {}  ", phrase), "").unwrap();
+        assert_eq!(a.public_key_hex(), b.public_key_hex());
+        
+        // Bad checksum, unknown word, wrong length
+        let bad_checksum = ["abandon"; 24].join(" ");
+        assert!(matches!(Keypair::from_mnemonic(&bad_checksum, ""), Err(CryptoError::InvalidMnemonic(_))));
+        assert!(Keypair::from_mnemonic(&phrase.replace("art", "artt"), "").is_err());
+        let twelve = ["abandon"; 11].join(" ") + " about";
+        assert!(matches!(Keypair::from_mnemonic(&twelve, ""), Err(CryptoError::InvalidMnemonic(_))));
+    }
+    
+    #[test]
+    fn test_verify_batch_reports_per_item() {
+        let kps: Vec<Keypair> = (0..4).map(|_| Keypair::generate()).collect();
+        // Repeated pubkeys and enough items to take the parallel path
+        let mut items: Vec<(String, Vec<u8>, String)> = (0..100).map(|i| {
+            let kp = &kps[i % kps.len()];
+            let msg = format!("heartbeat {}", i).into_bytes();
+            let sig = kp.sign(&msg);
+            (kp.public_key_hex(), msg, sig)
+        }).collect();
+        items[17].1 = b"tampered".to_vec();
+        items[42].0 = "zz".to_string();
+        items[63].2 = "00".repeat(64);
+        
+        let results = verify_batch(&items);
+        assert_eq!(results.len(), 100);
+        for (i, ok) in results.iter().enumerate() {
+            assert_eq!(*ok, ![17, 42, 63].contains(&i), "item {}", i);
+        }
+        
+        // Small batches take the sequential path with identical results
+        assert_eq!(verify_batch(&items[10..20]), results[10..20].to_vec());
+        assert!(verify_batch(&[]).is_empty());
+    }
+    
+    #[test]
+    fn test_public_key_validity() {
+        let kp = Keypair::generate();
+        assert!(is_valid_public_key(&kp.public_key_hex()));
+        assert!(!is_valid_public_key("not hex"));
+        assert!(!is_valid_public_key("02abcd"));
+        // x = 5 has no y on secp256k1 (5³ + 7 is a non-residue)
+        assert!(!is_valid_public_key(&format!("02{:0>64}", "5")));
+        
+        // parse_pubkey says why
+        assert!(parse_pubkey(&kp.public_key_hex()).is_ok());
+        assert!(matches!(parse_pubkey("not hex"), Err(CryptoError::HexError(_))));
+        assert!(matches!(parse_pubkey(&format!("02{:0>64}", "5")), Err(CryptoError::InvalidPublicKey)));
+    }
 }
@@ -8,8 +8,11 @@
 //! - `crypto` - Cryptographic primitives (ECDSA signing/verification)
 //! - `consensus` - Proof-of-Life consensus engine
 //! - `api` - HTTP API for device communication
-//! - `storage` - Persistent chain storage
+//! - `storage` - Chain storage trait with sled and in-memory backends
 //! - `network` - P2P networking (channel-based architecture)
+//! - `simulation` - Deterministic heartbeat simulator for testing
+//! - `metrics` - Prometheus metrics exposition
+//! - `node` - Running node (network, API, block production) with graceful shutdown
 
 pub mod types;
 pub mod crypto;
@@ -17,6 +20,9 @@ pub mod consensus;
 pub mod api;
 pub mod storage;
 pub mod network;
+pub mod simulation;
+pub mod metrics;
+pub mod node;
 
 pub use types::*;
 pub use crypto::Keypair;
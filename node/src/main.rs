@@ -2,17 +2,13 @@
 //! 
 //! A Proof-of-Life consensus node for the Pulse Network.
 //! 
-//! Usage:
-//!   pulse-node [OPTIONS]
-//! 
-//! Options:
-//!   --port <PORT>       API port (default: 8080)
-//!   --p2p-port <PORT>   P2P port (default: 4001)
-//!   --data-dir <PATH>   Data directory (default: ./pulse-data)
-//!   --threshold <N>     Minimum live participants (default: 1)
-//!   --interval <MS>     Block interval in ms (default: 5000)
-//!   --peers <ADDRS>     Comma-separated peer multiaddrs (e.g. /ip4/1.2.3.4/tcp/4001)
+//! Run `pulse-node --help` for the options. Settings can also be kept in a
+//! TOML file passed with `--config`; its keys are the `Config` field names
+//! (e.g. `api_port = 8081`, `peers = ["/ip4/1.2.3.4/tcp/4001"]`, and a
+//! `[simulation]` table), and flags on the command line override it.
 
+use serde::Deserialize;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -20,110 +16,362 @@ use tracing::{info, error, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 
 use pulse_node::{
-    api::{self, AppState},
-    api::websocket::WsEvent,
-    api::events::NodeEvent,
-    consensus::{ConsensusConfig, ProofOfLife},
-    crypto::Keypair,
-    network::{self, NetworkMessage, ChainSyncRequest, ChainSyncResponse},
+    api::{parse_cors_origin, ApiConfig, AppState},
+    api::rate_limit::RateLimitConfig,
+    consensus::{ConsensusConfig, EarningCapPolicy, ProofOfLife, RewardPolicy},
+    network::NetworkConfig,
+    network::wire::WireConfig,
+    node::{Node, NodeConfig},
+    simulation::SimulationConfig,
     storage::Storage,
-    types::{Heartbeat, Motion},
 };
 
-#[derive(Debug)]
+/// How log lines are written
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LogFormat {
+    /// Multi-line, human-readable
+    Pretty,
+    /// One JSON object per event, for log aggregators
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown log format '{}' (pretty|json)", other)),
+        }
+    }
+}
+
+/// Node settings, from `--config` and/or command-line flags
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 struct Config {
+    chain_id: String,
     api_port: u16,
     p2p_port: u16,
     data_dir: String,
     n_threshold: usize,
     block_interval_ms: u64,
     reward_per_block: f64,
+    min_block_security: f64,
+    continuity_factors_height: u64,
+    max_reward_per_participant: f64,
+    reward_policy: RewardPolicy,
+    repair_chain: bool,
+    rebuild_accounts: bool,
+    persist_baselines: bool,
+    require_domain_tags: bool,
+    request_ids: bool,
+    flood_burst: u32,
+    pulse_device_limit: u32,
+    ws_capacity: usize,
+    max_ws_connections: usize,
+    ws_token: Option<String>,
+    max_body_bytes: usize,
+    api_token: Option<String>,
+    cors_origins: Vec<String>,
+    compression: bool,
+    peer_idle_timeout_secs: u64,
+    seen_cache_size: usize,
+    max_peers: usize,
+    heartbeat_batch_ms: u64,
+    heartbeat_batch_max: usize,
     simulate: bool,
+    allow_simulate: bool,
+    simulation: SimulationConfig,
     peers: Vec<String>,
+    peer_api_port: u16,
+    bootstrap: Vec<String>,
+    bootstrap_url: Option<String>,
+    prune_depth: u64,
+    cleanup_interval_blocks: u64,
+    weight_alpha: f64,
+    weight_beta: f64,
+    weight_gamma: f64,
+    max_continuity_ms: u64,
+    log_format: LogFormat,
+    log_level: String,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            chain_id: String::new(),
             api_port: 8080,
             p2p_port: 4001,
             data_dir: "./pulse-data".to_string(),
             n_threshold: 1,
             block_interval_ms: 5000,
             reward_per_block: 100.0,
+            min_block_security: 0.0,
+            continuity_factors_height: 0,
+            max_reward_per_participant: 0.0,
+            reward_policy: RewardPolicy::Weighted,
+            repair_chain: false,
+            rebuild_accounts: false,
+            persist_baselines: false,
+            require_domain_tags: false,
+            request_ids: true,
+            flood_burst: 50,
+            pulse_device_limit: 0,
+            ws_capacity: 256,
+            max_ws_connections: 1000,
+            ws_token: None,
+            max_body_bytes: 64 * 1024,
+            api_token: None,
+            cors_origins: Vec::new(),
+            compression: true,
+            peer_idle_timeout_secs: 300,
+            seen_cache_size: 10_000,
+            max_peers: 50,
+            heartbeat_batch_ms: 200,
+            heartbeat_batch_max: 100,
             simulate: false,
+            allow_simulate: false,
+            simulation: SimulationConfig::default(),
             peers: Vec::new(),
+            peer_api_port: 8080,
+            bootstrap: Vec::new(),
+            bootstrap_url: None,
+            prune_depth: 0,
+            cleanup_interval_blocks: 6,
+            weight_alpha: 0.4,
+            weight_beta: 0.3,
+            weight_gamma: 0.3,
+            max_continuity_ms: 300_000,
+            log_format: LogFormat::Pretty,
+            log_level: "info".to_string(),
         }
     }
 }
 
-fn parse_args() -> Config {
-    let mut config = Config::default();
-    let args: Vec<String> = std::env::args().collect();
+impl Config {
+    /// Most verbose level logged (validated in `parse_args`)
+    fn log_level(&self) -> Level {
+        self.log_level.parse().unwrap_or(Level::INFO)
+    }
     
-    let mut i = 1;
+    /// Consensus settings derived from this config
+    fn consensus_config(&self) -> ConsensusConfig {
+        ConsensusConfig {
+            chain_id: self.chain_id.clone(),
+            n_threshold: self.n_threshold,
+            block_interval_ms: self.block_interval_ms,
+            initial_reward_per_block: self.reward_per_block,
+            min_block_security: self.min_block_security,
+            continuity_factors_height: self.continuity_factors_height,
+            reward_policy: self.reward_policy,
+            earning_cap: if self.max_reward_per_participant > 0.0 {
+                EarningCapPolicy::PerBlock(self.max_reward_per_participant)
+            } else {
+                EarningCapPolicy::Off
+            },
+            repair_chain_on_startup: self.repair_chain,
+            rebuild_accounts_on_startup: self.rebuild_accounts,
+            persist_biometric_baselines: self.persist_baselines,
+            require_domain_tags: self.require_domain_tags,
+            weight_alpha: self.weight_alpha,
+            weight_beta: self.weight_beta,
+            weight_gamma: self.weight_gamma,
+            max_continuity_ms: self.max_continuity_ms,
+            ..Default::default()
+        }
+    }
+}
+
+/// Printed by `--help`
+const USAGE: &str = "\
+Usage: pulse-node [OPTIONS]
+
+Options:
+  --config <PATH>     TOML config file; flags given here override its values
+  --port <PORT>       API port (default: 8080)
+  --p2p-port <PORT>   P2P port (default: 4001)
+  --data-dir <PATH>   Data directory (default: ./pulse-data)
+  --chain-id <ID>     Network to join; nodes only accept blocks from their own chain id (default: the original network)
+  --threshold <N>     Minimum live participants (default: 1)
+  --interval <MS>     Block interval in ms (default: 5000)
+  --peers <ADDRS>     Comma-separated peer multiaddrs (e.g. /ip4/1.2.3.4/tcp/4001)
+  --peer-api-port <PORT>   API port of the --peers hosts, for fetching their chain over HTTP on startup, 0 syncs over P2P only (default: 8080)
+  --bootstrap <ADDRS> Comma-separated DHT bootstrap nodes (e.g. /ip4/1.2.3.4/tcp/4001/p2p/<peer id>)
+  --bootstrap-url <URL>    On a fresh data dir, import a peer's /snapshot before syncing
+  --min-security <S>  Minimum block security required to produce/accept blocks (default: 0)
+  --continuity-factors-height <N>  First height whose blocks must embed continuity factors; set it on networks with older blocks (default: 0)
+  --reward-policy <P> Block reward split: weighted, equal, sqrt_weighted; all nodes must agree (default: weighted)
+  --max-reward-per-participant <PULSE>  Cap any one device's reward per block, handing the excess to the rest; all nodes must agree, 0 disables (default: 0)
+  --prune-depth <N>   Keep only headers for blocks more than N below the tip, 0 disables (default: 0)
+  --cleanup-interval <N>   Every N block intervals, forget devices that stopped pulsing, 0 disables (default: 6)
+  --repair-chain      On startup, truncate a corrupted stored chain to its last valid block
+  --rebuild-accounts  On startup, recompute all balances from the blocks and overwrite stored accounts
+  --persist-baselines Keep per-device biometric baselines across restarts
+  --require-domain-tags    Refuse heartbeats and transactions signed in the legacy, untagged format
+  --no-request-id     Disable X-Request-Id propagation and per-request tracing spans
+  --flood-burst <N>   Max requests per second per IP before pre-parse rejection, 0 disables (default: 50)
+  --heartbeat-batch-ms <MS>  Coalesce outgoing heartbeats over this window into one gossip message, 0 disables (default: 200)
+  --heartbeat-batch-max <N>  Max heartbeats per gossip batch (default: 100)
+  --pulse-device-limit <N>  Max heartbeats per minute per device pubkey on /pulse, 0 disables (default: 0)
+  --ws-capacity <N>   Events buffered per WebSocket client before a slow one skips ahead (default: 256)
+  --max-ws-connections <N>  Max concurrent WebSocket clients; more get 503, 0 disables (default: 1000)
+  --ws-token <TOKEN>  Require ?token=<TOKEN> or `Authorization: Bearer <TOKEN>` to open /ws
+  --max-body <BYTES>  Largest API request body; bigger ones get 413 (default: 65536; admin imports allow 64 MiB)
+  --api-token <TOKEN> Require `Authorization: Bearer <TOKEN>` on /pulse, /tx and /admin (admin routes need one)
+  --cors-origins <LIST>    Comma-separated browser origins allowed to call the API (default: any)
+  --no-compression    Send all P2P messages uncompressed (compressed ones are still accepted)
+  --peer-idle-timeout <SECS>  Disconnect peers silent this long, 0 disables (default: 300; seeds exempt)
+  --seen-cache-size <N>  Recent gossip heartbeats/blocks remembered to drop duplicates, 0 disables (default: 10000)
+  --max-peers <N>     Max connected peers; past it the newest non-seed peer is dropped, 0 disables (default: 50)
+  --log-format <F>    Log output: pretty, json (default: pretty)
+  --log-level <L>     Most verbose level logged: error, warn, info, debug, trace (default: info)
+  --simulate          Generate heartbeats from simulated devices
+  --simulate-seed <N> Seed the simulator for a reproducible heartbeat stream (implies --simulate; alias --sim-seed)
+  --simulate-devices <N>   Number of simulated devices (default: 3)
+  --simulate-profile <P>   Simulated activity: resting, mixed, active (default: mixed)
+  --allow-simulate    Serve POST /admin/simulate (needs --api-token) to start/stop the simulator at runtime; always on in debug builds
+  -h, --help          Print this help
+";
+
+/// Build the config from `args` (without the program name): the `--config`
+/// file if one is given, then every other flag applied on top of it.
+/// Unknown flags and malformed values are errors.
+fn parse_args(args: &[String]) -> Result<Config, String> {
+    let mut config = match args.iter().position(|a| a == "--config") {
+        Some(pos) => {
+            let path = args.get(pos + 1).ok_or("--config requires a value")?;
+            load_config_file(path)?
+        }
+        None => Config::default(),
+    };
+    
+    let mut i = 0;
     while i < args.len() {
         match args[i].as_str() {
-            "--port" => {
-                config.api_port = args.get(i + 1)
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(8080);
-                i += 1;
-            }
-            "--p2p-port" => {
-                config.p2p_port = args.get(i + 1)
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(4001);
-                i += 1;
+            "--config" => {
+                i += 1; // loaded above
             }
-            "--data-dir" => {
-                config.data_dir = args.get(i + 1)
-                    .cloned()
-                    .unwrap_or_else(|| "./pulse-data".to_string());
-                i += 1;
-            }
-            "--threshold" => {
-                config.n_threshold = args.get(i + 1)
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(1);
-                i += 1;
+            "--port" => config.api_port = flag_value(args, &mut i)?,
+            "--p2p-port" => config.p2p_port = flag_value(args, &mut i)?,
+            "--data-dir" => config.data_dir = flag_value(args, &mut i)?,
+            "--chain-id" => config.chain_id = flag_value(args, &mut i)?,
+            "--threshold" => config.n_threshold = flag_value(args, &mut i)?,
+            "--interval" => config.block_interval_ms = flag_value(args, &mut i)?,
+            "--min-security" => config.min_block_security = flag_value(args, &mut i)?,
+            "--continuity-factors-height" => config.continuity_factors_height = flag_value(args, &mut i)?,
+            "--reward-policy" => config.reward_policy = flag_value(args, &mut i)?,
+            "--max-reward-per-participant" => config.max_reward_per_participant = flag_value(args, &mut i)?,
+            "--peers" => {
+                let peers: String = flag_value(args, &mut i)?;
+                config.peers = peers.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
             }
-            "--interval" => {
-                config.block_interval_ms = args.get(i + 1)
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(5000);
-                i += 1;
+            "--bootstrap" => {
+                let addrs: String = flag_value(args, &mut i)?;
+                config.bootstrap = addrs.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
             }
-            "--peers" => {
-                if let Some(peers_str) = args.get(i + 1) {
-                    config.peers = peers_str.split(',')
-                        .map(|s| s.trim().to_string())
-                        .filter(|s| !s.is_empty())
-                        .collect();
-                }
-                i += 1;
+            "--peer-api-port" => config.peer_api_port = flag_value(args, &mut i)?,
+            "--bootstrap-url" => config.bootstrap_url = Some(flag_value(args, &mut i)?),
+            "--prune-depth" => config.prune_depth = flag_value(args, &mut i)?,
+            "--cleanup-interval" => config.cleanup_interval_blocks = flag_value(args, &mut i)?,
+            "--repair-chain" => config.repair_chain = true,
+            "--rebuild-accounts" => config.rebuild_accounts = true,
+            "--persist-baselines" => config.persist_baselines = true,
+            "--require-domain-tags" => config.require_domain_tags = true,
+            "--no-request-id" => config.request_ids = false,
+            "--flood-burst" => config.flood_burst = flag_value(args, &mut i)?,
+            "--pulse-device-limit" => config.pulse_device_limit = flag_value(args, &mut i)?,
+            "--ws-capacity" => config.ws_capacity = flag_value(args, &mut i)?,
+            "--max-ws-connections" => config.max_ws_connections = flag_value(args, &mut i)?,
+            "--ws-token" => config.ws_token = Some(flag_value(args, &mut i)?),
+            "--max-body" => config.max_body_bytes = flag_value(args, &mut i)?,
+            "--api-token" => config.api_token = Some(flag_value(args, &mut i)?),
+            "--cors-origins" => {
+                let origins: String = flag_value(args, &mut i)?;
+                config.cors_origins = origins.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
             }
-            "--simulate" => {
+            "--no-compression" => config.compression = false,
+            "--peer-idle-timeout" => config.peer_idle_timeout_secs = flag_value(args, &mut i)?,
+            "--seen-cache-size" => config.seen_cache_size = flag_value(args, &mut i)?,
+            "--max-peers" => config.max_peers = flag_value(args, &mut i)?,
+            "--heartbeat-batch-ms" => config.heartbeat_batch_ms = flag_value(args, &mut i)?,
+            "--heartbeat-batch-max" => config.heartbeat_batch_max = flag_value(args, &mut i)?,
+            "--log-format" => config.log_format = flag_value(args, &mut i)?,
+            "--log-level" => config.log_level = flag_value(args, &mut i)?,
+            "--simulate" => config.simulate = true,
+            "--allow-simulate" => config.allow_simulate = true,
+            "--simulate-seed" | "--sim-seed" => {
+                config.simulation.seed = Some(flag_value(args, &mut i)?);
                 config.simulate = true;
             }
-            _ => {}
+            "--simulate-devices" => config.simulation.devices = flag_value(args, &mut i)?,
+            "--simulate-profile" => config.simulation.profile = flag_value(args, &mut i)?,
+            other => return Err(format!("unknown option '{}' (see --help)", other)),
         }
         i += 1;
     }
     
-    config
+    config.log_level.parse::<Level>()
+        .map_err(|_| format!("invalid log level '{}' (error|warn|info|debug|trace)", config.log_level))?;
+    for origin in &config.cors_origins {
+        parse_cors_origin(origin)?;
+    }
+    config.consensus_config().validate().map_err(|e| e.to_string())?;
+    Ok(config)
+}
+
+/// Parse the value following the flag at `args[*i]`, advancing `i` past it
+fn flag_value<T>(args: &[String], i: &mut usize) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let flag = &args[*i];
+    *i += 1;
+    let raw = args.get(*i).ok_or_else(|| format!("{} requires a value", flag))?;
+    raw.parse().map_err(|e| format!("invalid value '{}' for {}: {}", raw, flag, e))
+}
+
+fn load_config_file(path: &str) -> Result<Config, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("can't read config file {}: {}", path, e))?;
+    toml::from_str(&text).map_err(|e| format!("invalid config file {}: {}", path, e))
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    let _subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .with_target(false)
-        .pretty()
-        .init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+    let config = match parse_args(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("pulse-node: {}", e);
+            std::process::exit(2);
+        }
+    };
     
-    let config = parse_args();
+    // Initialize logging
+    let subscriber = FmtSubscriber::builder()
+        .with_max_level(config.log_level())
+        .with_target(false);
+    match config.log_format {
+        LogFormat::Pretty => subscriber.pretty().init(),
+        LogFormat::Json => subscriber.json().flatten_event(true).init(),
+    }
     
     println!(r#"
     ╔═══════════════════════════════════════════════════════════╗
@@ -138,6 +386,9 @@ async fn main() -> anyhow::Result<()> {
     info!("  API Port: {}", config.api_port);
     info!("  P2P Port: {}", config.p2p_port);
     info!("  Data Dir: {}", config.data_dir);
+    if !config.chain_id.is_empty() {
+        info!("  Chain ID: {}", config.chain_id);
+    }
     info!("  Threshold: {} participants", config.n_threshold);
     info!("  Block Interval: {}ms", config.block_interval_ms);
     if !config.peers.is_empty() {
@@ -145,12 +396,7 @@ async fn main() -> anyhow::Result<()> {
     }
     
     // Create consensus engine with persistent storage
-    let consensus_config = ConsensusConfig {
-        n_threshold: config.n_threshold,
-        block_interval_ms: config.block_interval_ms,
-        initial_reward_per_block: config.reward_per_block,
-        ..Default::default()
-    };
+    let consensus_config = config.consensus_config();
 
     // Open persistent storage
     let storage = match Storage::open(&config.data_dir) {
@@ -158,14 +404,24 @@ async fn main() -> anyhow::Result<()> {
             info!("💾 Storage opened at: {}", config.data_dir);
             Arc::new(s)
         }
-        Err(e) => {
+        Err(e) if !e.is_unwritable() => {
+            // Locked by another node, corrupt, ...: running without the chain would
+            // look like persistence to the operator while silently losing data
             error!("❌ Failed to open storage at {}: {}", config.data_dir, e);
+            anyhow::bail!("can't open storage at {}: {}", config.data_dir, e);
+        }
+        Err(e) => {
+            error!("❌ Data directory {} isn't writable: {}", config.data_dir, e);
             error!("   Falling back to in-memory mode (data will NOT persist!)");
             let pol = ProofOfLife::new(consensus_config.clone());
             let state: AppState = Arc::new(RwLock::new(pol));
             return run_node(state, &config).await;
         }
     };
+    
+    if let Some(ref url) = config.bootstrap_url {
+        bootstrap_from_snapshot(&storage, url).await;
+    }
 
     let pol = match ProofOfLife::with_storage(consensus_config.clone(), storage) {
         Ok(p) => p,
@@ -180,280 +436,174 @@ async fn main() -> anyhow::Result<()> {
     run_node(state, &config).await
 }
 
-async fn run_node(state: AppState, config: &Config) -> anyhow::Result<()> {
-    // Start P2P network — returns a handle (cloneable, channel-based) + incoming message receiver
-    let (net_handle, mut incoming_rx) = network::start(config.p2p_port).await?;
-    info!("🔑 P2P Peer ID: {}", net_handle.info.peer_id);
-    
-    // Start API server
-    let addr = format!("0.0.0.0:{}", config.api_port);
-    let handles = api::start_server(
-        state.clone(),
-        &addr,
-        net_handle.clone(),
-    ).await?;
-    let broadcaster = handles.broadcaster;
-    let event_log = handles.event_log;
-    
-    // Log node start event
-    {
-        let pol = state.read().await;
-        event_log.push(NodeEvent::NodeStarted {
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH).unwrap()
-                .as_millis() as u64,
-            version: api::NODE_VERSION.to_string(),
-            chain_height: pol.chain_height(),
-        }).await;
-    }
-    
-    // Spawn task to process incoming P2P messages
-    let msg_state = state.clone();
-    let msg_broadcaster = broadcaster.clone();
-    let msg_net = net_handle.clone();
-    tokio::spawn(async move {
-        while let Some(msg) = incoming_rx.recv().await {
-            match msg {
-                NetworkMessage::Heartbeat(hb) => {
-                    let mut pol = msg_state.write().await;
-                    if let Err(e) = pol.receive_heartbeat(hb) {
-                        warn!("📨 P2P heartbeat rejected: {}", e);
-                    }
-                }
-                NetworkMessage::Block(block) => {
-                    let mut pol = msg_state.write().await;
-                    match pol.receive_block(block.clone()) {
-                        Ok(()) => {
-                            // Broadcast to WebSocket clients on success
-                            msg_broadcaster.broadcast(WsEvent::NewBlock { block });
-                            let stats = pol.get_stats();
-                            msg_broadcaster.broadcast(WsEvent::Stats { stats });
-                        }
-                        Err(pulse_node::consensus::ConsensusError::InvalidPreviousHash) => {
-                            // We're behind — request chain sync
-                            let our_height = pol.chain_height();
-                            drop(pol);
-                            if block.index > our_height + 1 {
-                                info!("📨 We're behind (at {}, got block #{}), requesting chain sync", our_height, block.index);
-                                let req = ChainSyncRequest { from_height: our_height + 1 };
-                                msg_net.broadcast_chain_sync_request(&req).await;
-                            } else {
-                                warn!("📨 P2P block #{} rejected: prev_hash mismatch (possible fork)", block.index);
-                            }
-                        }
-                        Err(e) => {
-                            warn!("📨 P2P block rejected: {}", e);
-                        }
-                    }
-                }
-                NetworkMessage::ChainSyncRequest(req) => {
-                    info!("📨 Chain sync request from height {}", req.from_height);
-                    let pol = msg_state.read().await;
-                    let blocks = pol.get_blocks_from(req.from_height);
-                    drop(pol);
-                    
-                    if !blocks.is_empty() {
-                        let resp = ChainSyncResponse { blocks };
-                        msg_net.broadcast_chain_sync_response(&resp).await;
-                    }
-                }
-                NetworkMessage::ChainSyncResponse(resp) => {
-                    info!("📨 Chain sync response: {} blocks", resp.blocks.len());
-                    if !resp.blocks.is_empty() {
-                        let mut pol = msg_state.write().await;
-                        match pol.replace_chain(resp.blocks) {
-                            Ok(()) => {
-                                let stats = pol.get_stats();
-                                msg_broadcaster.broadcast(WsEvent::Stats { stats });
-                                info!("✅ Chain synced from peer");
-                            }
-                            Err(e) => {
-                                warn!("Chain sync failed: {}", e);
-                            }
-                        }
-                    }
-                }
-            }
+/// Import a snapshot from `url` into a fresh store. Failures are logged and
+/// the node falls back to normal sync.
+async fn bootstrap_from_snapshot(storage: &Storage, url: &str) {
+    match storage.chain_height() {
+        Ok(0) => {}
+        Ok(height) => {
+            info!("📦 Skipping bootstrap: already have {} blocks", height);
+            return;
+        }
+        Err(e) => {
+            warn!("📦 Skipping bootstrap: {}", e);
+            return;
         }
-    });
-    
-    // Connect to explicit peers and sync chain via HTTP
-    if !config.peers.is_empty() {
-        let peer_net = net_handle.clone();
-        let peer_state = state.clone();
-        let peers = config.peers.clone();
-        let sync_broadcaster = broadcaster.clone();
-        let api_port = config.api_port;
-        tokio::spawn(async move {
-            // Give the network a moment to start listening
-            tokio::time::sleep(Duration::from_secs(2)).await;
-            
-            for peer_addr in &peers {
-                peer_net.dial_peer(peer_addr).await;
-            }
-            
-            // Try HTTP-based chain sync from seed peers
-            // Extract IP from multiaddr (format: /ip4/X.X.X.X/tcp/PORT)
-            for peer_addr in &peers {
-                let parts: Vec<&str> = peer_addr.split('/').collect();
-                if parts.len() >= 5 && parts[1] == "ip4" {
-                    let ip = parts[2];
-                    // Peer API is on port 8080 by default; for local testing, try common ports
-                    for api_port in &[8080u16, 8081, 8082, 3000] {
-                        let url = format!("http://{}:{}/blocks?offset=0&limit=200", ip, api_port);
-                        info!("📡 Attempting HTTP chain sync from {}", url);
-                        
-                        match reqwest::get(&url).await {
-                            Ok(resp) if resp.status().is_success() => {
-                                if let Ok(body) = resp.json::<serde_json::Value>().await {
-                                    if let Some(blocks_val) = body.get("data").and_then(|d: &serde_json::Value| d.get("blocks")) {
-                                        if let Ok(blocks) = serde_json::from_value::<Vec<pulse_node::types::PulseBlock>>(blocks_val.clone()) {
-                                            if !blocks.is_empty() {
-                                                info!("📡 Got {} blocks from peer HTTP API", blocks.len());
-                                                let mut pol = peer_state.write().await;
-                                                match pol.replace_chain(blocks) {
-                                                    Ok(()) => {
-                                                        let stats = pol.get_stats();
-                                                        sync_broadcaster.broadcast(WsEvent::Stats { stats });
-                                                        info!("✅ Chain synced from peer via HTTP!");
-                                                        return; // Success, stop trying
-                                                    }
-                                                    Err(e) => warn!("HTTP chain sync failed: {}", e),
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            _ => {} // Try next port
-                        }
-                    }
-                }
-            }
-            
-            // Fallback: try gossipsub chain sync
-            let pol = peer_state.read().await;
-            let current_height = pol.chain_height();
-            drop(pol);
-            let req = ChainSyncRequest { from_height: current_height + 1 };
-            peer_net.broadcast_chain_sync_request(&req).await;
-        });
     }
     
-    // Block production loop
-    let block_state = state.clone();
-    let block_interval = config.block_interval_ms;
-    let block_broadcaster = broadcaster.clone();
-    let block_event_log = event_log.clone();
-    let block_net = net_handle.clone();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_millis(block_interval));
-        loop {
-            interval.tick().await;
-            
-            let mut pol = block_state.write().await;
-            
-            let pool_size = pol.heartbeat_pool_size();
-            if pool_size > 0 {
-                block_broadcaster.broadcast(WsEvent::HeartbeatCount { count: pool_size });
-            }
-            
-            if let Ok(Some(block)) = pol.try_create_block() {
-                // Log block event
-                block_event_log.push(NodeEvent::BlockCreated {
-                    timestamp: block.timestamp,
-                    index: block.index,
-                    block_hash: block.block_hash.clone(),
-                    n_live: block.n_live,
-                    total_weight: block.total_weight,
-                    security: block.security,
-                    rewards_distributed: 100.0,
-                }).await;
-                
-                for hb in &block.heartbeats {
-                    block_event_log.push(NodeEvent::HeartbeatReceived {
-                        timestamp: hb.timestamp,
-                        device_pubkey: hb.device_pubkey[..16].to_string() + "...",
-                        heart_rate: hb.heart_rate,
-                        weight: hb.weight(),
-                    }).await;
-                }
-                
-                // Broadcast to WebSocket
-                block_broadcaster.broadcast(WsEvent::NewBlock { block: block.clone() });
-                let stats = pol.get_stats();
-                block_broadcaster.broadcast(WsEvent::Stats { stats });
-                
-                // Release consensus lock BEFORE sending to P2P (avoid holding across await)
-                drop(pol);
-                
-                // Broadcast to P2P network
-                block_net.broadcast_block(&block).await;
-            }
+    info!("📦 Bootstrapping from snapshot at {}", url);
+    let body = match reqwest::get(url).await.and_then(|r| r.error_for_status()) {
+        Ok(resp) => resp.bytes().await,
+        Err(e) => Err(e),
+    };
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("📦 Snapshot download failed, syncing normally: {}", e);
+            return;
         }
-    });
-    
-    // Simulation mode
-    if config.simulate {
-        let sim_state = state.clone();
-        tokio::spawn(async move {
-            simulate_heartbeats(sim_state).await;
-        });
+    };
+    match storage.import_snapshot(std::io::Cursor::new(body)) {
+        Ok(summary) => info!("✅ Bootstrapped {} blocks and {} accounts from snapshot",
+            summary.blocks, summary.accounts),
+        Err(e) => warn!("📦 Snapshot rejected, syncing normally: {}", e),
     }
+}
+
+async fn run_node(state: AppState, config: &Config) -> anyhow::Result<()> {
+    let node_config = NodeConfig {
+        api_addr: format!("0.0.0.0:{}", config.api_port),
+        p2p_port: config.p2p_port,
+        identity_path: Some(std::path::Path::new(&config.data_dir).join("p2p_identity.key")),
+        block_interval: Duration::from_millis(config.block_interval_ms),
+        prune_depth: (config.prune_depth > 0).then_some(config.prune_depth),
+        device_cleanup_interval: (config.cleanup_interval_blocks > 0)
+            .then(|| Duration::from_millis(config.block_interval_ms * config.cleanup_interval_blocks)),
+        peers: config.peers.clone(),
+        peer_api_port: (config.peer_api_port > 0).then_some(config.peer_api_port),
+        network: NetworkConfig {
+            wire: WireConfig {
+                compression: config.compression,
+                ..Default::default()
+            },
+            idle_peer_timeout: (config.peer_idle_timeout_secs > 0)
+                .then(|| Duration::from_secs(config.peer_idle_timeout_secs)),
+            seen_cache_size: config.seen_cache_size,
+            max_peers: (config.max_peers > 0).then_some(config.max_peers),
+            heartbeat_batch_window: (config.heartbeat_batch_ms > 0)
+                .then(|| Duration::from_millis(config.heartbeat_batch_ms)),
+            heartbeat_batch_max: config.heartbeat_batch_max,
+            bootstrap_peers: config.bootstrap.clone(),
+            ..Default::default()
+        },
+        api: ApiConfig {
+            request_ids: config.request_ids,
+            flood_guard: (config.flood_burst > 0).then(|| RateLimitConfig {
+                max_requests: config.flood_burst,
+                window: Duration::from_secs(1),
+                ..Default::default()
+            }),
+            pulse_device_limit: (config.pulse_device_limit > 0).then(|| RateLimitConfig {
+                max_requests: config.pulse_device_limit,
+                window: Duration::from_secs(60),
+                ..Default::default()
+            }),
+            ws_capacity: config.ws_capacity,
+            max_ws_connections: config.max_ws_connections,
+            ws_token: config.ws_token.clone(),
+            max_body_bytes: config.max_body_bytes,
+            api_token: config.api_token.clone(),
+            // Validated in parse_args
+            cors_origins: config.cors_origins.iter()
+                .filter_map(|origin| parse_cors_origin(origin).ok())
+                .collect(),
+            // Falling back to in-memory mode makes /health report the node unready
+            require_storage: true,
+            allow_simulate: config.allow_simulate || cfg!(debug_assertions),
+            ..Default::default()
+        },
+        simulation: config.simulate.then(|| config.simulation.clone()),
+    };
     
-    info!("🚀 Pulse node running!");
+    let node = Node::start(state, node_config).await?;
     tokio::signal::ctrl_c().await?;
-    info!("👋 Shutting down...");
+    node.shutdown().await?;
     
     Ok(())
 }
 
-/// Simulate heartbeats for testing (when --simulate is passed)
-async fn simulate_heartbeats(state: AppState) {
-    use rand::{Rng, SeedableRng};
-    use rand::rngs::StdRng;
-    
-    info!("🎭 Starting heartbeat simulation...");
-    
-    let devices: Vec<Keypair> = (0..3)
-        .map(|_| Keypair::generate())
-        .collect();
-    
-    for (i, kp) in devices.iter().enumerate() {
-        info!("  Device {}: {}...", i, &kp.public_key_hex()[..16]);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulse_node::simulation::ActivityProfile;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
     }
-    
-    let mut interval = tokio::time::interval(Duration::from_secs(2));
-    let mut rng = StdRng::from_entropy();
-    
-    loop {
-        interval.tick().await;
-        
-        for device in &devices {
-            let activity: f64 = rng.gen_range(0.0..0.5);
-            
-            let mut hb = Heartbeat {
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64,
-                heart_rate: 70 + (activity * 60.0) as u16 + rng.gen_range(0..10),
-                motion: Motion {
-                    x: rng.gen_range(-0.2..0.2) + activity * 0.5,
-                    y: rng.gen_range(-0.2..0.2) + activity * 0.3,
-                    z: rng.gen_range(-0.1..0.1) + activity * 0.2,
-                },
-                temperature: 36.5 + rng.gen_range(-0.5..0.5),
-                device_pubkey: device.public_key_hex(),
-                signature: String::new(),
-            };
-            
-            hb.signature = device.sign(&hb.signable_bytes());
-            
-            let mut pol = state.write().await;
-            if pol.receive_heartbeat(hb).is_ok() {
-                // Heartbeat accepted
-            }
-        }
+
+    #[test]
+    fn test_cli_flags_override_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.toml");
+        std::fs::write(&path, r#"
+            api_port = 9000
+            p2p_port = 9001
+            peers = ["/ip4/10.0.0.1/tcp/4001"]
+
+            [simulation]
+            devices = 5
+            profile = "active"
+        "#).unwrap();
+
+        let config = parse_args(&args(&format!("--config {} --port 9100", path.display()))).unwrap();
+        assert_eq!(config.api_port, 9100);
+        assert_eq!(config.p2p_port, 9001);
+        assert_eq!(config.peers, vec!["/ip4/10.0.0.1/tcp/4001".to_string()]);
+        assert_eq!(config.simulation.devices, 5);
+        assert_eq!(config.simulation.profile, ActivityProfile::Active);
+        // Unset keys keep their defaults
+        assert_eq!(config.block_interval_ms, 5000);
+        assert_eq!(config.simulation.interval_ms, 2000);
+    }
+
+    #[test]
+    fn test_unknown_or_malformed_options_rejected() {
+        assert!(parse_args(&args("--prot 8081")).unwrap_err().contains("--prot"));
+        assert!(parse_args(&args("--port eighty")).is_err());
+        assert!(parse_args(&args("--port")).is_err());
+        assert!(parse_args(&args("--simulate-profile sprinting")).is_err());
+        assert!(parse_args(&args("--log-format xml")).is_err());
+        assert!(parse_args(&args("--log-level loud")).is_err());
+        assert!(parse_args(&args("--chain-id pulse/test")).unwrap_err().contains("chain_id"));
+        assert!(parse_args(&args("--cors-origins https://a.example.com,a.example.com")).unwrap_err().contains("a.example.com"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.toml");
+        std::fs::write(&path, "api_prot = 8081\n").unwrap();
+        assert!(parse_args(&args(&format!("--config {}", path.display()))).is_err());
+    }
+
+    #[test]
+    fn test_log_options() {
+        let config = parse_args(&args("--log-format json --log-level debug")).unwrap();
+        assert_eq!(config.log_format, LogFormat::Json);
+        assert_eq!(config.log_level(), Level::DEBUG);
+
+        let config = parse_args(&[]).unwrap();
+        assert_eq!(config.log_format, LogFormat::Pretty);
+        assert_eq!(config.log_level(), Level::INFO);
+    }
+
+    #[test]
+    fn test_weight_coefficients_must_sum_to_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.toml");
+        std::fs::write(&path, "weight_alpha = 0.6\nweight_beta = 0.2\nweight_gamma = 0.2\n").unwrap();
+        let config = parse_args(&args(&format!("--config {}", path.display()))).unwrap();
+        assert_eq!(config.consensus_config().weight_alpha, 0.6);
+
+        std::fs::write(&path, "weight_alpha = 0.6\n").unwrap();
+        let err = parse_args(&args(&format!("--config {}", path.display()))).unwrap_err();
+        assert!(err.contains("sum to 1.0"), "{}", err);
     }
 }
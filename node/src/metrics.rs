@@ -0,0 +1,107 @@
+//! Prometheus metrics for node operators (`GET /metrics`).
+//!
+//! Kept dependency-free: consensus counters are plain integers updated under
+//! the consensus lock, and the text exposition format is rendered by hand.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Event counters maintained by the consensus engine
+#[derive(Debug, Clone, Default)]
+pub struct ConsensusMetrics {
+    /// Heartbeats accepted into the pool
+    pub heartbeats_accepted: u64,
+    /// Heartbeats rejected, by reason (`ConsensusError::kind`)
+    pub heartbeats_rejected: BTreeMap<&'static str, u64>,
+    /// Blocks produced locally
+    pub blocks_created: u64,
+    /// Blocks received from peers and appended
+    pub blocks_received: u64,
+    /// Heartbeat signature checks answered from the verification cache
+    pub signature_cache_hits: u64,
+}
+
+impl ConsensusMetrics {
+    pub fn record_heartbeat_rejected(&mut self, reason: &'static str) {
+        *self.heartbeats_rejected.entry(reason).or_insert(0) += 1;
+    }
+}
+
+/// Point-in-time node state exported as gauges
+#[derive(Debug, Clone, Default)]
+pub struct NodeGauges {
+    pub chain_height: u64,
+    pub heartbeat_pool_size: usize,
+    pub tx_pool_size: usize,
+    pub active_accounts: usize,
+    pub total_minted: f64,
+    pub peer_count: usize,
+    pub ws_clients: usize,
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Render gauges and counters in the Prometheus text exposition format (0.0.4)
+pub fn render(gauges: &NodeGauges, counters: &ConsensusMetrics) -> String {
+    let mut out = String::new();
+
+    gauge(&mut out, "pulse_chain_height", "Height of the local chain tip.", gauges.chain_height);
+    gauge(&mut out, "pulse_heartbeat_pool_size", "Verified heartbeats waiting for the next block.", gauges.heartbeat_pool_size);
+    gauge(&mut out, "pulse_tx_pool_size", "Pending transactions.", gauges.tx_pool_size);
+    gauge(&mut out, "pulse_active_accounts", "Accounts with on-chain state.", gauges.active_accounts);
+    gauge(&mut out, "pulse_total_minted", "Total PULSE minted.", gauges.total_minted);
+    gauge(&mut out, "pulse_peer_count", "Connected P2P peers.", gauges.peer_count);
+    gauge(&mut out, "pulse_ws_clients", "Connected WebSocket clients.", gauges.ws_clients);
+
+    counter(&mut out, "pulse_heartbeats_accepted_total", "Heartbeats accepted into the pool.", counters.heartbeats_accepted);
+    let _ = writeln!(out, "# HELP pulse_heartbeats_rejected_total Heartbeats rejected, by reason.");
+    let _ = writeln!(out, "# TYPE pulse_heartbeats_rejected_total counter");
+    for (reason, count) in &counters.heartbeats_rejected {
+        let _ = writeln!(out, "pulse_heartbeats_rejected_total{{reason=\"{}\"}} {}", reason, count);
+    }
+    counter(&mut out, "pulse_blocks_created_total", "Blocks produced by this node.", counters.blocks_created);
+    counter(&mut out, "pulse_blocks_received_total", "Blocks received from peers and appended.", counters.blocks_received);
+    counter(&mut out, "pulse_signature_cache_hits_total", "Heartbeat signature checks answered from the verification cache.", counters.signature_cache_hits);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_exposition_format() {
+        let mut counters = ConsensusMetrics { heartbeats_accepted: 7, blocks_created: 2, ..Default::default() };
+        counters.record_heartbeat_rejected("stale_heartbeat");
+        counters.record_heartbeat_rejected("stale_heartbeat");
+        counters.record_heartbeat_rejected("invalid_heart_rate");
+
+        let gauges = NodeGauges { chain_height: 12, peer_count: 3, total_minted: 1200.5, ..Default::default() };
+        let text = render(&gauges, &counters);
+
+        assert!(text.contains("# TYPE pulse_chain_height gauge\npulse_chain_height 12\n"));
+        assert!(text.contains("pulse_peer_count 3\n"));
+        assert!(text.contains("pulse_total_minted 1200.5\n"));
+        assert!(text.contains("pulse_heartbeats_accepted_total 7\n"));
+        assert!(text.contains("pulse_heartbeats_rejected_total{reason=\"stale_heartbeat\"} 2\n"));
+        assert!(text.contains("pulse_heartbeats_rejected_total{reason=\"invalid_heart_rate\"} 1\n"));
+        assert!(text.contains("pulse_blocks_created_total 2\n"));
+
+        // Every sample line belongs to a declared metric
+        for line in text.lines().filter(|l| !l.starts_with('#')) {
+            let name = line.split(['{', ' ']).next().unwrap();
+            assert!(text.contains(&format!("# TYPE {} ", name)), "undeclared: {}", line);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Request-response codec for chain sync.
+//!
+//! A node that falls behind asks one peer (or each connected peer) for the
+//! blocks above its height, and only the requester gets the answer, instead
+//! of a chain dump flooding the whole gossip mesh. Both directions use the
+//! [`wire`](super::wire) framing, so large responses are compressed; a stream
+//! carries exactly one message and is closed after writing.
+
+use async_trait::async_trait;
+use libp2p::futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use libp2p::{request_response, StreamProtocol};
+use std::io;
+
+use super::wire::{self, WireConfig};
+use super::{ChainSyncRequest, ChainSyncResponse};
+
+/// Protocol name for chain sync requests
+pub const CHAIN_SYNC_PROTOCOL: StreamProtocol = StreamProtocol::new("/pulse/chain-sync/1.0.0");
+
+/// Largest encoded request we read
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+/// Largest encoded response we read (same bound as a decompressed payload)
+const MAX_RESPONSE_BYTES: u64 = wire::MAX_DECOMPRESSED_BYTES as u64;
+
+/// Encodes chain sync requests and responses with the wire codec
+#[derive(Debug, Clone, Default)]
+pub struct ChainSyncCodec {
+    wire: WireConfig,
+}
+
+impl ChainSyncCodec {
+    pub fn new(wire: WireConfig) -> Self {
+        Self { wire }
+    }
+}
+
+/// Read a whole stream (up to `limit` bytes) and decode it
+async fn read_message<T, M>(io: &mut T, limit: u64) -> io::Result<M>
+where
+    T: AsyncRead + Unpin + Send,
+    M: serde::de::DeserializeOwned,
+{
+    let mut data = Vec::new();
+    io.take(limit).read_to_end(&mut data).await?;
+    wire::decode(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Encode a message, write it and close the stream
+async fn write_message<T, M>(io: &mut T, msg: &M, config: &WireConfig) -> io::Result<()>
+where
+    T: AsyncWrite + Unpin + Send,
+    M: serde::Serialize,
+{
+    let data = wire::encode(msg, config).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    io.write_all(&data).await?;
+    io.close().await
+}
+
+#[async_trait]
+impl request_response::Codec for ChainSyncCodec {
+    type Protocol = StreamProtocol;
+    type Request = ChainSyncRequest;
+    type Response = ChainSyncResponse;
+
+    async fn read_request<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<ChainSyncRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io, MAX_REQUEST_BYTES).await
+    }
+
+    async fn read_response<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<ChainSyncResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io, MAX_RESPONSE_BYTES).await
+    }
+
+    async fn write_request<T>(&mut self, _: &StreamProtocol, io: &mut T, req: ChainSyncRequest) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, &req, &self.wire).await
+    }
+
+    async fn write_response<T>(&mut self, _: &StreamProtocol, io: &mut T, resp: ChainSyncResponse) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, &resp, &self.wire).await
+    }
+}
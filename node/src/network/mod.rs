@@ -8,59 +8,428 @@
 //! - `NetworkMessage` channel (mpsc): network sends received messages FROM peers
 //!   to the consensus/processing task
 //! - `NetworkHandle`: cheaply cloneable handle for sending commands + querying state
+//!
+//! Gossip payloads are framed by the [`wire`] codec (optional zstd compression).
+//! Chain sync is point-to-point over request-response (see [`chain_sync`]).
+//! Peers are discovered via mDNS on the local network and a Kademlia DHT
+//! (seeded from `NetworkConfig::bootstrap_peers`) beyond it.
+
+pub mod chain_sync;
+pub mod wire;
 
 use libp2p::{
     core::upgrade,
+    multiaddr::Protocol,
     futures::StreamExt,
     gossipsub::{self, IdentTopic, MessageAuthenticity},
+    identify,
+    kad,
     mdns,
+    request_response::{self, ProtocolSupport},
     noise,
-    swarm::{NetworkBehaviour, SwarmEvent},
-    tcp, yamux, Multiaddr, PeerId, Swarm, Transport,
+    swarm::{behaviour::toggle::Toggle, dial_opts::DialOpts, ConnectionId, NetworkBehaviour, SwarmEvent},
+    tcp, yamux, Multiaddr, PeerId, StreamProtocol, Swarm, Transport,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
-use tokio::sync::{mpsc, RwLock};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::error::{SendTimeoutError, TrySendError};
+use tokio::sync::{mpsc, oneshot, RwLock};
 use tracing::{info, debug, warn, error};
 
+use crate::crypto::{hash_sha256, verify_signature};
 use crate::types::{Heartbeat, PulseBlock};
 
 /// Topics for gossipsub
 pub const HEARTBEAT_TOPIC: &str = "pulse/heartbeats/1.0.0";
+/// Several heartbeats coalesced into one gossip message (a JSON array)
+pub const HEARTBEAT_BATCH_TOPIC: &str = "pulse/heartbeats-batch/1.0.0";
 pub const BLOCK_TOPIC: &str = "pulse/blocks/1.0.0";
-pub const CHAIN_SYNC_TOPIC: &str = "pulse/chain-sync/1.0.0";
+
+/// Kademlia protocol name; keeps the Pulse DHT separate from other libp2p networks
+pub const KAD_PROTOCOL: StreamProtocol = StreamProtocol::new("/pulse/kad/1.0.0");
+/// Identify protocol version, exchanged so peers learn each other's listen addresses
+pub const IDENTIFY_PROTOCOL_VERSION: &str = "/pulse/1.0.0";
+
+/// How often the event loop checks for idle peers
+const IDLE_SWEEP_INTERVAL: Duration = Duration::from_secs(15);
+/// How often dials that outlived `NetworkConfig::dial_timeout` are failed
+const DIAL_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the shared peer details are refreshed, so `last_seen` stays current
+const PEER_INFO_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the DHT routing table is refreshed from the bootstrap nodes
+const KAD_BOOTSTRAP_INTERVAL: Duration = Duration::from_secs(300);
+/// Score penalty for a gossip payload that fails to decode or deserialize
+const UNDECODABLE_PENALTY: i32 = 1;
+/// Score penalty for a heartbeat with a bad signature or a block with a bad hash
+const INVALID_MESSAGE_PENALTY: i32 = 2;
+
+/// What to do when the network command queue is full
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnqueuePolicy {
+    /// Drop the message immediately (fine for high-rate, soon-stale data)
+    Drop,
+    /// Wait up to the given time for room, then drop
+    Backpressure(Duration),
+}
+
+/// P2P network settings
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    /// Payload encoding/compression
+    pub wire: wire::WireConfig,
+    /// Capacity of the command queue feeding the swarm task
+    pub command_queue_capacity: usize,
+    /// Full-queue policy for forwarded heartbeats
+    pub heartbeat_policy: EnqueuePolicy,
+    /// Full-queue policy for produced blocks
+    pub block_policy: EnqueuePolicy,
+    /// Full-queue policy for chain sync requests/responses
+    pub chain_sync_policy: EnqueuePolicy,
+    /// How long to wait for a peer's chain sync response
+    pub chain_sync_timeout: Duration,
+    /// How long an explicit dial may take before it is reported as failed
+    pub dial_timeout: Duration,
+    /// Disconnect peers that haven't sent us a gossip message for this long
+    /// (explicitly dialed seed peers are exempt). `None` keeps idle peers.
+    pub idle_peer_timeout: Option<Duration>,
+    /// Recently received heartbeats and blocks remembered to drop semantic
+    /// duplicates (re-encoded or replayed gossip) before they reach consensus. 0 disables.
+    pub seen_cache_size: usize,
+    /// Kademlia bootstrap nodes (multiaddrs ending in `/p2p/<peer id>`) for
+    /// discovering peers beyond the local network. Empty relies on mDNS and `--peers`.
+    pub bootstrap_peers: Vec<String>,
+    /// Discover peers on the local network via mDNS
+    pub mdns: bool,
+    /// Connection cap; past it the newest non-seed peer is disconnected. `None` is unlimited.
+    pub max_peers: Option<usize>,
+    /// Peers whose misbehaviour score falls below this are banned
+    pub ban_threshold: i32,
+    /// How long a banned peer is disconnected and refused
+    pub ban_duration: Duration,
+    /// Outgoing heartbeats submitted within this window are published as one
+    /// batch message. `None` publishes each heartbeat on its own.
+    pub heartbeat_batch_window: Option<Duration>,
+    /// A batch is published as soon as it reaches this many heartbeats
+    pub heartbeat_batch_max: usize,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            wire: wire::WireConfig::default(),
+            command_queue_capacity: 256,
+            heartbeat_policy: EnqueuePolicy::Drop,
+            block_policy: EnqueuePolicy::Backpressure(Duration::from_secs(5)),
+            chain_sync_policy: EnqueuePolicy::Backpressure(Duration::from_secs(2)),
+            chain_sync_timeout: Duration::from_secs(30),
+            dial_timeout: Duration::from_secs(10),
+            idle_peer_timeout: Some(Duration::from_secs(300)),
+            seen_cache_size: 10_000,
+            bootstrap_peers: Vec::new(),
+            mdns: true,
+            max_peers: Some(50),
+            ban_threshold: -10,
+            ban_duration: Duration::from_secs(600),
+            heartbeat_batch_window: Some(Duration::from_millis(200)),
+            heartbeat_batch_max: 100,
+        }
+    }
+}
+
+/// Snapshot of command-queue enqueue outcomes
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PublishStats {
+    pub heartbeats_dropped: u64,
+    pub blocks_dropped: u64,
+    pub chain_sync_dropped: u64,
+    /// Enqueues that found the queue full and had to wait
+    pub backpressure_waits: u64,
+}
+
+/// Enqueue counters shared by all handle clones
+#[derive(Default)]
+struct PublishMetrics {
+    heartbeats_dropped: AtomicU64,
+    blocks_dropped: AtomicU64,
+    chain_sync_dropped: AtomicU64,
+    backpressure_waits: AtomicU64,
+}
+
+/// Last gossip activity per connected peer, for idle eviction
+#[derive(Debug, Default)]
+struct PeerActivity {
+    last_seen: HashMap<PeerId, Instant>,
+    connected_at: HashMap<PeerId, Instant>,
+    direction: HashMap<PeerId, PeerDirection>,
+    /// Peers we dialed explicitly (seed peers) — never evicted
+    exempt: HashSet<PeerId>,
+}
+
+impl PeerActivity {
+    /// Record activity from a peer (connecting counts, so new peers get a full timeout)
+    fn touch(&mut self, peer: PeerId, now: Instant) {
+        self.last_seen.insert(peer, now);
+    }
+
+    /// Record a new connection (also counts as activity)
+    fn connected(&mut self, peer: PeerId, now: Instant, direction: PeerDirection) {
+        self.touch(peer, now);
+        self.connected_at.insert(peer, now);
+        self.direction.insert(peer, direction);
+    }
+
+    fn exempt(&mut self, peer: PeerId) {
+        self.exempt.insert(peer);
+    }
+
+    /// Forget a peer; returns whether it was tracked as connected
+    fn remove(&mut self, peer: &PeerId) -> bool {
+        self.last_seen.remove(peer);
+        self.exempt.remove(peer);
+        self.direction.remove(peer);
+        self.connected_at.remove(peer).is_some()
+    }
+
+    /// Wall-clock view of a tracked peer; `now`/`now_ms` anchor the Instant → unix ms conversion
+    fn detail(&self, peer: &PeerId, score: i32, now: Instant, now_ms: u64) -> Option<PeerDetail> {
+        let to_unix_ms = |at: &Instant| now_ms.saturating_sub(now.saturating_duration_since(*at).as_millis() as u64);
+        let connected_at = self.connected_at.get(peer)?;
+        Some(PeerDetail {
+            peer_id: peer.to_string(),
+            direction: self.direction.get(peer).copied().unwrap_or(PeerDirection::Inbound),
+            connected_since: to_unix_ms(connected_at),
+            last_seen: self.last_seen.get(peer).map_or(to_unix_ms(connected_at), to_unix_ms),
+            score,
+        })
+    }
+
+    /// Most recently connected non-exempt peer — the one dropped when over the peer cap
+    fn newest_evictable(&self) -> Option<PeerId> {
+        self.connected_at.iter()
+            .filter(|(peer, _)| !self.exempt.contains(peer))
+            .max_by_key(|(_, at)| **at)
+            .map(|(peer, _)| *peer)
+    }
+
+    /// Non-exempt peers silent for longer than `timeout` as of `now`
+    fn idle_peers(&self, now: Instant, timeout: Duration) -> Vec<PeerId> {
+        self.last_seen.iter()
+            .filter(|(peer, _)| !self.exempt.contains(peer))
+            .filter(|(_, seen)| now.saturating_duration_since(**seen) > timeout)
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+}
+
+/// Outcome of an explicit dial: `Err` carries the reason it failed
+pub type DialResult = Result<(), String>;
+
+/// Explicit dials waiting to connect, by connection id, with their deadlines
+#[derive(Debug, Default)]
+struct PendingDials {
+    dials: HashMap<ConnectionId, (oneshot::Sender<DialResult>, Instant)>,
+}
+
+impl PendingDials {
+    fn insert(&mut self, id: ConnectionId, reply: oneshot::Sender<DialResult>, deadline: Instant) {
+        self.dials.insert(id, (reply, deadline));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.dials.is_empty()
+    }
+
+    /// Report the outcome of a dial, if `id` is one we're waiting on
+    fn resolve(&mut self, id: &ConnectionId, result: DialResult) {
+        if let Some((reply, _)) = self.dials.remove(id) {
+            let _ = reply.send(result);
+        }
+    }
+
+    /// Remove and return dials whose deadline has passed as of `now`
+    fn expired(&mut self, now: Instant) -> Vec<(ConnectionId, oneshot::Sender<DialResult>)> {
+        let ids: Vec<ConnectionId> = self.dials.iter()
+            .filter(|(_, (_, deadline))| now >= *deadline)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.into_iter()
+            .filter_map(|id| self.dials.remove(&id).map(|(reply, _)| (id, reply)))
+            .collect()
+    }
+}
+
+/// Per-peer misbehaviour scores (0 is neutral, penalties subtract) and
+/// temporary bans for peers that drop below the threshold
+#[derive(Debug, Default)]
+struct PeerScores {
+    scores: HashMap<PeerId, i32>,
+    banned_until: HashMap<PeerId, Instant>,
+}
+
+impl PeerScores {
+    fn score(&self, peer: &PeerId) -> i32 {
+        self.scores.get(peer).copied().unwrap_or(0)
+    }
+
+    /// Subtract `penalty` from a peer's score. Returns true if this drops it
+    /// below `threshold`, in which case it is banned until `now + ban`.
+    fn penalize(&mut self, peer: PeerId, penalty: i32, threshold: i32, now: Instant, ban: Duration) -> bool {
+        let score = self.scores.entry(peer).or_insert(0);
+        *score -= penalty;
+        if *score < threshold && !self.banned_until.contains_key(&peer) {
+            self.banned_until.insert(peer, now + ban);
+            return true;
+        }
+        false
+    }
+
+    /// Whether a peer is banned as of `now`. An expired ban is lifted and the
+    /// peer starts over with a neutral score.
+    fn is_banned(&mut self, peer: &PeerId, now: Instant) -> bool {
+        match self.banned_until.get(peer) {
+            Some(until) if now < *until => true,
+            Some(_) => {
+                self.banned_until.remove(peer);
+                self.scores.remove(peer);
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+/// Bounded LRU set of content keys (heartbeat signable-bytes hashes, block
+/// hashes) already forwarded to consensus. Gossipsub's own dedup is per message
+/// id, so the same heartbeat re-encoded or replayed by a peer gets through it.
+#[derive(Debug, Default)]
+struct SeenCache {
+    capacity: usize,
+    /// Key → generation of its last sighting
+    entries: HashMap<String, u64>,
+    /// Generation → key, oldest first
+    order: BTreeMap<u64, String>,
+    generation: u64,
+}
+
+impl SeenCache {
+    fn new(capacity: usize) -> Self {
+        Self { capacity, ..Default::default() }
+    }
+
+    /// Record a sighting of `key`; true if it wasn't already in the cache.
+    /// Always true when the cache is disabled (capacity 0).
+    fn insert(&mut self, key: String) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        self.generation += 1;
+        if let Some(generation) = self.entries.get_mut(&key) {
+            self.order.remove(generation);
+            *generation = self.generation;
+            self.order.insert(self.generation, key);
+            return false;
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key.clone(), self.generation);
+        self.order.insert(self.generation, key);
+        true
+    }
+}
+
+/// Most blocks a chain sync response carries; longer syncs take several windows
+pub const MAX_SYNC_BLOCKS: u64 = 500;
 
 /// Chain sync request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainSyncRequest {
     pub from_height: u64,
+    /// Most blocks wanted in the response (0 = as many as the responder allows)
+    #[serde(default)]
+    pub max_blocks: u64,
+}
+
+impl ChainSyncRequest {
+    /// Blocks to send back: the requested count, capped at [`MAX_SYNC_BLOCKS`]
+    pub fn window(&self) -> u64 {
+        match self.max_blocks {
+            0 => MAX_SYNC_BLOCKS,
+            n => n.min(MAX_SYNC_BLOCKS),
+        }
+    }
 }
 
 /// Chain sync response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainSyncResponse {
     pub blocks: Vec<PulseBlock>,
+    /// Responder's chain height, so the requester knows whether more windows follow
+    #[serde(default)]
+    pub tip_height: u64,
 }
 
 /// Messages received FROM the network (peers → us)
 #[derive(Debug, Clone)]
 pub enum NetworkMessage {
     Heartbeat(Heartbeat),
-    Block(PulseBlock),
-    ChainSyncRequest(ChainSyncRequest),
-    ChainSyncResponse(ChainSyncResponse),
+    /// A gossiped block and the peer that relayed it (who can serve a chain sync)
+    Block { block: PulseBlock, from: String },
+    /// A peer asked us for blocks; answer with [`NetworkHandle::respond_chain_sync`]
+    ChainSyncRequest { request_id: u64, request: ChainSyncRequest },
+    /// Answer to a chain sync request we sent, and the peer that answered
+    ChainSyncResponse { response: ChainSyncResponse, from: String },
+    /// First connection to a peer opened; `peer_count` includes it
+    PeerConnected { peer_id: String, peer_count: usize },
+    /// Last connection to a peer closed
+    PeerDisconnected { peer_id: String, peer_count: usize },
 }
 
 /// Commands sent TO the network (us → swarm)
 #[derive(Debug)]
 pub enum NetworkCommand {
     BroadcastHeartbeat(Heartbeat),
+    /// Publish several heartbeats as one message on the batch topic
+    BroadcastHeartbeatBatch(Vec<Heartbeat>),
     BroadcastBlock(PulseBlock),
-    BroadcastChainSyncRequest(ChainSyncRequest),
-    BroadcastChainSyncResponse(ChainSyncResponse),
-    DialPeer(String),
+    /// Ask one peer, or every connected peer if `None`, for blocks
+    RequestChainSync(Option<PeerId>, ChainSyncRequest),
+    /// Answer an inbound chain sync request
+    RespondChainSync(u64, ChainSyncResponse),
+    /// Dial an address; the sender learns whether the connection came up
+    DialPeer(String, oneshot::Sender<DialResult>),
+    /// Stop the event loop, closing the command channel; acknowledged once the loop has exited
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// Which side opened a peer connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PeerDirection {
+    /// The peer dialed us
+    Inbound,
+    /// We dialed the peer
+    Outbound,
+}
+
+/// A connected peer as reported on `/peers`
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerDetail {
+    pub peer_id: String,
+    pub direction: PeerDirection,
+    /// Unix ms the connection was established
+    pub connected_since: u64,
+    /// Unix ms of the last gossip from the peer (its connection time if it hasn't gossiped)
+    pub last_seen: u64,
+    /// Misbehaviour score (0 is neutral)
+    pub score: i32,
 }
 
 /// Shared peer info (atomics + RwLock for lock-free reads)
@@ -69,6 +438,8 @@ pub struct PeerInfo {
     pub peer_id: String,
     peer_count: Arc<AtomicUsize>,
     peer_list: Arc<RwLock<Vec<String>>>,
+    peer_scores: Arc<RwLock<BTreeMap<String, i32>>>,
+    peer_details: Arc<RwLock<Vec<PeerDetail>>>,
 }
 
 impl PeerInfo {
@@ -77,6 +448,8 @@ impl PeerInfo {
             peer_id,
             peer_count: Arc::new(AtomicUsize::new(0)),
             peer_list: Arc::new(RwLock::new(Vec::new())),
+            peer_scores: Arc::new(RwLock::new(BTreeMap::new())),
+            peer_details: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
@@ -87,6 +460,30 @@ impl PeerInfo {
     pub async fn connected_peers(&self) -> Vec<String> {
         self.peer_list.read().await.clone()
     }
+
+    /// Misbehaviour score of each connected peer (0 is neutral)
+    pub async fn peer_scores(&self) -> BTreeMap<String, i32> {
+        self.peer_scores.read().await.clone()
+    }
+
+    /// Direction, connection time and last activity of each connected peer
+    pub async fn peer_details(&self) -> Vec<PeerDetail> {
+        self.peer_details.read().await.clone()
+    }
+
+    /// Refresh the shared peer list, count, scores and details; returns the peer count
+    async fn update(&self, connected: Vec<PeerId>, scores: &PeerScores, activity: &PeerActivity) -> usize {
+        self.peer_count.store(connected.len(), Ordering::Relaxed);
+        *self.peer_scores.write().await = connected.iter()
+            .map(|p| (p.to_string(), scores.score(p)))
+            .collect();
+        let (now, now_ms) = (Instant::now(), unix_time_ms());
+        *self.peer_details.write().await = connected.iter()
+            .filter_map(|p| activity.detail(p, scores.score(p), now, now_ms))
+            .collect();
+        *self.peer_list.write().await = connected.iter().map(|p| p.to_string()).collect();
+        connected.len()
+    }
 }
 
 /// Cheaply cloneable handle for interacting with the network from any task.
@@ -95,27 +492,172 @@ impl PeerInfo {
 pub struct NetworkHandle {
     cmd_tx: mpsc::Sender<NetworkCommand>,
     pub info: PeerInfo,
+    config: Arc<NetworkConfig>,
+    metrics: Arc<PublishMetrics>,
+    /// Feeds the heartbeat batcher; `None` sends each heartbeat straight to the swarm task
+    batch_tx: Option<mpsc::Sender<Heartbeat>>,
 }
 
 impl NetworkHandle {
+    fn new(cmd_tx: mpsc::Sender<NetworkCommand>, info: PeerInfo, config: NetworkConfig) -> Self {
+        Self {
+            cmd_tx,
+            info,
+            config: Arc::new(config),
+            metrics: Arc::new(PublishMetrics::default()),
+            batch_tx: None,
+        }
+    }
+
+    /// A handle with no swarm behind it; its commands arrive on the returned receiver
+    #[cfg(test)]
+    pub(crate) fn detached(config: NetworkConfig) -> (Self, mpsc::Receiver<NetworkCommand>) {
+        let (cmd_tx, cmd_rx) = mpsc::channel(config.command_queue_capacity.max(1));
+        (Self::new(cmd_tx, PeerInfo::new("detached".into()), config), cmd_rx)
+    }
+
+    /// Start the background task that coalesces heartbeats into batches, if
+    /// batching is configured. Must be called from within a tokio runtime.
+    fn with_heartbeat_batching(mut self) -> Self {
+        if let Some(window) = self.config.heartbeat_batch_window {
+            let (batch_tx, batch_rx) = mpsc::channel(self.config.command_queue_capacity.max(1));
+            tokio::spawn(run_heartbeat_batcher(self.clone(), batch_rx, window, self.config.heartbeat_batch_max.max(1)));
+            self.batch_tx = Some(batch_tx);
+        }
+        self
+    }
+
     pub async fn broadcast_heartbeat(&self, hb: &Heartbeat) {
-        let _ = self.cmd_tx.send(NetworkCommand::BroadcastHeartbeat(hb.clone())).await;
+        if let Some(batch_tx) = &self.batch_tx {
+            if batch_tx.try_send(hb.clone()).is_err() {
+                self.metrics.heartbeats_dropped.fetch_add(1, Ordering::Relaxed);
+                debug!("📤 Heartbeat dropped: batch queue full");
+            }
+            return;
+        }
+        let cmd = NetworkCommand::BroadcastHeartbeat(hb.clone());
+        self.enqueue(cmd, self.config.heartbeat_policy, &self.metrics.heartbeats_dropped).await;
+    }
+
+    /// Send a batch of heartbeats to the swarm task; a dropped batch counts every heartbeat in it
+    async fn broadcast_heartbeat_batch(&self, batch: Vec<Heartbeat>) {
+        let count = batch.len() as u64;
+        let cmd = NetworkCommand::BroadcastHeartbeatBatch(batch);
+        if !self.enqueue(cmd, self.config.heartbeat_policy, &self.metrics.heartbeats_dropped).await {
+            // enqueue counted the batch once
+            self.metrics.heartbeats_dropped.fetch_add(count - 1, Ordering::Relaxed);
+        }
     }
 
     pub async fn broadcast_block(&self, block: &PulseBlock) {
-        let _ = self.cmd_tx.send(NetworkCommand::BroadcastBlock(block.clone())).await;
+        let cmd = NetworkCommand::BroadcastBlock(block.clone());
+        if !self.enqueue(cmd, self.config.block_policy, &self.metrics.blocks_dropped).await {
+            warn!("📤 Block #{} dropped: network command queue full", block.index);
+        }
     }
 
-    pub async fn broadcast_chain_sync_request(&self, req: &ChainSyncRequest) {
-        let _ = self.cmd_tx.send(NetworkCommand::BroadcastChainSyncRequest(req.clone())).await;
+    /// Request blocks from `peer` (a peer ID), or from every connected peer if `None`.
+    /// Responses arrive as [`NetworkMessage::ChainSyncResponse`].
+    pub async fn request_chain_sync(&self, peer: Option<&str>, req: &ChainSyncRequest) {
+        let peer = match peer.map(str::parse::<PeerId>).transpose() {
+            Ok(peer) => peer,
+            Err(e) => {
+                warn!("📤 Chain sync request skipped: invalid peer ID: {}", e);
+                return;
+            }
+        };
+        let cmd = NetworkCommand::RequestChainSync(peer, req.clone());
+        self.enqueue(cmd, self.config.chain_sync_policy, &self.metrics.chain_sync_dropped).await;
     }
 
-    pub async fn broadcast_chain_sync_response(&self, resp: &ChainSyncResponse) {
-        let _ = self.cmd_tx.send(NetworkCommand::BroadcastChainSyncResponse(resp.clone())).await;
+    /// Answer the inbound chain sync request `request_id`; only the requester receives it
+    pub async fn respond_chain_sync(&self, request_id: u64, resp: ChainSyncResponse) {
+        let cmd = NetworkCommand::RespondChainSync(request_id, resp);
+        self.enqueue(cmd, self.config.chain_sync_policy, &self.metrics.chain_sync_dropped).await;
     }
 
-    pub async fn dial_peer(&self, addr: &str) {
-        let _ = self.cmd_tx.send(NetworkCommand::DialPeer(addr.to_string())).await;
+    /// Dial a peer. The returned receiver resolves once the connection is
+    /// established, or with the reason it wasn't (an unreachable address fails
+    /// after `NetworkConfig::dial_timeout`). It errors if the swarm task is gone.
+    pub async fn dial_peer(&self, addr: &str) -> oneshot::Receiver<DialResult> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let _ = self.cmd_tx.send(NetworkCommand::DialPeer(addr.to_string(), reply_tx)).await;
+        reply_rx
+    }
+
+    /// Stop the swarm task and wait for its event loop to exit. Later commands
+    /// from any handle are dropped (the channel is closed), and the incoming
+    /// message receiver returned by [`start`] yields `None`.
+    pub async fn shutdown(&self) {
+        let (ack_tx, ack_rx) = oneshot::channel();
+        if self.cmd_tx.send(NetworkCommand::Shutdown(ack_tx)).await.is_ok() {
+            let _ = ack_rx.await;
+        }
+    }
+
+    /// Whether the swarm task has exited (shut down or crashed), so commands go nowhere
+    pub fn is_closed(&self) -> bool {
+        self.cmd_tx.is_closed()
+    }
+
+    /// Enqueue outcomes since startup
+    pub fn publish_stats(&self) -> PublishStats {
+        PublishStats {
+            heartbeats_dropped: self.metrics.heartbeats_dropped.load(Ordering::Relaxed),
+            blocks_dropped: self.metrics.blocks_dropped.load(Ordering::Relaxed),
+            chain_sync_dropped: self.metrics.chain_sync_dropped.load(Ordering::Relaxed),
+            backpressure_waits: self.metrics.backpressure_waits.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Send a command to the swarm task according to the policy.
+    /// Returns false (and counts a drop) if the command didn't make it into the queue.
+    async fn enqueue(&self, cmd: NetworkCommand, policy: EnqueuePolicy, dropped: &AtomicU64) -> bool {
+        let sent = match self.cmd_tx.try_send(cmd) {
+            Ok(()) => true,
+            Err(TrySendError::Closed(_)) => false,
+            Err(TrySendError::Full(cmd)) => match policy {
+                EnqueuePolicy::Drop => false,
+                EnqueuePolicy::Backpressure(timeout) => {
+                    self.metrics.backpressure_waits.fetch_add(1, Ordering::Relaxed);
+                    match self.cmd_tx.send_timeout(cmd, timeout).await {
+                        Ok(()) => true,
+                        Err(SendTimeoutError::Timeout(_)) | Err(SendTimeoutError::Closed(_)) => false,
+                    }
+                }
+            },
+        };
+        if !sent {
+            dropped.fetch_add(1, Ordering::Relaxed);
+            debug!("📤 Network command dropped (queue full or closed)");
+        }
+        sent
+    }
+}
+
+/// Collect heartbeats from `batch_rx` and hand them to the swarm task in
+/// batches: a batch closes `window` after its first heartbeat, or as soon as
+/// it holds `max` heartbeats. Exits once every batching handle is dropped.
+async fn run_heartbeat_batcher(
+    handle: NetworkHandle,
+    mut batch_rx: mpsc::Receiver<Heartbeat>,
+    window: Duration,
+    max: usize,
+) {
+    while let Some(first) = batch_rx.recv().await {
+        let mut batch = vec![first];
+        let deadline = tokio::time::sleep(window);
+        tokio::pin!(deadline);
+        while batch.len() < max {
+            tokio::select! {
+                _ = &mut deadline => break,
+                hb = batch_rx.recv() => match hb {
+                    Some(hb) => batch.push(hb),
+                    None => break,
+                },
+            }
+        }
+        handle.broadcast_heartbeat_batch(batch).await;
     }
 }
 
@@ -123,17 +665,35 @@ impl NetworkHandle {
 #[derive(NetworkBehaviour)]
 struct PulseBehaviour {
     gossipsub: gossipsub::Behaviour,
-    mdns: mdns::tokio::Behaviour,
+    mdns: Toggle<mdns::tokio::Behaviour>,
+    kademlia: kad::Behaviour<kad::store::MemoryStore>,
+    identify: identify::Behaviour,
+    chain_sync: request_response::Behaviour<chain_sync::ChainSyncCodec>,
 }
 
 /// Start the P2P network. Returns a handle for other tasks to use, 
 /// and the receiver for incoming messages from peers.
 /// The network runs in a background task — caller does NOT need to poll it.
+/// Uses a fresh identity each time; see [`start_with_identity`] for a stable peer ID.
 pub async fn start(
     port: u16,
+    config: NetworkConfig,
+) -> anyhow::Result<(NetworkHandle, mpsc::Receiver<NetworkMessage>)> {
+    start_with_identity(port, config, None).await
+}
+
+/// Start the P2P network with the ed25519 identity stored at `identity_path`,
+/// generating and saving one there if it doesn't exist yet (so the peer ID
+/// survives restarts). `None` uses an ephemeral identity.
+pub async fn start_with_identity(
+    port: u16,
+    config: NetworkConfig,
+    identity_path: Option<&Path>,
 ) -> anyhow::Result<(NetworkHandle, mpsc::Receiver<NetworkMessage>)> {
-    // Generate identity
-    let local_key = libp2p::identity::Keypair::generate_ed25519();
+    let local_key = match identity_path {
+        Some(path) => load_or_generate_identity(path)?,
+        None => libp2p::identity::Keypair::generate_ed25519(),
+    };
     let local_peer_id = PeerId::from(local_key.public());
     info!("🔑 Local peer ID: {}", local_peer_id);
 
@@ -162,12 +722,38 @@ pub async fn start(
     ).map_err(|e| anyhow::anyhow!("Gossipsub error: {}", e))?;
 
     // Create mDNS for local peer discovery
-    let mdns = mdns::tokio::Behaviour::new(
-        mdns::Config::default(),
+    let mdns = if config.mdns {
+        Some(mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)?)
+    } else {
+        None
+    };
+
+    // Create Kademlia for peer discovery across the internet. Server mode so
+    // other nodes can query us without an externally confirmed address.
+    let mut kad_config = kad::Config::default();
+    kad_config.set_protocol_names(vec![KAD_PROTOCOL]);
+    let mut kademlia = kad::Behaviour::with_config(
         local_peer_id,
-    )?;
+        kad::store::MemoryStore::new(local_peer_id),
+        kad_config,
+    );
+    kademlia.set_mode(Some(kad::Mode::Server));
 
-    let behaviour = PulseBehaviour { gossipsub, mdns };
+    // Identify tells us the listen addresses of inbound peers, which the DHT
+    // needs to hand them out to others
+    let identify = identify::Behaviour::new(identify::Config::new(
+        IDENTIFY_PROTOCOL_VERSION.to_string(),
+        local_key.public(),
+    ));
+
+    // Point-to-point chain sync
+    let chain_sync = request_response::Behaviour::with_codec(
+        chain_sync::ChainSyncCodec::new(config.wire.clone()),
+        [(chain_sync::CHAIN_SYNC_PROTOCOL, ProtocolSupport::Full)],
+        request_response::Config::default().with_request_timeout(config.chain_sync_timeout),
+    );
+
+    let behaviour = PulseBehaviour { gossipsub, mdns: mdns.into(), kademlia, identify, chain_sync };
 
     let mut swarm = Swarm::new(
         transport,
@@ -182,50 +768,192 @@ pub async fn start(
 
     // Subscribe to topics
     let heartbeat_topic = IdentTopic::new(HEARTBEAT_TOPIC);
+    let heartbeat_batch_topic = IdentTopic::new(HEARTBEAT_BATCH_TOPIC);
     let block_topic = IdentTopic::new(BLOCK_TOPIC);
-    let chain_sync_topic = IdentTopic::new(CHAIN_SYNC_TOPIC);
     swarm.behaviour_mut().gossipsub.subscribe(&heartbeat_topic)?;
+    swarm.behaviour_mut().gossipsub.subscribe(&heartbeat_batch_topic)?;
     swarm.behaviour_mut().gossipsub.subscribe(&block_topic)?;
-    swarm.behaviour_mut().gossipsub.subscribe(&chain_sync_topic)?;
     info!("📡 Subscribed to gossip topics");
 
     // Channels
-    let (cmd_tx, cmd_rx) = mpsc::channel::<NetworkCommand>(256);
+    let (cmd_tx, cmd_rx) = mpsc::channel::<NetworkCommand>(config.command_queue_capacity.max(1));
     let (msg_tx, msg_rx) = mpsc::channel::<NetworkMessage>(256);
 
     let peer_info = PeerInfo::new(local_peer_id.to_string());
-    let handle = NetworkHandle {
-        cmd_tx,
-        info: peer_info.clone(),
-    };
+    let handle = NetworkHandle::new(cmd_tx, peer_info.clone(), config.clone()).with_heartbeat_batching();
 
     // Spawn the event loop as a background task
     tokio::spawn(run_event_loop(
         swarm,
-        heartbeat_topic,
-        block_topic,
-        chain_sync_topic,
+        GossipTopics { heartbeat: heartbeat_topic, heartbeat_batch: heartbeat_batch_topic, block: block_topic },
         cmd_rx,
         msg_tx,
         peer_info,
+        config,
     ));
 
     Ok((handle, msg_rx))
 }
 
+/// Load a protobuf-encoded libp2p keypair from `path`, or generate an ed25519
+/// one and write it there atomically (temp file + rename, owner-only on unix)
+pub fn load_or_generate_identity(path: &Path) -> anyhow::Result<libp2p::identity::Keypair> {
+    if path.exists() {
+        let bytes = std::fs::read(path)?;
+        let key = libp2p::identity::Keypair::from_protobuf_encoding(&bytes)
+            .map_err(|e| anyhow::anyhow!("Invalid identity key at {}: {}", path.display(), e))?;
+        info!("🔑 Loaded P2P identity from {}", path.display());
+        return Ok(key);
+    }
+
+    let key = libp2p::identity::Keypair::generate_ed25519();
+    let bytes = key.to_protobuf_encoding()
+        .map_err(|e| anyhow::anyhow!("Failed to encode identity key: {}", e))?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)?;
+    info!("🔑 Generated new P2P identity at {}", path.display());
+    Ok(key)
+}
+
+/// Peer ID from the trailing `/p2p/<peer id>` component of a multiaddr
+fn peer_id_of(addr: &Multiaddr) -> Option<PeerId> {
+    match addr.iter().last() {
+        Some(Protocol::P2p(peer_id)) => Some(peer_id),
+        _ => None,
+    }
+}
+
+/// Add the bootstrap nodes to the DHT routing table, dial them, and start a
+/// Kademlia bootstrap. Returns the dialed addresses (without peer IDs).
+fn bootstrap_dht(swarm: &mut Swarm<PulseBehaviour>, bootstrap_peers: &[String]) -> Vec<Multiaddr> {
+    let mut dialed = Vec::new();
+    for addr in bootstrap_peers {
+        let multiaddr = match addr.parse::<Multiaddr>() {
+            Ok(multiaddr) => multiaddr,
+            Err(e) => {
+                error!("❌ Invalid bootstrap multiaddr '{}': {}", addr, e);
+                continue;
+            }
+        };
+        let Some(peer_id) = peer_id_of(&multiaddr) else {
+            error!("❌ Bootstrap address '{}' has no /p2p/<peer id>", addr);
+            continue;
+        };
+        swarm.behaviour_mut().kademlia.add_address(&peer_id, multiaddr.clone());
+        info!("📞 Dialing bootstrap node {} at {}", peer_id, multiaddr);
+        if let Err(e) = swarm.dial(multiaddr.clone()) {
+            error!("❌ Failed to dial bootstrap node: {}", e);
+        }
+        dialed.push(without_peer_id(&multiaddr));
+    }
+    if !dialed.is_empty() {
+        if let Err(e) = swarm.behaviour_mut().kademlia.bootstrap() {
+            warn!("DHT bootstrap failed: {}", e);
+        }
+    }
+    dialed
+}
+
+/// Strip any `/p2p/<peer id>` component so dialed and connected addresses compare equal
+fn without_peer_id(addr: &Multiaddr) -> Multiaddr {
+    addr.iter().filter(|p| !matches!(p, Protocol::P2p(_))).collect()
+}
+
+/// Subscribed gossip topics
+struct GossipTopics {
+    heartbeat: IdentTopic,
+    heartbeat_batch: IdentTopic,
+    block: IdentTopic,
+}
+
 /// The network event loop — runs forever in its own task.
 /// Owns the swarm exclusively (no Mutex needed).
+/// Wall-clock time in milliseconds, for timestamps shown to operators
+fn unix_time_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Dock a misbehaving peer's score, disconnecting it if that gets it banned
+fn penalize(swarm: &mut Swarm<PulseBehaviour>, scores: &mut PeerScores, config: &NetworkConfig, peer: PeerId, penalty: i32) {
+    if scores.penalize(peer, penalty, config.ban_threshold, Instant::now(), config.ban_duration) {
+        warn!("🚫 Banning peer {} for {}s (score {})", peer, config.ban_duration.as_secs(), scores.score(&peer));
+        let _ = swarm.disconnect_peer_id(peer);
+    }
+}
+
 async fn run_event_loop(
     mut swarm: Swarm<PulseBehaviour>,
-    heartbeat_topic: IdentTopic,
-    block_topic: IdentTopic,
-    chain_sync_topic: IdentTopic,
+    topics: GossipTopics,
     mut cmd_rx: mpsc::Receiver<NetworkCommand>,
     msg_tx: mpsc::Sender<NetworkMessage>,
     peer_info: PeerInfo,
+    config: NetworkConfig,
 ) {
+    let mut activity = PeerActivity::default();
+    let mut seed_addrs: HashSet<Multiaddr> = HashSet::new();
+    let mut idle_sweep = tokio::time::interval(IDLE_SWEEP_INTERVAL);
+    let mut peer_refresh = tokio::time::interval(PEER_INFO_REFRESH_INTERVAL);
+    let mut pending_dials = PendingDials::default();
+    let mut dial_sweep = tokio::time::interval(DIAL_SWEEP_INTERVAL);
+    let mut seen = SeenCache::new(config.seen_cache_size);
+    let mut scores = PeerScores::default();
+    // Inbound chain sync requests waiting for the node's answer
+    let mut sync_responders: HashMap<u64, request_response::ResponseChannel<ChainSyncResponse>> = HashMap::new();
+    let mut next_sync_id: u64 = 0;
+    let mut kad_bootstrap = tokio::time::interval(KAD_BOOTSTRAP_INTERVAL);
+    kad_bootstrap.tick().await; // first tick is immediate; we bootstrap below
+
+    // Bootstrap nodes are seeds too: exempt from idle eviction
+    seed_addrs.extend(bootstrap_dht(&mut swarm, &config.bootstrap_peers));
+
     loop {
         tokio::select! {
+            // Evict peers that connected but never (or no longer) gossip
+            _ = idle_sweep.tick(), if config.idle_peer_timeout.is_some() => {
+                let timeout = config.idle_peer_timeout.unwrap_or_default();
+                for peer_id in activity.idle_peers(Instant::now(), timeout) {
+                    info!("💤 Disconnecting idle peer {} (silent for over {}s)", peer_id, timeout.as_secs());
+                    let _ = swarm.disconnect_peer_id(peer_id);
+                }
+            }
+
+            // Give up on explicit dials that never connected
+            _ = dial_sweep.tick(), if !pending_dials.is_empty() => {
+                for (connection_id, reply) in pending_dials.expired(Instant::now()) {
+                    swarm.close_connection(connection_id);
+                    let _ = reply.send(Err(format!("timed out after {}s", config.dial_timeout.as_secs())));
+                }
+            }
+
+            // Keep the shared last-seen times current between connection changes
+            _ = peer_refresh.tick() => {
+                peer_info.update(swarm.connected_peers().copied().collect(), &scores, &activity).await;
+            }
+
+            // Refresh the DHT routing table
+            _ = kad_bootstrap.tick(), if !config.bootstrap_peers.is_empty() => {
+                if let Err(e) = swarm.behaviour_mut().kademlia.bootstrap() {
+                    debug!("DHT bootstrap skipped: {}", e);
+                }
+            }
+
+
             // Process incoming swarm events
             event = swarm.select_next_some() => {
                 match event {
@@ -235,6 +963,7 @@ async fn run_event_loop(
                                 for (peer_id, addr) in peers {
                                     info!("🔍 Discovered peer: {} at {}", peer_id, addr);
                                     swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
+                                    swarm.behaviour_mut().kademlia.add_address(&peer_id, addr);
                                 }
                             }
                             mdns::Event::Expired(peers) => {
@@ -244,52 +973,166 @@ async fn run_event_loop(
                             }
                         }
                     }
-                    SwarmEvent::Behaviour(PulseBehaviourEvent::Gossipsub(gs_event)) => {
-                        if let gossipsub::Event::Message { message, .. } = gs_event {
-                            let topic = message.topic.as_str();
-
-                            if topic == HEARTBEAT_TOPIC {
-                                if let Ok(hb) = serde_json::from_slice::<Heartbeat>(&message.data) {
-                                    let _ = msg_tx.send(NetworkMessage::Heartbeat(hb)).await;
+                    SwarmEvent::Behaviour(PulseBehaviourEvent::ChainSync(event)) => {
+                        match event {
+                            request_response::Event::Message { peer, message } => {
+                                activity.touch(peer, Instant::now());
+                                match message {
+                                    request_response::Message::Request { request, channel, .. } => {
+                                        next_sync_id += 1;
+                                        sync_responders.insert(next_sync_id, channel);
+                                        let _ = msg_tx.send(NetworkMessage::ChainSyncRequest {
+                                            request_id: next_sync_id,
+                                            request,
+                                        }).await;
+                                    }
+                                    request_response::Message::Response { response, .. } => {
+                                        let from = peer.to_string();
+                                        let _ = msg_tx.send(NetworkMessage::ChainSyncResponse { response, from }).await;
+                                    }
+                                }
+                            }
+                            request_response::Event::OutboundFailure { peer, error, .. } => {
+                                debug!("Chain sync request to {} failed: {}", peer, error);
+                            }
+                            request_response::Event::InboundFailure { peer, error, .. } => {
+                                debug!("Chain sync response to {} failed: {}", peer, error);
+                            }
+                            request_response::Event::ResponseSent { .. } => {}
+                        }
+                    }
+                    SwarmEvent::Behaviour(PulseBehaviourEvent::Identify(identify::Event::Received { peer_id, info }))
+                        if info.protocols.contains(&KAD_PROTOCOL) =>
+                    {
+                        for addr in info.listen_addrs {
+                            swarm.behaviour_mut().kademlia.add_address(&peer_id, addr);
+                        }
+                    }
+                    SwarmEvent::Behaviour(PulseBehaviourEvent::Kademlia(kad_event)) => {
+                        match kad_event {
+                            kad::Event::RoutingUpdated { peer, is_new_peer: true, .. } => {
+                                info!("🌐 DHT discovered peer: {}", peer);
+                                swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer);
+                            }
+                            kad::Event::OutboundQueryProgressed { result: kad::QueryResult::Bootstrap(result), step, .. } => {
+                                match result {
+                                    Ok(ok) if step.last => debug!("🌐 DHT bootstrap done ({} peers remaining)", ok.num_remaining),
+                                    Ok(_) => {}
+                                    Err(e) => debug!("DHT bootstrap query failed: {:?}", e),
                                 }
-                            } else if topic == BLOCK_TOPIC {
-                                if let Ok(block) = serde_json::from_slice::<PulseBlock>(&message.data) {
-                                    let _ = msg_tx.send(NetworkMessage::Block(block)).await;
+                            }
+                            _ => {}
+                        }
+                    }
+                    SwarmEvent::Behaviour(PulseBehaviourEvent::Gossipsub(gossipsub::Event::Message { propagation_source, message, .. })) => {
+                        activity.touch(propagation_source, Instant::now());
+                        // Blame the signed author when known, not an honest relay
+                        let author = message.source.unwrap_or(propagation_source);
+                        if scores.is_banned(&author, Instant::now()) {
+                            continue;
+                        }
+                        let topic = message.topic.as_str();
+
+                        if topic == HEARTBEAT_TOPIC || topic == HEARTBEAT_BATCH_TOPIC {
+                            let heartbeats = if topic == HEARTBEAT_TOPIC {
+                                wire::decode::<Heartbeat>(&message.data).map(|hb| vec![hb])
+                            } else {
+                                wire::decode::<Vec<Heartbeat>>(&message.data)
+                            };
+                            let heartbeats = match heartbeats {
+                                Ok(heartbeats) => heartbeats,
+                                Err(e) => {
+                                    debug!("📨 Undecodable gossip payload on {} from {}: {}", topic, author, e);
+                                    penalize(&mut swarm, &mut scores, &config, author, UNDECODABLE_PENALTY);
+                                    peer_info.update(swarm.connected_peers().copied().collect(), &scores, &activity).await;
+                                    continue;
                                 }
-                            } else if topic == CHAIN_SYNC_TOPIC {
-                                // Discriminate request vs response: try request first (smaller)
-                                if let Ok(req) = serde_json::from_slice::<ChainSyncRequest>(&message.data) {
-                                    // Make sure it's actually a request (has from_height, no blocks field)
-                                    if serde_json::from_slice::<ChainSyncResponse>(&message.data).is_err() {
-                                        let _ = msg_tx.send(NetworkMessage::ChainSyncRequest(req)).await;
-                                    } else {
-                                        // Both parsed — it's a response (has blocks field)
-                                        if let Ok(resp) = serde_json::from_slice::<ChainSyncResponse>(&message.data) {
-                                            let _ = msg_tx.send(NetworkMessage::ChainSyncResponse(resp)).await;
-                                        }
-                                    }
-                                } else if let Ok(resp) = serde_json::from_slice::<ChainSyncResponse>(&message.data) {
-                                    let _ = msg_tx.send(NetworkMessage::ChainSyncResponse(resp)).await;
+                            };
+                            // Unpack batches; the author is penalized once per message, not per bad heartbeat
+                            let mut invalid = false;
+                            for mut hb in heartbeats {
+                                if hb.resolve_pubkey().is_err()
+                                    || !verify_signature(&hb.device_pubkey, &hb.signable_bytes(), &hb.signature).unwrap_or(false)
+                                {
+                                    debug!("📨 Heartbeat with invalid signature from {}", author);
+                                    invalid = true;
+                                } else if seen.insert(hash_sha256(&hb.signable_bytes())) {
+                                    let _ = msg_tx.send(NetworkMessage::Heartbeat(hb)).await;
                                 } else {
-                                    warn!("📨 Unrecognized chain sync message");
+                                    debug!("📨 Duplicate heartbeat from {} dropped", propagation_source);
                                 }
                             }
+                            if invalid {
+                                penalize(&mut swarm, &mut scores, &config, author, INVALID_MESSAGE_PENALTY);
+                                peer_info.update(swarm.connected_peers().copied().collect(), &scores, &activity).await;
+                            }
+                        } else if topic == BLOCK_TOPIC {
+                            let block = match wire::decode::<PulseBlock>(&message.data) {
+                                Ok(block) => block,
+                                Err(e) => {
+                                    debug!("📨 Undecodable gossip payload on {} from {}: {}", topic, author, e);
+                                    penalize(&mut swarm, &mut scores, &config, author, UNDECODABLE_PENALTY);
+                                    peer_info.update(swarm.connected_peers().copied().collect(), &scores, &activity).await;
+                                    continue;
+                                }
+                            };
+                            if block.block_hash != block.compute_hash() {
+                                debug!("📨 Block #{} with invalid hash from {}", block.index, author);
+                                penalize(&mut swarm, &mut scores, &config, author, INVALID_MESSAGE_PENALTY);
+                                peer_info.update(swarm.connected_peers().copied().collect(), &scores, &activity).await;
+                            } else if seen.insert(block.block_hash.clone()) {
+                                let from = propagation_source.to_string();
+                                let _ = msg_tx.send(NetworkMessage::Block { block, from }).await;
+                            } else {
+                                debug!("📨 Duplicate block #{} from {} dropped", block.index, propagation_source);
+                            }
                         }
                     }
                     SwarmEvent::NewListenAddr { address, .. } => {
                         info!("📡 Listening on {}", address);
                     }
-                    SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                    SwarmEvent::ConnectionEstablished { peer_id, connection_id, endpoint, num_established, .. } => {
+                        if scores.is_banned(&peer_id, Instant::now()) {
+                            debug!("🚫 Refusing banned peer {}", peer_id);
+                            pending_dials.resolve(&connection_id, Err(format!("peer {} is banned", peer_id)));
+                            let _ = swarm.disconnect_peer_id(peer_id);
+                            continue;
+                        }
+                        pending_dials.resolve(&connection_id, Ok(()));
+                        if num_established.get() > 1 {
+                            continue;
+                        }
                         info!("🤝 Connected to peer: {}", peer_id);
-                        let peers: Vec<String> = swarm.connected_peers().map(|p| p.to_string()).collect();
-                        peer_info.peer_count.store(peers.len(), Ordering::Relaxed);
-                        *peer_info.peer_list.write().await = peers;
+                        let direction = if endpoint.is_dialer() { PeerDirection::Outbound } else { PeerDirection::Inbound };
+                        activity.connected(peer_id, Instant::now(), direction);
+                        if endpoint.is_dialer() && seed_addrs.contains(&without_peer_id(endpoint.get_remote_address())) {
+                            activity.exempt(peer_id);
+                        }
+                        // Over the cap: drop the newest non-seed peer (usually this one)
+                        if config.max_peers.is_some_and(|max| swarm.connected_peers().count() > max) {
+                            if let Some(victim) = activity.newest_evictable() {
+                                info!("🚪 Peer limit reached, disconnecting {}", victim);
+                                let _ = swarm.disconnect_peer_id(victim);
+                                if victim == peer_id {
+                                    activity.remove(&peer_id);
+                                    continue;
+                                }
+                            }
+                        }
+                        let peer_count = peer_info.update(swarm.connected_peers().copied().collect(), &scores, &activity).await;
+                        let _ = msg_tx.send(NetworkMessage::PeerConnected { peer_id: peer_id.to_string(), peer_count }).await;
                     }
-                    SwarmEvent::ConnectionClosed { peer_id, .. } => {
-                        info!("👋 Disconnected from peer: {}", peer_id);
-                        let peers: Vec<String> = swarm.connected_peers().map(|p| p.to_string()).collect();
-                        peer_info.peer_count.store(peers.len(), Ordering::Relaxed);
-                        *peer_info.peer_list.write().await = peers;
+                    SwarmEvent::OutgoingConnectionError { connection_id, peer_id, error } => {
+                        debug!("Outgoing connection to {:?} failed: {}", peer_id, error);
+                        pending_dials.resolve(&connection_id, Err(error.to_string()));
+                    }
+                    SwarmEvent::ConnectionClosed { peer_id, num_established, .. } => {
+                        let peer_count = peer_info.update(swarm.connected_peers().copied().collect(), &scores, &activity).await;
+                        // Peers we refused or evicted were never announced as connected
+                        if num_established == 0 && activity.remove(&peer_id) {
+                            info!("👋 Disconnected from peer: {}", peer_id);
+                            let _ = msg_tx.send(NetworkMessage::PeerDisconnected { peer_id: peer_id.to_string(), peer_count }).await;
+                        }
                     }
                     _ => {}
                 }
@@ -299,55 +1142,86 @@ async fn run_event_loop(
             cmd = cmd_rx.recv() => {
                 match cmd {
                     Some(NetworkCommand::BroadcastHeartbeat(hb)) => {
-                        if let Ok(data) = serde_json::to_vec(&hb) {
+                        if let Ok(data) = wire::encode(&hb, &config.wire) {
                             if let Err(e) = swarm.behaviour_mut().gossipsub.publish(
-                                heartbeat_topic.clone(), data
+                                topics.heartbeat.clone(), data
                             ) {
                                 debug!("P2P heartbeat broadcast skipped: {}", e);
                             }
                         }
                     }
+                    Some(NetworkCommand::BroadcastHeartbeatBatch(batch)) => {
+                        if let Ok(data) = wire::encode(&batch, &config.wire) {
+                            if let Err(e) = swarm.behaviour_mut().gossipsub.publish(
+                                topics.heartbeat_batch.clone(), data
+                            ) {
+                                debug!("P2P heartbeat batch ({} heartbeats) broadcast skipped: {}", batch.len(), e);
+                            }
+                        }
+                    }
                     Some(NetworkCommand::BroadcastBlock(block)) => {
-                        if let Ok(data) = serde_json::to_vec(&block) {
+                        if let Ok(data) = wire::encode(&block, &config.wire) {
                             match swarm.behaviour_mut().gossipsub.publish(
-                                block_topic.clone(), data
+                                topics.block.clone(), data
                             ) {
                                 Ok(_) => info!("📤 Broadcast block #{}", block.index),
                                 Err(e) => debug!("P2P block broadcast skipped: {}", e),
                             }
                         }
                     }
-                    Some(NetworkCommand::BroadcastChainSyncRequest(req)) => {
-                        if let Ok(data) = serde_json::to_vec(&req) {
-                            match swarm.behaviour_mut().gossipsub.publish(
-                                chain_sync_topic.clone(), data
-                            ) {
-                                Ok(_) => info!("📤 Chain sync request from height {}", req.from_height),
-                                Err(e) => warn!("Chain sync request failed: {}", e),
-                            }
+                    Some(NetworkCommand::RequestChainSync(peer, req)) => {
+                        let targets: Vec<PeerId> = match peer {
+                            Some(peer) => vec![peer],
+                            None => swarm.connected_peers().copied().collect(),
+                        };
+                        if targets.is_empty() {
+                            debug!("Chain sync request skipped: no connected peers");
+                        }
+                        for peer in targets {
+                            swarm.behaviour_mut().chain_sync.send_request(&peer, req.clone());
+                            info!("📤 Chain sync request to {} from height {}", peer, req.from_height);
                         }
                     }
-                    Some(NetworkCommand::BroadcastChainSyncResponse(resp)) => {
-                        if let Ok(data) = serde_json::to_vec(&resp) {
-                            match swarm.behaviour_mut().gossipsub.publish(
-                                chain_sync_topic.clone(), data
-                            ) {
-                                Ok(_) => info!("📤 Chain sync response ({} blocks)", resp.blocks.len()),
-                                Err(e) => warn!("Chain sync response failed: {}", e),
+                    Some(NetworkCommand::RespondChainSync(request_id, resp)) => {
+                        match sync_responders.remove(&request_id) {
+                            Some(channel) => {
+                                let count = resp.blocks.len();
+                                match swarm.behaviour_mut().chain_sync.send_response(channel, resp) {
+                                    Ok(()) => info!("📤 Chain sync response ({} blocks)", count),
+                                    Err(_) => debug!("Chain sync response dropped: requester gone"),
+                                }
                             }
+                            None => debug!("Chain sync response for unknown request {}", request_id),
                         }
                     }
-                    Some(NetworkCommand::DialPeer(addr)) => {
+                    Some(NetworkCommand::DialPeer(addr, reply)) => {
                         match addr.parse::<Multiaddr>() {
                             Ok(multiaddr) => {
                                 info!("📞 Dialing peer at {}", multiaddr);
-                                if let Err(e) = swarm.dial(multiaddr) {
-                                    error!("❌ Failed to dial peer: {}", e);
+                                seed_addrs.insert(without_peer_id(&multiaddr));
+                                let opts = DialOpts::from(multiaddr);
+                                let connection_id = opts.connection_id();
+                                match swarm.dial(opts) {
+                                    Ok(()) => pending_dials.insert(connection_id, reply, Instant::now() + config.dial_timeout),
+                                    Err(e) => {
+                                        error!("❌ Failed to dial peer: {}", e);
+                                        let _ = reply.send(Err(e.to_string()));
+                                    }
                                 }
                             }
-                            Err(e) => error!("❌ Invalid multiaddr '{}': {}", addr, e),
+                            Err(e) => {
+                                error!("❌ Invalid multiaddr '{}': {}", addr, e);
+                                let _ = reply.send(Err(format!("invalid multiaddr '{}': {}", addr, e)));
+                            }
                         }
                     }
+                    Some(NetworkCommand::Shutdown(ack)) => {
+                        info!("🛑 Shutting down P2P");
+                        cmd_rx.close();
+                        drop(msg_tx);
+                        let _ = ack.send(());
+                        break;
+                    }
                     None => {
                         info!("Network command channel closed, shutting down P2P");
                         break;
@@ -359,3 +1233,450 @@ async fn run_event_loop(
 }
 
 // Peer info is updated inline in the event loop (ConnectionEstablished/Closed events)
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Motion;
+
+    fn test_handle(capacity: usize, block_wait: Duration) -> (NetworkHandle, mpsc::Receiver<NetworkCommand>) {
+        let (cmd_tx, cmd_rx) = mpsc::channel(capacity);
+        let config = NetworkConfig {
+            block_policy: EnqueuePolicy::Backpressure(block_wait),
+            ..Default::default()
+        };
+        (NetworkHandle::new(cmd_tx, PeerInfo::new("test".into()), config), cmd_rx)
+    }
+
+    fn heartbeat() -> Heartbeat {
+        Heartbeat {
+            timestamp: 1,
+            heart_rate: 70,
+            motion: Motion { x: 0.0, y: 0.0, z: 0.0 },
+            temperature: 36.5,
+            device_pubkey: "00".repeat(33),
+            signature: String::new(),
+            signature_version: 0,
+            recoverable: false,
+            metadata: Default::default(),
+        }
+    }
+
+    fn block() -> PulseBlock {
+        PulseBlock {
+            index: 1, timestamp: 0, previous_hash: String::new(),
+            heartbeats: vec![], transactions: vec![], n_live: 0,
+            total_weight: 0.0, security: 0.0, bio_entropy: String::new(),
+            continuity_factors: Default::default(), confidence_factors: Default::default(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(), chain_id: String::new(), block_hash: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_drops_heartbeats_but_backpressures_blocks() {
+        let (handle, mut cmd_rx) = test_handle(1, Duration::from_secs(5));
+        let _dial = handle.dial_peer("/ip4/127.0.0.1/tcp/1").await; // fill the queue
+
+        // Heartbeat: dropped immediately
+        handle.broadcast_heartbeat(&heartbeat()).await;
+        assert_eq!(handle.publish_stats().heartbeats_dropped, 1);
+
+        // Block: waits for room instead of dropping
+        let h = handle.clone();
+        let publish = tokio::spawn(async move { h.broadcast_block(&block()).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!publish.is_finished(), "block enqueue should be waiting for room");
+        assert!(matches!(cmd_rx.recv().await, Some(NetworkCommand::DialPeer(..))));
+        publish.await.unwrap();
+        assert!(matches!(cmd_rx.recv().await, Some(NetworkCommand::BroadcastBlock(_))));
+
+        let stats = handle.publish_stats();
+        assert_eq!(stats.blocks_dropped, 0);
+        assert_eq!(stats.backpressure_waits, 1);
+    }
+
+    #[test]
+    fn test_identity_persists_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("p2p").join("identity.key");
+
+        let first = load_or_generate_identity(&path).unwrap();
+        assert!(path.exists());
+        assert!(!path.with_extension("tmp").exists());
+        let second = load_or_generate_identity(&path).unwrap();
+        assert_eq!(PeerId::from(first.public()), PeerId::from(second.public()));
+
+        std::fs::write(&path, b"garbage").unwrap();
+        assert!(load_or_generate_identity(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_times_out_and_counts_drop() {
+        let (handle, _cmd_rx) = test_handle(1, Duration::from_millis(20));
+        let _dial = handle.dial_peer("/ip4/127.0.0.1/tcp/1").await;
+
+        handle.broadcast_block(&block()).await;
+        let stats = handle.publish_stats();
+        assert_eq!(stats.blocks_dropped, 1);
+        assert_eq!(stats.backpressure_waits, 1);
+    }
+
+    #[test]
+    fn test_idle_peers_evicted_except_seeds() {
+        let start = Instant::now();
+        let timeout = Duration::from_secs(300);
+        let (scanner, chatty, seed) = (PeerId::random(), PeerId::random(), PeerId::random());
+
+        let mut activity = PeerActivity::default();
+        for peer in [scanner, chatty, seed] {
+            activity.touch(peer, start);
+        }
+        activity.exempt(seed);
+        activity.touch(chatty, start + Duration::from_secs(200));
+
+        // Within the timeout nobody is idle
+        assert!(activity.idle_peers(start + timeout, timeout).is_empty());
+
+        // Past it, only the silent non-seed peer goes
+        let later = start + Duration::from_secs(400);
+        assert_eq!(activity.idle_peers(later, timeout), vec![scanner]);
+
+        // Once the chatty peer falls silent too, it's evicted as well; the seed never is
+        let mut idle = activity.idle_peers(start + Duration::from_secs(600), timeout);
+        idle.sort();
+        let mut expected = vec![scanner, chatty];
+        expected.sort();
+        assert_eq!(idle, expected);
+
+        activity.remove(&scanner);
+        assert_eq!(activity.idle_peers(start + Duration::from_secs(600), timeout), vec![chatty]);
+    }
+
+    #[tokio::test]
+    async fn test_peer_details_report_direction_and_times() {
+        let (dialed, dialer) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+        let now_ms = 1_000_000;
+
+        let mut activity = PeerActivity::default();
+        activity.connected(dialed, now - Duration::from_secs(60), PeerDirection::Outbound);
+        activity.connected(dialer, now - Duration::from_secs(30), PeerDirection::Inbound);
+        activity.touch(dialed, now - Duration::from_secs(5));
+
+        let detail = activity.detail(&dialed, -3, now, now_ms).unwrap();
+        assert_eq!(detail.direction, PeerDirection::Outbound);
+        assert_eq!(detail.connected_since, now_ms - 60_000);
+        assert_eq!(detail.last_seen, now_ms - 5_000);
+        assert_eq!(detail.score, -3);
+        assert_eq!(activity.detail(&dialer, 0, now, now_ms).unwrap().direction, PeerDirection::Inbound);
+
+        // The shared view lists only connected peers that are tracked, ids unchanged
+        let info = PeerInfo::new("local".into());
+        info.update(vec![dialed, dialer], &PeerScores::default(), &activity).await;
+        assert_eq!(info.connected_peers().await, vec![dialed.to_string(), dialer.to_string()]);
+        let details = info.peer_details().await;
+        assert_eq!(details.len(), 2);
+        assert_eq!(serde_json::to_value(&details[0]).unwrap()["direction"], "outbound");
+
+        activity.remove(&dialer);
+        assert!(activity.detail(&dialer, 0, now, now_ms).is_none());
+        info.update(vec![dialed], &PeerScores::default(), &activity).await;
+        assert_eq!(info.peer_details().await.len(), 1);
+    }
+
+    #[test]
+    fn test_peer_limit_evicts_newest_non_seed() {
+        let start = Instant::now();
+        let (seed, old, newest_seed) = (PeerId::random(), PeerId::random(), PeerId::random());
+
+        let mut activity = PeerActivity::default();
+        assert_eq!(activity.newest_evictable(), None);
+        activity.connected(seed, start, PeerDirection::Inbound);
+        activity.exempt(seed);
+        activity.connected(old, start + Duration::from_secs(1), PeerDirection::Inbound);
+        assert_eq!(activity.newest_evictable(), Some(old));
+
+        // A seed connecting later doesn't shield the newest ordinary peer
+        activity.connected(newest_seed, start + Duration::from_secs(2), PeerDirection::Inbound);
+        activity.exempt(newest_seed);
+        assert_eq!(activity.newest_evictable(), Some(old));
+
+        let late = PeerId::random();
+        activity.connected(late, start + Duration::from_secs(3), PeerDirection::Inbound);
+        assert_eq!(activity.newest_evictable(), Some(late));
+
+        // Only announced peers report as removed
+        assert!(activity.remove(&late));
+        assert!(!activity.remove(&late));
+        assert_eq!(activity.newest_evictable(), Some(old));
+    }
+
+    #[test]
+    fn test_misbehaving_peer_banned_until_cooldown() {
+        let start = Instant::now();
+        let ban = Duration::from_secs(600);
+        let (bad, good) = (PeerId::random(), PeerId::random());
+        let mut scores = PeerScores::default();
+
+        // Penalties accumulate; reaching the threshold isn't enough, dropping below it is
+        for _ in 0..5 {
+            assert!(!scores.penalize(bad, INVALID_MESSAGE_PENALTY, -10, start, ban));
+        }
+        assert_eq!(scores.score(&bad), -10);
+        assert!(!scores.is_banned(&bad, start));
+        assert!(scores.penalize(bad, UNDECODABLE_PENALTY, -10, start, ban));
+        assert!(scores.is_banned(&bad, start));
+        assert!(scores.is_banned(&bad, start + ban - Duration::from_secs(1)));
+
+        // Further misbehaviour while banned doesn't re-trigger or extend the ban
+        assert!(!scores.penalize(bad, UNDECODABLE_PENALTY, -10, start + ban / 2, ban));
+        assert_eq!(scores.score(&good), 0);
+        assert!(!scores.is_banned(&good, start));
+
+        // After the cooldown the peer is let back in with a clean score
+        assert!(!scores.is_banned(&bad, start + ban));
+        assert_eq!(scores.score(&bad), 0);
+    }
+
+    #[test]
+    fn test_seen_cache_drops_duplicates_and_evicts_least_recent() {
+        let mut seen = SeenCache::new(2);
+        let hb_key = hash_sha256(&heartbeat().signable_bytes());
+        assert!(seen.insert(hb_key.clone()));
+
+        // The same heartbeat re-sent with a different (unsigned) metadata tag
+        let mut tagged = heartbeat();
+        tagged.metadata.insert("fw".into(), "1.2".into());
+        assert!(!seen.insert(hash_sha256(&tagged.signable_bytes())));
+
+        // The heartbeat was just seen again, so the block key is evicted first
+        assert!(seen.insert("block-a".into()));
+        assert!(!seen.insert(hb_key.clone()));
+        assert!(seen.insert("block-b".into()));
+        assert!(!seen.insert(hb_key));
+        assert!(seen.insert("block-a".into()));
+
+        // Disabled: everything passes
+        let mut off = SeenCache::new(0);
+        assert!(off.insert("x".into()));
+        assert!(off.insert("x".into()));
+    }
+
+    #[tokio::test]
+    async fn test_dht_discovers_peers_through_bootstrap_node() {
+        // mDNS off, so the only way C can find B is through A's routing table
+        let config = NetworkConfig { mdns: false, idle_peer_timeout: None, ..Default::default() };
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let (a, _a_rx) = start(port, config.clone()).await.unwrap();
+        let config = NetworkConfig {
+            bootstrap_peers: vec![format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", port, a.info.peer_id)],
+            ..config
+        };
+
+        let (b, _b_rx) = start(0, config.clone()).await.unwrap();
+        let connected = |handle: NetworkHandle, peer: String| async move {
+            for _ in 0..100 {
+                if handle.info.connected_peers().await.contains(&peer) {
+                    return true;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            false
+        };
+        assert!(connected(a.clone(), b.info.peer_id.clone()).await);
+        tokio::time::sleep(Duration::from_millis(500)).await; // let A identify B
+
+        let (c, _c_rx) = start(0, config).await.unwrap();
+        assert!(connected(c.clone(), b.info.peer_id.clone()).await, "C never found B via the DHT");
+
+        for handle in [a, b, c] {
+            handle.shutdown().await;
+        }
+    }
+
+    /// Next chain sync message on `rx` within `wait`, skipping other traffic
+    async fn next_sync_message(rx: &mut mpsc::Receiver<NetworkMessage>, wait: Duration) -> Option<NetworkMessage> {
+        tokio::time::timeout(wait, async {
+            while let Some(msg) = rx.recv().await {
+                if matches!(msg, NetworkMessage::ChainSyncRequest { .. } | NetworkMessage::ChainSyncResponse { .. }) {
+                    return Some(msg);
+                }
+            }
+            None
+        }).await.ok().flatten()
+    }
+
+    #[tokio::test]
+    async fn test_chain_sync_response_goes_only_to_requester() {
+        let config = NetworkConfig { mdns: false, idle_peer_timeout: None, ..Default::default() };
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let (a, mut a_rx) = start(port, config.clone()).await.unwrap();
+        let config = NetworkConfig {
+            bootstrap_peers: vec![format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", port, a.info.peer_id)],
+            ..config
+        };
+        let (b, mut b_rx) = start(0, config.clone()).await.unwrap();
+        let (c, mut c_rx) = start(0, config).await.unwrap();
+        for _ in 0..100 {
+            if a.info.peer_count() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(a.info.peer_count() >= 2);
+        while !b.info.connected_peers().await.contains(&a.info.peer_id) {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        b.request_chain_sync(Some(&a.info.peer_id), &ChainSyncRequest { from_height: 3, max_blocks: 0 }).await;
+        let request_id = match next_sync_message(&mut a_rx, Duration::from_secs(10)).await {
+            Some(NetworkMessage::ChainSyncRequest { request_id, request }) => {
+                assert_eq!(request.from_height, 3);
+                request_id
+            }
+            other => panic!("expected a chain sync request, got {:?}", other),
+        };
+        a.respond_chain_sync(request_id, ChainSyncResponse { blocks: vec![block()], tip_height: 1 }).await;
+
+        match next_sync_message(&mut b_rx, Duration::from_secs(10)).await {
+            Some(NetworkMessage::ChainSyncResponse { response, from }) => {
+                assert_eq!(response.blocks.len(), 1);
+                assert_eq!(from, a.info.peer_id);
+            }
+            other => panic!("expected a chain sync response, got {:?}", other),
+        }
+        // The other peer never sees it
+        assert!(next_sync_message(&mut c_rx, Duration::from_millis(500)).await.is_none());
+
+        for handle in [a, b, c] {
+            handle.shutdown().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dial_reports_outcome() {
+        let config = NetworkConfig { mdns: false, idle_peer_timeout: None, ..Default::default() };
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let (a, _a_rx) = start(port, config.clone()).await.unwrap();
+        let (b, _b_rx) = start(0, config).await.unwrap();
+
+        let dial = b.dial_peer(&format!("/ip4/127.0.0.1/tcp/{}", port)).await;
+        assert_eq!(tokio::time::timeout(Duration::from_secs(10), dial).await.unwrap().unwrap(), Ok(()));
+
+        // Nothing listens on a freshly released port
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let dial = b.dial_peer(&format!("/ip4/127.0.0.1/tcp/{}", closed)).await;
+        assert!(tokio::time::timeout(Duration::from_secs(15), dial).await.unwrap().unwrap().is_err());
+
+        let dial = b.dial_peer("not a multiaddr").await;
+        assert!(dial.await.unwrap().unwrap_err().contains("invalid multiaddr"));
+
+        for handle in [a, b] {
+            handle.shutdown().await;
+        }
+    }
+
+    #[test]
+    fn test_pending_dials_expire_at_deadline() {
+        let start = Instant::now();
+        let mut dials = PendingDials::default();
+        let (fast, slow) = (ConnectionId::new_unchecked(1), ConnectionId::new_unchecked(2));
+        let (fast_tx, mut fast_rx) = oneshot::channel();
+        let (slow_tx, _slow_rx) = oneshot::channel();
+        dials.insert(fast, fast_tx, start + Duration::from_secs(5));
+        dials.insert(slow, slow_tx, start + Duration::from_secs(10));
+
+        assert!(dials.expired(start + Duration::from_secs(4)).is_empty());
+        dials.resolve(&fast, Ok(()));
+        assert_eq!(fast_rx.try_recv().unwrap(), Ok(()));
+
+        let expired = dials.expired(start + Duration::from_secs(10));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].0, slow);
+        assert!(dials.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_coalesced_into_batches() {
+        let (cmd_tx, mut cmd_rx) = mpsc::channel(16);
+        let config = NetworkConfig {
+            heartbeat_batch_window: Some(Duration::from_millis(100)),
+            heartbeat_batch_max: 3,
+            ..Default::default()
+        };
+        let handle = NetworkHandle::new(cmd_tx, PeerInfo::new("test".into()), config).with_heartbeat_batching();
+
+        for timestamp in 1..=5 {
+            handle.broadcast_heartbeat(&Heartbeat { timestamp, ..heartbeat() }).await;
+        }
+
+        // A full batch goes out at once, the remainder when the window closes
+        let mut sizes = Vec::new();
+        while sizes.iter().sum::<usize>() < 5 {
+            match tokio::time::timeout(Duration::from_secs(2), cmd_rx.recv()).await {
+                Ok(Some(NetworkCommand::BroadcastHeartbeatBatch(batch))) => sizes.push(batch.len()),
+                other => panic!("expected a heartbeat batch, got {:?}", other),
+            }
+        }
+        assert_eq!(sizes, vec![3, 2]);
+        assert_eq!(handle.publish_stats().heartbeats_dropped, 0);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_batch_unpacked_by_receiver() {
+        let config = NetworkConfig { mdns: false, idle_peer_timeout: None, ..Default::default() };
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let (a, _a_rx) = start(port, config.clone()).await.unwrap();
+        let config = NetworkConfig {
+            bootstrap_peers: vec![format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", port, a.info.peer_id)],
+            ..config
+        };
+        let (b, mut b_rx) = start(0, config).await.unwrap();
+
+        let device = crate::crypto::Keypair::generate();
+        let heartbeats: Vec<Heartbeat> = (1..=3).map(|timestamp| {
+            let mut hb = Heartbeat { timestamp, device_pubkey: device.public_key_hex(), ..heartbeat() };
+            hb.signature = device.sign(&hb.signable_bytes());
+            hb
+        }).collect();
+
+        // Republish until the gossip mesh forms; duplicates are dropped by the seen-cache
+        let mut received = HashSet::new();
+        let deadline = Instant::now() + Duration::from_secs(15);
+        while received.len() < heartbeats.len() && Instant::now() < deadline {
+            a.broadcast_heartbeat_batch(heartbeats.clone()).await;
+            let wait = tokio::time::sleep(Duration::from_millis(500));
+            tokio::pin!(wait);
+            loop {
+                tokio::select! {
+                    _ = &mut wait => break,
+                    Some(msg) = b_rx.recv() => if let NetworkMessage::Heartbeat(hb) = msg {
+                        assert!(received.insert(hb.timestamp), "heartbeat {} delivered twice", hb.timestamp);
+                    },
+                }
+            }
+        }
+        assert_eq!(received, HashSet::from([1, 2, 3]));
+
+        for handle in [a, b] {
+            handle.shutdown().await;
+        }
+    }
+
+    #[test]
+    fn test_bootstrap_address_needs_peer_id() {
+        let peer = PeerId::random();
+        let addr: Multiaddr = format!("/ip4/10.0.0.1/tcp/4001/p2p/{}", peer).parse().unwrap();
+        assert_eq!(peer_id_of(&addr), Some(peer));
+        assert_eq!(peer_id_of(&"/ip4/10.0.0.1/tcp/4001".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn test_seed_address_matching_ignores_peer_id() {
+        let plain: Multiaddr = "/ip4/10.0.0.1/tcp/4001".parse().unwrap();
+        let with_id = plain.clone().with(Protocol::P2p(PeerId::random()));
+        assert_eq!(without_peer_id(&with_id), plain);
+        assert_eq!(without_peer_id(&plain), plain);
+    }
+}
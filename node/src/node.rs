@@ -0,0 +1,887 @@
+//! A running Pulse node: P2P network, HTTP API, block production and the
+//! background tasks that connect them.
+//!
+//! [`Node::start`] spawns everything; [`Node::shutdown`] stops it in order —
+//! block production finishes its current tick, the API stops accepting
+//! requests, the swarm task exits, and storage is flushed — so nothing
+//! written before shutdown is lost.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::api::{self, ApiConfig, AppState};
+use crate::api::events::{now_ms, EventLog, NodeEvent};
+use crate::api::websocket::{WsBroadcaster, WsEvent};
+use crate::consensus::{ConsensusError, SyncState};
+use crate::network::{self, NetworkConfig, NetworkHandle, NetworkMessage, ChainSyncRequest, ChainSyncResponse};
+use crate::simulation::{SimulationConfig, SimulationControl};
+use crate::types::PulseBlock;
+use libp2p::{multiaddr::Protocol, Multiaddr};
+use std::sync::Arc;
+
+/// How long shutdown waits for in-flight API requests before abandoning them
+const API_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+/// A chain sync with no response for this long is abandoned, so a new one can start
+const SYNC_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often an in-flight sync is checked for having stalled
+const SYNC_STALL_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Dial attempts per `--peers` address before giving up on it
+const SEED_DIAL_ATTEMPTS: u32 = 3;
+/// Pause between dial attempts to the same seed
+const SEED_DIAL_RETRY: Duration = Duration::from_secs(2);
+/// Rounds of HTTP chain bootstrap against the seed peers before falling back to P2P sync
+const HTTP_SYNC_ATTEMPTS: u32 = 6;
+/// Pause after the first failed round; doubles each round up to [`HTTP_SYNC_MAX_BACKOFF`]
+const HTTP_SYNC_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const HTTP_SYNC_MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Per-request timeout, so an unresponsive peer doesn't stall a round
+const HTTP_SYNC_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Node configuration
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    /// API listen address (port 0 picks a free port)
+    pub api_addr: String,
+    /// P2P listen port
+    pub p2p_port: u16,
+    /// Where the P2P identity key is kept (None uses an ephemeral identity)
+    pub identity_path: Option<PathBuf>,
+    /// Block production interval
+    pub block_interval: Duration,
+    /// After producing a block, prune bodies more than this many blocks below the tip
+    pub prune_depth: Option<u64>,
+    /// How often to forget devices that stopped pulsing (None never does)
+    pub device_cleanup_interval: Option<Duration>,
+    /// Seed peer multiaddrs to dial and sync from on startup
+    pub peers: Vec<String>,
+    /// API port of the seed peers, for fetching their chain over HTTP on
+    /// startup (None syncs over P2P only)
+    pub peer_api_port: Option<u16>,
+    pub network: NetworkConfig,
+    pub api: ApiConfig,
+    /// Generate heartbeats from simulated devices
+    pub simulation: Option<SimulationConfig>,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            api_addr: "0.0.0.0:8080".to_string(),
+            p2p_port: 4001,
+            identity_path: None,
+            block_interval: Duration::from_millis(5000),
+            prune_depth: None,
+            device_cleanup_interval: Some(Duration::from_secs(30)), // every 6 blocks
+            peers: Vec::new(),
+            peer_api_port: None,
+            network: NetworkConfig::default(),
+            api: ApiConfig::default(),
+            simulation: None,
+        }
+    }
+}
+
+/// A started node. Dropping it leaves the tasks running; call [`Node::shutdown`]
+/// to stop them and flush storage.
+pub struct Node {
+    state: AppState,
+    network: NetworkHandle,
+    api_addr: std::net::SocketAddr,
+    shutdown_tx: watch::Sender<bool>,
+    block_task: JoinHandle<()>,
+    message_task: JoinHandle<()>,
+    api_task: JoinHandle<()>,
+    sync_task: Option<JoinHandle<()>>,
+    simulation: SimulationControl,
+    cleanup_task: Option<JoinHandle<()>>,
+}
+
+impl Node {
+    /// Start the network, API server and background tasks
+    pub async fn start(state: AppState, config: NodeConfig) -> anyhow::Result<Self> {
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+        // Start P2P network — returns a handle (cloneable, channel-based) + incoming message receiver
+        let (net_handle, incoming_rx) = network::start_with_identity(
+            config.p2p_port,
+            config.network.clone(),
+            config.identity_path.as_deref(),
+        ).await?;
+        info!("🔑 P2P Peer ID: {}", net_handle.info.peer_id);
+
+        // Start API server
+        let handles = api::start_server(
+            state.clone(),
+            &config.api_addr,
+            net_handle.clone(),
+            &config.api,
+            shutdown_signal(shutdown_rx.clone()),
+        ).await?;
+        let broadcaster = handles.broadcaster;
+        let event_log = handles.event_log;
+
+        // Log node start event
+        {
+            let pol = state.read().await;
+            event_log.push(NodeEvent::NodeStarted {
+                timestamp: now_ms(),
+                version: api::NODE_VERSION.to_string(),
+                chain_height: pol.chain_height(),
+            }).await;
+        }
+
+        let message_task = tokio::spawn(process_messages(
+            state.clone(), incoming_rx, broadcaster.clone(), event_log.clone(), net_handle.clone(),
+        ));
+
+        let sync_task = (!config.peers.is_empty()).then(|| tokio::spawn(sync_from_peers(
+            state.clone(), config.peers.clone(), config.peer_api_port, broadcaster.clone(), net_handle.clone(),
+        )));
+
+        let block_task = tokio::spawn(produce_blocks(
+            state.clone(),
+            config.block_interval,
+            config.prune_depth,
+            broadcaster.clone(),
+            event_log.clone(),
+            net_handle.clone(),
+            shutdown_rx.clone(),
+        ));
+
+        if let Some(sim_config) = config.simulation {
+            handles.simulation.start(sim_config).await;
+        }
+
+        let cleanup_task = config.device_cleanup_interval.map(|period| {
+            tokio::spawn(cleanup_devices(state.clone(), period, shutdown_rx.clone()))
+        });
+
+        info!("🚀 Pulse node running!");
+
+        Ok(Self {
+            state,
+            network: net_handle,
+            api_addr: handles.local_addr,
+            shutdown_tx,
+            block_task,
+            message_task,
+            api_task: handles.server,
+            sync_task,
+            simulation: handles.simulation,
+            cleanup_task,
+        })
+    }
+
+    /// Shared consensus state
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// Handle to the P2P network
+    pub fn network(&self) -> &NetworkHandle {
+        &self.network
+    }
+
+    /// Address the API server is bound to
+    pub fn api_addr(&self) -> std::net::SocketAddr {
+        self.api_addr
+    }
+
+    /// Stop all tasks and flush storage. Block production finishes the tick it
+    /// is in (including broadcasting a freshly created block) before the
+    /// network is closed.
+    pub async fn shutdown(self) -> Result<(), ConsensusError> {
+        info!("👋 Shutting down...");
+        let _ = self.shutdown_tx.send(true);
+
+        if let Some(task) = self.sync_task {
+            task.abort();
+        }
+        self.simulation.stop().await;
+        if let Some(task) = self.cleanup_task {
+            let _ = task.await;
+        }
+        let _ = self.block_task.await;
+
+        let mut api_task = self.api_task;
+        if tokio::time::timeout(API_DRAIN_TIMEOUT, &mut api_task).await.is_err() {
+            warn!("🌐 API requests still open after {}s, closing them", API_DRAIN_TIMEOUT.as_secs());
+            api_task.abort();
+        }
+
+        // Closing the network ends the incoming message stream, which lets
+        // the message task finish whatever it was applying
+        self.network.shutdown().await;
+        let _ = self.message_task.await;
+
+        let pol = self.state.read().await;
+        pol.flush()?;
+        info!("💾 Storage flushed at height {}", pol.chain_height());
+        Ok(())
+    }
+}
+
+/// Resolves once shutdown has been signalled (or the sender is gone)
+async fn shutdown_signal(mut rx: watch::Receiver<bool>) {
+    let _ = rx.wait_for(|stop| *stop).await;
+}
+
+/// An in-flight windowed chain sync
+struct SyncProgress {
+    /// Peer we're fetching windows from
+    peer: String,
+    /// Tip height the peer last reported
+    target_height: u64,
+    last_activity: Instant,
+}
+
+impl SyncProgress {
+    fn stalled(&self) -> bool {
+        self.last_activity.elapsed() > SYNC_STALL_TIMEOUT
+    }
+}
+
+/// Apply messages received from peers
+async fn process_messages(
+    state: AppState,
+    mut incoming_rx: tokio::sync::mpsc::Receiver<NetworkMessage>,
+    broadcaster: Arc<WsBroadcaster>,
+    event_log: EventLog,
+    net: NetworkHandle,
+) {
+    let mut sync: Option<SyncProgress> = None;
+    let mut stall_check = tokio::time::interval(SYNC_STALL_CHECK_INTERVAL);
+    loop {
+        let msg = tokio::select! {
+            msg = incoming_rx.recv() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = stall_check.tick() => {
+                // A peer that stopped serving our sync no longer holds up block production
+                if let Some(progress) = sync.take_if(|progress| progress.stalled()) {
+                    warn!("📨 Chain sync from {} stalled at target #{}", progress.peer, progress.target_height);
+                    state.write().await.end_sync();
+                }
+                continue;
+            }
+        };
+        match msg {
+            NetworkMessage::Heartbeat(hb) => {
+                let mut pol = state.write().await;
+                if let Err(e) = pol.receive_heartbeat(hb) {
+                    warn!("📨 P2P heartbeat rejected: {}", e);
+                }
+            }
+            NetworkMessage::Block { block, from } => {
+                let mut pol = state.write().await;
+                match pol.receive_block(block.clone()) {
+                    Ok(()) => {
+                        // Broadcast to WebSocket clients on success
+                        broadcaster.broadcast(WsEvent::NewBlock { block });
+                        let stats = pol.get_stats();
+                        broadcaster.broadcast(WsEvent::Stats { stats });
+                    }
+                    Err(ConsensusError::InvalidPreviousHash) => {
+                        // We're behind — request chain sync. The gossiped height is
+                        // unverified, so only the serving peer's responses mark us syncing.
+                        let our_height = pol.chain_height();
+                        drop(pol);
+                        if block.index > our_height + 1 {
+                            // One sync at a time; later blocks arriving meanwhile don't restart it
+                            if sync.as_ref().is_none_or(SyncProgress::stalled) {
+                                info!("📨 We're behind (at {}, got block #{}), requesting chain sync from {}", our_height, block.index, from);
+                                let req = ChainSyncRequest { from_height: our_height + 1, max_blocks: 0 };
+                                net.request_chain_sync(Some(&from), &req).await;
+                                sync = Some(SyncProgress { peer: from, target_height: block.index, last_activity: Instant::now() });
+                            }
+                        } else {
+                            warn!("📨 P2P block #{} rejected: prev_hash mismatch (possible fork)", block.index);
+                        }
+                    }
+                    Err(ConsensusError::IncompatibleNetwork(_)) => {
+                        // Already reported once by consensus; don't request sync from a foreign network
+                    }
+                    Err(e) => {
+                        warn!("📨 P2P block rejected: {}", e);
+                    }
+                }
+            }
+            NetworkMessage::ChainSyncRequest { request_id, request } => {
+                info!("📨 Chain sync request from height {}", request.from_height);
+                let pol = state.read().await;
+                let blocks = pol.get_blocks_window(request.from_height, request.window() as usize);
+                let tip_height = pol.chain_height();
+                drop(pol);
+
+                // Always answer (possibly empty) so the requester isn't left waiting
+                net.respond_chain_sync(request_id, ChainSyncResponse { blocks, tip_height }).await;
+            }
+            NetworkMessage::ChainSyncResponse { response, from } => {
+                info!("📨 Chain sync response from {}: {} blocks (peer tip #{})",
+                    from, response.blocks.len(), response.tip_height);
+                // Responses to a fan-out request are all applied, but only one peer is followed
+                let ours = match &sync {
+                    Some(progress) => progress.peer == from || progress.stalled(),
+                    None => true,
+                };
+
+                let mut pol = state.write().await;
+                let result = pol.apply_sync_window(response.blocks);
+                if ours {
+                    // Progress keeps us syncing toward the peer's tip; anything else ends the sync
+                    match result {
+                        Ok(applied) if applied > 0 => pol.note_peer_height(response.tip_height),
+                        _ => pol.end_sync(),
+                    }
+                }
+                let height = pol.chain_height();
+                let stats = pol.get_stats();
+                drop(pol);
+                match result {
+                    Ok(applied) => {
+                        if applied > 0 {
+                            broadcaster.broadcast(WsEvent::Stats { stats });
+                        }
+                        if !ours {
+                            continue;
+                        }
+                        if applied > 0 && height < response.tip_height {
+                            info!("📥 Synced to #{} of #{}, requesting next window", height, response.tip_height);
+                            let req = ChainSyncRequest { from_height: height + 1, max_blocks: 0 };
+                            net.request_chain_sync(Some(&from), &req).await;
+                            sync = Some(SyncProgress {
+                                peer: from,
+                                target_height: response.tip_height,
+                                last_activity: Instant::now(),
+                            });
+                        } else if let Some(progress) = sync.take() {
+                            info!("✅ Chain synced from peer at #{} (target #{})", height, progress.target_height);
+                        }
+                    }
+                    Err(ConsensusError::IncompatibleNetwork(_)) => {
+                        if ours {
+                            sync = None;
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Chain sync failed: {}", e);
+                        if ours {
+                            sync = None;
+                        }
+                    }
+                }
+            }
+            NetworkMessage::PeerConnected { peer_id, peer_count } => {
+                event_log.push(NodeEvent::PeerConnected { timestamp: now_ms(), peer_id, peer_count }).await;
+                broadcaster.broadcast(WsEvent::Peers { count: peer_count });
+            }
+            NetworkMessage::PeerDisconnected { peer_id, peer_count } => {
+                event_log.push(NodeEvent::PeerDisconnected { timestamp: now_ms(), peer_id, peer_count }).await;
+                broadcaster.broadcast(WsEvent::Peers { count: peer_count });
+            }
+        }
+    }
+}
+
+/// Dial a seed peer, retrying failed attempts; true once connected
+async fn dial_seed(net: &NetworkHandle, addr: &str) -> bool {
+    for attempt in 1..=SEED_DIAL_ATTEMPTS {
+        match net.dial_peer(addr).await.await {
+            Ok(Ok(())) => return true,
+            Ok(Err(e)) => warn!("⚠️ Dial {} failed (attempt {}/{}): {}", addr, attempt, SEED_DIAL_ATTEMPTS, e),
+            // Swarm task gone: the node is shutting down
+            Err(_) => return false,
+        }
+        if attempt < SEED_DIAL_ATTEMPTS {
+            tokio::time::sleep(SEED_DIAL_RETRY).await;
+        }
+    }
+    false
+}
+
+/// Connect to explicit peers and catch up: from their HTTP APIs if a peer API
+/// port is configured, otherwise (or if that fails) over P2P chain sync
+async fn sync_from_peers(
+    state: AppState,
+    peers: Vec<String>,
+    peer_api_port: Option<u16>,
+    broadcaster: Arc<WsBroadcaster>,
+    net: NetworkHandle,
+) {
+    let dials = peers.iter().map(|addr| dial_seed(&net, addr));
+    let connected = futures_util::future::join_all(dials).await.into_iter().filter(|ok| *ok).count();
+    info!("🤝 Connected to {}/{} seed peers", connected, peers.len());
+
+    if let Some(port) = peer_api_port {
+        let bases: Vec<String> = peers.iter().filter_map(|addr| peer_api_base(addr, port)).collect();
+        if let Some(base) = http_bootstrap(&state, &bases, &broadcaster).await {
+            info!("✅ Chain synced from peer via HTTP ({})", base);
+            return;
+        }
+    }
+
+    // Fallback: ask the connected peers directly over P2P
+    let pol = state.read().await;
+    let current_height = pol.chain_height();
+    drop(pol);
+    let req = ChainSyncRequest { from_height: current_height + 1, max_blocks: 0 };
+    net.request_chain_sync(None, &req).await;
+}
+
+/// Base URL of a seed peer's API: the host of its multiaddr with `port`
+fn peer_api_base(addr: &str, port: u16) -> Option<String> {
+    let multiaddr: Multiaddr = addr.parse().ok()?;
+    multiaddr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(format!("http://{}:{}", ip, port)),
+        Protocol::Ip6(ip) => Some(format!("http://[{}]:{}", ip, port)),
+        Protocol::Dns(host) | Protocol::Dns4(host) | Protocol::Dns6(host) => Some(format!("http://{}:{}", host, port)),
+        _ => None,
+    })
+}
+
+/// Adopt a seed's chain from its `/blocks`, retrying with exponential backoff
+/// while no seed answers. Returns the base URL whose chain was adopted; `None`
+/// once every seed answered without a better chain or the retries ran out.
+async fn http_bootstrap(state: &AppState, bases: &[String], broadcaster: &WsBroadcaster) -> Option<String> {
+    if bases.is_empty() {
+        return None;
+    }
+    let client = reqwest::Client::builder().timeout(HTTP_SYNC_REQUEST_TIMEOUT).build().ok()?;
+    let mut backoff = HTTP_SYNC_INITIAL_BACKOFF;
+    for attempt in 1..=HTTP_SYNC_ATTEMPTS {
+        let mut answered = false;
+        for base in bases {
+            info!("📡 Attempting HTTP chain sync from {}", base);
+            match fetch_blocks(&client, base).await {
+                Ok(blocks) => {
+                    answered = true;
+                    if blocks.is_empty() {
+                        continue;
+                    }
+                    info!("📡 Got {} blocks from peer HTTP API", blocks.len());
+                    let mut pol = state.write().await;
+                    match pol.import_chain(blocks) {
+                        Ok(true) => {
+                            broadcaster.broadcast(WsEvent::Stats { stats: pol.get_stats() });
+                            return Some(base.clone());
+                        }
+                        Ok(false) => {}
+                        Err(e) => warn!("HTTP chain sync from {} rejected: {}", base, e),
+                    }
+                }
+                Err(e) => warn!("📡 HTTP chain sync from {} failed (attempt {}/{}): {}", base, attempt, HTTP_SYNC_ATTEMPTS, e),
+            }
+        }
+        // A seed that answered has nothing better for us; retrying won't change that
+        if answered {
+            return None;
+        }
+        if attempt < HTTP_SYNC_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(HTTP_SYNC_MAX_BACKOFF);
+        }
+    }
+    warn!("📡 No seed peer API reachable, falling back to P2P chain sync");
+    None
+}
+
+/// The first page of a peer's chain from its `/blocks` endpoint
+async fn fetch_blocks(client: &reqwest::Client, base: &str) -> Result<Vec<PulseBlock>, String> {
+    let url = format!("{}/blocks?offset=0&limit=200", base);
+    let resp = client.get(&url).send().await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let body: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
+    let blocks = body.get("data").and_then(|d| d.get("blocks")).cloned()
+        .ok_or_else(|| "response has no blocks".to_string())?;
+    serde_json::from_value(blocks).map_err(|e| e.to_string())
+}
+
+/// Block production loop; exits between ticks once shutdown is signalled
+async fn produce_blocks(
+    state: AppState,
+    block_interval: Duration,
+    prune_depth: Option<u64>,
+    broadcaster: Arc<WsBroadcaster>,
+    event_log: EventLog,
+    net: NetworkHandle,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut interval = tokio::time::interval(block_interval);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.wait_for(|stop| *stop) => break,
+        }
+
+        let mut pol = state.write().await;
+
+        let pool_size = pol.heartbeat_pool_size();
+        if pool_size > 0 {
+            broadcaster.broadcast(WsEvent::HeartbeatCount { count: pool_size });
+        }
+
+        // A block built on a tip we know is stale would only be orphaned
+        if let SyncState::Syncing { current, target } = pol.sync_state() {
+            debug!("⏸️ Block production paused while syncing (#{} of #{})", current, target);
+            continue;
+        }
+
+        if let Ok(Some(block)) = pol.try_create_block() {
+            // Log block event
+            event_log.push(NodeEvent::BlockCreated {
+                timestamp: block.timestamp,
+                index: block.index,
+                block_hash: block.block_hash.clone(),
+                n_live: block.n_live,
+                total_weight: block.total_weight,
+                security: block.security,
+                rewards_distributed: 100.0,
+            }).await;
+
+            for hb in &block.heartbeats {
+                event_log.push(NodeEvent::HeartbeatReceived {
+                    timestamp: hb.timestamp,
+                    device_pubkey: hb.device_pubkey[..16].to_string() + "...",
+                    heart_rate: hb.heart_rate,
+                    weight: hb.weight(),
+                    confidence: None,
+                    hrv_sdnn: None,
+                }).await;
+            }
+
+            // Transactions that no longer fit have to be resubmitted by their senders
+            let dropped = pol.take_dropped_transactions();
+            if !dropped.is_empty() {
+                info!("🗑️ {} transaction(s) dropped from block #{}; senders must resubmit",
+                    dropped.len(), block.index);
+            }
+
+            if let Some(depth) = prune_depth {
+                match pol.prune(block.index.saturating_sub(depth)) {
+                    Ok(0) => {}
+                    Ok(pruned) => info!("✂️ Pruned {} old block(s) to headers", pruned),
+                    Err(e) => warn!("⚠️ Failed to prune blocks: {}", e),
+                }
+            }
+
+            // Broadcast to WebSocket
+            broadcaster.broadcast(WsEvent::NewBlock { block: block.clone() });
+            let stats = pol.get_stats();
+            broadcaster.broadcast(WsEvent::Stats { stats });
+
+            // Release consensus lock BEFORE sending to P2P (avoid holding across await)
+            drop(pol);
+
+            // Broadcast to P2P network
+            net.broadcast_block(&block).await;
+        }
+    }
+    info!("⏹️ Block production stopped");
+}
+
+/// Periodically drop continuity and biometric tracking for devices that
+/// stopped pulsing, so the per-device maps don't grow without bound
+async fn cleanup_devices(state: AppState, period: Duration, mut shutdown: watch::Receiver<bool>) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.wait_for(|stop| *stop) => break,
+        }
+
+        let mut pol = state.write().await;
+        let dropped = pol.cleanup_stale_continuity();
+        if dropped > 0 {
+            info!("🧹 Stopped tracking {} idle device(s), {} still tracked", dropped, pol.tracked_devices());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::{ConsensusConfig, ProofOfLife};
+    use crate::storage::Storage;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_shutdown_stops_tasks_and_persists_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(Storage::open(dir.path()).unwrap());
+        let pol = ProofOfLife::with_storage(ConsensusConfig::default(), storage.clone()).unwrap();
+        let state: AppState = Arc::new(RwLock::new(pol));
+
+        let config = NodeConfig {
+            api_addr: "127.0.0.1:0".to_string(),
+            p2p_port: 0,
+            block_interval: Duration::from_millis(50),
+            simulation: Some(SimulationConfig { seed: Some(7), interval_ms: 20, ..Default::default() }),
+            ..Default::default()
+        };
+        let node = Node::start(state.clone(), config).await.unwrap();
+        let api_addr = node.api_addr();
+        let net = node.network().clone();
+
+        // Wait for the simulator to get a block produced
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while state.read().await.chain_height() == 0 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        }).await.expect("node never produced a block");
+
+        tokio::time::timeout(Duration::from_secs(10), node.shutdown())
+            .await
+            .expect("shutdown hung")
+            .unwrap();
+
+        // Nothing is producing blocks or accepting requests any more
+        let height = state.read().await.chain_height();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(state.read().await.chain_height(), height);
+        assert_eq!(storage.chain_height().unwrap(), height);
+        assert!(tokio::net::TcpStream::connect(api_addr).await.is_err());
+
+        // The swarm task is gone, so commands are dropped rather than queued
+        net.request_chain_sync(None, &ChainSyncRequest { from_height: 1, max_blocks: 0 }).await;
+        assert_eq!(net.publish_stats().chain_sync_dropped, 1);
+    }
+
+    async fn event_types(api_addr: std::net::SocketAddr) -> Vec<String> {
+        let body: serde_json::Value = reqwest::get(format!("http://{}/events", api_addr))
+            .await.unwrap().json().await.unwrap();
+        body["data"].as_array().unwrap().iter()
+            .map(|e| e["type"].as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    async fn wait_for_event(api_addr: std::net::SocketAddr, kind: &str) {
+        tokio::time::timeout(Duration::from_secs(15), async {
+            while !event_types(api_addr).await.iter().any(|t| t == kind) {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }).await.unwrap_or_else(|_| panic!("no {} event", kind));
+    }
+
+    #[tokio::test]
+    async fn test_transaction_and_peer_events_are_logged() {
+        // Two live participants per block, so the node can't produce one mid-test
+        let config = ConsensusConfig { n_threshold: 2, ..Default::default() };
+        let new_state = || -> AppState { Arc::new(RwLock::new(ProofOfLife::new(config.clone()))) };
+        let free_port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let state_a = new_state();
+        let a = Node::start(state_a.clone(), NodeConfig {
+            api_addr: "127.0.0.1:0".to_string(),
+            p2p_port: free_port,
+            ..Default::default()
+        }).await.unwrap();
+
+        // An accepted /tx lands in the event log
+        let sender = crate::crypto::Keypair::generate();
+        let other = crate::crypto::Keypair::generate();
+        let heartbeat = |kp: &crate::crypto::Keypair| {
+            let mut hb = crate::types::Heartbeat {
+                timestamp: now_ms(),
+                heart_rate: 72,
+                motion: crate::types::Motion { x: 0.1, y: 0.1, z: 0.05 },
+                temperature: 36.7,
+                device_pubkey: kp.public_key_hex(),
+                signature: String::new(),
+                signature_version: 0,
+                recoverable: false,
+                metadata: Default::default(),
+            };
+            hb.signature = kp.sign(&hb.signable_bytes());
+            hb
+        };
+        // Mint the sender a balance, then keep it pulsing for the transfer
+        {
+            let mut pol = state_a.write().await;
+            pol.receive_heartbeat(heartbeat(&sender)).unwrap();
+            pol.receive_heartbeat(heartbeat(&other)).unwrap();
+            pol.try_create_block().unwrap().unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        let hb = heartbeat(&sender);
+        let mut tx = crate::types::Transaction {
+            tx_id: "event-tx".into(),
+            sender_pubkey: sender.public_key_hex(),
+            recipient_pubkey: crate::crypto::Keypair::generate().public_key_hex(),
+            amount: 1.0,
+            fee: 0.0,
+            timestamp: now_ms(),
+            signature: String::new(),
+            heartbeat_signature: hb.signature.clone(),
+            not_before_height: 0,
+            nonce: 1,
+            signature_version: 0,
+        };
+        tx.signature = sender.sign(&tx.signable_bytes());
+        let client = reqwest::Client::new();
+        let base = format!("http://{}", a.api_addr());
+        assert!(client.post(format!("{}/pulse", base)).json(&hb).send().await.unwrap().status().is_success());
+        let resp = client.post(format!("{}/tx", base)).json(&tx).send().await.unwrap();
+        assert!(resp.status().is_success(), "{:?}", resp.text().await);
+        assert!(event_types(a.api_addr()).await.contains(&"transaction_received".to_string()));
+
+        // A peer connecting and leaving shows up on the other side
+        let b = Node::start(new_state(), NodeConfig {
+            api_addr: "127.0.0.1:0".to_string(),
+            p2p_port: 0,
+            peers: vec![format!("/ip4/127.0.0.1/tcp/{}", free_port)],
+            ..Default::default()
+        }).await.unwrap();
+        wait_for_event(a.api_addr(), "peer_connected").await;
+        b.shutdown().await.unwrap();
+        wait_for_event(a.api_addr(), "peer_disconnected").await;
+        a.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_peer_api_base_from_multiaddr() {
+        assert_eq!(peer_api_base("/ip4/10.0.0.7/tcp/4001", 9000).as_deref(), Some("http://10.0.0.7:9000"));
+        assert_eq!(peer_api_base("/ip6/::1/tcp/4001", 8080).as_deref(), Some("http://[::1]:8080"));
+        assert_eq!(peer_api_base("/dns4/seed.example.org/tcp/4001/p2p/12D3KooWDpJ7As7BWAwRMfu1VU2WCqNjvq387JEYKDBj4kx6nXTN", 8080).as_deref(),
+            Some("http://seed.example.org:8080"));
+        assert_eq!(peer_api_base("/memory/1", 8080), None);
+        assert_eq!(peer_api_base("not a multiaddr", 8080), None);
+    }
+
+    #[tokio::test]
+    async fn test_http_bootstrap_adopts_peer_chain() {
+        let kp = crate::crypto::Keypair::generate();
+        let a = Node::start(Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default()))), NodeConfig {
+            api_addr: "127.0.0.1:0".to_string(),
+            p2p_port: 0,
+            block_interval: Duration::from_secs(3600),
+            ..Default::default()
+        }).await.unwrap();
+        {
+            let mut pol = a.state().write().await;
+            for heart_rate in [62, 71, 80] {
+                let mut hb = crate::types::Heartbeat {
+                    timestamp: now_ms(),
+                    heart_rate,
+                    motion: crate::types::Motion { x: 0.1, y: 0.1, z: 0.05 },
+                    temperature: 36.7,
+                    device_pubkey: kp.public_key_hex(),
+                    signature: String::new(),
+                    signature_version: 0,
+                    recoverable: false,
+                    metadata: Default::default(),
+                };
+                hb.signature = kp.sign(&hb.signable_bytes());
+                pol.receive_heartbeat(hb).unwrap();
+                pol.try_create_block().unwrap().unwrap();
+            }
+        }
+
+        let state_b: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let broadcaster = WsBroadcaster::new(16);
+        let dead = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let bases = vec![
+            format!("http://127.0.0.1:{}", dead),
+            format!("http://127.0.0.1:{}", a.api_addr().port()),
+        ];
+        let source = http_bootstrap(&state_b, &bases, &broadcaster).await;
+        assert_eq!(source.as_deref(), Some(bases[1].as_str()));
+        assert_eq!(state_b.read().await.chain_height(), 3);
+
+        // Nothing better on offer: no retries, caller falls back to P2P
+        assert_eq!(http_bootstrap(&state_b, &bases[1..], &broadcaster).await, None);
+        a.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_long_chain_syncs_in_windows() {
+        const CHAIN_LENGTH: u64 = 2000;
+        let kp = crate::crypto::Keypair::generate();
+        let heartbeat = |heart_rate: u16| {
+            let mut hb = crate::types::Heartbeat {
+                timestamp: now_ms(),
+                heart_rate,
+                motion: crate::types::Motion { x: 0.1, y: 0.1, z: 0.05 },
+                temperature: 36.7,
+                device_pubkey: kp.public_key_hex(),
+                signature: String::new(),
+                signature_version: 0,
+                recoverable: false,
+                metadata: Default::default(),
+            };
+            hb.signature = kp.sign(&hb.signable_bytes());
+            hb
+        };
+        let mut pol = ProofOfLife::new(ConsensusConfig::default());
+        for i in 0..CHAIN_LENGTH {
+            pol.receive_heartbeat(heartbeat(60 + (i * 7 % 31) as u16)).unwrap();
+            pol.try_create_block().unwrap().unwrap();
+        }
+        // A single response only carries a window of it
+        let req = ChainSyncRequest { from_height: 1, max_blocks: 0 };
+        assert_eq!(pol.get_blocks_window(1, req.window() as usize).len() as u64, network::MAX_SYNC_BLOCKS);
+        let state_a: AppState = Arc::new(RwLock::new(pol));
+
+        let free_port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let a = Node::start(state_a.clone(), NodeConfig {
+            api_addr: "127.0.0.1:0".to_string(),
+            p2p_port: free_port,
+            block_interval: Duration::from_millis(200),
+            ..Default::default()
+        }).await.unwrap();
+        let state_b: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let b = Node::start(state_b.clone(), NodeConfig {
+            api_addr: "127.0.0.1:0".to_string(),
+            p2p_port: 0,
+            block_interval: Duration::from_secs(3600),
+            peers: vec![format!("/ip4/127.0.0.1/tcp/{}", free_port)],
+            ..Default::default()
+        }).await.unwrap();
+
+        // Keep A producing; its gossiped blocks tell B how far behind it is
+        tokio::time::timeout(Duration::from_secs(60), async {
+            let mut i = 0;
+            while state_b.read().await.chain_height() < CHAIN_LENGTH {
+                state_a.write().await.receive_heartbeat(heartbeat(70 + i % 11)).unwrap();
+                i += 1;
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+        }).await.expect("B never caught up");
+
+        let (a_pol, b_pol) = (state_a.read().await, state_b.read().await);
+        let tip = b_pol.get_block_by_index(CHAIN_LENGTH).unwrap();
+        assert_eq!(tip.block_hash, a_pol.get_block_by_index(CHAIN_LENGTH).unwrap().block_hash);
+        drop((a_pol, b_pol));
+        b.shutdown().await.unwrap();
+        a.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_spoofed_gossip_tip_doesnt_pause_production() {
+        let state: AppState = Arc::new(RwLock::new(ProofOfLife::new(ConsensusConfig::default())));
+        let (net, _incoming) = network::start_with_identity(0, NetworkConfig::default(), None).await.unwrap();
+        let (msg_tx, msg_rx) = tokio::sync::mpsc::channel(8);
+        let task = tokio::spawn(process_messages(
+            state.clone(), msg_rx, Arc::new(WsBroadcaster::new(16)), EventLog::new(), net,
+        ));
+
+        // Correctly hashed, but claiming a far-off height on a parent nobody has;
+        // the peer that sent it never answers the sync it triggers
+        let mut spoofed = state.read().await.latest_block().unwrap().clone();
+        spoofed.index = 1_000_000;
+        spoofed.timestamp += 1;
+        spoofed.previous_hash = "ab".repeat(32);
+        spoofed.block_hash = spoofed.compute_hash();
+        for _ in 0..3 {
+            msg_tx.send(NetworkMessage::Block { block: spoofed.clone(), from: "spoofer".to_string() }).await.unwrap();
+        }
+        drop(msg_tx);
+        task.await.unwrap();
+
+        // Block production only pauses while Syncing
+        assert_eq!(state.read().await.sync_state(), SyncState::Synced);
+    }
+}
@@ -0,0 +1,101 @@
+//! In-memory [`ChainStore`] for tests and embedders that don't need persistence.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard};
+
+use super::{ChainStore, StorageError};
+use crate::consensus::biometrics::Baseline;
+use crate::types::{Account, BlockHeader, PulseBlock};
+
+/// Chain store that keeps blocks and accounts in memory; `flush` is a no-op
+#[derive(Default)]
+pub struct MemStore {
+    blocks: Mutex<BTreeMap<u64, PulseBlock>>,
+    headers: Mutex<BTreeMap<u64, BlockHeader>>,
+    accounts: Mutex<HashMap<String, Account>>,
+    baselines: Mutex<BTreeMap<String, Baseline>>,
+}
+
+impl MemStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Lock, recovering the data if a panicking writer poisoned the mutex
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl ChainStore for MemStore {
+    fn save_block(&self, block: &PulseBlock) -> Result<(), StorageError> {
+        lock(&self.blocks).insert(block.index, block.clone());
+        lock(&self.headers).remove(&block.index);
+        Ok(())
+    }
+
+    fn load_block(&self, index: u64) -> Result<PulseBlock, StorageError> {
+        if let Some(block) = lock(&self.blocks).get(&index) {
+            return Ok(block.clone());
+        }
+        if lock(&self.headers).contains_key(&index) {
+            return Err(StorageError::BlockPruned(index));
+        }
+        Err(StorageError::BlockNotFound(index))
+    }
+
+    fn load_all_blocks(&self) -> Result<Vec<PulseBlock>, StorageError> {
+        Ok(lock(&self.blocks).values().cloned().collect())
+    }
+
+    fn save_account(&self, account: &Account) -> Result<(), StorageError> {
+        lock(&self.accounts).insert(account.pubkey.clone(), account.clone());
+        Ok(())
+    }
+
+    fn load_all_accounts(&self) -> Result<Vec<Account>, StorageError> {
+        Ok(lock(&self.accounts).values().cloned().collect())
+    }
+
+    fn truncate_blocks_from(&self, height: u64) -> Result<usize, StorageError> {
+        lock(&self.headers).split_off(&height);
+        let removed = lock(&self.blocks).split_off(&height);
+        Ok(removed.len())
+    }
+
+    fn replace_accounts(&self, accounts: &[Account]) -> Result<(), StorageError> {
+        let mut stored = lock(&self.accounts);
+        stored.clear();
+        stored.extend(accounts.iter().map(|a| (a.pubkey.clone(), a.clone())));
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn prune_blocks(&self, keep_after: u64) -> Result<usize, StorageError> {
+        let mut blocks = lock(&self.blocks);
+        let mut headers = lock(&self.headers);
+        let prunable: Vec<u64> = blocks.range(1..keep_after.max(1)).map(|(&index, _)| index).collect();
+        for index in &prunable {
+            if let Some(block) = blocks.remove(index) {
+                headers.insert(*index, block.header());
+            }
+        }
+        Ok(prunable.len())
+    }
+
+    fn load_block_headers(&self) -> Result<Vec<BlockHeader>, StorageError> {
+        Ok(lock(&self.headers).values().cloned().collect())
+    }
+
+    fn save_baseline(&self, pubkey: &str, baseline: &Baseline) -> Result<(), StorageError> {
+        lock(&self.baselines).insert(pubkey.to_string(), *baseline);
+        Ok(())
+    }
+
+    fn load_baselines(&self) -> Result<Vec<(String, Baseline)>, StorageError> {
+        Ok(lock(&self.baselines).iter().map(|(k, v)| (k.clone(), *v)).collect())
+    }
+}
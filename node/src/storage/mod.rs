@@ -1,11 +1,30 @@
-//! Persistent storage for the Pulse chain using sled embedded database.
+//! Persistent storage for the Pulse chain.
+//!
+//! Consensus talks to a [`ChainStore`]; [`Storage`] (the sled embedded
+//! database) is the production backend and [`MemStore`] keeps everything in
+//! memory for tests and embedders.
+//!
+//! Sled values are encoded with [`wire::to_bytes`]: JSON by default, bincode
+//! with the `binary-wire` feature. Either build reads both. Activity-feed
+//! events are internally tagged, which bincode can't decode, so they are
+//! always stored as JSON.
+
+pub mod mem;
+pub mod snapshot;
+
+pub use mem::MemStore;
+pub use snapshot::SnapshotSummary;
 
 use sled::{Db, Tree};
+use std::io::{Read, Write};
 use std::path::Path;
 use thiserror::Error;
 use tracing::info;
 
-use crate::types::{PulseBlock, Account};
+use crate::api::events::NodeEvent;
+use crate::consensus::biometrics::Baseline;
+use crate::network::wire::{self, WireError};
+use crate::types::{to_micro, BlockHeader, PulseBlock, Account, Transaction};
 
 #[derive(Error, Debug)]
 pub enum StorageError {
@@ -13,8 +32,129 @@ pub enum StorageError {
     Database(#[from] sled::Error),
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+    #[error("Encoding error: {0}")]
+    Encoding(#[from] WireError),
     #[error("Block not found: {0}")]
     BlockNotFound(u64),
+    #[error("Block {0} has been pruned (only its header is kept)")]
+    BlockPruned(u64),
+    #[error("Storage backend error: {0}")]
+    Backend(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid snapshot: {0}")]
+    InvalidSnapshot(String),
+    #[error("Data directory {0} is locked by another process (is another node using it?)")]
+    AlreadyLocked(String),
+}
+
+impl StorageError {
+    /// The path can't be written at all (permissions, read-only filesystem),
+    /// as opposed to a locked, corrupt or otherwise broken database
+    pub fn is_unwritable(&self) -> bool {
+        let io = match self {
+            StorageError::Io(e) | StorageError::Database(sled::Error::Io(e)) => e,
+            _ => return false,
+        };
+        matches!(io.kind(), std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem)
+    }
+}
+
+/// Chain persistence used by the consensus engine
+pub trait ChainStore: Send + Sync {
+    /// Save a block, overwriting any stored block with the same index
+    fn save_block(&self, block: &PulseBlock) -> Result<(), StorageError>;
+    
+    /// Load a block by index
+    fn load_block(&self, index: u64) -> Result<PulseBlock, StorageError>;
+    
+    /// Load all blocks, ordered by index
+    fn load_all_blocks(&self) -> Result<Vec<PulseBlock>, StorageError>;
+    
+    /// Save account state
+    fn save_account(&self, account: &Account) -> Result<(), StorageError>;
+    
+    /// Load all accounts
+    fn load_all_accounts(&self) -> Result<Vec<Account>, StorageError>;
+    
+    /// Delete every block at or above `height`. Returns how many were removed.
+    fn truncate_blocks_from(&self, height: u64) -> Result<usize, StorageError>;
+    
+    /// Replace all stored accounts with the given set
+    fn replace_accounts(&self, accounts: &[Account]) -> Result<(), StorageError>;
+    
+    /// Make everything written so far durable
+    fn flush(&self) -> Result<(), StorageError>;
+    
+    /// Replace the bodies of blocks `1..keep_after` with their headers (genesis
+    /// is always kept). Returns how many bodies were dropped. Backends that
+    /// don't support pruning keep every block.
+    fn prune_blocks(&self, _keep_after: u64) -> Result<usize, StorageError> {
+        Ok(0)
+    }
+    
+    /// Headers of pruned blocks, ordered by index
+    fn load_block_headers(&self) -> Result<Vec<BlockHeader>, StorageError> {
+        Ok(Vec::new())
+    }
+    
+    /// Save a device's calibrated biometric baseline. Backends that don't keep
+    /// baselines drop it, and the device recalibrates after a restart.
+    fn save_baseline(&self, _pubkey: &str, _baseline: &Baseline) -> Result<(), StorageError> {
+        Ok(())
+    }
+    
+    /// All saved biometric baselines as (pubkey, baseline)
+    fn load_baselines(&self) -> Result<Vec<(String, Baseline)>, StorageError> {
+        Ok(Vec::new())
+    }
+    
+    /// Append an activity-feed event. Backends that don't keep events drop it,
+    /// and the feed starts empty after a restart.
+    fn save_event(&self, _event: &NodeEvent) -> Result<(), StorageError> {
+        Ok(())
+    }
+    
+    /// The `limit` most recent saved events, oldest first
+    fn load_recent_events(&self, _limit: usize) -> Result<Vec<NodeEvent>, StorageError> {
+        Ok(Vec::new())
+    }
+    
+    /// Up to `limit` saved events newer than `timestamp`, oldest first
+    fn load_events_since(&self, _timestamp: u64, _limit: usize) -> Result<Vec<NodeEvent>, StorageError> {
+        Ok(Vec::new())
+    }
+    
+    /// Look up a confirmed transaction by id, returning it with its block index.
+    /// The default scans every block; indexed backends should override it.
+    fn find_transaction(&self, tx_id: &str) -> Result<Option<(u64, Transaction)>, StorageError> {
+        Ok(self.load_all_blocks()?.into_iter().rev().find_map(|block| {
+            let index = block.index;
+            block.transactions.into_iter()
+                .find(|tx| tx.tx_id == tx_id)
+                .map(|tx| (index, tx))
+        }))
+    }
+    
+    /// Transactions sent or received by `pubkey`, newest first, with their block
+    /// indexes; plus the total number of such transactions. The default scans
+    /// every block; indexed backends should override it.
+    fn account_transactions(
+        &self,
+        pubkey: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<(u64, Transaction)>, usize), StorageError> {
+        let history: Vec<(u64, Transaction)> = self.load_all_blocks()?.into_iter().rev()
+            .flat_map(|block| {
+                let index = block.index;
+                block.transactions.into_iter().rev().map(move |tx| (index, tx))
+            })
+            .filter(|(_, tx)| tx.sender_pubkey == pubkey || tx.recipient_pubkey == pubkey)
+            .collect();
+        let total = history.len();
+        Ok((history.into_iter().skip(offset).take(limit).collect(), total))
+    }
 }
 
 /// Persistent storage for the Pulse chain
@@ -23,26 +163,150 @@ pub struct Storage {
     blocks: Tree,
     accounts: Tree,
     metadata: Tree,
+    /// tx_id -> index of the block that includes it
+    tx_index: Tree,
+    /// pubkey/block index/position -> transaction, for each sender and recipient
+    account_txs: Tree,
+    /// Headers of pruned blocks, keyed like `blocks`
+    headers: Tree,
+    /// Calibrated biometric baselines, keyed by device pubkey
+    baselines: Tree,
+    /// Activity-feed events, keyed by timestamp then a unique id
+    events: Tree,
+}
+
+/// How long saved events are kept before being dropped
+const EVENT_RETENTION_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Metadata key set once the transaction indexes cover every stored block
+const TX_INDEX_MARKER: &str = "tx_index_built";
+
+/// Metadata key set while a verified snapshot is being moved into place
+const SNAPSHOT_PENDING_MARKER: &str = "snapshot_import_pending";
+
+/// Trees a snapshot is staged in until it has been read and verified in full
+const STAGED_BLOCKS_TREE: &str = "snapshot_blocks";
+const STAGED_ACCOUNTS_TREE: &str = "snapshot_accounts";
+
+/// An account record as stored before amounts were integer micro-PULSE
+#[derive(serde::Deserialize)]
+struct LegacyAccount {
+    pubkey: String,
+    balance: f64,
+    last_heartbeat: u64,
+    total_earned: f64,
+    blocks_participated: u64,
+    #[serde(default)]
+    nonce: u64,
+}
+
+impl From<LegacyAccount> for Account {
+    fn from(legacy: LegacyAccount) -> Self {
+        Account {
+            pubkey: legacy.pubkey,
+            balance: to_micro(legacy.balance),
+            last_heartbeat: legacy.last_heartbeat,
+            total_earned: to_micro(legacy.total_earned),
+            blocks_participated: legacy.blocks_participated,
+            nonce: legacy.nonce,
+        }
+    }
+}
+
+/// Key for an account history entry; sorts by block then position within the pubkey
+fn account_tx_key(pubkey: &str, block_index: u64, position: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(pubkey.len() + 13);
+    key.extend_from_slice(pubkey.as_bytes());
+    key.push(b'/');
+    key.extend_from_slice(&block_index.to_be_bytes());
+    key.extend_from_slice(&position.to_be_bytes());
+    key
 }
 
 impl Storage {
     /// Open or create storage at the given path
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StorageError> {
-        let db = sled::open(path)?;
+        match sled::open(path.as_ref()) {
+            Ok(db) => Self::from_db(db),
+            // sled reports a held lock file as a generic I/O error
+            Err(sled::Error::Io(e)) if e.to_string().contains("could not acquire lock") => {
+                Err(StorageError::AlreadyLocked(path.as_ref().display().to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+    
+    fn from_db(db: Db) -> Result<Self, StorageError> {
         let blocks = db.open_tree("blocks")?;
         let accounts = db.open_tree("accounts")?;
         let metadata = db.open_tree("metadata")?;
+        let tx_index = db.open_tree("tx_index")?;
+        let account_txs = db.open_tree("account_txs")?;
+        let headers = db.open_tree("headers")?;
+        let baselines = db.open_tree("baselines")?;
+        let events = db.open_tree("events")?;
         
         info!("💾 Storage opened");
         
-        Ok(Self { db, blocks, accounts, metadata })
+        let storage = Self { db, blocks, accounts, metadata, tx_index, account_txs, headers, baselines, events };
+        if storage.metadata.get(SNAPSHOT_PENDING_MARKER)?.is_some() {
+            info!("💾 Finishing interrupted snapshot import");
+            storage.commit_staged_snapshot()?;
+        }
+        if storage.metadata.get(TX_INDEX_MARKER)?.is_none() {
+            storage.rebuild_tx_indexes()?;
+        }
+        Ok(storage)
+    }
+    
+    /// Index every stored block's transactions (databases created before the indexes existed)
+    fn rebuild_tx_indexes(&self) -> Result<(), StorageError> {
+        let blocks = self.load_all_blocks()?;
+        for block in &blocks {
+            self.index_transactions(block)?;
+        }
+        if !blocks.is_empty() {
+            info!("💾 Indexed transactions of {} stored blocks", blocks.len());
+        }
+        self.metadata.insert(TX_INDEX_MARKER, &[1u8])?;
+        Ok(())
+    }
+    
+    fn index_transactions(&self, block: &PulseBlock) -> Result<(), StorageError> {
+        for (position, tx) in block.transactions.iter().enumerate() {
+            self.tx_index.insert(tx.tx_id.as_bytes(), &block.index.to_be_bytes())?;
+            let value = wire::to_bytes(tx)?;
+            self.account_txs.insert(account_tx_key(&tx.sender_pubkey, block.index, position as u32), value.clone())?;
+            self.account_txs.insert(account_tx_key(&tx.recipient_pubkey, block.index, position as u32), value)?;
+        }
+        Ok(())
+    }
+    
+    fn unindex_transactions(&self, block: &PulseBlock) -> Result<(), StorageError> {
+        for (position, tx) in block.transactions.iter().enumerate() {
+            // Leave the id alone if a later block re-used it
+            if self.tx_index.get(tx.tx_id.as_bytes())?.as_deref() == Some(&block.index.to_be_bytes()[..]) {
+                self.tx_index.remove(tx.tx_id.as_bytes())?;
+            }
+            self.account_txs.remove(account_tx_key(&tx.sender_pubkey, block.index, position as u32))?;
+            self.account_txs.remove(account_tx_key(&tx.recipient_pubkey, block.index, position as u32))?;
+        }
+        Ok(())
     }
     
     /// Save a block
     pub fn save_block(&self, block: &PulseBlock) -> Result<(), StorageError> {
         let key = block.index.to_be_bytes();
-        let value = serde_json::to_vec(block)?;
-        self.blocks.insert(key, value)?;
+        let value = wire::to_bytes(block)?;
+        // Overwriting a block (chain replacement) drops the old block's index entries
+        if let Some(old) = self.blocks.insert(key, value)? {
+            if let Ok(old) = wire::from_bytes::<PulseBlock>(&old) {
+                self.unindex_transactions(&old)?;
+            }
+        }
+        self.index_transactions(block)?;
+        // A full copy supersedes a pruned header (e.g. after a chain replacement)
+        self.headers.remove(key)?;
         
         // Update chain height
         self.metadata.insert("chain_height", &block.index.to_be_bytes())?;
@@ -53,19 +317,24 @@ impl Storage {
     /// Load a block by index
     pub fn load_block(&self, index: u64) -> Result<PulseBlock, StorageError> {
         let key = index.to_be_bytes();
-        let value = self.blocks.get(key)?
-            .ok_or(StorageError::BlockNotFound(index))?;
-        let block: PulseBlock = serde_json::from_slice(&value)?;
+        let Some(value) = self.blocks.get(key)? else {
+            if self.headers.contains_key(key)? {
+                return Err(StorageError::BlockPruned(index));
+            }
+            return Err(StorageError::BlockNotFound(index));
+        };
+        let block: PulseBlock = wire::from_bytes(&value)?;
         Ok(block)
     }
     
-    /// Load all blocks (for chain reconstruction)
+    /// Load all blocks that still have bodies (for chain reconstruction; see
+    /// `load_block_headers` for the pruned ones)
     pub fn load_all_blocks(&self) -> Result<Vec<PulseBlock>, StorageError> {
         let mut blocks = Vec::new();
         
         for result in self.blocks.iter() {
             let (_, value) = result?;
-            let block: PulseBlock = serde_json::from_slice(&value)?;
+            let block: PulseBlock = wire::from_bytes(&value)?;
             blocks.push(block);
         }
         
@@ -75,6 +344,50 @@ impl Storage {
         Ok(blocks)
     }
     
+    /// Look up a confirmed transaction by id, returning it with its block index.
+    /// Index entries left behind by a replaced or truncated block read as not found.
+    pub fn find_transaction(&self, tx_id: &str) -> Result<Option<(u64, Transaction)>, StorageError> {
+        let Some(bytes) = self.tx_index.get(tx_id.as_bytes())? else {
+            return Ok(None);
+        };
+        let arr: [u8; 8] = bytes.as_ref().try_into().unwrap_or([0; 8]);
+        let index = u64::from_be_bytes(arr);
+        
+        let block = match self.load_block(index) {
+            Ok(block) => block,
+            Err(StorageError::BlockNotFound(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        Ok(block.transactions.into_iter()
+            .find(|tx| tx.tx_id == tx_id)
+            .map(|tx| (index, tx)))
+    }
+    
+    /// Transactions sent or received by `pubkey`, newest first, with their block
+    /// indexes; plus the total number of such transactions. Unknown pubkeys have
+    /// an empty history.
+    pub fn account_transactions(
+        &self,
+        pubkey: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<(u64, Transaction)>, usize), StorageError> {
+        let mut prefix = pubkey.as_bytes().to_vec();
+        prefix.push(b'/');
+        
+        let mut total = 0;
+        let mut page = Vec::new();
+        for result in self.account_txs.scan_prefix(&prefix).rev() {
+            let (key, value) = result?;
+            if total >= offset && page.len() < limit {
+                let index_bytes: [u8; 8] = key[prefix.len()..prefix.len() + 8].try_into().unwrap_or([0; 8]);
+                page.push((u64::from_be_bytes(index_bytes), wire::from_bytes(&value)?));
+            }
+            total += 1;
+        }
+        Ok((page, total))
+    }
+    
     /// Get chain height
     pub fn chain_height(&self) -> Result<u64, StorageError> {
         match self.metadata.get("chain_height")? {
@@ -88,7 +401,7 @@ impl Storage {
     
     /// Save account state
     pub fn save_account(&self, account: &Account) -> Result<(), StorageError> {
-        let value = serde_json::to_vec(account)?;
+        let value = wire::to_bytes(account)?;
         self.accounts.insert(account.pubkey.as_bytes(), value)?;
         Ok(())
     }
@@ -96,10 +409,7 @@ impl Storage {
     /// Load account state
     pub fn load_account(&self, pubkey: &str) -> Result<Option<Account>, StorageError> {
         match self.accounts.get(pubkey.as_bytes())? {
-            Some(value) => {
-                let account: Account = serde_json::from_slice(&value)?;
-                Ok(Some(account))
-            }
+            Some(value) => Ok(Some(self.decode_account(&value)?)),
             None => Ok(None),
         }
     }
@@ -110,25 +420,327 @@ impl Storage {
         
         for result in self.accounts.iter() {
             let (_, value) = result?;
-            let account: Account = serde_json::from_slice(&value)?;
-            accounts.push(account);
+            accounts.push(self.decode_account(&value)?);
         }
         
         Ok(accounts)
     }
     
+    /// Decode a stored account. Records written before amounts were integer
+    /// micro-PULSE hold PULSE floats; they're converted and saved back in the
+    /// current format, once.
+    fn decode_account(&self, value: &[u8]) -> Result<Account, StorageError> {
+        match wire::from_bytes::<Account>(value) {
+            Ok(account) => Ok(account),
+            Err(e) => {
+                let legacy: LegacyAccount = wire::from_bytes(value).map_err(|_| e)?;
+                let account = Account::from(legacy);
+                self.save_account(&account)?;
+                Ok(account)
+            }
+        }
+    }
+    
+    /// Delete every block at or above `height` (chain repair). Returns how many were removed.
+    pub fn truncate_blocks_from(&self, height: u64) -> Result<usize, StorageError> {
+        let mut removed = 0;
+        for result in self.blocks.range(height.to_be_bytes()..) {
+            let (key, value) = result?;
+            // Unindex its transactions (a corrupt block's entries are caught at lookup)
+            if let Ok(block) = wire::from_bytes::<PulseBlock>(&value) {
+                self.unindex_transactions(&block)?;
+            }
+            self.blocks.remove(key)?;
+            removed += 1;
+        }
+        for result in self.headers.range(height.to_be_bytes()..) {
+            let (key, _) = result?;
+            self.headers.remove(key)?;
+        }
+        match height.checked_sub(1) {
+            Some(tip) => self.metadata.insert("chain_height", &tip.to_be_bytes())?,
+            None => self.metadata.remove("chain_height")?,
+        };
+        Ok(removed)
+    }
+    
+    /// Replace all stored accounts with the given set
+    pub fn replace_accounts<'a>(&self, accounts: impl IntoIterator<Item = &'a Account>) -> Result<(), StorageError> {
+        self.accounts.clear()?;
+        for account in accounts {
+            self.save_account(account)?;
+        }
+        Ok(())
+    }
+    
     /// Flush to disk
     pub fn flush(&self) -> Result<(), StorageError> {
         self.db.flush()?;
         Ok(())
     }
+    
+    /// Replace the bodies of blocks `1..keep_after` with compact headers,
+    /// dropping their transaction index entries. Genesis is always kept so the
+    /// network can still be identified. Returns how many bodies were dropped.
+    pub fn prune_blocks(&self, keep_after: u64) -> Result<usize, StorageError> {
+        let mut pruned = 0;
+        for result in self.blocks.range(1u64.to_be_bytes()..keep_after.max(1).to_be_bytes()) {
+            let (key, value) = result?;
+            let block: PulseBlock = wire::from_bytes(&value)?;
+            self.headers.insert(&key, wire::to_bytes(&block.header())?)?;
+            self.unindex_transactions(&block)?;
+            self.blocks.remove(key)?;
+            pruned += 1;
+        }
+        Ok(pruned)
+    }
+    
+    /// Headers of pruned blocks, ordered by index
+    pub fn load_block_headers(&self) -> Result<Vec<BlockHeader>, StorageError> {
+        self.headers.iter()
+            .map(|result| Ok(wire::from_bytes(&result?.1)?))
+            .collect()
+    }
+    
+    /// Save a device's calibrated biometric baseline
+    pub fn save_baseline(&self, pubkey: &str, baseline: &Baseline) -> Result<(), StorageError> {
+        self.baselines.insert(pubkey.as_bytes(), wire::to_bytes(baseline)?)?;
+        Ok(())
+    }
+    
+    /// Load all saved biometric baselines as (pubkey, baseline)
+    pub fn load_baselines(&self) -> Result<Vec<(String, Baseline)>, StorageError> {
+        self.baselines.iter()
+            .map(|result| {
+                let (key, value) = result?;
+                Ok((String::from_utf8_lossy(&key).into_owned(), wire::from_bytes(&value)?))
+            })
+            .collect()
+    }
+    
+    /// Append an activity-feed event, dropping events older than the retention window
+    pub fn save_event(&self, event: &NodeEvent) -> Result<(), StorageError> {
+        let mut key = event.timestamp().to_be_bytes().to_vec();
+        key.extend_from_slice(&self.db.generate_id()?.to_be_bytes());
+        self.events.insert(key, serde_json::to_vec(event)?)?;
+        
+        let cutoff = event.timestamp().saturating_sub(EVENT_RETENTION_MS);
+        for key in self.events.range(..cutoff.to_be_bytes()).keys() {
+            self.events.remove(key?)?;
+        }
+        Ok(())
+    }
+    
+    /// The `limit` most recent saved events, oldest first
+    pub fn load_recent_events(&self, limit: usize) -> Result<Vec<NodeEvent>, StorageError> {
+        let mut events = self.events.iter().values().rev()
+            .take(limit)
+            .map(|value| Ok(serde_json::from_slice(&value?)?))
+            .collect::<Result<Vec<NodeEvent>, StorageError>>()?;
+        events.reverse();
+        Ok(events)
+    }
+    
+    /// Up to `limit` saved events newer than `timestamp`, oldest first
+    pub fn load_events_since(&self, timestamp: u64, limit: usize) -> Result<Vec<NodeEvent>, StorageError> {
+        self.events.range(timestamp.saturating_add(1).to_be_bytes()..).values()
+            .take(limit)
+            .map(|value| Ok(serde_json::from_slice(&value?)?))
+            .collect()
+    }
+    
+    /// Stream every block (in index order) and account as a snapshot.
+    /// A pruned store can't produce one, since snapshots carry full history.
+    pub fn export_snapshot(&self, writer: impl Write) -> Result<SnapshotSummary, StorageError> {
+        if let Some(result) = self.headers.iter().next() {
+            let (key, _) = result?;
+            let index = u64::from_be_bytes(key.as_ref().try_into().unwrap_or([0; 8]));
+            return Err(StorageError::BlockPruned(index));
+        }
+        let mut snapshot = snapshot::SnapshotWriter::new(writer)?;
+        for result in self.blocks.iter() {
+            let (_, value) = result?;
+            snapshot.block(&wire::from_bytes(&value)?)?;
+        }
+        for result in self.accounts.iter() {
+            let (_, value) = result?;
+            snapshot.account(&wire::from_bytes(&value)?)?;
+        }
+        snapshot.finish()
+    }
+    
+    /// Replace the stored chain and accounts with a snapshot. Every block's hash
+    /// and link to its predecessor is checked as it is read; the snapshot is
+    /// staged in separate trees and only swapped in once it has been read in
+    /// full, so a bad or truncated snapshot leaves the current data untouched.
+    pub fn import_snapshot(&self, reader: impl Read) -> Result<SnapshotSummary, StorageError> {
+        let staged_blocks = self.db.open_tree(STAGED_BLOCKS_TREE)?;
+        let staged_accounts = self.db.open_tree(STAGED_ACCOUNTS_TREE)?;
+        staged_blocks.clear()?;
+        staged_accounts.clear()?;
+        
+        let staged = (|| {
+            let mut snapshot = snapshot::SnapshotReader::new(reader)?;
+            let mut previous: Option<PulseBlock> = None;
+            while let Some(record) = snapshot.next_record()? {
+                match record {
+                    snapshot::SnapshotRecord::Block(block) => {
+                        snapshot::verify_link(&block, previous.as_ref())?;
+                        staged_blocks.insert(block.index.to_be_bytes(), wire::to_bytes(&block)?)?;
+                        previous = Some(block);
+                    }
+                    snapshot::SnapshotRecord::Account(account) => {
+                        staged_accounts.insert(account.pubkey.as_bytes(), wire::to_bytes(&account)?)?;
+                    }
+                }
+            }
+            if previous.is_none() {
+                return Err(StorageError::InvalidSnapshot("no blocks".into()));
+            }
+            Ok(snapshot.summary())
+        })();
+        
+        let summary = match staged {
+            Ok(summary) => summary,
+            Err(e) => {
+                self.db.drop_tree(STAGED_BLOCKS_TREE)?;
+                self.db.drop_tree(STAGED_ACCOUNTS_TREE)?;
+                return Err(e);
+            }
+        };
+        
+        // From here the import completes even if we crash: `open` resumes it
+        self.metadata.insert(SNAPSHOT_PENDING_MARKER, &[1u8])?;
+        self.db.flush()?;
+        self.commit_staged_snapshot()?;
+        
+        info!("💾 Imported snapshot: {} blocks, {} accounts", summary.blocks, summary.accounts);
+        Ok(summary)
+    }
+    
+    /// Replace live blocks and accounts with the staged snapshot and reindex
+    fn commit_staged_snapshot(&self) -> Result<(), StorageError> {
+        let staged_blocks = self.db.open_tree(STAGED_BLOCKS_TREE)?;
+        let staged_accounts = self.db.open_tree(STAGED_ACCOUNTS_TREE)?;
+        
+        self.blocks.clear()?;
+        self.headers.clear()?;
+        self.accounts.clear()?;
+        self.tx_index.clear()?;
+        self.account_txs.clear()?;
+        for result in staged_blocks.iter() {
+            let (key, value) = result?;
+            self.blocks.insert(key, value)?;
+        }
+        for result in staged_accounts.iter() {
+            let (key, value) = result?;
+            self.accounts.insert(key, value)?;
+        }
+        match self.blocks.last()? {
+            Some((key, _)) => self.metadata.insert("chain_height", key)?,
+            None => self.metadata.remove("chain_height")?,
+        };
+        self.rebuild_tx_indexes()?;
+        
+        self.db.drop_tree(STAGED_BLOCKS_TREE)?;
+        self.db.drop_tree(STAGED_ACCOUNTS_TREE)?;
+        self.metadata.remove(SNAPSHOT_PENDING_MARKER)?;
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+impl ChainStore for Storage {
+    fn save_block(&self, block: &PulseBlock) -> Result<(), StorageError> {
+        Storage::save_block(self, block)
+    }
+    
+    fn load_block(&self, index: u64) -> Result<PulseBlock, StorageError> {
+        Storage::load_block(self, index)
+    }
+    
+    fn load_all_blocks(&self) -> Result<Vec<PulseBlock>, StorageError> {
+        Storage::load_all_blocks(self)
+    }
+    
+    fn save_account(&self, account: &Account) -> Result<(), StorageError> {
+        Storage::save_account(self, account)
+    }
+    
+    fn load_all_accounts(&self) -> Result<Vec<Account>, StorageError> {
+        Storage::load_all_accounts(self)
+    }
+    
+    fn truncate_blocks_from(&self, height: u64) -> Result<usize, StorageError> {
+        Storage::truncate_blocks_from(self, height)
+    }
+    
+    fn replace_accounts(&self, accounts: &[Account]) -> Result<(), StorageError> {
+        Storage::replace_accounts(self, accounts)
+    }
+    
+    fn flush(&self) -> Result<(), StorageError> {
+        Storage::flush(self)
+    }
+    
+    fn prune_blocks(&self, keep_after: u64) -> Result<usize, StorageError> {
+        Storage::prune_blocks(self, keep_after)
+    }
+    
+    fn load_block_headers(&self) -> Result<Vec<BlockHeader>, StorageError> {
+        Storage::load_block_headers(self)
+    }
+    
+    fn save_baseline(&self, pubkey: &str, baseline: &Baseline) -> Result<(), StorageError> {
+        Storage::save_baseline(self, pubkey, baseline)
+    }
+    
+    fn load_baselines(&self) -> Result<Vec<(String, Baseline)>, StorageError> {
+        Storage::load_baselines(self)
+    }
+    
+    fn save_event(&self, event: &NodeEvent) -> Result<(), StorageError> {
+        Storage::save_event(self, event)
+    }
+    
+    fn load_recent_events(&self, limit: usize) -> Result<Vec<NodeEvent>, StorageError> {
+        Storage::load_recent_events(self, limit)
+    }
+    
+    fn load_events_since(&self, timestamp: u64, limit: usize) -> Result<Vec<NodeEvent>, StorageError> {
+        Storage::load_events_since(self, timestamp, limit)
+    }
+    
+    fn find_transaction(&self, tx_id: &str) -> Result<Option<(u64, Transaction)>, StorageError> {
+        Storage::find_transaction(self, tx_id)
+    }
+    
+    fn account_transactions(
+        &self,
+        pubkey: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<(u64, Transaction)>, usize), StorageError> {
+        Storage::account_transactions(self, pubkey, offset, limit)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::Amount;
     use tempfile::tempdir;
     
+    #[test]
+    fn test_open_locked_directory_fails() {
+        let dir = tempdir().unwrap();
+        let _first = Storage::open(dir.path()).unwrap();
+        
+        let err = Storage::open(dir.path()).err().unwrap();
+        assert!(matches!(err, StorageError::AlreadyLocked(_)), "{}", err);
+        assert!(!err.is_unwritable());
+    }
+    
     #[test]
     fn test_storage_roundtrip() {
         let dir = tempdir().unwrap();
@@ -144,6 +756,11 @@ mod tests {
             total_weight: 0.0,
             security: 0.0,
             bio_entropy: "0".repeat(64),
+            continuity_factors: Default::default(),
+            confidence_factors: Default::default(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
             block_hash: "xyz".to_string(),
         };
         
@@ -172,6 +789,11 @@ mod tests {
                 previous_hash: String::new(), heartbeats: vec![],
                 transactions: vec![], n_live: 0, total_weight: 0.0,
                 security: 0.0, bio_entropy: String::new(),
+                continuity_factors: Default::default(),
+                confidence_factors: Default::default(),
+                heartbeats_root: String::new(),
+                tx_root: String::new(),
+                chain_id: String::new(),
                 block_hash: format!("hash{}", i),
             };
             storage.save_block(&block).unwrap();
@@ -190,17 +812,42 @@ mod tests {
 
         let account = Account {
             pubkey: "abc123".to_string(),
-            balance: 42.5,
+            balance: 42_500_000,
             last_heartbeat: 1000,
-            total_earned: 100.0,
+            total_earned: 100_000_000,
             blocks_participated: 5,
+            nonce: 2,
         };
         storage.save_account(&account).unwrap();
 
         let loaded = storage.load_account("abc123").unwrap().unwrap();
         assert_eq!(loaded.pubkey, "abc123");
-        assert!((loaded.balance - 42.5).abs() < 1e-10);
+        assert_eq!(loaded.balance, 42_500_000);
         assert_eq!(loaded.blocks_participated, 5);
+        assert_eq!(loaded.nonce, 2);
+    }
+
+    #[test]
+    fn test_legacy_float_account_migrated() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        // As stored when balances were f64 PULSE
+        let legacy = br#"{"pubkey":"alice","balance":12.5,"last_heartbeat":1700000055000,"total_earned":20.25,"blocks_participated":3}"#;
+        storage.accounts.insert("alice", &legacy[..]).unwrap();
+
+        let accounts = storage.load_all_accounts().unwrap();
+        assert_eq!(accounts, vec![Account {
+            pubkey: "alice".to_string(),
+            balance: 12_500_000,
+            last_heartbeat: 1700000055000,
+            total_earned: 20_250_000,
+            blocks_participated: 3,
+            nonce: 0,
+        }]);
+        // Written back in the current format
+        let stored = storage.accounts.get("alice").unwrap().unwrap();
+        assert_eq!(wire::from_bytes::<Account>(&stored).unwrap(), accounts[0]);
+        assert_eq!(storage.load_account("alice").unwrap().unwrap(), accounts[0]);
     }
 
     #[test]
@@ -220,16 +867,326 @@ mod tests {
             index: 7, timestamp: 0, previous_hash: String::new(),
             heartbeats: vec![], transactions: vec![], n_live: 0,
             total_weight: 0.0, security: 0.0, bio_entropy: String::new(),
+            continuity_factors: Default::default(),
+            confidence_factors: Default::default(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
             block_hash: String::new(),
         };
         storage.save_block(&block).unwrap();
         assert_eq!(storage.chain_height().unwrap(), 7);
     }
 
+    #[test]
+    fn test_truncate_blocks_from() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        for i in 0..5u64 {
+            let block = PulseBlock {
+                index: i, timestamp: 0, previous_hash: String::new(),
+                heartbeats: vec![], transactions: vec![], n_live: 0,
+                total_weight: 0.0, security: 0.0, bio_entropy: String::new(),
+                continuity_factors: Default::default(),
+                confidence_factors: Default::default(),
+                heartbeats_root: String::new(),
+                tx_root: String::new(),
+                chain_id: String::new(),
+                block_hash: format!("hash{}", i),
+            };
+            storage.save_block(&block).unwrap();
+        }
+
+        assert_eq!(storage.truncate_blocks_from(3).unwrap(), 2);
+        assert_eq!(storage.load_all_blocks().unwrap().len(), 3);
+        assert_eq!(storage.chain_height().unwrap(), 2);
+        assert!(storage.load_block(3).is_err());
+    }
+
+    #[test]
+    fn test_find_transaction() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        let tx = |id: &str| Transaction {
+            tx_id: id.to_string(),
+            sender_pubkey: "s".to_string(),
+            recipient_pubkey: "r".to_string(),
+            amount: 2.5,
+            fee: 0.0,
+            timestamp: 0,
+            heartbeat_signature: String::new(),
+            not_before_height: 0,
+            nonce: 1,
+            signature_version: 0,
+            signature: String::new(),
+        };
+        for (i, txs) in [vec![], vec![tx("a"), tx("b")], vec![tx("c")]].into_iter().enumerate() {
+            let block = PulseBlock {
+                index: i as u64, timestamp: 0, previous_hash: String::new(),
+                heartbeats: vec![], transactions: txs, n_live: 0,
+                total_weight: 0.0, security: 0.0, bio_entropy: String::new(),
+                continuity_factors: Default::default(),
+                confidence_factors: Default::default(),
+                heartbeats_root: String::new(),
+                tx_root: String::new(),
+                chain_id: String::new(),
+                block_hash: String::new(),
+            };
+            storage.save_block(&block).unwrap();
+        }
+
+        let (index, found) = storage.find_transaction("b").unwrap().unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(found.amount, 2.5);
+        assert_eq!(storage.find_transaction("c").unwrap().unwrap().0, 2);
+        assert!(storage.find_transaction("missing").unwrap().is_none());
+
+        // Truncated blocks take their index entries with them
+        storage.truncate_blocks_from(2).unwrap();
+        assert!(storage.find_transaction("c").unwrap().is_none());
+        assert!(storage.find_transaction("a").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_account_transactions_newest_first() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        let tx = |id: &str, from: &str, to: &str| Transaction {
+            tx_id: id.to_string(),
+            sender_pubkey: from.to_string(),
+            recipient_pubkey: to.to_string(),
+            amount: 1.0,
+            fee: 0.0,
+            timestamp: 0,
+            heartbeat_signature: String::new(),
+            not_before_height: 0,
+            nonce: 1,
+            signature_version: 0,
+            signature: String::new(),
+        };
+        let block = |index: u64, transactions: Vec<Transaction>| PulseBlock {
+            index, timestamp: 0, previous_hash: String::new(),
+            heartbeats: vec![], transactions, n_live: 0,
+            total_weight: 0.0, security: 0.0, bio_entropy: String::new(),
+            continuity_factors: Default::default(),
+            confidence_factors: Default::default(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
+            block_hash: String::new(),
+        };
+
+        {
+            let storage = Storage::open(&path).unwrap();
+            storage.save_block(&block(1, vec![tx("t1", "alice", "bob"), tx("t2", "carol", "alice")])).unwrap();
+            storage.save_block(&block(2, vec![tx("t3", "bob", "alice")])).unwrap();
+
+            let (page, total) = storage.account_transactions("alice", 0, 10).unwrap();
+            assert_eq!(total, 3);
+            let ids: Vec<_> = page.iter().map(|(i, tx)| (*i, tx.tx_id.as_str())).collect();
+            assert_eq!(ids, vec![(2, "t3"), (1, "t2"), (1, "t1")]);
+
+            let (page, total) = storage.account_transactions("alice", 1, 1).unwrap();
+            assert_eq!((total, page[0].1.tx_id.as_str()), (3, "t2"));
+
+            // No history is an empty page, not an error
+            let (page, total) = storage.account_transactions("dave", 0, 10).unwrap();
+            assert!(page.is_empty());
+            assert_eq!(total, 0);
+            // Prefixes of other pubkeys don't match
+            assert_eq!(storage.account_transactions("ali", 0, 10).unwrap().1, 0);
+
+            // Replacing block 2 drops its old entries
+            storage.save_block(&block(2, vec![tx("t4", "erin", "bob")])).unwrap();
+            assert_eq!(storage.account_transactions("alice", 0, 10).unwrap().1, 2);
+            assert!(storage.find_transaction("t3").unwrap().is_none());
+            storage.flush().unwrap();
+        }
+
+        // Databases written before the indexes existed are indexed on open
+        {
+            let db = sled::open(&path).unwrap();
+            for tree in ["tx_index", "account_txs"] {
+                db.open_tree(tree).unwrap().clear().unwrap();
+            }
+            db.open_tree("metadata").unwrap().remove(TX_INDEX_MARKER).unwrap();
+            db.flush().unwrap();
+        }
+        let storage = Storage::open(&path).unwrap();
+        assert_eq!(storage.account_transactions("bob", 0, 10).unwrap().1, 2);
+        assert_eq!(storage.find_transaction("t4").unwrap().unwrap().0, 2);
+    }
+
+    fn linked_chain(len: u64) -> Vec<PulseBlock> {
+        let mut chain: Vec<PulseBlock> = Vec::new();
+        for index in 0..len {
+            let mut block = PulseBlock {
+                index, timestamp: index * 1000,
+                previous_hash: chain.last().map(|b| b.block_hash.clone()).unwrap_or_default(),
+                heartbeats: vec![], transactions: vec![], n_live: 0,
+                total_weight: 0.0, security: 0.0, bio_entropy: String::new(),
+                continuity_factors: Default::default(),
+                confidence_factors: Default::default(),
+                heartbeats_root: String::new(),
+                tx_root: String::new(),
+                chain_id: String::new(),
+                block_hash: String::new(),
+            };
+            if index == 1 {
+                block.transactions.push(Transaction {
+                    tx_id: "snap-tx".to_string(),
+                    sender_pubkey: "alice".to_string(),
+                    recipient_pubkey: "bob".to_string(),
+                    amount: 1.0,
+                    fee: 0.0,
+                    timestamp: 0,
+                    heartbeat_signature: String::new(),
+                    not_before_height: 0,
+                    nonce: 1,
+                    signature_version: 0,
+                    signature: String::new(),
+                });
+            }
+            block.block_hash = block.compute_hash();
+            chain.push(block);
+        }
+        chain
+    }
+
+    fn account(pubkey: &str, balance: Amount) -> Account {
+        Account { pubkey: pubkey.to_string(), balance, ..Default::default() }
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let source_dir = tempdir().unwrap();
+        let source = Storage::open(source_dir.path()).unwrap();
+        for block in linked_chain(4) {
+            source.save_block(&block).unwrap();
+        }
+        source.save_account(&account("alice", 5)).unwrap();
+        source.save_account(&account("bob", 7)).unwrap();
+
+        let mut snapshot = Vec::new();
+        let exported = source.export_snapshot(&mut snapshot).unwrap();
+        assert_eq!(exported, SnapshotSummary { blocks: 4, accounts: 2 });
+
+        let dest_dir = tempdir().unwrap();
+        let dest = Storage::open(dest_dir.path()).unwrap();
+        dest.save_block(&linked_chain(1)[0]).unwrap();
+        assert_eq!(dest.import_snapshot(snapshot.as_slice()).unwrap(), exported);
+
+        assert_eq!(dest.chain_height().unwrap(), 3);
+        let blocks = dest.load_all_blocks().unwrap();
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks[3].block_hash, source.load_block(3).unwrap().block_hash);
+        assert_eq!(dest.load_account("bob").unwrap().unwrap().balance, 7);
+        // Transaction indexes are rebuilt for the imported blocks
+        assert_eq!(dest.find_transaction("snap-tx").unwrap().unwrap().0, 1);
+        assert_eq!(dest.account_transactions("alice", 0, 10).unwrap().1, 1);
+    }
+
+    #[test]
+    fn test_bad_snapshot_leaves_store_untouched() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        for block in linked_chain(2) {
+            storage.save_block(&block).unwrap();
+        }
+        storage.save_account(&account("carol", 3)).unwrap();
+
+        let encode = |blocks: &[PulseBlock]| {
+            let mut out = Vec::new();
+            let mut writer = snapshot::SnapshotWriter::new(&mut out).unwrap();
+            for block in blocks {
+                writer.block(block).unwrap();
+            }
+            writer.account(&account("mallory", 1_000_000_000)).unwrap();
+            writer.finish().unwrap();
+            out
+        };
+
+        // Broken link
+        let mut chain = linked_chain(4);
+        chain[2].previous_hash = "f".repeat(64);
+        chain[2].block_hash = chain[2].compute_hash();
+        assert!(matches!(storage.import_snapshot(encode(&chain).as_slice()), Err(StorageError::InvalidSnapshot(_))));
+
+        // Truncated download
+        let good = encode(&linked_chain(4));
+        assert!(matches!(storage.import_snapshot(&good[..good.len() - 3]), Err(StorageError::InvalidSnapshot(_))));
+
+        // Not a snapshot at all
+        assert!(storage.import_snapshot(&b"<html>"[..]).is_err());
+
+        assert_eq!(storage.chain_height().unwrap(), 1);
+        assert_eq!(storage.load_all_blocks().unwrap().len(), 2);
+        assert!(storage.load_account("mallory").unwrap().is_none());
+        assert!(storage.load_account("carol").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_interrupted_snapshot_import_resumes_on_open() {
+        let dir = tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        
+        // A verified snapshot was staged but the swap never ran
+        let staged = db.open_tree(STAGED_BLOCKS_TREE).unwrap();
+        for block in linked_chain(3) {
+            staged.insert(block.index.to_be_bytes(), serde_json::to_vec(&block).unwrap()).unwrap();
+        }
+        db.open_tree("metadata").unwrap().insert(SNAPSHOT_PENDING_MARKER, &[1u8]).unwrap();
+        
+        let storage = Storage::from_db(db).unwrap();
+        assert_eq!(storage.chain_height().unwrap(), 2);
+        assert_eq!(storage.load_all_blocks().unwrap().len(), 3);
+        assert!(storage.metadata.get(SNAPSHOT_PENDING_MARKER).unwrap().is_none());
+    }
+
     #[test]
     fn test_flush() {
         let dir = tempdir().unwrap();
         let storage = Storage::open(dir.path()).unwrap();
         assert!(storage.flush().is_ok());
     }
+
+    #[test]
+    fn test_prune_blocks_keeps_headers() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        let chain = linked_chain(5);
+        for block in &chain {
+            storage.save_block(block).unwrap();
+        }
+
+        assert_eq!(storage.prune_blocks(3).unwrap(), 2);
+        assert!(matches!(storage.load_block(1), Err(StorageError::BlockPruned(1))));
+        assert!(matches!(storage.load_block(2), Err(StorageError::BlockPruned(2))));
+        assert!(matches!(storage.load_block(9), Err(StorageError::BlockNotFound(9))));
+        assert_eq!(storage.load_block(0).unwrap().block_hash, chain[0].block_hash);
+
+        // Headers keep the linkage; bodies and their tx index entries are gone
+        let headers = storage.load_block_headers().unwrap();
+        assert_eq!(headers, vec![chain[1].header(), chain[2].header()]);
+        assert_eq!(storage.load_all_blocks().unwrap().len(), 3);
+        assert!(storage.find_transaction("snap-tx").unwrap().is_none());
+        assert_eq!(storage.chain_height().unwrap(), 4);
+        assert!(matches!(storage.export_snapshot(Vec::new()), Err(StorageError::BlockPruned(1))));
+
+        // Pruning again is a no-op; re-saving a block restores its body
+        assert_eq!(storage.prune_blocks(3).unwrap(), 0);
+        storage.save_block(&chain[1]).unwrap();
+        assert_eq!(storage.load_block(1).unwrap().block_hash, chain[1].block_hash);
+        assert_eq!(storage.load_block_headers().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_baselines_roundtrip() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        assert!(storage.load_baselines().unwrap().is_empty());
+        let baseline = Baseline { mean_hr: 54.5, sdnn: 0.4, rmssd: 9.0, samples: 30 };
+        storage.save_baseline("athlete", &baseline).unwrap();
+        assert_eq!(storage.load_baselines().unwrap(), vec![("athlete".to_string(), baseline)]);
+    }
 }
@@ -0,0 +1,163 @@
+//! Chain snapshot format for fast bootstrap (`GET /snapshot`, `--bootstrap-url`).
+//!
+//! A snapshot is the magic bytes followed by length-prefixed records: a one
+//! byte tag, a big-endian `u32` payload length and the JSON payload. Blocks
+//! come first in index order, then accounts, then an end record carrying both
+//! counts so a truncated download is detected rather than half-imported.
+
+use std::io::{Read, Write};
+
+use super::StorageError;
+use crate::types::{Account, PulseBlock};
+
+/// Leading bytes of every snapshot (format version 1)
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"PULSESN1";
+
+/// Largest record payload accepted on import
+const MAX_RECORD_BYTES: u32 = 64 * 1024 * 1024;
+
+const TAG_BLOCK: u8 = b'B';
+const TAG_ACCOUNT: u8 = b'A';
+const TAG_END: u8 = b'E';
+
+/// What a snapshot contained
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct SnapshotSummary {
+    pub blocks: u64,
+    pub accounts: u64,
+}
+
+/// One record read from a snapshot
+pub enum SnapshotRecord {
+    Block(PulseBlock),
+    Account(Account),
+}
+
+/// Streams records into a snapshot
+pub struct SnapshotWriter<W: Write> {
+    inner: W,
+    summary: SnapshotSummary,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    pub fn new(mut inner: W) -> Result<Self, StorageError> {
+        inner.write_all(SNAPSHOT_MAGIC)?;
+        Ok(Self { inner, summary: SnapshotSummary { blocks: 0, accounts: 0 } })
+    }
+
+    /// Append a block; blocks must be written in index order, before any account
+    pub fn block(&mut self, block: &PulseBlock) -> Result<(), StorageError> {
+        self.record(TAG_BLOCK, &serde_json::to_vec(block)?)?;
+        self.summary.blocks += 1;
+        Ok(())
+    }
+
+    pub fn account(&mut self, account: &Account) -> Result<(), StorageError> {
+        self.record(TAG_ACCOUNT, &serde_json::to_vec(account)?)?;
+        self.summary.accounts += 1;
+        Ok(())
+    }
+
+    /// Write the end record and flush
+    pub fn finish(mut self) -> Result<SnapshotSummary, StorageError> {
+        let mut counts = [0u8; 16];
+        counts[..8].copy_from_slice(&self.summary.blocks.to_be_bytes());
+        counts[8..].copy_from_slice(&self.summary.accounts.to_be_bytes());
+        self.record(TAG_END, &counts)?;
+        self.inner.flush()?;
+        Ok(self.summary)
+    }
+
+    fn record(&mut self, tag: u8, payload: &[u8]) -> Result<(), StorageError> {
+        let len = u32::try_from(payload.len())
+            .map_err(|_| StorageError::InvalidSnapshot("record too large".into()))?;
+        self.inner.write_all(&[tag])?;
+        self.inner.write_all(&len.to_be_bytes())?;
+        self.inner.write_all(payload)?;
+        Ok(())
+    }
+}
+
+/// Reads records back, checking the framing and the end record's counts
+pub struct SnapshotReader<R: Read> {
+    inner: R,
+    seen: SnapshotSummary,
+    finished: bool,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    pub fn new(mut inner: R) -> Result<Self, StorageError> {
+        let mut magic = [0u8; 8];
+        inner.read_exact(&mut magic).map_err(truncated)?;
+        if &magic != SNAPSHOT_MAGIC {
+            return Err(StorageError::InvalidSnapshot("not a Pulse snapshot".into()));
+        }
+        Ok(Self { inner, seen: SnapshotSummary { blocks: 0, accounts: 0 }, finished: false })
+    }
+
+    /// Next record, or `None` after a valid end record
+    pub fn next_record(&mut self) -> Result<Option<SnapshotRecord>, StorageError> {
+        if self.finished {
+            return Ok(None);
+        }
+        let mut header = [0u8; 5];
+        self.inner.read_exact(&mut header).map_err(truncated)?;
+        let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+        if len > MAX_RECORD_BYTES {
+            return Err(StorageError::InvalidSnapshot(format!("record of {} bytes", len)));
+        }
+        let mut payload = vec![0u8; len as usize];
+        self.inner.read_exact(&mut payload).map_err(truncated)?;
+
+        match header[0] {
+            TAG_BLOCK if self.seen.accounts == 0 => {
+                self.seen.blocks += 1;
+                Ok(Some(SnapshotRecord::Block(serde_json::from_slice(&payload)?)))
+            }
+            TAG_ACCOUNT => {
+                self.seen.accounts += 1;
+                Ok(Some(SnapshotRecord::Account(serde_json::from_slice(&payload)?)))
+            }
+            TAG_END if payload.len() == 16 => {
+                let blocks = u64::from_be_bytes(payload[..8].try_into().unwrap_or_default());
+                let accounts = u64::from_be_bytes(payload[8..].try_into().unwrap_or_default());
+                if (SnapshotSummary { blocks, accounts }) != self.seen {
+                    return Err(StorageError::InvalidSnapshot(format!(
+                        "end record claims {} blocks / {} accounts, read {} / {}",
+                        blocks, accounts, self.seen.blocks, self.seen.accounts)));
+                }
+                self.finished = true;
+                Ok(None)
+            }
+            tag => Err(StorageError::InvalidSnapshot(format!("unexpected record tag {:#04x}", tag))),
+        }
+    }
+
+    /// Counts of the records read so far
+    pub fn summary(&self) -> SnapshotSummary {
+        self.seen
+    }
+}
+
+fn truncated(e: std::io::Error) -> StorageError {
+    if e.kind() == std::io::ErrorKind::UnexpectedEof {
+        StorageError::InvalidSnapshot("truncated".into())
+    } else {
+        StorageError::Io(e)
+    }
+}
+
+/// Check `block` is the next link after `previous` (or a genesis at index 0)
+pub fn verify_link(block: &PulseBlock, previous: Option<&PulseBlock>) -> Result<(), StorageError> {
+    if block.block_hash != block.compute_hash() || !block.merkle_roots_valid() {
+        return Err(StorageError::InvalidSnapshot(format!("block #{} hash doesn't match its contents", block.index)));
+    }
+    let linked = match previous {
+        Some(prev) => block.index == prev.index + 1 && block.previous_hash == prev.block_hash,
+        None => block.index == 0,
+    };
+    if !linked {
+        return Err(StorageError::InvalidSnapshot(format!("chain link broken at block #{}", block.index)));
+    }
+    Ok(())
+}
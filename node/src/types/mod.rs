@@ -1,8 +1,15 @@
 //! Core data types for the Pulse Network.
 
+pub mod merkle;
+
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+use crate::crypto::{self, CryptoError};
+use crate::network::wire;
+
+pub use merkle::verify_merkle_proof;
+
 /// Motion vector from device accelerometer
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Motion {
@@ -17,6 +24,81 @@ impl Motion {
     }
 }
 
+/// Coefficients of the heartbeat weight formula W_i = α·HR_norm + β·M_norm + γ·continuity.
+/// They should sum to 1.0 so weights stay in [0, 1].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeightParams {
+    /// Heart rate weight (α)
+    pub alpha: f64,
+    /// Motion weight (β)
+    pub beta: f64,
+    /// Continuity weight (γ)
+    pub gamma: f64,
+}
+
+impl Default for WeightParams {
+    fn default() -> Self {
+        Self {
+            alpha: 0.4,
+            beta: 0.3,
+            gamma: 0.3,
+        }
+    }
+}
+
+/// Optional fields are left out of JSON when empty. Bincode has no field
+/// names to tell a missing field apart, so it always gets them.
+fn skip_empty_map<K, V>(map: &BTreeMap<K, V>) -> bool {
+    map.is_empty() && !wire::writing_bincode()
+}
+
+/// See [`skip_empty_map`]
+fn skip_empty_str(s: &str) -> bool {
+    s.is_empty() && !wire::writing_bincode()
+}
+
+/// See [`skip_empty_map`]
+fn skip_false(b: &bool) -> bool {
+    !*b && !wire::writing_bincode()
+}
+
+/// See [`skip_empty_map`]
+fn skip_zero(v: &u8) -> bool {
+    *v == 0 && !wire::writing_bincode()
+}
+
+/// See [`skip_empty_map`]
+fn skip_zero_u64(v: &u64) -> bool {
+    *v == 0 && !wire::writing_bincode()
+}
+
+/// See [`skip_empty_map`]
+fn skip_zero_f64(v: &f64) -> bool {
+    *v == 0.0 && !wire::writing_bincode()
+}
+
+/// Current signing format. Version 0 (legacy) signs the bare JSON; version 1
+/// prefixes it with a per-type domain tag, so a signature over one message
+/// type can never be valid for another.
+pub const SIGNATURE_VERSION: u8 = 1;
+
+/// Domain tag of version 1 heartbeat signatures
+const HEARTBEAT_DOMAIN_TAG: &[u8] = b"pulse-heartbeat-v1";
+
+/// Domain tag of version 1 transaction signatures
+const TRANSACTION_DOMAIN_TAG: &[u8] = b"pulse-tx-v1";
+
+/// Prefix `body` with `tag` unless it's a legacy (version 0) signature
+fn domain_separated(tag: &[u8], signature_version: u8, body: Vec<u8>) -> Vec<u8> {
+    if signature_version == 0 {
+        return body;
+    }
+    let mut bytes = Vec::with_capacity(tag.len() + body.len());
+    bytes.extend_from_slice(tag);
+    bytes.extend(body);
+    bytes
+}
+
 /// A heartbeat packet from a device - the atomic unit of Proof-of-Life
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Heartbeat {
@@ -28,11 +110,24 @@ pub struct Heartbeat {
     pub motion: Motion,
     /// Body temperature in Celsius
     pub temperature: f32,
-    /// Device/user public key (hex-encoded)
+    /// Device/user public key (hex-encoded). May be omitted from recoverable
+    /// heartbeats; see [`Heartbeat::resolve_pubkey`].
+    #[serde(default, skip_serializing_if = "skip_empty_str")]
     pub device_pubkey: String,
+    /// Signing format, see [`SIGNATURE_VERSION`]. 0 for legacy clients.
+    #[serde(default, skip_serializing_if = "skip_zero")]
+    pub signature_version: u8,
     /// ECDSA signature of the packet (hex-encoded)
     #[serde(default)]
     pub signature: String,
+    /// The signature is 65-byte recoverable and the pubkey is not signed:
+    /// it is derived from the signature instead
+    #[serde(default, skip_serializing_if = "skip_false")]
+    pub recoverable: bool,
+    /// Vendor tags (firmware version, device model, ...) for analytics.
+    /// Not signed, not weighted and not committed to block hashes.
+    #[serde(default, skip_serializing_if = "skip_empty_map")]
+    pub metadata: BTreeMap<String, String>,
 }
 
 impl Heartbeat {
@@ -44,11 +139,18 @@ impl Heartbeat {
     ///
     /// The continuity factor requires external state (how long this device has
     /// been continuously pulsing), so it's passed as a parameter.
+    /// Uses the default coefficients; see [`Heartbeat::weight_with_params`].
     pub fn weight_with_continuity(&self, continuity_factor: f64) -> f64 {
-        const ALPHA: f64 = 0.4;  // Heart rate weight
-        const BETA: f64 = 0.3;   // Motion weight  
-        const GAMMA: f64 = 0.3;  // Continuity weight
-        
+        self.weight_with_params(continuity_factor, &WeightParams::default())
+    }
+    
+    /// Weighted contribution using explicit coefficients (from `ConsensusConfig`)
+    pub fn weight_with_params(&self, continuity_factor: f64, params: &WeightParams) -> f64 {
+        self.weight_breakdown(continuity_factor, params).weight
+    }
+    
+    /// The normalized components behind [`Heartbeat::weight_with_params`]
+    pub fn weight_breakdown(&self, continuity_factor: f64, params: &WeightParams) -> WeightBreakdown {
         // Normalize heart rate to [0, 1] using sigmoid-like mapping:
         // - 30 BPM (minimum valid) → ~0.0
         // - 70 BPM (resting) → ~0.5
@@ -57,18 +159,18 @@ impl Heartbeat {
         // This prevents extreme HR from giving disproportionate advantage
         let hr_norm = Self::normalize_heart_rate(self.heart_rate);
         
-        // Normalize motion magnitude to [0, 1]:
-        // - 0.0 g (stationary) → 0.0
-        // - 0.5 g (walking) → ~0.5
-        // - 2.0+ g (running/vigorous) → 1.0
-        // Capped to prevent accelerometer spoofing from being profitable
-        let motion_norm = (self.motion.magnitude() / 2.0).min(1.0);
+        let motion_norm = Self::normalize_motion(self.motion.magnitude());
         
         // Continuity: [0, 1] — how long this device has been continuously pulsing
         // 0.0 = just joined, 1.0 = pulsing for full window (e.g., 5+ minutes)
-        let cont_norm = continuity_factor.clamp(0.0, 1.0);
+        let continuity = continuity_factor.clamp(0.0, 1.0);
         
-        ALPHA * hr_norm + BETA * motion_norm + GAMMA * cont_norm
+        WeightBreakdown {
+            hr_norm,
+            motion_norm,
+            continuity,
+            weight: params.alpha * hr_norm + params.beta * motion_norm + params.gamma * continuity,
+        }
     }
     
     /// Backward-compatible weight (assumes full continuity)
@@ -82,7 +184,7 @@ impl Heartbeat {
     ///  - Resting HR (~60-70) gives moderate weight
     ///  - Active HR (~100-150) gives higher weight  
     ///  - Extreme HR (>180) plateaus — no incentive to game via overexertion
-    fn normalize_heart_rate(hr: u16) -> f64 {
+    pub fn normalize_heart_rate(hr: u16) -> f64 {
         let hr = hr as f64;
         // Logistic: 1 / (1 + e^(-k*(x - midpoint)))
         // k=0.04 gives a gentle S-curve across 30-220 BPM range
@@ -90,19 +192,64 @@ impl Heartbeat {
         1.0 / (1.0 + (-0.04 * (hr - 100.0)).exp())
     }
     
-    /// Get the signable portion of the heartbeat (excludes signature).
+    /// Normalize motion magnitude (g) to [0, 1]:
+    /// - 0.0 g (stationary) → 0.0
+    /// - 0.5 g (walking) → ~0.5
+    /// - 2.0+ g (running/vigorous) → 1.0
+    ///
+    /// Capped to prevent accelerometer spoofing from being profitable
+    pub fn normalize_motion(magnitude: f64) -> f64 {
+        (magnitude / 2.0).min(1.0)
+    }
+    
+    /// Serialized size of the metadata tags (keys plus values)
+    pub fn metadata_bytes(&self) -> usize {
+        self.metadata.iter().map(|(k, v)| k.len() + v.len()).sum()
+    }
+    
+    /// Get the signable portion of the heartbeat (excludes signature and metadata).
     /// Uses sorted keys for cross-platform compatibility (iOS, Android, Web).
     pub fn signable_bytes(&self) -> Vec<u8> {
         let mut map = BTreeMap::new();
-        map.insert("device_pubkey", serde_json::to_value(&self.device_pubkey).unwrap());
+        if self.recoverable {
+            map.insert("recoverable", serde_json::Value::Bool(true));
+        } else {
+            map.insert("device_pubkey", serde_json::to_value(&self.device_pubkey).unwrap());
+        }
         map.insert("heart_rate", serde_json::to_value(self.heart_rate).unwrap());
         map.insert("motion", serde_json::to_value(&self.motion).unwrap());
         map.insert("temperature", serde_json::to_value(self.temperature).unwrap());
         map.insert("timestamp", serde_json::to_value(self.timestamp).unwrap());
-        serde_json::to_vec(&map).unwrap()
+        domain_separated(HEARTBEAT_DOMAIN_TAG, self.signature_version, serde_json::to_vec(&map).unwrap())
+    }
+    
+    /// For recoverable heartbeats, derive the signer's pubkey from the signature.
+    /// An omitted `device_pubkey` is filled in; a claimed one must match.
+    /// Non-recoverable heartbeats are left as they are.
+    pub fn resolve_pubkey(&mut self) -> Result<(), CryptoError> {
+        if !self.recoverable {
+            return Ok(());
+        }
+        let recovered = crypto::recover_pubkey(&self.signable_bytes(), &self.signature)?;
+        if self.device_pubkey.is_empty() {
+            self.device_pubkey = recovered;
+        } else if !crypto::same_pubkey(&self.device_pubkey, &recovered)? {
+            return Err(CryptoError::VerificationFailed);
+        }
+        Ok(())
     }
 }
 
+/// Components of a heartbeat's weight, each in [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct WeightBreakdown {
+    pub hr_norm: f64,
+    pub motion_norm: f64,
+    pub continuity: f64,
+    /// α·hr_norm + β·motion_norm + γ·continuity
+    pub weight: f64,
+}
+
 /// A pulse-backed transaction
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
@@ -114,10 +261,25 @@ pub struct Transaction {
     pub recipient_pubkey: String,
     /// Amount of PULSE tokens
     pub amount: f64,
+    /// Fee in PULSE paid on top of `amount`, shared among the block's
+    /// participants. Producers include higher fees first when a block is full.
+    #[serde(default, skip_serializing_if = "skip_zero_f64")]
+    pub fee: f64,
     /// Unix timestamp in milliseconds
     pub timestamp: u64,
     /// Reference to sender's heartbeat signature (proves life)
     pub heartbeat_signature: String,
+    /// Earliest block height this transaction may be included in (0 = any).
+    /// Used for vesting and scheduled payments.
+    #[serde(default, skip_serializing_if = "skip_zero_u64")]
+    pub not_before_height: u64,
+    /// Per-sender sequence number; must be exactly one more than the sender's
+    /// last included transaction. Prevents replaying a signed transaction.
+    #[serde(default, skip_serializing_if = "skip_zero_u64")]
+    pub nonce: u64,
+    /// Signing format, see [`SIGNATURE_VERSION`]. 0 for legacy clients.
+    #[serde(default, skip_serializing_if = "skip_zero")]
+    pub signature_version: u8,
     /// Transaction signature
     #[serde(default)]
     pub signature: String,
@@ -134,7 +296,17 @@ impl Transaction {
         map.insert("sender_pubkey", serde_json::to_value(&self.sender_pubkey).unwrap());
         map.insert("timestamp", serde_json::to_value(self.timestamp).unwrap());
         map.insert("tx_id", serde_json::to_value(&self.tx_id).unwrap());
-        serde_json::to_vec(&map).unwrap()
+        // Only signed when set, so existing clients' signatures stay valid
+        if self.not_before_height > 0 {
+            map.insert("not_before_height", serde_json::to_value(self.not_before_height).unwrap());
+        }
+        if self.nonce > 0 {
+            map.insert("nonce", serde_json::to_value(self.nonce).unwrap());
+        }
+        if self.fee != 0.0 {
+            map.insert("fee", serde_json::to_value(self.fee).unwrap());
+        }
+        domain_separated(TRANSACTION_DOMAIN_TAG, self.signature_version, serde_json::to_vec(&map).unwrap())
     }
 }
 
@@ -161,66 +333,290 @@ pub struct PulseBlock {
     /// Provides non-deterministic randomness for the network
     #[serde(default)]
     pub bio_entropy: String,
+    /// Continuity factor each participant was weighted with (pubkey -> [0, 1]),
+    /// so receivers can recompute the exact reward split. Empty on legacy blocks.
+    #[serde(default, skip_serializing_if = "skip_empty_map")]
+    pub continuity_factors: BTreeMap<String, f64>,
+    /// Biometric confidence each participant's weight was scaled by (pubkey -> [0, 1]).
+    /// Empty on blocks from before confidence weighting; missing entries count as 1.
+    #[serde(default, skip_serializing_if = "skip_empty_map")]
+    pub confidence_factors: BTreeMap<String, f64>,
+    /// Merkle root over the heartbeats' signable bytes. When both roots are set
+    /// the hash commits to them instead of the full arrays; empty on legacy blocks.
+    #[serde(default, skip_serializing_if = "skip_empty_str")]
+    pub heartbeats_root: String,
+    /// Merkle root over the transactions' signable bytes (see `heartbeats_root`)
+    #[serde(default, skip_serializing_if = "skip_empty_str")]
+    pub tx_root: String,
+    /// Network this block belongs to (`ConsensusConfig::chain_id`); empty on
+    /// the original network
+    #[serde(default, skip_serializing_if = "skip_empty_str")]
+    pub chain_id: String,
     /// Block hash
     #[serde(default)]
     pub block_hash: String,
 }
 
+/// What remains of a block after its body is pruned: enough to keep chain
+/// linkage and cumulative weight verifiable. Also served to light clients,
+/// which check heartbeat and transaction proofs against the Merkle roots.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub index: u64,
+    pub timestamp: u64,
+    pub previous_hash: String,
+    pub block_hash: String,
+    pub n_live: usize,
+    pub total_weight: f64,
+    pub security: f64,
+    /// See [`PulseBlock::heartbeats_root`]; empty for legacy blocks
+    #[serde(default, skip_serializing_if = "skip_empty_str")]
+    pub heartbeats_root: String,
+    /// See [`PulseBlock::tx_root`]; empty for legacy blocks
+    #[serde(default, skip_serializing_if = "skip_empty_str")]
+    pub tx_root: String,
+}
+
 impl PulseBlock {
-    /// Compute the block hash
+    /// Compact header kept when this block's body is pruned, and served to light clients
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            index: self.index,
+            timestamp: self.timestamp,
+            previous_hash: self.previous_hash.clone(),
+            block_hash: self.block_hash.clone(),
+            n_live: self.n_live,
+            total_weight: self.total_weight,
+            security: self.security,
+            heartbeats_root: self.heartbeats_root.clone(),
+            tx_root: self.tx_root.clone(),
+        }
+    }
+    
+    /// Compute the block hash.
+    /// Blocks with Merkle roots commit to the roots only; legacy blocks hash the
+    /// full heartbeat and transaction arrays. Roots are taken as-is here — use
+    /// `merkle_roots_valid` to check they match the contents.
     pub fn compute_hash(&self) -> String {
         use sha2::{Sha256, Digest};
         
-        let data = serde_json::json!({
+        let mut data = serde_json::json!({
             "index": self.index,
             "timestamp": self.timestamp,
             "previous_hash": self.previous_hash,
-            "heartbeats": self.heartbeats,
-            "transactions": self.transactions,
             "n_live": self.n_live,
             "total_weight": self.total_weight,
             "security": self.security,
             "bio_entropy": self.bio_entropy,
         });
+        if self.has_merkle_roots() {
+            data["heartbeats_root"] = serde_json::to_value(&self.heartbeats_root).unwrap();
+            data["tx_root"] = serde_json::to_value(&self.tx_root).unwrap();
+        } else {
+            data["heartbeats"] = serde_json::to_value(&self.heartbeats).unwrap();
+            data["transactions"] = serde_json::to_value(&self.transactions).unwrap();
+        }
+        // Heartbeat metadata is non-consensus: never part of the hash
+        if let Some(heartbeats) = data["heartbeats"].as_array_mut() {
+            for hb in heartbeats {
+                if let Some(obj) = hb.as_object_mut() {
+                    obj.remove("metadata");
+                }
+            }
+        }
+        // Only committed when present, so legacy block hashes are unchanged
+        if !self.continuity_factors.is_empty() {
+            data["continuity_factors"] = serde_json::to_value(&self.continuity_factors).unwrap();
+        }
+        if !self.confidence_factors.is_empty() {
+            data["confidence_factors"] = serde_json::to_value(&self.confidence_factors).unwrap();
+        }
+        if !self.chain_id.is_empty() {
+            data["chain_id"] = serde_json::to_value(&self.chain_id).unwrap();
+        }
         
         let bytes = serde_json::to_vec(&data).unwrap();
         let hash = Sha256::digest(&bytes);
         hex::encode(hash)
     }
     
+    /// Whether this block commits to its contents via Merkle roots
+    pub fn has_merkle_roots(&self) -> bool {
+        !self.heartbeats_root.is_empty() && !self.tx_root.is_empty()
+    }
+    
+    /// Merkle root over this block's heartbeats
+    pub fn compute_heartbeats_root(&self) -> String {
+        merkle::merkle_root(self.heartbeats.iter().map(|hb| hb.signable_bytes()))
+    }
+    
+    /// Merkle root over this block's transactions
+    pub fn compute_tx_root(&self) -> String {
+        merkle::merkle_root(self.transactions.iter().map(|tx| tx.signable_bytes()))
+    }
+    
+    /// Fill in both Merkle roots from the current contents (call before `compute_hash`)
+    pub fn update_merkle_roots(&mut self) {
+        self.heartbeats_root = self.compute_heartbeats_root();
+        self.tx_root = self.compute_tx_root();
+    }
+    
+    /// Roots match the contents (vacuously true for legacy blocks without roots)
+    pub fn merkle_roots_valid(&self) -> bool {
+        !self.has_merkle_roots()
+            || (self.heartbeats_root == self.compute_heartbeats_root() && self.tx_root == self.compute_tx_root())
+    }
+    
+    /// Inclusion proof for the transaction with `tx_id`, checkable against
+    /// `tx_root` with [`verify_merkle_proof`] over the transaction's signable bytes
+    pub fn merkle_proof(&self, tx_id: &str) -> Option<Vec<String>> {
+        let index = self.transactions.iter().position(|tx| tx.tx_id == tx_id)?;
+        merkle::merkle_proof(self.transactions.iter().map(|tx| tx.signable_bytes()), index)
+    }
+    
+    /// Weight of each heartbeat as used for the reward split, in heartbeat order.
+    /// Uses the embedded continuity factors when present, full continuity otherwise,
+    /// scaled by the embedded confidence factors when present.
+    pub fn participant_weights(&self) -> Vec<f64> {
+        self.participant_weights_with(&WeightParams::default())
+    }
+    
+    /// [`PulseBlock::participant_weights`] with explicit weight coefficients
+    pub fn participant_weights_with(&self, params: &WeightParams) -> Vec<f64> {
+        self.heartbeats.iter().map(|hb| {
+            let continuity = self.continuity_factors.get(&hb.device_pubkey).copied().unwrap_or(1.0);
+            let weight = hb.weight_with_params(continuity, params);
+            weight * self.confidence_factors.get(&hb.device_pubkey).copied().unwrap_or(1.0)
+        }).collect()
+    }
+    
     /// Calculate fork probability P_fork = e^(-k * S)
     pub fn fork_probability(&self, k: f64) -> f64 {
         (-k * self.security).exp()
     }
 }
 
+/// Token amount in micro-PULSE. Balances and rewards are integers so that
+/// splitting a block reward and summing supply are exact.
+pub type Amount = u128;
+
+/// Micro-PULSE per PULSE
+pub const MICRO_PER_PULSE: Amount = 1_000_000;
+
+/// Convert PULSE to micro-PULSE, rounding to the nearest unit (negative and NaN become 0)
+pub fn to_micro(pulse: f64) -> Amount {
+    (pulse * MICRO_PER_PULSE as f64).round() as Amount
+}
+
+/// Convert micro-PULSE to PULSE, for display
+pub fn to_pulse(amount: Amount) -> f64 {
+    amount as f64 / MICRO_PER_PULSE as f64
+}
+
 /// Network statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkStats {
     pub chain_length: u64,
-    pub total_minted: f64,
+    /// Total minted, in micro-PULSE
+    pub total_minted: Amount,
     pub active_accounts: usize,
     pub current_tps: f64,
+    /// Observed mean seconds between recent blocks (the target until there are two)
     pub avg_block_time: f64,
+    /// Configured block interval in seconds
+    #[serde(default)]
+    pub target_block_time: f64,
     pub total_security: f64,
-    /// Current block reward (after halvings)
-    pub current_block_reward: f64,
+    /// Current block reward (after halvings), in micro-PULSE
+    pub current_block_reward: Amount,
     /// Current halving epoch
     pub halving_epoch: u64,
     /// Cumulative chain weight (for fork resolution)
     pub cumulative_weight: f64,
     /// Inflation rate: tokens_per_block / total_supply
     pub inflation_rate: f64,
+    /// Average fraction of participants carried over between recent consecutive blocks
+    #[serde(default)]
+    pub liveness_continuity: f64,
+    /// Fewest devices controlling a majority of recent heartbeat weight
+    #[serde(default)]
+    pub nakamoto_coefficient: usize,
+    /// P_fork of the latest block, using the fork constant adapted to its n_live
+    #[serde(default)]
+    pub current_fork_probability: f64,
+    /// Fork constant k adapted to the latest block's n_live
+    #[serde(default)]
+    pub adaptive_k: f64,
+}
+
+/// Proof-of-reserves style supply audit.
+/// Healthy chains satisfy sum_balances + sum_staked + total_burned == total_minted.
+/// All amounts are in micro-PULSE.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupplyAudit {
+    pub total_minted: Amount,
+    pub sum_balances: Amount,
+    pub sum_staked: Amount,
+    /// Always 0: withheld rewards are never minted, so nothing is burned
+    pub total_burned: Amount,
+    /// total_minted - (sum_balances + sum_staked + total_burned); exactly 0 when consistent
+    pub discrepancy: i128,
+}
+
+/// Pending heartbeats and transactions, for debugging why no block is being
+/// produced. Pubkeys are shortened to a prefix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolSnapshot {
+    /// Pooled heartbeats, ordered by pubkey prefix
+    pub heartbeats: Vec<PooledHeartbeat>,
+    /// Pending transactions in arrival order
+    pub transactions: Vec<PooledTransaction>,
+    /// Live participants required to produce a block
+    pub n_threshold: usize,
+    /// Further heartbeats needed to reach `n_threshold` (0 once it's met)
+    pub heartbeats_needed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PooledHeartbeat {
+    pub pubkey_prefix: String,
+    pub heart_rate: u16,
+    /// Weight it would contribute to a block produced now
+    pub weight: f64,
+    /// Time since the heartbeat's own timestamp
+    pub age_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PooledTransaction {
+    pub tx_id: String,
+    pub sender_prefix: String,
+    pub recipient_prefix: String,
+    pub amount: f64,
+    pub fee: f64,
 }
 
-/// Account balance and state
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Account balance and state. Amounts are in micro-PULSE.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct Account {
     pub pubkey: String,
-    pub balance: f64,
+    pub balance: Amount,
     pub last_heartbeat: u64,
-    pub total_earned: f64,
+    pub total_earned: Amount,
     pub blocks_participated: u64,
+    /// Nonce of the last transaction from this account included in a block
+    #[serde(default)]
+    pub nonce: u64,
+}
+
+/// Field accounts are ranked by when paging through them, largest first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountSort {
+    #[default]
+    Balance,
+    TotalEarned,
+    BlocksParticipated,
 }
 
 #[cfg(test)]
@@ -239,6 +635,9 @@ mod tests {
             temperature: 36.6,
             device_pubkey: "aabbccdd".to_string(),
             signature: String::new(),
+            signature_version: 0,
+            recoverable: false,
+            metadata: BTreeMap::new(),
         }
     }
 
@@ -286,6 +685,45 @@ mod tests {
         assert!(w > 0.0 && w <= 1.0, "weight out of range: {}", w);
     }
 
+    #[test]
+    fn test_heartbeat_metadata_is_non_consensus() {
+        let plain = sample_heartbeat();
+        let mut tagged = sample_heartbeat();
+        tagged.metadata.insert("firmware".to_string(), "2.4.1".to_string());
+        tagged.metadata.insert("model".to_string(), "PulseBand S".to_string());
+        
+        assert_eq!(plain.signable_bytes(), tagged.signable_bytes());
+        assert_eq!(plain.weight(), tagged.weight());
+        assert_eq!(tagged.metadata_bytes(), "firmware2.4.1modelPulseBand S".len());
+        
+        let json = serde_json::to_string(&tagged).unwrap();
+        let decoded: Heartbeat = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.metadata, tagged.metadata);
+        
+        // Untagged heartbeats serialize exactly as before
+        assert!(!serde_json::to_string(&plain).unwrap().contains("metadata"));
+        
+        // Tags don't affect the block hash either
+        let block = |hb: Heartbeat| PulseBlock {
+            index: 1,
+            timestamp: 0,
+            previous_hash: String::new(),
+            heartbeats: vec![hb],
+            transactions: vec![],
+            n_live: 1,
+            total_weight: 0.5,
+            security: 0.5,
+            bio_entropy: String::new(),
+            continuity_factors: BTreeMap::new(),
+            confidence_factors: BTreeMap::new(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
+            block_hash: String::new(),
+        };
+        assert_eq!(block(plain).compute_hash(), block(tagged).compute_hash());
+    }
+
     #[test]
     fn test_heartbeat_weight_with_continuity_zero() {
         let hb = sample_heartbeat();
@@ -294,6 +732,64 @@ mod tests {
         assert!(w1 > w0);
     }
 
+    #[test]
+    fn test_block_merkle_roots_and_tx_proofs() {
+        let tx = |i: u64| Transaction {
+            tx_id: format!("tx{}", i),
+            sender_pubkey: "s".to_string(),
+            recipient_pubkey: "r".to_string(),
+            amount: i as f64,
+            fee: 0.0,
+            timestamp: 100 + i,
+            heartbeat_signature: String::new(),
+            not_before_height: 0,
+            nonce: i,
+            signature_version: 0,
+            signature: "sig".to_string(),
+        };
+        let mut block = PulseBlock {
+            index: 1,
+            timestamp: 0,
+            previous_hash: String::new(),
+            heartbeats: vec![sample_heartbeat()],
+            transactions: (1..=5).map(tx).collect(),
+            n_live: 1,
+            total_weight: 0.5,
+            security: 0.5,
+            bio_entropy: String::new(),
+            continuity_factors: BTreeMap::new(),
+            confidence_factors: BTreeMap::new(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
+            block_hash: String::new(),
+        };
+        let legacy_hash = block.compute_hash();
+        assert!(!block.has_merkle_roots());
+        assert!(block.merkle_roots_valid());
+        
+        block.update_merkle_roots();
+        assert!(block.merkle_roots_valid());
+        assert_ne!(block.compute_hash(), legacy_hash);
+        
+        // Light-client check: a transaction plus its proof against tx_root alone
+        for t in &block.transactions {
+            let proof = block.merkle_proof(&t.tx_id).unwrap();
+            assert!(verify_merkle_proof(&t.signable_bytes(), &proof, &block.tx_root));
+        }
+        assert!(block.merkle_proof("missing").is_none());
+        let proof = block.merkle_proof("tx1").unwrap();
+        assert!(!verify_merkle_proof(&tx(2).signable_bytes(), &proof, &block.tx_root));
+        
+        // Roots make contents tamper-evident even though the hash only covers the roots
+        block.transactions[3].amount = 1000.0;
+        assert!(!block.merkle_roots_valid());
+        
+        // Blocks without roots don't serialize the fields
+        let json = serde_json::to_string(&PulseBlock { tx_root: String::new(), ..block }).unwrap();
+        assert!(!json.contains("tx_root"));
+    }
+
     #[test]
     fn test_transaction_serialization_roundtrip() {
         let tx = Transaction {
@@ -301,8 +797,12 @@ mod tests {
             sender_pubkey: "sender".to_string(),
             recipient_pubkey: "recipient".to_string(),
             amount: 42.5,
+            fee: 0.0,
             timestamp: 1700000000000,
             heartbeat_signature: "sig".to_string(),
+            not_before_height: 0,
+            nonce: 0,
+            signature_version: 0,
             signature: String::new(),
         };
         let json = serde_json::to_string(&tx).unwrap();
@@ -311,6 +811,40 @@ mod tests {
         assert!((tx2.amount - 42.5).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_pre_upgrade_block_keeps_its_hash() {
+        // Stored before transactions had timelocks or nonces; without Merkle
+        // roots, its hash covers the transactions' full JSON
+        let block: PulseBlock = serde_json::from_value(serde_json::json!({
+            "index": 7,
+            "timestamp": 1700000060000u64,
+            "previous_hash": "ab".repeat(32),
+            "heartbeats": [{
+                "timestamp": 1700000055000u64,
+                "heart_rate": 72,
+                "motion": { "x": 0.5, "y": 0.25, "z": 0.0 },
+                "temperature": 36.5,
+                "device_pubkey": format!("02{}", "11".repeat(32)),
+                "signature": "cd".repeat(64),
+            }],
+            "transactions": [{
+                "tx_id": "tx-legacy",
+                "sender_pubkey": format!("02{}", "11".repeat(32)),
+                "recipient_pubkey": format!("03{}", "22".repeat(32)),
+                "amount": 12.5,
+                "timestamp": 1700000058000u64,
+                "heartbeat_signature": "cd".repeat(64),
+                "signature": "ef".repeat(64),
+            }],
+            "n_live": 1,
+            "total_weight": 0.75,
+            "security": 0.75,
+            "bio_entropy": "9f".repeat(32),
+            "block_hash": "f9c05ef15064eb60b6b2d1b2e4860c43802281d3c8624fdcf646e89a0b7d69f2",
+        })).unwrap();
+        assert_eq!(block.compute_hash(), block.block_hash);
+    }
+
     #[test]
     fn test_transaction_signable_bytes_excludes_signature() {
         let mut tx = Transaction {
@@ -318,13 +852,52 @@ mod tests {
             sender_pubkey: "s".to_string(),
             recipient_pubkey: "r".to_string(),
             amount: 10.0,
+            fee: 0.0,
             timestamp: 100,
             heartbeat_signature: "hs".to_string(),
+            not_before_height: 0,
+            nonce: 0,
+            signature_version: 0,
             signature: String::new(),
         };
         let b1 = tx.signable_bytes();
         tx.signature = "changed".to_string();
         assert_eq!(b1, tx.signable_bytes());
+        
+        // The timelock is covered by the signature
+        tx.not_before_height = 10;
+        assert_ne!(b1, tx.signable_bytes());
+        
+        // So is the nonce
+        let b2 = tx.signable_bytes();
+        tx.nonce = 1;
+        assert_ne!(b2, tx.signable_bytes());
+    }
+
+    #[test]
+    fn test_domain_tags_separate_signatures() {
+        let kp = crate::crypto::Keypair::generate();
+        let mut hb = sample_heartbeat();
+        hb.device_pubkey = kp.public_key_hex();
+        let legacy = hb.signable_bytes();
+        hb.signature_version = SIGNATURE_VERSION;
+        let tagged = hb.signable_bytes();
+        assert!(tagged.starts_with(HEARTBEAT_DOMAIN_TAG));
+        assert_eq!(&tagged[HEARTBEAT_DOMAIN_TAG.len()..], legacy.as_slice());
+        
+        // Even a body valid as both types gets a signature valid only for one
+        let sig = kp.sign(&tagged);
+        let as_tx = domain_separated(TRANSACTION_DOMAIN_TAG, SIGNATURE_VERSION, legacy.clone());
+        assert!(crate::crypto::verify_signature(&kp.public_key_hex(), &tagged, &sig).unwrap());
+        assert!(!crate::crypto::verify_signature(&kp.public_key_hex(), &as_tx, &sig).unwrap());
+        
+        // Legacy signatures don't carry over to the tagged format, or vice versa
+        assert!(!crate::crypto::verify_signature(&kp.public_key_hex(), &tagged, &kp.sign(&legacy)).unwrap());
+        
+        // The version only appears on the wire when set
+        assert!(serde_json::to_string(&hb).unwrap().contains("\"signature_version\":1"));
+        hb.signature_version = 0;
+        assert!(!serde_json::to_string(&hb).unwrap().contains("signature_version"));
     }
 
     #[test]
@@ -339,6 +912,11 @@ mod tests {
             total_weight: 0.0,
             security: 0.0,
             bio_entropy: "00".to_string(),
+            continuity_factors: BTreeMap::new(),
+            confidence_factors: BTreeMap::new(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
             block_hash: String::new(),
         };
         assert_eq!(block.compute_hash(), block.compute_hash());
@@ -357,6 +935,11 @@ mod tests {
             total_weight: 0.0,
             security: 0.0,
             bio_entropy: String::new(),
+            continuity_factors: BTreeMap::new(),
+            confidence_factors: BTreeMap::new(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
             block_hash: String::new(),
         };
         let mut b2 = b1.clone();
@@ -376,6 +959,11 @@ mod tests {
             total_weight: 0.5,
             security: 0.5,
             bio_entropy: "ff".to_string(),
+            continuity_factors: BTreeMap::new(),
+            confidence_factors: BTreeMap::new(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
             block_hash: "hash".to_string(),
         };
         let json = serde_json::to_string(&block).unwrap();
@@ -390,7 +978,11 @@ mod tests {
             index: 1, timestamp: 0, previous_hash: String::new(),
             heartbeats: vec![], transactions: vec![],
             n_live: 5, total_weight: 3.0, security: 3.0,
-            bio_entropy: String::new(), block_hash: String::new(),
+            bio_entropy: String::new(), continuity_factors: BTreeMap::new(), confidence_factors: BTreeMap::new(),
+            heartbeats_root: String::new(),
+            tx_root: String::new(),
+            chain_id: String::new(),
+            block_hash: String::new(),
         };
         let p = block.fork_probability(0.5);
         // e^(-0.5 * 3.0) ≈ 0.2231
@@ -404,25 +996,39 @@ mod tests {
     fn test_network_stats_default_fields() {
         let stats = NetworkStats {
             chain_length: 10,
-            total_minted: 1000.0,
+            total_minted: 1000 * MICRO_PER_PULSE,
             active_accounts: 5,
             current_tps: 2.0,
             avg_block_time: 5.0,
+            target_block_time: 5.0,
             total_security: 50.0,
-            current_block_reward: 100.0,
+            current_block_reward: 100 * MICRO_PER_PULSE,
             halving_epoch: 0,
             cumulative_weight: 50.0,
             inflation_rate: 0.1,
+            liveness_continuity: 0.5,
+            nakamoto_coefficient: 3,
+            current_fork_probability: 0.01,
+            adaptive_k: 0.5,
         };
         let json = serde_json::to_string(&stats).unwrap();
         let s2: NetworkStats = serde_json::from_str(&json).unwrap();
         assert_eq!(s2.chain_length, 10);
+        assert_eq!(s2.total_minted, 1_000_000_000);
     }
 
     #[test]
     fn test_account_default() {
         let acc = Account::default();
-        assert_eq!(acc.balance, 0.0);
+        assert_eq!(acc.balance, 0);
         assert_eq!(acc.blocks_participated, 0);
     }
+
+    #[test]
+    fn test_pulse_micro_conversion() {
+        assert_eq!(to_micro(1.5), 1_500_000);
+        assert_eq!(to_micro(0.1 + 0.2), 300_000);
+        assert_eq!(to_micro(-1.0), 0);
+        assert_eq!(to_pulse(2_500_000), 2.5);
+    }
 }